@@ -0,0 +1,191 @@
+//! Flash throughput benchmark (`bench`).
+//!
+//! Writes generated data to a scratch address, times the transfer and
+//! compares the result with what the serial line could carry at the
+//! transfer baud rate.
+//!
+//! The serial protocol has no command that only erases a range, so the
+//! scratch area is not erased afterwards but written a second time with
+//! `0xFF` bytes. That leaves the same contents as an erase, at the cost of
+//! a second, untimed transfer of the same size.
+
+use {
+    super::flash::{ensure_not_interrupted, with_loaderboot},
+    crate::{Cli, config::Config, summary::RunSummary, util::format_size},
+    anyhow::Result,
+    console::style,
+    hisiflash::{ChipFamily, Flasher},
+    rust_i18n::t,
+    std::{
+        path::Path,
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// Bits on the wire per payload byte with 8N1 framing.
+const BITS_PER_BYTE: f64 = 10.0;
+
+/// Timing of one benchmark transfer.
+#[derive(Debug, Clone, Copy)]
+struct BenchResult {
+    bytes: usize,
+    elapsed: Duration,
+    baud: u32,
+}
+
+impl BenchResult {
+    /// Payload throughput in MiB/s.
+    #[allow(clippy::cast_precision_loss)]
+    fn mib_per_sec(&self) -> f64 {
+        self.bytes as f64
+            / (1024.0 * 1024.0)
+            / self
+                .elapsed
+                .as_secs_f64()
+    }
+
+    /// Share of the raw line rate spent on payload, in percent.
+    #[allow(clippy::cast_precision_loss)]
+    fn efficiency_percent(&self) -> f64 {
+        let line_bytes_per_sec = f64::from(self.baud) / BITS_PER_BYTE;
+        self.bytes as f64
+            / self
+                .elapsed
+                .as_secs_f64()
+            / line_bytes_per_sec
+            * 100.0
+    }
+}
+
+/// Generate `size` bytes of pseudo-random data (xorshift32).
+///
+/// Random rather than constant data keeps adapters and the device from
+/// benefiting from runs of identical bytes.
+fn bench_data(size: usize, seed: u32) -> Vec<u8> {
+    let mut state = seed | 1;
+    (0..size)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state.to_le_bytes()[0]
+        })
+        .collect()
+}
+
+/// Bench command implementation.
+pub(crate) fn cmd_bench(
+    cli: &Cli,
+    config: &mut Config,
+    loaderboot: &Path,
+    size: u32,
+    address: u32,
+    late_baud: bool,
+    chip: ChipFamily,
+) -> Result<RunSummary> {
+    let size = size as usize;
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    let data = bench_data(size, seed);
+
+    // LoaderBoot and a late baud switch are not part of the measurement.
+    let result = with_loaderboot(cli, config, loaderboot, late_baud, chip, |flasher| {
+        run_bench(cli, flasher, &data, address)
+    })?;
+
+    println!(
+        "{}",
+        t!(
+            "bench.result",
+            speed = format!("{:.3}", result.mib_per_sec()),
+            size = format_size(result.bytes as u64),
+            secs = format!(
+                "{:.2}",
+                result
+                    .elapsed
+                    .as_secs_f64()
+            ),
+            efficiency = format!("{:.1}", result.efficiency_percent()),
+            baud = result.baud
+        )
+    );
+
+    Ok(RunSummary {
+        partitions: 1,
+        bytes: result.bytes as u64,
+    })
+}
+
+/// Time the scratch write over a running LoaderBoot, then fill the area
+/// with `0xFF` and reset.
+fn run_bench(
+    cli: &Cli,
+    flasher: &mut dyn Flasher,
+    data: &[u8],
+    address: u32,
+) -> Result<BenchResult> {
+    if !cli.quiet {
+        eprintln!(
+            "{} {}",
+            style("⏱").cyan(),
+            t!(
+                "bench.writing",
+                size = format_size(data.len() as u64),
+                addr = format!("{address:08X}")
+            )
+        );
+    }
+    let started = Instant::now();
+    flasher.download_bins_only(&[(data, address)], &mut |_, _, _| {})?;
+    let result = BenchResult {
+        bytes: data.len(),
+        elapsed: started.elapsed(),
+        baud: flasher
+            .target_baud()
+            .unwrap_or_else(|| flasher.connection_baud()),
+    };
+    ensure_not_interrupted()?;
+
+    if !cli.quiet {
+        eprintln!("{} {}", style("🗑").red(), t!("bench.restoring"));
+    }
+    // Not an erase: the area is written again with the erased value.
+    let erased = vec![0xFF; data.len()];
+    flasher.download_bins_only(&[(&erased, address)], &mut |_, _, _| {})?;
+    ensure_not_interrupted()?;
+
+    flasher.reset()?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_data_is_sized_and_varied() {
+        let data = bench_data(4096, 0);
+        assert_eq!(data.len(), 4096);
+        assert_eq!(data, bench_data(4096, 0));
+        assert_ne!(data, bench_data(4096, 42));
+
+        let distinct: std::collections::BTreeSet<u8> = data
+            .iter()
+            .copied()
+            .collect();
+        assert!(distinct.len() > 200, "{}", distinct.len());
+    }
+
+    #[test]
+    fn test_bench_result_rates() {
+        let result = BenchResult {
+            bytes: 1024 * 1024,
+            elapsed: Duration::from_secs(16),
+            baud: 921_600,
+        };
+        assert!((result.mib_per_sec() - 0.0625).abs() < 1e-9);
+        // 65536 B/s of the 92160 B/s the line carries.
+        assert!((result.efficiency_percent() - 71.111).abs() < 0.001);
+    }
+}
@@ -0,0 +1,126 @@
+//! Config file management command implementations.
+
+use {
+    crate::{CliError, config::Config},
+    anyhow::{Context, Result},
+    console::style,
+    rust_i18n::t,
+    std::{
+        fs,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Default local config file name written by `config init`.
+const LOCAL_CONFIG_FILE: &str = "hisiflash.toml";
+
+/// Resolve where `config init` should write the template.
+fn init_target(output: Option<&Path>, global: bool) -> Result<PathBuf> {
+    if let Some(path) = output {
+        return Ok(path.to_path_buf());
+    }
+    if global {
+        return Config::global_config_path()
+            .ok_or_else(|| CliError::Config(t!("config.no_global_dir").to_string()).into());
+    }
+    Ok(PathBuf::from(LOCAL_CONFIG_FILE))
+}
+
+/// `config init` implementation: write an annotated config template.
+pub(crate) fn cmd_config_init(
+    output: Option<&Path>,
+    global: bool,
+    force: bool,
+    quiet: bool,
+) -> Result<()> {
+    let path = init_target(output, global)?;
+
+    if path.exists() && !force {
+        return Err(CliError::Config(
+            t!(
+                "config.already_exists",
+                path = path
+                    .display()
+                    .to_string()
+            )
+            .to_string(),
+        )
+        .into());
+    }
+
+    if let Some(parent) = path
+        .parent()
+        .filter(|p| {
+            !p.as_os_str()
+                .is_empty()
+        })
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(&path, Config::default_with_comments())
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    if !quiet {
+        eprintln!(
+            "{} {}",
+            style("✓").green(),
+            t!(
+                "config.written",
+                path = path
+                    .display()
+                    .to_string()
+            )
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_init_writes_template() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp
+            .path()
+            .join("nested/hisiflash.toml");
+
+        cmd_config_init(Some(&path), false, false, true).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, Config::default_with_comments());
+    }
+
+    #[test]
+    fn test_config_init_refuses_to_overwrite() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp
+            .path()
+            .join("hisiflash.toml");
+        fs::write(&path, "# existing\n").unwrap();
+
+        let err = cmd_config_init(Some(&path), false, false, true).unwrap_err();
+        assert!(
+            err.downcast_ref::<CliError>()
+                .is_some()
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# existing\n");
+
+        cmd_config_init(Some(&path), false, true, true).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            Config::default_with_comments()
+        );
+    }
+
+    #[test]
+    fn test_init_target_defaults_to_local_file() {
+        assert_eq!(
+            init_target(None, false).unwrap(),
+            PathBuf::from(LOCAL_CONFIG_FILE)
+        );
+    }
+}
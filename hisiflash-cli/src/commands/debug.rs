@@ -0,0 +1,125 @@
+//! Hidden developer utilities (`debug`).
+//!
+//! `debug frame <KIND>` prints the SEBOOT command frame hisiflash would send,
+//! without opening a port, so it can be diffed against captures of the
+//! vendor tool.
+
+use {
+    crate::Cli,
+    clap::Subcommand,
+    hisiflash::{ChipFamily, target::ws63::protocol::CommandFrame},
+};
+
+/// Frames `debug frame` can build.
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FrameKind {
+    /// Handshake at the global --baud (default: the chip's handshake baud).
+    Handshake,
+    /// Baud rate switch to the global --baud (default: the chip's
+    /// recommended flash baud).
+    SetBaud,
+    /// Download command for an image.
+    Download {
+        /// Flash address.
+        #[arg(short, long, value_parser = crate::parse_hex_u32)]
+        address: u32,
+
+        /// Image length in bytes.
+        #[arg(long, value_parser = crate::parse_size)]
+        len: u32,
+
+        /// Erase size (default: LEN rounded up to 4 KiB).
+        #[arg(long, value_parser = crate::parse_size)]
+        erase_size: Option<u32>,
+
+        /// Mark the image as a ROM image.
+        #[arg(long)]
+        rom: bool,
+    },
+    /// Erase the whole flash.
+    EraseAll,
+    /// Reset the device.
+    Reset,
+    /// Switch the device to DFU mode.
+    Dfu,
+}
+
+/// Build the frame for `kind`.
+fn build_frame(kind: &FrameKind, baud: Option<u32>, chip: ChipFamily) -> CommandFrame {
+    match kind {
+        FrameKind::Handshake => {
+            CommandFrame::handshake(baud.unwrap_or_else(|| chip.handshake_baud()))
+        },
+        FrameKind::SetBaud => {
+            CommandFrame::set_baud_rate(crate::resolve_effective_baud(baud, chip))
+        },
+        FrameKind::Download {
+            address,
+            len,
+            erase_size,
+            rom,
+        } => {
+            // Same alignment as the flasher.
+            let erase_size = erase_size.unwrap_or_else(|| len.saturating_add(0xFFF) & !0xFFF);
+            CommandFrame::download_image(*address, *len, erase_size, *rom)
+        },
+        FrameKind::EraseAll => CommandFrame::erase_all(),
+        FrameKind::Reset => CommandFrame::reset(),
+        FrameKind::Dfu => CommandFrame::switch_dfu(),
+    }
+}
+
+/// Format bytes as space-separated uppercase hex.
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `debug frame` implementation: print the frame as hex on stdout and its
+/// decoded form on stderr.
+pub(crate) fn cmd_debug_frame(cli: &Cli, kind: &FrameKind) {
+    let chip = cli
+        .chip
+        .map(ChipFamily::from)
+        .unwrap_or_default();
+    let frame = build_frame(kind, cli.baud, chip);
+    println!("{}", hex_bytes(&frame.build()));
+    if !cli.quiet {
+        eprintln!("{}", frame.describe());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_frame_matches_flasher_frames() {
+        let chip = ChipFamily::Ws63;
+        assert_eq!(
+            build_frame(&FrameKind::Handshake, None, chip).build(),
+            CommandFrame::handshake(115_200).build()
+        );
+        assert_eq!(
+            build_frame(&FrameKind::SetBaud, Some(460_800), chip).build(),
+            CommandFrame::set_baud_rate(460_800).build()
+        );
+        let download = FrameKind::Download {
+            address: 0x0023_0000,
+            len: 0x1001,
+            erase_size: None,
+            rom: false,
+        };
+        assert_eq!(
+            build_frame(&download, None, chip).build(),
+            CommandFrame::download_image(0x0023_0000, 0x1001, 0x2000, false).build()
+        );
+        assert_eq!(
+            hex_bytes(&build_frame(&FrameKind::Reset, None, chip).build()[..6]),
+            "EF BE AD DE 0C 00"
+        );
+    }
+}
@@ -1,16 +1,115 @@
-//! Flash, write, and erase command implementations.
+//! Flash, write, region write, erase, OTP and DFU command implementations.
 
 use {
-    crate::{Cli, CliError, config::Config, get_port, use_fancy_output, was_interrupted},
+    crate::{
+        Cli, CliError, config::Config, get_port, progress_file::ProgressFile, summary::RunSummary,
+        use_fancy_output, util::format_size, was_interrupted,
+    },
     anyhow::{Context, Result},
     console::style,
-    hisiflash::{ChipFamily, Flasher, Fwpkg},
+    dialoguer::{Confirm, theme::ColorfulTheme},
+    hisiflash::{
+        ChipFamily, Error as LibError, Flasher, Fwpkg, FwpkgBinInfo, FwpkgBuilder, OtpConfirmation,
+        PartitionFilter, PartitionType, target::ws63,
+    },
     indicatif::{ProgressBar, ProgressStyle},
     rust_i18n::t,
-    std::path::PathBuf,
+    std::{
+        collections::BTreeMap,
+        fs::File,
+        io::{BufWriter, Read},
+        path::{Path, PathBuf},
+        time::Duration,
+    },
 };
 
-fn ensure_not_interrupted() -> Result<()> {
+/// Create the flasher for `chip`, applying `--handshake-baud` and
+/// `--reset-settle-ms` and attaching the `--trace-file` handshake log and
+/// the `--dump-device-errors` file.
+pub(super) fn create_flasher(
+    cli: &Cli,
+    chip: ChipFamily,
+    port: &str,
+    baud: u32,
+    late_baud: bool,
+) -> Result<Box<dyn Flasher>> {
+    if let Some(handshake_baud) = cli.handshake_baud {
+        check_handshake_baud(chip, handshake_baud)?;
+    }
+
+    let trace_sink = cli
+        .trace_file
+        .as_deref()
+        .map(|path| {
+            File::create(path).with_context(|| {
+                t!(
+                    "error.write_trace_file",
+                    path = path
+                        .display()
+                        .to_string()
+                )
+                .to_string()
+            })
+        })
+        .transpose()?;
+    let device_error_sink = cli
+        .dump_device_errors
+        .as_deref()
+        .map(|path| {
+            File::create(path).with_context(|| {
+                t!(
+                    "error.write_device_error_file",
+                    path = path
+                        .display()
+                        .to_string()
+                )
+                .to_string()
+            })
+        })
+        .transpose()?;
+
+    let mut flasher = chip.create_flasher(port, baud, late_baud, cli.verbose)?;
+    if let Some(handshake_baud) = cli.handshake_baud {
+        flasher.set_handshake_baud(handshake_baud);
+    }
+    if let Some(settle_ms) = cli.reset_settle_ms {
+        flasher.set_reset_settle(Duration::from_millis(settle_ms));
+    }
+    flasher.set_auto_bootloader(
+        cli.auto_enter
+            .clone(),
+    );
+    if let Some(file) = trace_sink {
+        flasher.set_trace_sink(Box::new(BufWriter::new(file)));
+    }
+    if let Some(file) = device_error_sink {
+        flasher.set_device_error_sink(Box::new(file));
+    }
+    Ok(flasher)
+}
+
+/// Reject a `--handshake-baud` the boot ROM cannot be reached at.
+fn check_handshake_baud(chip: ChipFamily, baud: u32) -> Result<()> {
+    let supported = chip.supported_handshake_bauds();
+    if supported.contains(&baud) {
+        return Ok(());
+    }
+    let supported: Vec<String> = supported
+        .iter()
+        .map(u32::to_string)
+        .collect();
+    Err(CliError::Usage(
+        t!(
+            "error.unsupported_handshake_baud",
+            baud = baud,
+            supported = supported.join(", ")
+        )
+        .to_string(),
+    )
+    .into())
+}
+
+pub(super) fn ensure_not_interrupted() -> Result<()> {
     if was_interrupted() {
         Err(CliError::Cancelled(t!("error.interrupted").to_string()).into())
     } else {
@@ -18,6 +117,72 @@ fn ensure_not_interrupted() -> Result<()> {
     }
 }
 
+/// Run `op` against a device with LoaderBoot running.
+///
+/// Reads `loaderboot`, opens the port at the effective baud rate, connects
+/// and starts LoaderBoot (unless `flash.skip_loaderboot` is set) before
+/// handing the flasher to `op`. The port is closed afterwards either way.
+/// Shared by the commands that drive LoaderBoot directly instead of
+/// flashing a package.
+pub(super) fn with_loaderboot<T>(
+    cli: &Cli,
+    config: &mut Config,
+    loaderboot: &Path,
+    late_baud: bool,
+    chip: ChipFamily,
+    op: impl FnOnce(&mut dyn Flasher) -> Result<T>,
+) -> Result<T> {
+    if !cli.quiet {
+        eprintln!(
+            "{} {}",
+            style("📦").cyan(),
+            t!("write.loading_loaderboot", path = loaderboot.display())
+        );
+    }
+    let lb_data = std::fs::read(loaderboot).with_context(|| {
+        t!(
+            "error.read_loaderboot",
+            path = loaderboot
+                .display()
+                .to_string()
+        )
+    })?;
+
+    let port = get_port(cli, config)?;
+    let effective_baud = crate::resolve_effective_baud(cli.baud, chip);
+    if !cli.quiet {
+        eprintln!(
+            "{} {}",
+            style("🔌").cyan(),
+            t!("common.using_port", port = port, baud = effective_baud)
+        );
+    }
+
+    let mut flasher = create_flasher(cli, chip, &port, effective_baud, late_baud)?;
+    flasher.set_skip_loaderboot(
+        config
+            .flash
+            .skip_loaderboot,
+    );
+    let result = (|| {
+        ensure_not_interrupted()?;
+        if !cli.quiet {
+            eprintln!("{} {}", style("⏳").yellow(), t!("common.waiting_device"));
+        }
+        flasher.connect()?;
+        ensure_not_interrupted()?;
+        if !cli.quiet {
+            eprintln!("{} {}", style("✓").green(), t!("common.connected"));
+        }
+
+        flasher.write_bins(&lb_data, &[])?;
+        ensure_not_interrupted()?;
+        op(flasher.as_mut())
+    })();
+    flasher.close();
+    result
+}
+
 /// Outcome of a flash operation.
 ///
 /// `port` is always the serial port name that was actually used. When
@@ -28,6 +193,7 @@ fn ensure_not_interrupted() -> Result<()> {
 pub(crate) struct FlashOutcome {
     pub port: String,
     pub flasher: Option<Box<dyn Flasher>>,
+    pub summary: RunSummary,
 }
 
 /// Flash command implementation.
@@ -36,7 +202,25 @@ pub(crate) struct FlashOutcome {
 /// underlying serial port stays open) so a subsequent `--monitor` step can
 /// inherit the handle. Otherwise the flasher is reset and closed before
 /// returning, matching the previous behaviour.
-#[allow(clippy::too_many_arguments)]
+///
+/// If `filter` selects only part of a group of partitions the chip needs
+/// flashed together (see [`ChipFamily::coupled_partitions`]), flashing is
+/// refused unless `force` is set. If it leaves out security partitions that
+/// the package contains, the user is asked to confirm unless `force` is set
+/// or the run is non-interactive.
+///
+/// With `keep_going`, a failing partition does not stop the remaining ones;
+/// the command still fails afterwards with [`LibError::PartitionsFailed`].
+///
+/// `stall_timeout` aborts a partition transfer when no block has been
+/// acknowledged for that long. `checksum_fallback` lets YMODEM transfers
+/// start on a NAK and use 8-bit checksums for loaders without CRC mode.
+///
+/// `expect` is a comma-separated list of partition names the package must
+/// contain; flashing does not start if any of them is missing.
+///
+/// `allow_large` lifts the flasher's partition size limit.
+#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
 pub(crate) fn cmd_flash(
     cli: &Cli,
     config: &mut Config,
@@ -46,7 +230,17 @@ pub(crate) fn cmd_flash(
     skip_verify: bool,
     chip: ChipFamily,
     keep_open: bool,
+    force: bool,
+    progress_file: Option<&Path>,
+    keep_going: bool,
+    stall_timeout: Option<Duration>,
+    checksum_fallback: bool,
+    expect: Option<&String>,
+    allow_large: bool,
+    pre_erase: bool,
 ) -> Result<FlashOutcome> {
+    let filters = parse_filter(filter)?;
+
     if !cli.quiet {
         eprintln!(
             "{} {}",
@@ -65,16 +259,93 @@ pub(crate) fn cmd_flash(
         )
     })?;
 
+    if let Some(expect) = expect {
+        let missing = missing_expected_partitions(&fwpkg, expect);
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "{}",
+                t!(
+                    "flash.missing_expected_partitions",
+                    names = missing.join(", ")
+                )
+            );
+        }
+    }
+
     // Verify CRC
     if !skip_verify {
         fwpkg
-            .verify_crc()
+            .verify_crc_cancellable(&hisiflash::cancel_context_from_global())
             .context(t!("error.crc_failed").to_string())?;
         if !cli.quiet {
             eprintln!("{} {}", style("✓").green(), t!("flash.crc_passed"));
         }
+        verify_partitions(&fwpkg)?;
+    } else {
+        warn_crc_mismatch(&fwpkg);
     }
 
+    if let Some(filters) = &filters {
+        let split = split_coupled_groups(&fwpkg, filters, chip.coupled_partitions());
+        if split.is_empty() {
+            let excluded = excluded_security_partitions(&fwpkg, filters);
+            if !excluded.is_empty() {
+                confirm_security_exclusion(cli, force, &excluded)?;
+            }
+        } else {
+            check_split_groups(force, &split)?;
+        }
+    }
+
+    let progress_file = progress_file
+        .map(|path| {
+            ProgressFile::create(path).with_context(|| {
+                t!(
+                    "error.write_progress_file",
+                    path = path
+                        .display()
+                        .to_string()
+                )
+                .to_string()
+            })
+        })
+        .transpose()?;
+
+    flash_loaded_fwpkg(
+        cli,
+        config,
+        &fwpkg,
+        filters.as_deref(),
+        late_baud,
+        chip,
+        keep_open,
+        keep_going,
+        stall_timeout,
+        checksum_fallback,
+        allow_large,
+        pre_erase,
+        progress_file,
+    )
+}
+
+/// Connect, flash an already loaded and checked `fwpkg`, and reset the
+/// device. Shared by [`cmd_flash`] and [`cmd_write_manifest`].
+#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
+fn flash_loaded_fwpkg(
+    cli: &Cli,
+    config: &mut Config,
+    fwpkg: &Fwpkg,
+    filter: Option<&[PartitionFilter]>,
+    late_baud: bool,
+    chip: ChipFamily,
+    keep_open: bool,
+    keep_going: bool,
+    stall_timeout: Option<Duration>,
+    checksum_fallback: bool,
+    allow_large: bool,
+    pre_erase: bool,
+    mut progress_file: Option<ProgressFile>,
+) -> Result<FlashOutcome> {
     // Show partition info
     if !cli.quiet {
         eprintln!(
@@ -82,7 +353,7 @@ pub(crate) fn cmd_flash(
             style("ℹ").blue(),
             t!("flash.found_partitions", count = fwpkg.partition_count())
         );
-        for bin in &fwpkg.bins {
+        for bin in fwpkg {
             let type_str = if bin.is_loaderboot() {
                 "(LoaderBoot)"
             } else {
@@ -110,7 +381,23 @@ pub(crate) fn cmd_flash(
         );
     }
 
-    let mut flasher = chip.create_flasher(&port, effective_baud, late_baud, cli.verbose)?;
+    let mut flasher = create_flasher(cli, chip, &port, effective_baud, late_baud)?;
+    flasher.set_keep_going(keep_going);
+    flasher.set_stall_timeout(stall_timeout);
+    flasher.set_checksum_fallback(checksum_fallback);
+    flasher.set_skip_loaderboot(
+        config
+            .flash
+            .skip_loaderboot,
+    );
+    if allow_large {
+        flasher.set_max_partition_bytes(None);
+    }
+    if pre_erase {
+        // `--pre-erase` is the explicit confirmation for the full erase.
+        flasher.set_pre_erase(true);
+        flasher.arm_destructive();
+    }
     if let Err(err) = ensure_not_interrupted() {
         flasher.close();
         return Err(err);
@@ -130,6 +417,9 @@ pub(crate) fn cmd_flash(
     }
     if !cli.quiet {
         eprintln!("{} {}", style("✓").green(), t!("common.connected"));
+        if pre_erase {
+            eprintln!("{} {}", style("🗑").red(), t!("flash.pre_erasing"));
+        }
     }
 
     // Create progress bar
@@ -149,33 +439,30 @@ pub(crate) fn cmd_flash(
     };
 
     // Flash
-    let filter_names: Option<Vec<&str>> = filter
-        .as_ref()
-        .map(|f| {
-            f.split(',')
-                .collect()
-        });
-    let filter_slice = filter_names.as_deref();
-
     let mut current_partition = String::new();
+    let mut flashed: BTreeMap<String, usize> = BTreeMap::new();
 
     let flash_result = flasher.flash_fwpkg(
-        &fwpkg,
-        filter_slice,
+        fwpkg,
+        filter,
         &mut |name: &str, current: usize, total: usize| {
             if name != current_partition {
                 current_partition = name.to_string();
                 pb.set_message(t!("flash.flashing", name = name).to_string());
             }
+            flashed.insert(name.to_string(), total);
             if let Some(pct) = (current * 100).checked_div(total) {
                 pb.set_position(pct as u64);
             }
+            if let Some(progress_file) = &mut progress_file {
+                progress_file.update(name, current, total);
+            }
         },
     );
 
     if let Err(err) = flash_result {
         flasher.close();
-        return Err(err.into());
+        return Err(flash_error(err));
     }
 
     if let Err(err) = ensure_not_interrupted() {
@@ -209,6 +496,14 @@ pub(crate) fn cmd_flash(
         );
     }
 
+    let summary = RunSummary {
+        partitions: flashed.len(),
+        bytes: flashed
+            .values()
+            .map(|&total| total as u64)
+            .sum(),
+    };
+
     if keep_open {
         // Hand over the live flasher (port still open, reset already issued)
         // to the caller. The caller is responsible for either invoking
@@ -216,6 +511,7 @@ pub(crate) fn cmd_flash(
         Ok(FlashOutcome {
             port,
             flasher: Some(flasher),
+            summary,
         })
     } else {
         // Close the underlying serial port to release resources.
@@ -223,10 +519,288 @@ pub(crate) fn cmd_flash(
         Ok(FlashOutcome {
             port,
             flasher: None,
+            summary,
         })
     }
 }
 
+/// Names of the security partitions in `fwpkg` that `filters` leave out.
+fn excluded_security_partitions<'a>(fwpkg: &'a Fwpkg, filters: &[PartitionFilter]) -> Vec<&'a str> {
+    [
+        PartitionType::SecurityA,
+        PartitionType::SecurityB,
+        PartitionType::SecurityC,
+    ]
+    .into_iter()
+    .flat_map(|partition_type| fwpkg.bins_of_type(partition_type))
+    .filter(|bin| {
+        !filters
+            .iter()
+            .any(|f| f.matches(bin))
+    })
+    .map(|bin| {
+        bin.name
+            .as_str()
+    })
+    .collect()
+}
+
+/// A group of coupled partitions that a filter selects only part of.
+#[derive(Debug, PartialEq, Eq)]
+struct SplitGroup<'a> {
+    selected: Vec<&'a str>,
+    left_out: Vec<&'a str>,
+}
+
+/// Coupled partition groups in `fwpkg` that `filters` select some but not all
+/// members of.
+fn split_coupled_groups<'a>(
+    fwpkg: &'a Fwpkg,
+    filters: &[PartitionFilter],
+    groups: &[&[PartitionType]],
+) -> Vec<SplitGroup<'a>> {
+    groups
+        .iter()
+        .filter_map(|group| {
+            let (selected, left_out): (Vec<_>, Vec<_>) = group
+                .iter()
+                .flat_map(|partition_type| fwpkg.bins_of_type(*partition_type))
+                .partition(|bin| {
+                    filters
+                        .iter()
+                        .any(|f| f.matches(bin))
+                });
+            if selected.is_empty() || left_out.is_empty() {
+                return None;
+            }
+            let names = |bins: Vec<&'a FwpkgBinInfo>| {
+                bins.into_iter()
+                    .map(|bin| {
+                        bin.name
+                            .as_str()
+                    })
+                    .collect()
+            };
+            Some(SplitGroup {
+                selected: names(selected),
+                left_out: names(left_out),
+            })
+        })
+        .collect()
+}
+
+/// Refuse to flash part of a coupled group, or only warn with `force`.
+fn check_split_groups(force: bool, split: &[SplitGroup<'_>]) -> Result<()> {
+    let message = split
+        .iter()
+        .map(|group| {
+            t!(
+                "flash.coupled_split",
+                selected = group
+                    .selected
+                    .join(", "),
+                left_out = group
+                    .left_out
+                    .join(", ")
+            )
+            .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+    if !force {
+        return Err(CliError::Usage(
+            t!("flash.coupled_split_refused", groups = message).to_string(),
+        )
+        .into());
+    }
+    eprintln!(
+        "{} {}",
+        style("⚠")
+            .yellow()
+            .bold(),
+        style(t!("flash.coupled_split_forced", groups = message))
+            .yellow()
+            .bold()
+    );
+    Ok(())
+}
+
+/// Warn that security partitions will not be flashed and, unless `force` or
+/// `--non-interactive` is set, ask the user to confirm.
+fn confirm_security_exclusion(cli: &Cli, force: bool, excluded: &[&str]) -> Result<()> {
+    eprintln!(
+        "{} {}",
+        style("⚠")
+            .yellow()
+            .bold(),
+        style(t!(
+            "flash.security_excluded",
+            partitions = excluded.join(", ")
+        ))
+        .yellow()
+        .bold()
+    );
+    if force || cli.non_interactive {
+        return Ok(());
+    }
+
+    let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(t!("flash.confirm_security_excluded").to_string())
+        .default(false)
+        .interact_opt()
+        .context("security partition confirmation failed")?
+        .unwrap_or(false);
+    if confirmed {
+        Ok(())
+    } else {
+        Err(CliError::Cancelled(t!("flash.security_excluded_cancelled").to_string()).into())
+    }
+}
+
+/// Convert a flasher error, pointing at `--allow-large` when a partition
+/// was refused for its size.
+fn flash_error(err: LibError) -> anyhow::Error {
+    if matches!(err, LibError::PartitionTooLarge { .. }) {
+        let message = t!("flash.partition_too_large", error = err.to_string()).to_string();
+        anyhow::Error::new(err).context(message)
+    } else {
+        err.into()
+    }
+}
+
+/// Names from the comma-separated `--expect` value that `fwpkg` lacks.
+fn missing_expected_partitions<'a>(fwpkg: &Fwpkg, expect: &'a str) -> Vec<&'a str> {
+    expect
+        .split(',')
+        .map(str::trim)
+        .filter(|name| {
+            !name.is_empty()
+                && fwpkg
+                    .find_by_name(name)
+                    .is_none()
+        })
+        .collect()
+}
+
+/// Parse the comma-separated `--filter` value into partition filters.
+fn parse_filter(filter: Option<&String>) -> Result<Option<Vec<PartitionFilter>>> {
+    filter
+        .map(|f| {
+            f.split(',')
+                .map(|item| {
+                    item.parse()
+                        .map_err(|_| {
+                            CliError::Usage(t!("flash.invalid_filter", filter = item).to_string())
+                                .into()
+                        })
+                })
+                .collect()
+        })
+        .transpose()
+}
+
+/// Warn loudly when `--skip-verify` lets a package with a bad CRC through.
+///
+/// Printed even with `--quiet`: the user asked to bypass the check, not to
+/// hide that the package fails it.
+fn warn_crc_mismatch(fwpkg: &Fwpkg) {
+    if let Err(LibError::CrcMismatch { expected, actual }) = fwpkg.verify_crc() {
+        eprintln!(
+            "{} {}",
+            style("⚠")
+                .yellow()
+                .bold(),
+            style(crc_mismatch_warning(expected, actual))
+                .yellow()
+                .bold()
+        );
+    }
+}
+
+/// Format the skipped-CRC warning from [`LibError::CrcMismatch`]'s fields.
+fn crc_mismatch_warning(expected: u16, actual: u16) -> String {
+    t!(
+        "flash.crc_mismatch_skipped",
+        stored = format!("{expected:04X}"),
+        computed = format!("{actual:04X}")
+    )
+    .to_string()
+}
+
+/// Check that each partition's payload lies within the package.
+fn verify_partitions(fwpkg: &Fwpkg) -> Result<()> {
+    for bin in fwpkg {
+        fwpkg
+            .verify_partition_bounds(bin)
+            .with_context(|| t!("error.partition_verify_failed", name = &bin.name).to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Turn `write --partition NAME=FILE` arguments into `(file, address)` pairs
+/// using the chip's built-in default layout.
+pub(crate) fn resolve_named_partitions(
+    chip: ChipFamily,
+    partitions: &[(String, PathBuf)],
+) -> Result<Vec<(PathBuf, u32)>> {
+    if partitions.is_empty() {
+        return Ok(Vec::new());
+    }
+    if chip != ChipFamily::Ws63 {
+        return Err(CliError::Usage(t!("write.no_default_layout", chip = chip).to_string()).into());
+    }
+
+    partitions
+        .iter()
+        .map(|(name, path)| {
+            let entry = ws63::layout::find_partition(name).ok_or_else(|| {
+                let known: Vec<&str> = ws63::layout::DEFAULT_LAYOUT
+                    .iter()
+                    .map(|entry| entry.name)
+                    .collect();
+                CliError::Usage(
+                    t!(
+                        "write.unknown_partition",
+                        name = name,
+                        known = known.join(", ")
+                    )
+                    .to_string(),
+                )
+            })?;
+            Ok((path.clone(), entry.burn_addr))
+        })
+        .collect()
+}
+
+/// Path that makes `write` read a binary from stdin.
+const STDIN_PATH: &str = "-";
+
+/// Read a binary from `path`, or from stdin when it is `-`.
+fn read_binary(path: &Path) -> std::io::Result<Vec<u8>> {
+    if path == Path::new(STDIN_PATH) {
+        let mut data = Vec::new();
+        std::io::stdin()
+            .lock()
+            .read_to_end(&mut data)?;
+        Ok(data)
+    } else {
+        std::fs::read(path)
+    }
+}
+
+/// Stdin can only be consumed once, so at most one binary may use `-`.
+fn ensure_single_stdin_bin(bins: &[(PathBuf, u32)]) -> Result<()> {
+    let stdin_bins = bins
+        .iter()
+        .filter(|(path, _)| path == Path::new(STDIN_PATH))
+        .count();
+    if stdin_bins > 1 {
+        return Err(CliError::Usage(t!("write.stdin_once").to_string()).into());
+    }
+    Ok(())
+}
+
 /// Write command implementation.
 pub(crate) fn cmd_write(
     cli: &Cli,
@@ -234,8 +808,10 @@ pub(crate) fn cmd_write(
     loaderboot: &PathBuf,
     bins: &[(PathBuf, u32)],
     late_baud: bool,
+    allow_large: bool,
     chip: ChipFamily,
-) -> Result<()> {
+) -> Result<RunSummary> {
+    ensure_single_stdin_bin(bins)?;
     if !cli.quiet {
         eprintln!(
             "{} {}",
@@ -266,7 +842,7 @@ pub(crate) fn cmd_write(
                 )
             );
         }
-        let data = std::fs::read(path).with_context(|| {
+        let data = read_binary(path).with_context(|| {
             t!(
                 "error.read_binary",
                 path = path
@@ -287,7 +863,15 @@ pub(crate) fn cmd_write(
         );
     }
 
-    let mut flasher = chip.create_flasher(&port, effective_baud, late_baud, cli.verbose)?;
+    let mut flasher = create_flasher(cli, chip, &port, effective_baud, late_baud)?;
+    flasher.set_skip_loaderboot(
+        config
+            .flash
+            .skip_loaderboot,
+    );
+    if allow_large {
+        flasher.set_max_partition_bytes(None);
+    }
     if let Err(err) = ensure_not_interrupted() {
         flasher.close();
         return Err(err);
@@ -314,7 +898,7 @@ pub(crate) fn cmd_write(
         .collect();
     if let Err(err) = flasher.write_bins(&lb_data, &bins_ref) {
         flasher.close();
-        return Err(err.into());
+        return Err(flash_error(err));
     }
 
     if let Err(err) = ensure_not_interrupted() {
@@ -338,10 +922,69 @@ pub(crate) fn cmd_write(
         );
     }
 
-    Ok(())
+    Ok(RunSummary {
+        partitions: bin_data.len() + 1,
+        bytes: lb_data.len() as u64
+            + bin_data
+                .iter()
+                .map(|(data, _)| data.len() as u64)
+                .sum::<u64>(),
+    })
+}
+
+/// Write command implementation for `--manifest`.
+///
+/// Builds a package in memory from a `manifest.toml` plus partition images
+/// (see [`FwpkgBuilder::from_manifest`]) and flashes it like [`cmd_flash`]
+/// would, without writing an intermediate `.fwpkg`.
+pub(crate) fn cmd_write_manifest(
+    cli: &Cli,
+    config: &mut Config,
+    dir: &Path,
+    late_baud: bool,
+    allow_large: bool,
+    chip: ChipFamily,
+) -> Result<RunSummary> {
+    if !cli.quiet {
+        eprintln!(
+            "{} {}",
+            style("📦").cyan(),
+            t!("write.loading_manifest", path = dir.display())
+        );
+    }
+
+    let fwpkg = FwpkgBuilder::from_manifest(dir)
+        .and_then(Fwpkg::from_bytes)
+        .with_context(|| {
+            t!(
+                "error.load_manifest",
+                path = dir
+                    .display()
+                    .to_string()
+            )
+        })?;
+    verify_partitions(&fwpkg)?;
+
+    let outcome = flash_loaded_fwpkg(
+        cli,
+        config,
+        &fwpkg,
+        None,
+        late_baud,
+        chip,
+        false,
+        false,
+        None,
+        false,
+        allow_large,
+        false,
+        None,
+    )?;
+    Ok(outcome.summary)
 }
 
 /// Write program command implementation.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn cmd_write_program(
     cli: &Cli,
     config: &mut Config,
@@ -349,14 +992,16 @@ pub(crate) fn cmd_write_program(
     program: PathBuf,
     address: u32,
     late_baud: bool,
+    allow_large: bool,
     chip: ChipFamily,
-) -> Result<()> {
+) -> Result<RunSummary> {
     cmd_write(
         cli,
         config,
         loaderboot,
         &[(program, address)],
         late_baud,
+        allow_large,
         chip,
     )
 }
@@ -380,7 +1025,7 @@ pub(crate) fn cmd_erase(cli: &Cli, config: &mut Config, all: bool, chip: ChipFam
         );
     }
 
-    let mut flasher = chip.create_flasher(&port, effective_baud, false, cli.verbose)?;
+    let mut flasher = create_flasher(cli, chip, &port, effective_baud, false)?;
     if let Err(err) = ensure_not_interrupted() {
         flasher.close();
         return Err(err);
@@ -404,6 +1049,8 @@ pub(crate) fn cmd_erase(cli: &Cli, config: &mut Config, all: bool, chip: ChipFam
     if !cli.quiet {
         eprintln!("{} {}", style("🗑").red(), t!("erase.erasing"));
     }
+    // `--all` is the explicit confirmation for the full erase.
+    flasher.arm_destructive();
     if let Err(err) = flasher.erase_all() {
         flasher.close();
         return Err(err.into());
@@ -427,3 +1074,415 @@ pub(crate) fn cmd_erase(cli: &Cli, config: &mut Config, all: bool, chip: ChipFam
 
     Ok(())
 }
+
+/// OTP/eFuse write command implementation.
+///
+/// `confirmed` is `--i-understand-otp-is-permanent`; without it nothing is
+/// read or sent.
+pub(crate) fn cmd_write_otp(
+    cli: &Cli,
+    config: &mut Config,
+    loaderboot: &Path,
+    otp_file: &PathBuf,
+    confirmed: bool,
+    chip: ChipFamily,
+) -> Result<()> {
+    if !confirmed {
+        return Err(CliError::Usage(t!("write_otp.need_confirm_flag").to_string()).into());
+    }
+
+    let data = std::fs::read(otp_file).with_context(|| {
+        t!(
+            "error.read_binary",
+            path = otp_file
+                .display()
+                .to_string()
+        )
+    })?;
+
+    with_loaderboot(cli, config, loaderboot, false, chip, |flasher| {
+        write_otp(cli, flasher, &data)
+    })?;
+
+    if !cli.quiet {
+        eprintln!(
+            "\n{} {}",
+            style("✓")
+                .green()
+                .bold(),
+            t!("write_otp.completed")
+        );
+    }
+    Ok(())
+}
+
+/// Program the OTP/eFuse data over a running LoaderBoot.
+fn write_otp(cli: &Cli, flasher: &mut dyn Flasher, data: &[u8]) -> Result<()> {
+    if !cli.quiet {
+        eprintln!(
+            "{} {}",
+            style("🔥").red(),
+            t!("write_otp.writing", size = data.len())
+        );
+    }
+    // `--i-understand-otp-is-permanent` is the explicit confirmation.
+    flasher.write_otp(data, OtpConfirmation::i_understand_otp_is_permanent())?;
+    Ok(())
+}
+
+/// Region write command implementation.
+///
+/// Writes `file` at `address` without touching the rest of the partition.
+pub(crate) fn cmd_write_region(
+    cli: &Cli,
+    config: &mut Config,
+    loaderboot: &Path,
+    file: &Path,
+    address: u32,
+    late_baud: bool,
+    chip: ChipFamily,
+) -> Result<RunSummary> {
+    if !cli.quiet {
+        eprintln!(
+            "{} {}",
+            style("📦").cyan(),
+            t!(
+                "write.loading_binary",
+                path = file.display(),
+                addr = format!("{address:08X}")
+            )
+        );
+    }
+    let data = read_binary(file).with_context(|| {
+        t!(
+            "error.read_binary",
+            path = file
+                .display()
+                .to_string()
+        )
+    })?;
+    if data.is_empty() {
+        return Err(
+            CliError::Usage(t!("write_region.empty", path = file.display()).to_string()).into(),
+        );
+    }
+    let (len, (first, erase_size)) = u32::try_from(data.len())
+        .ok()
+        .and_then(|len| ws63::layout::region_erase_range(address, len).map(|range| (len, range)))
+        .ok_or_else(|| {
+            CliError::Usage(
+                t!(
+                    "write_region.out_of_range",
+                    size = format_size(data.len() as u64),
+                    addr = format!("{address:08X}")
+                )
+                .to_string(),
+            )
+        })?;
+    if (first, erase_size) != (address, len) && !cli.quiet {
+        let end = u64::from(first) + u64::from(erase_size);
+        eprintln!(
+            "{} {}",
+            style("⚠").yellow(),
+            t!(
+                "write_region.unaligned",
+                first = format!("{first:08X}"),
+                end = format!("{end:08X}")
+            )
+        );
+    }
+
+    with_loaderboot(cli, config, loaderboot, late_baud, chip, |flasher| {
+        write_region(cli, flasher, &data, address)
+    })?;
+
+    if !cli.quiet {
+        eprintln!(
+            "\n{} {}",
+            style("🎉")
+                .green()
+                .bold(),
+            t!("write_region.completed")
+        );
+    }
+
+    Ok(RunSummary {
+        partitions: 1,
+        bytes: data.len() as u64,
+    })
+}
+
+/// Write the region over a running LoaderBoot and reset.
+fn write_region(cli: &Cli, flasher: &mut dyn Flasher, data: &[u8], address: u32) -> Result<()> {
+    if !cli.quiet {
+        eprintln!(
+            "{} {}",
+            style("✍").cyan(),
+            t!(
+                "write_region.writing",
+                size = format_size(data.len() as u64),
+                addr = format!("{address:08X}")
+            )
+        );
+    }
+    flasher.write_at(address, data, &mut |_, _, _| {})?;
+    ensure_not_interrupted()?;
+
+    flasher.reset()?;
+    Ok(())
+}
+
+/// DFU command implementation.
+///
+/// Connects and switches the device to DFU mode. The device then stays in
+/// DFU mode, no longer speaking the serial download protocol, until it is
+/// reset, so no reset is sent here.
+pub(crate) fn cmd_dfu(cli: &Cli, config: &mut Config, chip: ChipFamily) -> Result<()> {
+    let port = get_port(cli, config)?;
+    let effective_baud = crate::resolve_effective_baud(cli.baud, chip);
+    if !cli.quiet {
+        eprintln!(
+            "{} {}",
+            style("🔌").cyan(),
+            t!("common.using_port", port = port, baud = effective_baud)
+        );
+    }
+
+    let mut flasher = create_flasher(cli, chip, &port, effective_baud, false)?;
+    if let Err(err) = ensure_not_interrupted() {
+        flasher.close();
+        return Err(err);
+    }
+
+    if !cli.quiet {
+        eprintln!("{} {}", style("⏳").yellow(), t!("common.waiting_device"));
+    }
+    if let Err(err) = flasher.connect() {
+        flasher.close();
+        return Err(err.into());
+    }
+    if let Err(err) = ensure_not_interrupted() {
+        flasher.close();
+        return Err(err);
+    }
+    if !cli.quiet {
+        eprintln!("{} {}", style("✓").green(), t!("common.connected"));
+        eprintln!("{} {}", style("🔀").cyan(), t!("dfu.switching"));
+    }
+    if let Err(err) = flasher.switch_to_dfu() {
+        flasher.close();
+        return Err(err.into());
+    }
+    flasher.close();
+
+    if !cli.quiet {
+        eprintln!(
+            "\n{} {}",
+            style("✓")
+                .green()
+                .bold(),
+            t!("dfu.completed")
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        hisiflash::{FwpkgBinInfo, FwpkgBuilder, FwpkgVersion},
+    };
+
+    #[test]
+    fn test_parse_filter_mixes_names_and_types() {
+        let filters = parse_filter(Some(&"app,type:security".to_string()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            filters,
+            vec![
+                PartitionFilter::Name("app".into()),
+                PartitionFilter::Type(vec![
+                    PartitionType::SecurityA,
+                    PartitionType::SecurityB,
+                    PartitionType::SecurityC,
+                ]),
+            ]
+        );
+        assert!(
+            parse_filter(None)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_check_handshake_baud() {
+        assert!(check_handshake_baud(ChipFamily::Ws63, 9600).is_ok());
+        assert!(check_handshake_baud(ChipFamily::Ws63, 115_200).is_ok());
+        let err = check_handshake_baud(ChipFamily::Ws63, 12_345).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CliError>(),
+            Some(CliError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_named_partitions_uses_ws63_layout() {
+        let named = [
+            ("app".to_string(), PathBuf::from("app.bin")),
+            ("NV".to_string(), PathBuf::from("nv.bin")),
+        ];
+        let bins = resolve_named_partitions(ChipFamily::Ws63, &named).unwrap();
+        assert_eq!(
+            bins,
+            [
+                (PathBuf::from("app.bin"), 0x0023_0000),
+                (PathBuf::from("nv.bin"), 0x005F_C000),
+            ]
+        );
+
+        let custom = [("custom".to_string(), PathBuf::from("custom.bin"))];
+        let err = resolve_named_partitions(ChipFamily::Ws63, &custom).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CliError>(),
+            Some(CliError::Usage(_))
+        ));
+        assert!(resolve_named_partitions(ChipFamily::Bs2x, &named).is_err());
+        assert!(
+            resolve_named_partitions(ChipFamily::Bs2x, &[])
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_excluded_security_partitions() {
+        let bin = |name: &str, offset, partition_type| FwpkgBinInfo {
+            name: name.into(),
+            offset,
+            length: 0,
+            burn_addr: 0,
+            burn_size: 4,
+            partition_type,
+            reserved: [0; 4],
+        };
+        let bytes = FwpkgBuilder::new(FwpkgVersion::V1)
+            .with_bin(bin("loaderboot", 200, PartitionType::Loader), vec![0; 4])
+            .with_bin(bin("root_sec", 204, PartitionType::SecurityA), vec![0; 4])
+            .with_bin(bin("app", 208, PartitionType::Normal), vec![0; 4])
+            .build()
+            .unwrap();
+        let fwpkg = Fwpkg::from_bytes(bytes).unwrap();
+
+        let app_only = parse_filter(Some(&"app".to_string()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            excluded_security_partitions(&fwpkg, &app_only),
+            ["root_sec"]
+        );
+
+        let with_security = parse_filter(Some(&"app,type:security".to_string()))
+            .unwrap()
+            .unwrap();
+        assert!(excluded_security_partitions(&fwpkg, &with_security).is_empty());
+    }
+
+    #[test]
+    fn test_split_coupled_groups() {
+        let bin = |name: &str, offset, partition_type| FwpkgBinInfo {
+            name: name.into(),
+            offset,
+            length: 0,
+            burn_addr: 0,
+            burn_size: 4,
+            partition_type,
+            reserved: [0; 4],
+        };
+        let bytes = FwpkgBuilder::new(FwpkgVersion::V1)
+            .with_bin(bin("loaderboot", 300, PartitionType::Loader), vec![0; 4])
+            .with_bin(bin("root_sec", 304, PartitionType::SecurityA), vec![0; 4])
+            .with_bin(bin("params_sec", 308, PartitionType::SecurityB), vec![0; 4])
+            .with_bin(bin("app", 312, PartitionType::Normal), vec![0; 4])
+            .build()
+            .unwrap();
+        let fwpkg = Fwpkg::from_bytes(bytes).unwrap();
+        let groups = ChipFamily::Ws63.coupled_partitions();
+        let split = |filter: &str| {
+            let filters = parse_filter(Some(&filter.to_string()))
+                .unwrap()
+                .unwrap();
+            split_coupled_groups(&fwpkg, &filters, groups)
+                .into_iter()
+                .map(|group| (group.selected, group.left_out))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            split("app,type:security_a"),
+            [(vec!["root_sec"], vec!["params_sec"])]
+        );
+        assert!(split("app").is_empty());
+        assert!(split("type:security").is_empty());
+
+        let filters = parse_filter(Some(&"root_sec".to_string()))
+            .unwrap()
+            .unwrap();
+        let split = split_coupled_groups(&fwpkg, &filters, groups);
+        assert!(check_split_groups(false, &split).is_err());
+        assert!(check_split_groups(true, &split).is_ok());
+    }
+
+    #[test]
+    fn test_missing_expected_partitions() {
+        let info = FwpkgBinInfo {
+            name: "loaderboot".into(),
+            offset: 100,
+            length: 0,
+            burn_addr: 0,
+            burn_size: 4,
+            partition_type: PartitionType::Loader,
+            reserved: [0; 4],
+        };
+        let bytes = FwpkgBuilder::new(FwpkgVersion::V1)
+            .with_bin(info, vec![0; 4])
+            .build()
+            .unwrap();
+        let fwpkg = Fwpkg::from_bytes(bytes).unwrap();
+
+        assert!(missing_expected_partitions(&fwpkg, "loaderboot").is_empty());
+        assert_eq!(
+            missing_expected_partitions(&fwpkg, "loaderboot, app_a,,app_b"),
+            ["app_a", "app_b"]
+        );
+    }
+
+    #[test]
+    fn test_flash_error_hints_allow_large() {
+        let err = flash_error(LibError::PartitionTooLarge {
+            name: "app".into(),
+            size: 2,
+            limit: 1,
+        });
+        assert!(
+            err.to_string()
+                .contains("--allow-large")
+        );
+        assert!(matches!(
+            err.downcast_ref::<LibError>(),
+            Some(LibError::PartitionTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_filter_rejects_unknown_type() {
+        let err = parse_filter(Some(&"type:nope".to_string())).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CliError>(),
+            Some(CliError::Usage(_))
+        ));
+    }
+}
@@ -1,37 +1,31 @@
 //! Firmware info and port listing command implementations.
 
 use {
+    crate::util::format_size,
     anyhow::{Context, Result},
     console::style,
-    hisiflash::{Fwpkg, FwpkgVersion, PartitionType, auto_detect_port, discover_ports},
+    hisiflash::{
+        Fwpkg, FwpkgBinInfo, FwpkgVersion, PartitionType, PortListing, auto_detect_port,
+        detect_port_listings,
+        image::fwpkg::{NAME_SIZE_V1, NAME_SIZE_V2},
+    },
     rust_i18n::t,
-    std::path::PathBuf,
+    std::{collections::BTreeMap, path::PathBuf},
 };
 
 /// List ports command implementation.
-pub(crate) fn cmd_list_ports(json: bool) -> Result<()> {
-    let detected = discover_ports();
+pub(crate) fn cmd_list_ports(json: bool, all: bool) -> Result<()> {
+    // Only USB ports by default; built-in UARTs are never HiSilicon boards.
+    let (detected, hidden): (Vec<PortListing>, Vec<PortListing>) = detect_port_listings()
+        .into_iter()
+        .partition(|p| all || p.is_usb());
 
     if json {
-        let ports: Vec<serde_json::Value> = detected
-            .iter()
-            .map(|p| {
-                serde_json::json!({
-                    "name": p.name,
-                    "device": p.device.name(),
-                    "known": p.device.is_known(),
-                    "vid": p.vid,
-                    "pid": p.pid,
-                    "manufacturer": p.manufacturer,
-                    "product": p.product,
-                    "serial": p.serial,
-                })
-            })
-            .collect();
         let output = serde_json::json!({
             "ok": true,
             "data": {
-                "ports": ports,
+                "ports": detected,
+                "hidden_non_usb": hidden.len(),
             }
         });
         println!("{}", serde_json::to_string_pretty(&output)?);
@@ -45,22 +39,19 @@ pub(crate) fn cmd_list_ports(json: bool) -> Result<()> {
             .underlined()
     );
 
+    if !hidden.is_empty() {
+        eprintln!(
+            "  {}",
+            style(t!("list_ports.hidden_non_usb", count = hidden.len())).dim()
+        );
+    }
+
     if detected.is_empty() {
         eprintln!("  {}", style(t!("list_ports.no_ports")).dim());
     } else {
         for port in &detected {
-            let device_type = if port
-                .device
-                .is_known()
-            {
-                format!(
-                    " [{}]",
-                    style(
-                        port.device
-                            .name()
-                    )
-                    .yellow()
-                )
+            let device_type = if port.known {
+                format!(" [{}]", style(port.device).yellow())
             } else {
                 String::new()
             };
@@ -74,19 +65,37 @@ pub(crate) fn cmd_list_ports(json: bool) -> Result<()> {
             } else {
                 String::new()
             };
+            let location = port
+                .usb_location
+                .as_ref()
+                .map(|location| format!(" @{location}"))
+                .unwrap_or_default();
 
             eprintln!(
-                "  {} {}{}{}{}",
+                "  {} {}{}{}{}{}",
                 style("•").green(),
                 style(&port.name).cyan(),
                 device_type,
                 vid_pid,
+                location,
                 if !product.is_empty() {
                     format!(" - {}", style(product).dim())
                 } else {
                     String::new()
                 }
             );
+            if let Some(notes) = port.notes {
+                eprintln!(
+                    "      {}",
+                    style(t!(
+                        "list_ports.adapter_note",
+                        vendor = port.vendor,
+                        note = notes
+                    ))
+                    .yellow()
+                    .dim()
+                );
+            }
         }
 
         // Show auto-detection result
@@ -110,10 +119,93 @@ pub(crate) fn cmd_list_ports(json: bool) -> Result<()> {
     Ok(())
 }
 
+/// Run the `--validate` checks, returning the first failure.
+fn validate_fwpkg(fwpkg: &Fwpkg) -> Result<()> {
+    fwpkg
+        .verify_crc()
+        .context(t!("error.crc_failed").to_string())?;
+    fwpkg
+        .verify_all_partition_bounds()
+        .context(t!("error.partition_bounds_failed").to_string())?;
+    Ok(())
+}
+
+/// Load `firmware` and print its partition table, for
+/// `flash --list-partitions`.
+pub(crate) fn cmd_list_partitions(firmware: &PathBuf) -> Result<()> {
+    eprintln!(
+        "{} {}",
+        style("📦").cyan(),
+        t!("flash.loading_firmware", path = firmware.display())
+    );
+
+    let fwpkg = Fwpkg::from_file(firmware).with_context(|| {
+        t!(
+            "error.load_firmware",
+            path = firmware
+                .display()
+                .to_string()
+        )
+    })?;
+
+    print_partition_table(&fwpkg.bins);
+    eprintln!("\n{}", t!("flash.list_partitions_only"));
+    Ok(())
+}
+
+/// Print one block per partition, as shown by `info`.
+fn print_partition_table(bins: &[FwpkgBinInfo]) {
+    eprintln!(
+        "\n{}",
+        style(t!("info.partitions_header"))
+            .bold()
+            .underlined()
+    );
+    for (i, bin) in bins
+        .iter()
+        .enumerate()
+    {
+        let type_str = format_partition_type(bin.partition_type);
+
+        eprintln!(
+            "\n  [{:2}] {}",
+            i,
+            style(&bin.name)
+                .cyan()
+                .bold()
+        );
+        eprintln!("       {}", t!("info.type", "type" = type_str));
+        eprintln!(
+            "       {}",
+            t!("info.offset", offset = format!("{:08X}", bin.offset))
+        );
+        eprintln!(
+            "       {}",
+            t!(
+                "info.length",
+                length = format_size(u64::from(bin.length)),
+                bytes = bin.length
+            )
+        );
+        eprintln!(
+            "       {}",
+            t!("info.burn_addr", addr = format!("{:08X}", bin.burn_addr))
+        );
+        eprintln!(
+            "       {}",
+            t!(
+                "info.burn_size",
+                size = format_size(u64::from(bin.burn_size)),
+                bytes = bin.burn_size
+            )
+        );
+    }
+}
+
 /// Info command implementation.
-pub(crate) fn cmd_info(firmware: &PathBuf, json: bool) -> Result<()> {
+pub(crate) fn cmd_info(firmware: &PathBuf, json: bool, validate: bool, map: bool) -> Result<()> {
     if json {
-        return cmd_info_json(firmware);
+        return cmd_info_json(firmware, validate);
     }
 
     eprintln!(
@@ -139,11 +231,15 @@ pub(crate) fn cmd_info(firmware: &PathBuf, json: bool) -> Result<()> {
     );
 
     // Show format version
-    let version_str = match fwpkg.version() {
-        FwpkgVersion::V1 => "V1 (32-byte names)",
-        FwpkgVersion::V2 => "V2 (260-byte names)",
+    let name_size = match fwpkg.version() {
+        FwpkgVersion::V1 => NAME_SIZE_V1,
+        FwpkgVersion::V2 => NAME_SIZE_V2,
     };
-    eprintln!("  {}: {}", t!("info.format"), version_str);
+    eprintln!(
+        "  {}: {} ({name_size}-byte names)",
+        t!("info.format"),
+        fwpkg.version()
+    );
 
     // Show package name for V2
     if !fwpkg
@@ -161,11 +257,24 @@ pub(crate) fn cmd_info(firmware: &PathBuf, json: bool) -> Result<()> {
         "  {}",
         t!(
             "info.total_size",
-            size = fwpkg
+            size = format_size(u64::from(
+                fwpkg
+                    .header
+                    .len
+            )),
+            bytes = fwpkg
                 .header
                 .len
         )
     );
+    eprintln!(
+        "  {}",
+        t!(
+            "info.flash_usage",
+            size = format_size(fwpkg.total_flash_bytes()),
+            breakdown = flash_usage_breakdown(&fwpkg.size_by_type())
+        )
+    );
     eprintln!(
         "  {}",
         t!(
@@ -190,45 +299,205 @@ pub(crate) fn cmd_info(firmware: &PathBuf, json: bool) -> Result<()> {
             t!("info.crc_valid", status = t!("info.no").to_string())
         ),
     }
+    let bounds_status = if fwpkg
+        .verify_all_partition_bounds()
+        .is_ok()
+    {
+        t!("info.yes")
+    } else {
+        t!("info.no")
+    };
+    eprintln!(
+        "  {}",
+        t!("info.bounds_valid", status = bounds_status.to_string())
+    );
+
+    print_partition_table(&fwpkg.bins);
+
+    if map {
+        print_partition_map(&fwpkg.bins);
+    }
+
+    if validate {
+        validate_fwpkg(&fwpkg)?;
+        for name in fwpkg.duplicate_names() {
+            eprintln!(
+                "{} {}",
+                style("⚠")
+                    .yellow()
+                    .bold(),
+                style(t!("info.duplicate_name", name = name))
+                    .yellow()
+                    .bold()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// List the flash used per partition type, largest first, as
+/// `Normal: 1.0 MiB, Loader: 32.0 KiB`.
+fn flash_usage_breakdown(sizes: &BTreeMap<PartitionType, u64>) -> String {
+    let mut sizes: Vec<(PartitionType, u64)> = sizes
+        .iter()
+        .map(|(&pt, &size)| (pt, size))
+        .collect();
+    sizes.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+    });
+    sizes
+        .iter()
+        .map(|(pt, size)| format!("{pt}: {}", format_size(*size)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Width of the size bar in the `--map` output, in characters.
+const MAP_BAR_WIDTH: u64 = 32;
+
+/// One row of the flash address map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MapRow<'a> {
+    /// A partition occupying `[start, end)`.
+    Partition {
+        name: &'a str,
+        partition_type: PartitionType,
+        start: u64,
+        end: u64,
+    },
+    /// Unused flash between two partitions.
+    Gap { start: u64, end: u64 },
+    /// Bytes claimed by the previous partition as well as the next one.
+    Overlap { start: u64, end: u64 },
+}
+
+impl MapRow<'_> {
+    fn range(&self) -> (u64, u64) {
+        match *self {
+            Self::Partition { start, end, .. }
+            | Self::Gap { start, end }
+            | Self::Overlap { start, end } => (start, end),
+        }
+    }
+}
+
+/// Lay out partitions in flash-address order, inserting gap and overlap rows.
+///
+/// The map covers `burn_addr..burn_addr + burn_size` of every partition; the
+/// LoaderBoot is included since it also has a burn address in the package.
+fn partition_map_rows(bins: &[FwpkgBinInfo]) -> Vec<MapRow<'_>> {
+    let mut sorted: Vec<&FwpkgBinInfo> = bins
+        .iter()
+        .collect();
+    sorted.sort_by_key(|bin| (bin.burn_addr, bin.burn_size));
+
+    let mut rows = Vec::with_capacity(sorted.len() * 2);
+    let mut cursor: Option<u64> = None;
+    for bin in sorted {
+        let start = u64::from(bin.burn_addr);
+        let end = start + u64::from(bin.burn_size);
+        if let Some(prev_end) = cursor {
+            if start > prev_end {
+                rows.push(MapRow::Gap {
+                    start: prev_end,
+                    end: start,
+                });
+            } else if start < prev_end {
+                rows.push(MapRow::Overlap {
+                    start,
+                    end: prev_end.min(end),
+                });
+            }
+        }
+        rows.push(MapRow::Partition {
+            name: &bin.name,
+            partition_type: bin.partition_type,
+            start,
+            end,
+        });
+        cursor = Some(cursor.map_or(end, |prev_end| prev_end.max(end)));
+    }
+    rows
+}
+
+/// Scale `size` against `largest` into a bar of at most [`MAP_BAR_WIDTH`]
+/// characters. Non-empty ranges always get at least one character.
+fn map_bar(size: u64, largest: u64, fill: char) -> String {
+    if size == 0 || largest == 0 {
+        return String::new();
+    }
+    let width = (size * MAP_BAR_WIDTH)
+        .div_ceil(largest)
+        .clamp(1, MAP_BAR_WIDTH);
+    std::iter::repeat_n(fill, usize::try_from(width).unwrap_or(1)).collect()
+}
+
+/// Print the `--map` flash address layout.
+fn print_partition_map(bins: &[FwpkgBinInfo]) {
+    let rows = partition_map_rows(bins);
+    let largest = rows
+        .iter()
+        .map(|row| {
+            let (start, end) = row.range();
+            end - start
+        })
+        .max()
+        .unwrap_or(0);
 
     eprintln!(
         "\n{}",
-        style(t!("info.partitions_header"))
+        style(t!("info.map_header"))
             .bold()
             .underlined()
     );
-    for (i, bin) in fwpkg
-        .bins
-        .iter()
-        .enumerate()
-    {
-        let type_str = format_partition_type(bin.partition_type);
-
-        eprintln!(
-            "\n  [{:2}] {}",
-            i,
-            style(&bin.name)
-                .cyan()
-                .bold()
-        );
-        eprintln!("       {}", t!("info.type", "type" = type_str));
-        eprintln!(
-            "       {}",
-            t!("info.offset", offset = format!("{:08X}", bin.offset))
+    for row in &rows {
+        let (start, end) = row.range();
+        let size = end - start;
+        let range = format!(
+            "{start:08X}-{:08X}",
+            end.saturating_sub(1)
+                .max(start)
         );
-        eprintln!("       {}", t!("info.length", length = bin.length));
-        eprintln!(
-            "       {}",
-            t!("info.burn_addr", addr = format!("{:08X}", bin.burn_addr))
-        );
-        eprintln!("       {}", t!("info.burn_size", size = bin.burn_size));
+        let (label, bar) = match *row {
+            MapRow::Partition {
+                name,
+                partition_type,
+                ..
+            } => (
+                format!(
+                    "{} ({})",
+                    style(name).cyan(),
+                    format_partition_type(partition_type)
+                ),
+                style(map_bar(size, largest, '█'))
+                    .green()
+                    .to_string(),
+            ),
+            MapRow::Gap { .. } => (
+                style(t!("info.map_gap"))
+                    .dim()
+                    .to_string(),
+                style(map_bar(size, largest, '░'))
+                    .dim()
+                    .to_string(),
+            ),
+            MapRow::Overlap { .. } => (
+                style(t!("info.map_overlap"))
+                    .red()
+                    .bold()
+                    .to_string(),
+                style(map_bar(size, largest, '▓'))
+                    .red()
+                    .to_string(),
+            ),
+        };
+        eprintln!("  {range}  {:>10}  {bar} {label}", format_size(size));
     }
-
-    Ok(())
 }
 
 /// Info command `--json` output: structured JSON to stdout.
-fn cmd_info_json(firmware: &PathBuf) -> Result<()> {
+fn cmd_info_json(firmware: &PathBuf, validate: bool) -> Result<()> {
     let fwpkg = Fwpkg::from_file(firmware).with_context(|| {
         t!(
             "error.load_firmware",
@@ -238,14 +507,16 @@ fn cmd_info_json(firmware: &PathBuf) -> Result<()> {
         )
     })?;
 
-    let version_str = match fwpkg.version() {
-        FwpkgVersion::V1 => "V1",
-        FwpkgVersion::V2 => "V2",
-    };
+    if validate {
+        validate_fwpkg(&fwpkg)?;
+    }
 
     let crc_valid = fwpkg
         .verify_crc()
         .is_ok();
+    let bounds_valid = fwpkg
+        .verify_all_partition_bounds()
+        .is_ok();
 
     let partitions: Vec<serde_json::Value> = fwpkg
         .bins
@@ -263,15 +534,26 @@ fn cmd_info_json(firmware: &PathBuf) -> Result<()> {
         })
         .collect();
 
+    let mut flash_by_type: BTreeMap<String, u64> = BTreeMap::new();
+    for (pt, size) in fwpkg.size_by_type() {
+        *flash_by_type
+            .entry(partition_type_str(pt))
+            .or_insert(0) += size;
+    }
+
     let info = serde_json::json!({
         "ok": true,
         "data": {
-            "format": version_str,
+            "format": fwpkg.version().to_string(),
             "package_name": fwpkg.package_name(),
             "partition_count": fwpkg.partition_count(),
             "total_size": fwpkg.header.len,
+            "total_flash_bytes": fwpkg.total_flash_bytes(),
+            "flash_by_type": flash_by_type,
             "crc": format!("0x{:04X}", fwpkg.header.crc),
             "crc_valid": crc_valid,
+            "bounds_valid": bounds_valid,
+            "duplicate_names": fwpkg.duplicate_names(),
             "partitions": partitions,
         }
     });
@@ -281,70 +563,40 @@ fn cmd_info_json(firmware: &PathBuf) -> Result<()> {
 }
 
 /// Format partition type as a plain string (no ANSI colors) for JSON output.
-pub(crate) fn partition_type_str(pt: PartitionType) -> &'static str {
+///
+/// Unknown types are reported as plain `Unknown` to keep the JSON contract
+/// stable; the numeric value is only shown in human-readable output.
+pub(crate) fn partition_type_str(pt: PartitionType) -> String {
     match pt {
-        PartitionType::Loader => "Loader",
-        PartitionType::Normal => "Normal",
-        PartitionType::KvNv => "KV-NV",
-        PartitionType::Efuse => "eFuse",
-        PartitionType::Otp => "OTP",
-        PartitionType::Flashboot => "FlashBoot",
-        PartitionType::Factory => "Factory",
-        PartitionType::Version => "Version",
-        PartitionType::SecurityA => "Security-A",
-        PartitionType::SecurityB => "Security-B",
-        PartitionType::SecurityC => "Security-C",
-        PartitionType::ProtocolA => "Protocol-A",
-        PartitionType::AppsA => "Apps-A",
-        PartitionType::RadioConfig => "RadioConfig",
-        PartitionType::Rom => "ROM",
-        PartitionType::Emmc => "eMMC",
-        PartitionType::Database => "Database",
-        PartitionType::Unknown(_) => "Unknown",
+        PartitionType::Unknown(_) => "Unknown".to_string(),
+        pt => pt.to_string(),
     }
 }
 
 /// Format partition type for display (with ANSI colors).
 pub(crate) fn format_partition_type(pt: PartitionType) -> String {
+    let name = pt.to_string();
     match pt {
-        PartitionType::Loader => style("Loader")
+        PartitionType::Loader | PartitionType::Flashboot => style(name)
             .yellow()
             .to_string(),
-        PartitionType::Normal => "Normal".to_string(),
-        PartitionType::KvNv => style("KV-NV")
+        PartitionType::KvNv => style(name)
             .magenta()
             .to_string(),
-        PartitionType::Efuse => style("eFuse")
+        PartitionType::Efuse
+        | PartitionType::Otp
+        | PartitionType::SecurityA
+        | PartitionType::SecurityB
+        | PartitionType::SecurityC => style(name)
             .red()
             .to_string(),
-        PartitionType::Otp => style("OTP")
-            .red()
-            .to_string(),
-        PartitionType::Flashboot => style("FlashBoot")
-            .yellow()
-            .to_string(),
-        PartitionType::Factory => style("Factory")
+        PartitionType::Factory => style(name)
             .blue()
             .to_string(),
-        PartitionType::Version => "Version".to_string(),
-        PartitionType::SecurityA => style("Security-A")
-            .red()
-            .to_string(),
-        PartitionType::SecurityB => style("Security-B")
-            .red()
-            .to_string(),
-        PartitionType::SecurityC => style("Security-C")
-            .red()
-            .to_string(),
-        PartitionType::ProtocolA => "Protocol-A".to_string(),
-        PartitionType::AppsA => "Apps-A".to_string(),
-        PartitionType::RadioConfig => "RadioConfig".to_string(),
-        PartitionType::Rom => "ROM".to_string(),
-        PartitionType::Emmc => "eMMC".to_string(),
-        PartitionType::Database => style("Database")
+        PartitionType::Database => style(name)
             .dim()
             .to_string(),
-        PartitionType::Unknown(v) => format!("Unknown({v})"),
+        _ => name,
     }
 }
 
@@ -352,8 +604,87 @@ pub(crate) fn format_partition_type(pt: PartitionType) -> String {
 mod tests {
     use {super::*, hisiflash::PartitionType};
 
+    fn bin(name: &str, burn_addr: u32, burn_size: u32) -> FwpkgBinInfo {
+        FwpkgBinInfo {
+            name: name.to_string(),
+            offset: 0,
+            length: burn_size,
+            burn_addr,
+            burn_size,
+            partition_type: PartitionType::Normal,
+            reserved: [0; 4],
+        }
+    }
+
+    // ---- partition map ----
+
+    #[test]
+    fn test_partition_map_rows_sorted_with_gaps() {
+        let bins = vec![
+            bin("app", 0x0004_0000, 0x1000),
+            bin("boot", 0x0000_0000, 0x2000),
+            bin("nv", 0x0000_2000, 0x1000),
+        ];
+        let rows = partition_map_rows(&bins);
+        let names: Vec<String> = rows
+            .iter()
+            .map(|row| match row {
+                MapRow::Partition { name, .. } => (*name).to_string(),
+                MapRow::Gap { start, end } => format!("gap {start:X}-{end:X}"),
+                MapRow::Overlap { start, end } => format!("overlap {start:X}-{end:X}"),
+            })
+            .collect();
+        assert_eq!(names, ["boot", "nv", "gap 3000-40000", "app"]);
+    }
+
+    #[test]
+    fn test_partition_map_rows_reports_overlap() {
+        let bins = vec![bin("a", 0x0, 0x2000), bin("b", 0x1000, 0x2000)];
+        let rows = partition_map_rows(&bins);
+        assert_eq!(
+            rows[1],
+            MapRow::Overlap {
+                start: 0x1000,
+                end: 0x2000,
+            }
+        );
+        assert_eq!(rows.len(), 3);
+    }
+
+    #[test]
+    fn test_map_bar_scales_and_keeps_small_ranges_visible() {
+        assert_eq!(
+            map_bar(100, 100, '#')
+                .chars()
+                .count(),
+            32
+        );
+        assert_eq!(
+            map_bar(50, 100, '#')
+                .chars()
+                .count(),
+            16
+        );
+        assert_eq!(map_bar(1, 1_000_000, '#'), "#");
+        assert_eq!(map_bar(0, 100, '#'), "");
+    }
+
     // ---- partition_type_str ----
 
+    #[test]
+    fn test_flash_usage_breakdown_lists_largest_first() {
+        let sizes = BTreeMap::from([
+            (PartitionType::Loader, 0x8000),
+            (PartitionType::Normal, 0x10_0000),
+            (PartitionType::Unknown(42), 512),
+        ]);
+        assert_eq!(
+            flash_usage_breakdown(&sizes),
+            "Normal: 1.0 MiB, Loader: 32.0 KiB, Unknown(42): 512 B"
+        );
+        assert_eq!(flash_usage_breakdown(&BTreeMap::new()), "");
+    }
+
     #[test]
     fn test_partition_type_str_all_variants() {
         assert_eq!(partition_type_str(PartitionType::Loader), "Loader");
@@ -2,7 +2,10 @@
 //!
 //! Each subcommand is implemented in its own module for clean separation.
 
+pub(crate) mod bench;
 pub(crate) mod completions;
+pub(crate) mod config;
+pub(crate) mod debug;
 pub(crate) mod firmware;
 pub(crate) mod flash;
 pub(crate) mod info;
@@ -3,20 +3,413 @@
 //! Dual-threaded serial monitor with keyboard input, timestamps, and log file
 //! support.
 
-pub(crate) use hisiflash::{clean_monitor_text, drain_utf8_lossy, format_monitor_output};
+pub(crate) use hisiflash::{clean_monitor_text, drain_utf8_lossy, format_monitor_output_since};
 use {
     crate::{Cli, clear_interrupted_flag, config::Config, get_port, was_interrupted},
     anyhow::{Context, Result},
     console::style,
     hisiflash::MonitorSession,
+    regex::Regex,
     rust_i18n::t,
+    serde::Serialize,
     std::{
         io,
         io::{IsTerminal, Write as _},
         path::PathBuf,
+        time::{Duration, Instant},
     },
 };
 
+/// How received serial data is written to the terminal and log file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MonitorOutput {
+    /// Decode UTF-8 and filter non-printable control characters.
+    Clean,
+    /// Decode UTF-8 but keep control characters.
+    Unfiltered,
+    /// Write received bytes verbatim to stdout: no decoding, line handling or
+    /// timestamps.
+    Raw,
+}
+
+/// Format of the `--log` file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub(crate) enum LogFormat {
+    /// The text shown on the terminal, without timestamps.
+    #[default]
+    Text,
+    /// One JSON object per line, e.g.
+    /// `{"ts":1699999999123,"line":"boot.","source":"device"}`.
+    Jsonl,
+}
+
+/// One line of a [`LogFormat::Jsonl`] log.
+#[derive(Serialize)]
+struct LogRecord<'a> {
+    /// Unix time in milliseconds when the line was completed.
+    ts: u64,
+    line: &'a str,
+    /// `device` for received data, `host` for keyboard input.
+    source: &'a str,
+}
+
+/// Writer for the monitor's `--log` file.
+///
+/// In [`LogFormat::Jsonl`] received text and keyboard input are buffered
+/// separately until a line is complete.
+struct MonitorLog<W: io::Write> {
+    out: W,
+    format: LogFormat,
+    device_line: String,
+    host_line: String,
+}
+
+impl<W: io::Write> MonitorLog<W> {
+    fn new(out: W, format: LogFormat) -> Self {
+        Self {
+            out,
+            format,
+            device_line: String::new(),
+            host_line: String::new(),
+        }
+    }
+
+    fn record(&mut self, ts: u64, line: &str, source: &str) -> io::Result<()> {
+        serde_json::to_writer(&mut self.out, &LogRecord { ts, line, source })
+            .map_err(io::Error::from)?;
+        self.out
+            .write_all(b"\n")
+    }
+
+    /// Log bytes written verbatim to the terminal (`--raw`).
+    fn raw(&mut self, data: &[u8], ts: u64) -> io::Result<()> {
+        match self.format {
+            LogFormat::Text => self
+                .out
+                .write_all(data),
+            LogFormat::Jsonl => self.device(&String::from_utf8_lossy(data), ts),
+        }
+    }
+
+    /// Log text received from the device.
+    fn device(&mut self, text: &str, ts: u64) -> io::Result<()> {
+        if self.format == LogFormat::Text {
+            return self
+                .out
+                .write_all(text.as_bytes());
+        }
+        let mut rest = text;
+        while let Some(end) = rest.find('\n') {
+            let mut line = std::mem::take(&mut self.device_line);
+            line.push_str(&rest[..end]);
+            self.record(ts, line.trim_end_matches('\r'), "device")?;
+            rest = &rest[end + 1..];
+        }
+        self.device_line
+            .push_str(rest);
+        Ok(())
+    }
+
+    /// Log bytes typed on the keyboard and sent to the device.
+    ///
+    /// Only kept in [`LogFormat::Jsonl`]; backspace edits the pending line.
+    fn host(&mut self, bytes: &[u8], ts: u64) -> io::Result<()> {
+        if self.format == LogFormat::Text {
+            return Ok(());
+        }
+        for ch in String::from_utf8_lossy(bytes).chars() {
+            match ch {
+                '\n' => {
+                    let line = std::mem::take(&mut self.host_line);
+                    self.record(ts, &line, "host")?;
+                },
+                '\x08' | '\x7f' => {
+                    self.host_line
+                        .pop();
+                },
+                '\t' => self
+                    .host_line
+                    .push(ch),
+                ch if ch.is_control() => {},
+                ch => self
+                    .host_line
+                    .push(ch),
+            }
+        }
+        Ok(())
+    }
+
+    /// Write out unfinished lines and flush.
+    fn finish(&mut self, ts: u64) -> io::Result<()> {
+        let device_line = std::mem::take(&mut self.device_line);
+        if !device_line.is_empty() {
+            self.record(ts, device_line.trim_end_matches('\r'), "device")?;
+        }
+        let host_line = std::mem::take(&mut self.host_line);
+        if !host_line.is_empty() {
+            self.record(ts, &host_line, "host")?;
+        }
+        self.out
+            .flush()
+    }
+}
+
+/// `--include`/`--exclude` filter on received lines.
+///
+/// Text is held back until its line is complete, then shown only if it
+/// matches `include` (when set) and does not match `exclude`.
+pub(crate) struct LineFilter {
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+    /// Apply the filter to the `--log` file too (`--filter-log`).
+    apply_to_log: bool,
+    pending: String,
+}
+
+impl LineFilter {
+    /// Create a filter; `None` when there is no pattern to apply.
+    pub(crate) fn new(
+        include: Option<Regex>,
+        exclude: Option<Regex>,
+        apply_to_log: bool,
+    ) -> Option<Self> {
+        (include.is_some() || exclude.is_some()).then(|| Self {
+            include,
+            exclude,
+            apply_to_log,
+            pending: String::new(),
+        })
+    }
+
+    /// Whether a complete line (without its line ending) is shown.
+    fn matches(&self, line: &str) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.is_match(line))
+            && !self
+                .exclude
+                .as_ref()
+                .is_some_and(|exclude| exclude.is_match(line))
+    }
+
+    /// Feed received text and return the completed lines that pass, with
+    /// their line endings.
+    fn push(&mut self, text: &str) -> String {
+        let mut shown = String::new();
+        let mut rest = text;
+        while let Some(end) = rest.find('\n') {
+            let mut line = std::mem::take(&mut self.pending);
+            line.push_str(&rest[..=end]);
+            if self.matches(line.trim_end_matches(['\r', '\n'])) {
+                shown.push_str(&line);
+            }
+            rest = &rest[end + 1..];
+        }
+        self.pending
+            .push_str(rest);
+        shown
+    }
+}
+
+/// Text `--local-echo` prints for a key sent to the device.
+///
+/// Backspace erases the previous character on screen.
+fn local_echo_text(code: crossterm::event::KeyCode) -> Option<String> {
+    use crossterm::event::KeyCode;
+
+    match code {
+        KeyCode::Char(c) => Some(c.to_string()),
+        KeyCode::Enter => Some("\r\n".to_string()),
+        KeyCode::Backspace => Some("\x08 \x08".to_string()),
+        KeyCode::Tab => Some("\t".to_string()),
+        _ => None,
+    }
+}
+
+/// Lines kept for Up/Down recall in `--line-input` mode.
+const HISTORY_LIMIT: usize = 100;
+
+/// Local line editor for `--line-input`.
+///
+/// Keystrokes edit a pending line that is drawn after the device's own
+/// output (usually its prompt); the line is only sent on Enter.
+#[derive(Default)]
+struct LineEditor {
+    line: Vec<char>,
+    cursor: usize,
+    history: Vec<String>,
+    /// Index into `history` while browsing it with Up/Down.
+    history_pos: Option<usize>,
+    /// The line being typed before history browsing started.
+    draft: Vec<char>,
+    /// Cursor column, relative to the start of the line, as last drawn.
+    drawn_cursor: usize,
+}
+
+impl LineEditor {
+    /// Apply a key press. Returns the finished line on Enter.
+    fn key(&mut self, code: crossterm::event::KeyCode) -> Option<String> {
+        use crossterm::event::KeyCode;
+
+        match code {
+            KeyCode::Char(c) => {
+                self.line
+                    .insert(self.cursor, c);
+                self.cursor += 1;
+            },
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.line
+                    .remove(self.cursor);
+            },
+            KeyCode::Delete
+                if self.cursor
+                    < self
+                        .line
+                        .len() =>
+            {
+                self.line
+                    .remove(self.cursor);
+            },
+            KeyCode::Left => {
+                self.cursor = self
+                    .cursor
+                    .saturating_sub(1);
+            },
+            KeyCode::Right => {
+                self.cursor = (self.cursor + 1).min(
+                    self.line
+                        .len(),
+                );
+            },
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => {
+                self.cursor = self
+                    .line
+                    .len();
+            },
+            KeyCode::Up => self.history_up(),
+            KeyCode::Down => self.history_down(),
+            KeyCode::Esc => self.replace_line(Vec::new()),
+            KeyCode::Enter => return Some(self.submit()),
+            _ => {},
+        }
+        None
+    }
+
+    fn replace_line(&mut self, line: Vec<char>) {
+        self.line = line;
+        self.cursor = self
+            .line
+            .len();
+    }
+
+    fn history_up(&mut self) {
+        let pos = match self.history_pos {
+            None if self
+                .history
+                .is_empty() =>
+            {
+                return;
+            },
+            None => {
+                self.draft = self
+                    .line
+                    .clone();
+                self.history
+                    .len()
+                    - 1
+            },
+            Some(pos) => pos.saturating_sub(1),
+        };
+        self.history_pos = Some(pos);
+        self.replace_line(
+            self.history[pos]
+                .chars()
+                .collect(),
+        );
+    }
+
+    fn history_down(&mut self) {
+        let Some(pos) = self.history_pos else {
+            return;
+        };
+        if pos + 1
+            < self
+                .history
+                .len()
+        {
+            self.history_pos = Some(pos + 1);
+            self.replace_line(
+                self.history[pos + 1]
+                    .chars()
+                    .collect(),
+            );
+        } else {
+            self.history_pos = None;
+            let draft = std::mem::take(&mut self.draft);
+            self.replace_line(draft);
+        }
+    }
+
+    /// Take the pending line and remember it in the history.
+    fn submit(&mut self) -> String {
+        let line: String = std::mem::take(&mut self.line)
+            .into_iter()
+            .collect();
+        self.cursor = 0;
+        self.history_pos = None;
+        self.draft
+            .clear();
+        if !line.is_empty()
+            && self
+                .history
+                .last()
+                != Some(&line)
+        {
+            if self
+                .history
+                .len()
+                == HISTORY_LIMIT
+            {
+                self.history
+                    .remove(0);
+            }
+            self.history
+                .push(line.clone());
+        }
+        line
+    }
+
+    /// Terminal output that replaces the previously drawn line with the
+    /// current one and places the cursor.
+    fn render(&mut self) -> String {
+        use std::fmt::Write as _;
+
+        let mut out = String::new();
+        if self.drawn_cursor > 0 {
+            let _ = write!(out, "\x1b[{}D", self.drawn_cursor);
+        }
+        out.extend(
+            self.line
+                .iter(),
+        );
+        out.push_str("\x1b[K");
+        let back = self
+            .line
+            .len()
+            - self.cursor;
+        if back > 0 {
+            let _ = write!(out, "\x1b[{back}D");
+        }
+        self.drawn_cursor = self.cursor;
+        out
+    }
+}
+
+/// How long Ctrl+\\ holds the UART break condition.
+const BREAK_DURATION: Duration = Duration::from_millis(250);
+
 fn contains_reset_evidence(text: &str) -> bool {
     let lower = text.to_ascii_lowercase();
     lower.contains("boot.")
@@ -34,14 +427,31 @@ fn contains_reset_evidence(text: &str) -> bool {
 /// - Ctrl+C: graceful exit
 /// - Ctrl+R: reset device (DTR/RTS toggle)
 /// - Ctrl+T: toggle timestamp display
+/// - Ctrl+Space: print a mark line; timestamps then count from the mark
+/// - Ctrl+\\: send a UART break, e.g. to stop a bootloader's autoboot
+///
+/// With `timeout`, the monitor also closes on its own once that much time has
+/// passed, so scripted runs cannot hang. With `no_input`, the keyboard is not
+/// read at all and Ctrl+C exits through the signal handler. With
+/// `line_input`, keystrokes are edited locally (see [`LineEditor`]) and each
+/// line is sent with `\r\n` on Enter. With `local_echo`, keys sent to the
+/// device are also printed, for devices that do not echo. With `line_filter`,
+/// received text is shown line by line, keeping only the lines it passes.
+#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
 pub(crate) fn cmd_monitor(
     cli: &Cli,
     config: &mut Config,
     monitor_port_override: Option<&str>,
     monitor_baud: u32,
     timestamp: bool,
-    clean_output: bool,
+    output: MonitorOutput,
     log_file: Option<&PathBuf>,
+    log_format: LogFormat,
+    timeout: Option<Duration>,
+    no_input: bool,
+    line_input: bool,
+    local_echo: bool,
+    line_filter: Option<LineFilter>,
 ) -> Result<()> {
     let port_name = if let Some(port) = monitor_port_override {
         port.to_string()
@@ -58,12 +468,23 @@ pub(crate) fn cmd_monitor(
         &port_name,
         monitor_baud,
         timestamp,
-        clean_output,
+        output,
         log_file,
+        log_format,
         false,
+        timeout,
+        no_input,
+        line_input,
+        local_echo,
+        line_filter,
     )
 }
 
+/// Whether a monitor started at `started` has run past its `timeout`.
+fn monitor_timed_out(started: Instant, timeout: Option<Duration>) -> bool {
+    timeout.is_some_and(|timeout| started.elapsed() >= timeout)
+}
+
 /// Run the serial monitor against an already-open [`MonitorSession`].
 ///
 /// Used by the `flash --monitor` handoff path so that the flasher's
@@ -71,14 +492,21 @@ pub(crate) fn cmd_monitor(
 /// chip emits right after reset, which would otherwise be lost in the
 /// close → reopen window). When `handed_over` is true, the opening status
 /// line clarifies that the existing handle is being reused.
+#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
 pub(crate) fn cmd_monitor_with_session(
     session: MonitorSession,
     port_name: &str,
     monitor_baud: u32,
     timestamp: bool,
-    clean_output: bool,
+    output: MonitorOutput,
     log_file: Option<&PathBuf>,
+    log_format: LogFormat,
     handed_over: bool,
+    timeout: Option<Duration>,
+    no_input: bool,
+    line_input: bool,
+    local_echo: bool,
+    line_filter: Option<LineFilter>,
 ) -> Result<()> {
     use {
         crossterm::{
@@ -91,7 +519,7 @@ pub(crate) fn cmd_monitor_with_session(
                 Arc, Mutex,
                 atomic::{AtomicBool, AtomicU64, Ordering},
             },
-            time::{Duration, SystemTime, UNIX_EPOCH},
+            time::{SystemTime, UNIX_EPOCH},
         },
     };
 
@@ -171,11 +599,18 @@ pub(crate) fn cmd_monitor_with_session(
     let running_reader = running.clone();
     let show_timestamp = Arc::new(AtomicBool::new(timestamp));
     let show_timestamp_reader = show_timestamp.clone();
+    let timestamp_origin: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
+    let timestamp_origin_reader = timestamp_origin.clone();
     let force_line_start = Arc::new(AtomicBool::new(false));
     let force_line_start_reader = force_line_start.clone();
+    // Whether the last local echo left the cursor at a line start; taken by
+    // the reader so timestamps line up after echoed input.
+    let echo_line_start: Arc<Mutex<Option<bool>>> = Arc::new(Mutex::new(None));
+    let echo_line_start_reader = echo_line_start.clone();
     let term_lock_reader = term_lock.clone();
     let tty_mode_reader = tty_mode;
-    let clean_output_reader = clean_output;
+    let output_reader = output;
+    let mut line_filter_reader = line_filter;
     let last_rx_millis = Arc::new(AtomicU64::new(0));
     let last_rx_millis_reader = last_rx_millis.clone();
     let reset_evidence_hits = Arc::new(AtomicU64::new(0));
@@ -184,7 +619,7 @@ pub(crate) fn cmd_monitor_with_session(
     let mut user_requested_exit = false;
 
     // Open log file if specified
-    let log_writer: Option<std::sync::Mutex<std::fs::File>> = if let Some(path) = log_file {
+    let log_writer: Option<Arc<Mutex<MonitorLog<std::fs::File>>>> = if let Some(path) = log_file {
         let file = std::fs::OpenOptions::new()
             .create(true)
             .append(true)
@@ -204,10 +639,18 @@ pub(crate) fn cmd_monitor_with_session(
             ),
             tty_mode,
         );
-        Some(std::sync::Mutex::new(file))
+        Some(Arc::new(Mutex::new(MonitorLog::new(file, log_format))))
     } else {
         None
     };
+    let log_writer_reader = log_writer.clone();
+    let log_host = |bytes: &[u8]| {
+        if let Some(ref log) = log_writer {
+            if let Ok(mut log) = log.lock() {
+                let _ = log.host(bytes, now_millis());
+            }
+        }
+    };
 
     // Reader thread: serial → terminal
     let reader_handle = std::thread::spawn(move || {
@@ -226,6 +669,25 @@ pub(crate) fn cmd_monitor_with_session(
                     let data = &buf[..n];
                     last_rx_millis_reader.store(now_millis(), Ordering::Relaxed);
 
+                    if output_reader == MonitorOutput::Raw {
+                        if contains_reset_evidence(&String::from_utf8_lossy(data)) {
+                            reset_evidence_hits_reader.fetch_add(1, Ordering::Relaxed);
+                        }
+                        if let Some(ref log) = log_writer_reader {
+                            if let Ok(mut log) = log.lock() {
+                                let _ = log.raw(data, now_millis());
+                            }
+                        }
+                        if let Ok(_guard) = term_lock_reader.lock() {
+                            let mut stdout = io::stdout().lock();
+                            let _ = stdout.write_all(data);
+                            stdout
+                                .flush()
+                                .ok();
+                        }
+                        continue;
+                    }
+
                     // Append to UTF-8 buffer for handling partial sequences
                     utf8_buf.extend_from_slice(data);
 
@@ -235,13 +697,37 @@ pub(crate) fn cmd_monitor_with_session(
                         reset_evidence_hits_reader.fetch_add(1, Ordering::Relaxed);
                     }
 
-                    let display_text = if clean_output_reader {
+                    let display_text = if output_reader == MonitorOutput::Clean {
                         clean_monitor_text(&decoded)
                     } else {
                         decoded
                     };
 
-                    if !display_text.is_empty() {
+                    let filtered = line_filter_reader
+                        .as_mut()
+                        .map(|filter| filter.push(&display_text));
+                    let shown_text = filtered
+                        .as_deref()
+                        .unwrap_or(&display_text);
+
+                    // Write to log file (no terminal timestamps)
+                    let log_text = if line_filter_reader
+                        .as_ref()
+                        .is_some_and(|filter| filter.apply_to_log)
+                    {
+                        shown_text
+                    } else {
+                        display_text.as_str()
+                    };
+                    if !log_text.is_empty() {
+                        if let Some(ref log) = log_writer_reader {
+                            if let Ok(mut log) = log.lock() {
+                                let _ = log.device(log_text, now_millis());
+                            }
+                        }
+                    }
+
+                    if !shown_text.is_empty() {
                         // [Sensitive] Explicitly force next serial chunk to start at new line
                         // after status/hint output, regardless of device chunk boundaries.
                         if force_line_start_reader.swap(false, Ordering::Relaxed) {
@@ -261,17 +747,26 @@ pub(crate) fn cmd_monitor_with_session(
                             at_line_start = true;
                         }
 
-                        // Write to log file (raw, no timestamps)
-                        if let Some(ref log) = log_writer {
-                            if let Ok(mut f) = log.lock() {
-                                let _ = f.write_all(display_text.as_bytes());
-                            }
+                        if let Some(line_start) = echo_line_start_reader
+                            .lock()
+                            .ok()
+                            .and_then(|mut echoed| echoed.take())
+                        {
+                            at_line_start = line_start;
                         }
 
                         // Process output with optional timestamps
                         let ts_enabled = show_timestamp_reader.load(Ordering::Relaxed);
-                        let output =
-                            format_monitor_output(&display_text, ts_enabled, &mut at_line_start);
+                        let origin = timestamp_origin_reader
+                            .lock()
+                            .ok()
+                            .and_then(|origin| *origin);
+                        let output = format_monitor_output_since(
+                            shown_text,
+                            ts_enabled,
+                            origin,
+                            &mut at_line_start,
+                        );
                         if let Ok(_guard) = term_lock_reader.lock() {
                             if tty_mode_reader {
                                 eprint!("{output}");
@@ -300,18 +795,64 @@ pub(crate) fn cmd_monitor_with_session(
     });
 
     // Enter raw mode for keyboard input
-    terminal::enable_raw_mode().context("Failed to enable raw terminal mode")?;
-
-    // Ensure we restore terminal on exit (even on panic)
-    let _raw_guard = RawModeGuard;
+    let _raw_guard = if no_input {
+        None
+    } else {
+        terminal::enable_raw_mode().context("Failed to enable raw terminal mode")?;
+        // Ensure we restore terminal on exit (even on panic)
+        Some(RawModeGuard)
+    };
 
     // Main thread: keyboard → serial
+    let started = Instant::now();
+    let mut line_editor = line_input.then(LineEditor::default);
+    // Echo goes to the terminal only, as with `--line-input`.
+    let echo_key = |code: KeyCode| {
+        if !local_echo || !tty_mode {
+            return;
+        }
+        let Some(text) = local_echo_text(code) else {
+            return;
+        };
+        if let Ok(_guard) = term_lock.lock() {
+            eprint!("{text}");
+            io::stderr()
+                .flush()
+                .ok();
+            if let Ok(mut echoed) = echo_line_start.lock() {
+                *echoed = Some(text.ends_with('\n'));
+            }
+        }
+    };
     while running.load(Ordering::Relaxed) {
         if was_interrupted() {
             signal_interrupted = true;
             running.store(false, Ordering::Relaxed);
             break;
         }
+        if monitor_timed_out(started, timeout) {
+            running.store(false, Ordering::Relaxed);
+            print_status_line(
+                &term_lock,
+                &format!(
+                    "{} {}",
+                    style("⏱").yellow(),
+                    t!(
+                        "monitor.timed_out",
+                        secs = timeout
+                            .unwrap_or_default()
+                            .as_secs()
+                    )
+                ),
+                tty_mode,
+            );
+            break;
+        }
+
+        if no_input {
+            std::thread::sleep(Duration::from_millis(50));
+            continue;
+        }
 
         // Poll for keyboard events with timeout
         if event::poll(Duration::from_millis(50))? {
@@ -477,23 +1018,93 @@ pub(crate) fn cmd_monitor_with_session(
                             tty_mode,
                         );
                     },
+                    // Ctrl+Space: mark, timestamps count from here
+                    (KeyCode::Char(' '), KeyModifiers::CONTROL) => {
+                        if let Ok(mut origin) = timestamp_origin.lock() {
+                            *origin = Some(Instant::now());
+                        }
+                        force_line_start.store(true, Ordering::Relaxed);
+                        print_status_line(
+                            &term_lock,
+                            &style(t!("monitor.mark"))
+                                .cyan()
+                                .bold()
+                                .to_string(),
+                            tty_mode,
+                        );
+                    },
+                    // Ctrl+\: send a UART break. Terminals deliver it as 0x1C,
+                    // which crossterm reports as Ctrl+4 on Unix.
+                    (KeyCode::Char('\\' | '4'), KeyModifiers::CONTROL) => {
+                        let break_result = (|| -> Result<()> {
+                            serial_writer.set_break(true)?;
+                            std::thread::sleep(BREAK_DURATION);
+                            serial_writer.set_break(false)?;
+                            Ok(())
+                        })();
+                        force_line_start.store(true, Ordering::Relaxed);
+                        let line = match break_result {
+                            Ok(()) => format!(
+                                "{} {}",
+                                style("⏎").cyan(),
+                                t!("monitor.break_sent", ms = BREAK_DURATION.as_millis())
+                            ),
+                            Err(err) => format!(
+                                "{} {}",
+                                style("⚠").yellow(),
+                                t!("monitor.break_failed", error = err.to_string())
+                            ),
+                        };
+                        print_status_line(&term_lock, &line, tty_mode);
+                    },
+                    // Line input: edit locally, send the whole line on Enter
+                    (code, KeyModifiers::NONE | KeyModifiers::SHIFT) if line_editor.is_some() => {
+                        let Some(editor) = line_editor.as_mut() else {
+                            continue;
+                        };
+                        let submitted = editor.key(code);
+                        // Echo goes to the terminal only; piped stdout
+                        // carries device output alone.
+                        if tty_mode {
+                            if let Ok(_guard) = term_lock.lock() {
+                                eprint!("{}", editor.render());
+                                io::stderr()
+                                    .flush()
+                                    .ok();
+                            }
+                        }
+                        if let Some(line) = submitted {
+                            let mut bytes = line.into_bytes();
+                            bytes.extend_from_slice(b"\r\n");
+                            let _ = serial_writer.write_bytes(&bytes);
+                            log_host(&bytes);
+                        }
+                    },
                     // Enter: send \r\n (works with both \n and \r\n devices)
                     (KeyCode::Enter, _) => {
                         let _ = serial_writer.write_bytes(b"\r\n");
+                        log_host(b"\r\n");
+                        echo_key(code);
                     },
                     // Regular character
                     (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
                         let mut buf = [0u8; 4];
                         let bytes = c.encode_utf8(&mut buf);
                         let _ = serial_writer.write_bytes(bytes.as_bytes());
+                        log_host(bytes.as_bytes());
+                        echo_key(code);
                     },
                     // Backspace
                     (KeyCode::Backspace, _) => {
                         let _ = serial_writer.write_bytes(&[0x08]);
+                        log_host(&[0x08]);
+                        echo_key(code);
                     },
                     // Tab
                     (KeyCode::Tab, _) => {
                         let _ = serial_writer.write_bytes(&[0x09]);
+                        log_host(&[0x09]);
+                        echo_key(code);
                     },
                     // Escape
                     (KeyCode::Esc, _) => {
@@ -507,6 +1118,11 @@ pub(crate) fn cmd_monitor_with_session(
 
     // Wait for reader thread to finish
     let _ = reader_handle.join();
+    if let Some(ref log) = log_writer {
+        if let Ok(mut log) = log.lock() {
+            let _ = log.finish(now_millis());
+        }
+    }
     print_status_line(
         &term_lock,
         &format!("{} {}", style("👋").cyan(), t!("monitor.closed")),
@@ -532,10 +1148,144 @@ impl Drop for RawModeGuard {
 
 #[cfg(test)]
 mod tests {
-    use {super::*, hisiflash::split_utf8};
+    use {
+        super::*,
+        hisiflash::{format_monitor_output, split_utf8},
+    };
+
+    // ---- MonitorLog ----
+
+    #[test]
+    fn test_monitor_log_jsonl_splits_lines_by_source() {
+        let mut log = MonitorLog::new(Vec::new(), LogFormat::Jsonl);
+        log.device("boo", 1)
+            .unwrap();
+        log.device("t.\r\nready\n> ", 2)
+            .unwrap();
+        log.host(b"lx", 3)
+            .unwrap();
+        log.host(&[0x08], 3)
+            .unwrap();
+        log.host(b"s\r\n", 4)
+            .unwrap();
+        log.finish(5)
+            .unwrap();
+
+        let text = String::from_utf8(log.out).unwrap();
+        let records: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            records,
+            [
+                serde_json::json!({"ts": 2, "line": "boot.", "source": "device"}),
+                serde_json::json!({"ts": 2, "line": "ready", "source": "device"}),
+                serde_json::json!({"ts": 4, "line": "ls", "source": "host"}),
+                serde_json::json!({"ts": 5, "line": "> ", "source": "device"}),
+            ]
+        );
+        assert!(text.starts_with(r#"{"ts":2,"line":"boot.","source":"device"}"#));
+    }
+
+    #[test]
+    fn test_monitor_log_text_keeps_device_output_only() {
+        let mut log = MonitorLog::new(Vec::new(), LogFormat::Text);
+        log.device("boot.\r\n", 1)
+            .unwrap();
+        log.host(b"ls\r\n", 2)
+            .unwrap();
+        log.finish(3)
+            .unwrap();
+        assert_eq!(log.out, b"boot.\r\n");
+    }
 
     // ---- split_utf8 ----
 
+    // ---- LineEditor ----
+
+    #[test]
+    fn test_line_editor_edits_and_recalls_history() {
+        use crossterm::event::KeyCode;
+
+        let mut editor = LineEditor::default();
+        for c in "ls -l".chars() {
+            assert_eq!(editor.key(KeyCode::Char(c)), None);
+        }
+        editor.key(KeyCode::Left);
+        editor.key(KeyCode::Backspace);
+        editor.key(KeyCode::Home);
+        editor.key(KeyCode::Delete);
+        editor.key(KeyCode::End);
+        editor.key(KeyCode::Char('a'));
+        assert_eq!(editor.key(KeyCode::Enter), Some("s la".to_string()));
+
+        editor.key(KeyCode::Enter);
+        for c in "help".chars() {
+            editor.key(KeyCode::Char(c));
+        }
+        editor.key(KeyCode::Enter);
+        // Empty lines are not remembered.
+        assert_eq!(editor.history, ["s la", "help"]);
+
+        editor.key(KeyCode::Char('x'));
+        editor.key(KeyCode::Up);
+        editor.key(KeyCode::Up);
+        editor.key(KeyCode::Up);
+        assert_eq!(editor.key(KeyCode::Enter), Some("s la".to_string()));
+        editor.key(KeyCode::Char('x'));
+        editor.key(KeyCode::Up);
+        editor.key(KeyCode::Down);
+        assert_eq!(editor.key(KeyCode::Enter), Some("x".to_string()));
+    }
+
+    #[test]
+    fn test_line_editor_render_redraws_relative_to_cursor() {
+        use crossterm::event::KeyCode;
+
+        let mut editor = LineEditor::default();
+        editor.key(KeyCode::Char('a'));
+        editor.key(KeyCode::Char('b'));
+        assert_eq!(editor.render(), "ab\x1b[K");
+        editor.key(KeyCode::Left);
+        assert_eq!(editor.render(), "\x1b[2Dab\x1b[K\x1b[1D");
+        editor.key(KeyCode::Enter);
+        assert_eq!(editor.render(), "\x1b[1D\x1b[K");
+    }
+
+    #[test]
+    fn test_local_echo_text() {
+        use crossterm::event::KeyCode;
+
+        assert_eq!(local_echo_text(KeyCode::Char('é')).as_deref(), Some("é"));
+        assert_eq!(local_echo_text(KeyCode::Enter).as_deref(), Some("\r\n"));
+        assert_eq!(
+            local_echo_text(KeyCode::Backspace).as_deref(),
+            Some("\x08 \x08")
+        );
+        assert_eq!(local_echo_text(KeyCode::Tab).as_deref(), Some("\t"));
+        assert_eq!(local_echo_text(KeyCode::Esc), None);
+    }
+
+    #[test]
+    fn test_line_filter_shows_complete_matching_lines() {
+        assert!(LineFilter::new(None, None, false).is_none());
+
+        let mut filter = LineFilter::new(
+            Some(Regex::new("^app").unwrap()),
+            Some(Regex::new("debug").unwrap()),
+            false,
+        )
+        .unwrap();
+        assert_eq!(filter.push("app: sta"), "");
+        assert_eq!(filter.push("rted\r\nwifi: scan\n"), "app: started\r\n");
+        assert_eq!(filter.push("app debug: tick\napp: done\nap"), "app: done\n");
+        assert_eq!(filter.push("p: x\n"), "app: x\n");
+
+        let mut filter = LineFilter::new(None, Some(Regex::new("^$").unwrap()), true).unwrap();
+        assert_eq!(filter.push("a\r\n\r\nb\n"), "a\r\nb\n");
+    }
+
     #[test]
     fn test_split_utf8_valid_ascii() {
         let (valid, remainder) = split_utf8(b"hello world");
@@ -668,6 +1418,14 @@ mod tests {
         assert_eq!(result, "\r\n\r\n");
     }
 
+    #[test]
+    fn test_monitor_timed_out() {
+        let started = Instant::now();
+        assert!(!monitor_timed_out(started, None));
+        assert!(!monitor_timed_out(started, Some(Duration::from_secs(3600))));
+        assert!(monitor_timed_out(started, Some(Duration::ZERO)));
+    }
+
     #[test]
     fn test_contains_reset_evidence_boot_pattern() {
         assert!(contains_reset_evidence("boot.\n"));
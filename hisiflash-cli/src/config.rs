@@ -64,20 +64,84 @@ pub struct FlashConfig {
     /// Use late baud rate change.
     #[serde(default)]
     pub late_baud: bool,
+    /// Never send LoaderBoot; the device is expected to run it already.
+    #[serde(default)]
+    pub skip_loaderboot: bool,
+}
+
+/// Serial monitor configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MonitorConfig {
+    /// Default monitor baud rate.
+    pub baud: Option<u32>,
+    /// Show timestamps on each line by default.
+    #[serde(default)]
+    pub timestamp: bool,
 }
 
 /// Main configuration structure.
+///
+/// Each field maps to a named TOML section. New options should be added to
+/// the section they belong to rather than at the top level.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
-    /// Port configuration.
-    #[serde(default)]
+    /// Port configuration (`[port]`, also accepted as `[serial]`).
+    #[serde(default, alias = "serial")]
     pub port: PortConfig,
-    /// Flash configuration.
+    /// Flash configuration (`[flash]`).
     #[serde(default)]
     pub flash: FlashConfig,
+    /// Monitor configuration (`[monitor]`).
+    #[serde(default)]
+    pub monitor: MonitorConfig,
 }
 
+/// Annotated configuration template written by `config init`.
+const CONFIG_TEMPLATE: &str = r#"# hisiflash configuration file
+#
+# Local:  ./hisiflash.toml
+# Global: ~/.config/hisiflash/config.toml
+#
+# Command-line arguments and HISIFLASH_* environment variables take
+# precedence over values set here.
+
+[port.connection]
+# Preferred serial port, e.g. "/dev/ttyUSB0" or "COM3".
+# serial = "/dev/ttyUSB0"
+# Default baud rate for data transfer.
+# baud = 921600
+
+[flash]
+# Default chip type: ws63, bs2x or bs25.
+# chip = "ws63"
+# Skip the FWPKG CRC check.
+skip_verify = false
+# Switch to the target baud rate only after LoaderBoot is transferred.
+late_baud = false
+# Never send LoaderBoot (only for devices already running it, e.g. after an
+# aborted flash; a running LoaderBoot is normally detected automatically).
+skip_loaderboot = false
+
+[monitor]
+# Baud rate for the serial monitor.
+# baud = 115200
+# Prefix each line with a timestamp.
+timestamp = false
+
+# Additional USB adapters treated as known devices during auto-detection.
+# [[port.usb_device]]
+# vid = 0x1A86
+# pid = 0x7523
+"#;
+
 impl Config {
+    /// Return an annotated TOML template describing every config section.
+    ///
+    /// The template parses back to [`Config::default`].
+    pub fn default_with_comments() -> &'static str {
+        CONFIG_TEMPLATE
+    }
+
     /// Load configuration from all available sources.
     pub fn load() -> Self {
         let mut config = Self::default();
@@ -244,6 +308,32 @@ impl Config {
             self.flash
                 .late_baud = true;
         }
+        if other
+            .flash
+            .skip_loaderboot
+        {
+            self.flash
+                .skip_loaderboot = true;
+        }
+
+        // Monitor config
+        if other
+            .monitor
+            .baud
+            .is_some()
+        {
+            self.monitor
+                .baud = other
+                .monitor
+                .baud;
+        }
+        if other
+            .monitor
+            .timestamp
+        {
+            self.monitor
+                .timestamp = true;
+        }
     }
 
     /// Save the port configuration (remembers serial port).
@@ -401,6 +491,102 @@ mod tests {
         assert!(!flash.late_baud);
     }
 
+    #[test]
+    fn test_default_monitor_config() {
+        let monitor = MonitorConfig::default();
+        assert!(
+            monitor
+                .baud
+                .is_none()
+        );
+        assert!(!monitor.timestamp);
+    }
+
+    // ---- Template ----
+
+    #[test]
+    fn test_default_with_comments_parses_to_defaults() {
+        let config: Config = toml::from_str(Config::default_with_comments()).unwrap();
+        assert!(
+            config
+                .port
+                .connection
+                .serial
+                .is_none()
+        );
+        assert!(
+            config
+                .flash
+                .chip
+                .is_none()
+        );
+        assert!(
+            !config
+                .flash
+                .skip_verify
+        );
+        assert!(
+            config
+                .monitor
+                .baud
+                .is_none()
+        );
+        assert!(
+            !config
+                .monitor
+                .timestamp
+        );
+    }
+
+    #[test]
+    fn test_default_with_comments_documents_sections() {
+        let template = Config::default_with_comments();
+        assert!(template.contains("[port.connection]"));
+        assert!(template.contains("[flash]"));
+        assert!(template.contains("[monitor]"));
+    }
+
+    #[test]
+    fn test_config_serial_section_alias() {
+        let config: Config = toml::from_str(
+            r#"
+[serial.connection]
+serial = "COM7"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config
+                .port
+                .connection
+                .serial
+                .as_deref(),
+            Some("COM7")
+        );
+    }
+
+    #[test]
+    fn test_config_merge_monitor() {
+        let mut base = Config::default();
+        let mut other = Config::default();
+        other
+            .monitor
+            .baud = Some(9600);
+        other
+            .monitor
+            .timestamp = true;
+        base.merge(other);
+        assert_eq!(
+            base.monitor
+                .baud,
+            Some(9600)
+        );
+        assert!(
+            base.monitor
+                .timestamp
+        );
+    }
+
     // ---- UsbDevice ----
 
     #[test]
@@ -565,6 +751,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_config_merge_skip_loaderboot() {
+        let mut base = Config::default();
+        let mut other = Config::default();
+        other
+            .flash
+            .skip_loaderboot = true;
+        base.merge(other);
+        assert!(
+            base.flash
+                .skip_loaderboot
+        );
+    }
+
     #[test]
     fn test_config_merge_late_baud() {
         let mut base = Config::default();
@@ -5,6 +5,7 @@
 //! - Flash FWPKG firmware packages
 //! - Write raw binary files to flash
 //! - Erase flash memory
+//! - Switch the device to DFU mode
 //! - Interactive serial port selection
 //! - Shell completion generation
 //! - Environment variable support
@@ -20,7 +21,10 @@ use {
     console::style,
     dialoguer::{Error as DialoguerError, Select, theme::ColorfulTheme},
     env_logger::Env,
-    hisiflash::{ChipFamily, Error as LibError, clear_interrupt_flag},
+    hisiflash::{
+        BootloaderEntry, ChipFamily, Error as LibError, PortAccess, check_port_access,
+        clear_interrupt_flag,
+    },
     log::debug,
     rust_i18n::t,
     std::{
@@ -28,6 +32,7 @@ use {
         io::IsTerminal,
         path::{Path, PathBuf},
         sync::OnceLock,
+        time::{Duration, Instant},
     },
     thiserror::Error,
 };
@@ -48,8 +53,11 @@ pub(crate) fn was_interrupted() -> bool {
     INTERRUPTED.load(std::sync::atomic::Ordering::Relaxed)
 }
 
+/// Forget a handled Ctrl-C, both here and in the library, so the next
+/// operation in this process does not start out cancelled.
 pub(crate) fn clear_interrupted_flag() {
     INTERRUPTED.store(false, std::sync::atomic::Ordering::Relaxed);
+    clear_interrupt_flag();
 }
 
 fn install_signal_handler() -> Result<()> {
@@ -74,19 +82,29 @@ fn install_signal_handler() -> Result<()> {
 mod commands;
 mod config;
 mod help;
+mod progress_file;
 mod serial;
+mod summary;
+mod util;
 
 use {
     commands::{
+        bench::cmd_bench,
         completions::{cmd_completions, cmd_completions_install},
+        config::cmd_config_init,
+        debug::{FrameKind, cmd_debug_frame},
         firmware::resolve_firmware,
-        flash::{cmd_erase, cmd_flash, cmd_write, cmd_write_program},
-        info::{cmd_info, cmd_list_ports},
-        monitor::{cmd_monitor, cmd_monitor_with_session},
+        flash::{
+            cmd_dfu, cmd_erase, cmd_flash, cmd_write, cmd_write_manifest, cmd_write_otp,
+            cmd_write_program, cmd_write_region, resolve_named_partitions,
+        },
+        info::{cmd_info, cmd_list_partitions, cmd_list_ports},
+        monitor::{LineFilter, LogFormat, MonitorOutput, cmd_monitor, cmd_monitor_with_session},
     },
     config::Config,
     help::{build_localized_command, detect_locale},
     serial::{SerialOptions, ask_remember_port, select_serial_port},
+    summary::{RunSummary, summary_line},
 };
 
 // Initialize i18n with locale files from the locales directory
@@ -115,6 +133,20 @@ pub(crate) struct Cli {
     #[arg(short, long, global = true, env = "HISIFLASH_BAUD")]
     pub(crate) baud: Option<u32>,
 
+    /// Baud rate for the initial handshake (default: 115200).
+    #[arg(long, global = true, value_name = "BAUD")]
+    pub(crate) handshake_baud: Option<u32>,
+
+    /// Wait this long after resetting the device before continuing, e.g.
+    /// before `flash --monitor` reuses the port (default: 200).
+    #[arg(long, global = true, value_name = "MS")]
+    pub(crate) reset_settle_ms: Option<u64>,
+
+    /// Reset the board into download mode via DTR/RTS before connecting
+    /// (preset `reset` or `dtr-boot`, or a sequence such as `R1|W0.1|R0`).
+    #[arg(long, global = true, value_name = "SEQUENCE", value_parser = parse_auto_enter)]
+    pub(crate) auto_enter: Option<BootloaderEntry>,
+
     /// Target chip type.
     #[arg(short, long, global = true, env = "HISIFLASH_CHIP")]
     pub(crate) chip: Option<Chip>,
@@ -127,7 +159,8 @@ pub(crate) struct Cli {
     #[arg(short, long, global = true, action = clap::ArgAction::Count)]
     pub(crate) verbose: u8,
 
-    /// Quiet mode (suppress non-essential output).
+    /// Quiet mode (suppress non-essential output; device commands print one
+    /// result line to stdout).
     #[arg(short, long, global = true)]
     pub(crate) quiet: bool,
 
@@ -139,7 +172,7 @@ pub(crate) struct Cli {
     #[arg(long, global = true)]
     pub(crate) confirm_port: bool,
 
-    /// List all available ports (including unknown types).
+    /// List all available ports (including non-USB and unknown types).
     #[arg(long, global = true)]
     pub(crate) list_all_ports: bool,
 
@@ -147,6 +180,14 @@ pub(crate) struct Cli {
     #[arg(long = "config", global = true, value_name = "PATH")]
     pub(crate) config_path: Option<PathBuf>,
 
+    /// Write a timestamped hex log of the connection handshake to this file.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub(crate) trace_file: Option<PathBuf>,
+
+    /// Save raw error responses from the device to this file.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub(crate) dump_device_errors: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -356,7 +397,8 @@ enum Commands {
         /// Path to the FWPKG firmware file (auto-detected if omitted).
         firmware: Option<PathBuf>,
 
-        /// Only flash specified partitions (comma-separated).
+        /// Only flash matching partitions (comma-separated names or
+        /// `type:<kind>`).
         #[arg(long)]
         filter: Option<String>,
 
@@ -368,6 +410,45 @@ enum Commands {
         #[arg(long)]
         skip_verify: bool,
 
+        /// Flash without asking when --filter leaves out security partitions.
+        #[arg(long = "force")]
+        force_security: bool,
+
+        /// Keep a JSON progress file at this path updated while flashing.
+        #[arg(long, value_name = "PATH")]
+        progress_file: Option<PathBuf>,
+
+        /// Continue with the remaining partitions when one fails.
+        #[arg(long)]
+        keep_going: bool,
+
+        /// Abort when no block has been acknowledged for this many seconds.
+        #[arg(long, value_name = "SECS")]
+        stall_timeout: Option<u64>,
+
+        /// Accept a NAK as the YMODEM start request and fall back to 8-bit
+        /// checksums, for loaders that do not speak CRC mode.
+        #[arg(long)]
+        checksum_fallback: bool,
+
+        /// Fail before flashing unless the package contains every named
+        /// partition (comma-separated).
+        #[arg(long, value_name = "NAMES")]
+        expect: Option<String>,
+
+        /// Allow partitions larger than the 64 MiB safety limit.
+        #[arg(long)]
+        allow_large: bool,
+
+        /// Print the package's partition table and exit without connecting.
+        #[arg(long)]
+        list_partitions: bool,
+
+        /// Erase the whole flash after LoaderBoot, before the first
+        /// partition is written.
+        #[arg(long)]
+        pre_erase: bool,
+
         /// Open serial monitor after flashing.
         #[arg(long)]
         monitor: bool,
@@ -387,21 +468,43 @@ enum Commands {
         /// Show raw monitor output without control-character filtering.
         #[arg(long = "monitor-raw", conflicts_with = "monitor_clean_output")]
         monitor_raw: bool,
+
+        /// Close the serial monitor after this many seconds.
+        #[arg(long, value_name = "SECS")]
+        monitor_timeout: Option<u64>,
     },
 
     /// Write raw binary files to flash.
     Write {
         /// LoaderBoot binary file.
-        #[arg(long, required = true)]
-        loaderboot: PathBuf,
+        #[arg(long, required_unless_present = "manifest")]
+        loaderboot: Option<PathBuf>,
 
-        /// Binary file to flash (format: file:address, can be repeated).
-        #[arg(long = "bin", value_parser = parse_bin_arg)]
+        /// Binary file to flash (format: file:address, can be repeated;
+        /// use `-` as the file to read it from stdin).
+        #[arg(long = "bin", value_parser = parse_bin_arg, allow_hyphen_values = true)]
         bins: Vec<(PathBuf, u32)>,
 
+        /// Standard partition to flash at its default address (format:
+        /// name=file, can be repeated).
+        #[arg(long = "partition", value_name = "NAME=FILE", value_parser = parse_partition_arg)]
+        partitions: Vec<(String, PathBuf)>,
+
+        /// Build the firmware from a `manifest.toml` directory and flash it.
+        #[arg(
+            long,
+            value_name = "DIR",
+            conflicts_with_all = ["loaderboot", "bins", "partitions"]
+        )]
+        manifest: Option<PathBuf>,
+
         /// Use late baud rate change.
         #[arg(long)]
         late_baud: bool,
+
+        /// Allow binaries larger than the 64 MiB safety limit.
+        #[arg(long)]
+        allow_large: bool,
     },
 
     /// Write a single binary with program data.
@@ -410,7 +513,7 @@ enum Commands {
         #[arg(long, required = true)]
         loaderboot: PathBuf,
 
-        /// Program binary file.
+        /// Program binary file (`-` reads it from stdin).
         program: PathBuf,
 
         /// Flash address for program.
@@ -420,6 +523,28 @@ enum Commands {
         /// Use late baud rate change.
         #[arg(long)]
         late_baud: bool,
+
+        /// Allow binaries larger than the 64 MiB safety limit.
+        #[arg(long)]
+        allow_large: bool,
+    },
+
+    /// Write data at an address, erasing only the sectors it touches.
+    WriteRegion {
+        /// LoaderBoot binary file.
+        #[arg(long, required = true)]
+        loaderboot: PathBuf,
+
+        /// Data file to write (`-` reads it from stdin).
+        file: PathBuf,
+
+        /// Flash address of the first byte.
+        #[arg(short, long, value_parser = parse_hex_u32)]
+        address: u32,
+
+        /// Use late baud rate change.
+        #[arg(long)]
+        late_baud: bool,
     },
 
     /// Erase flash memory.
@@ -429,6 +554,42 @@ enum Commands {
         all: bool,
     },
 
+    /// Program OTP/eFuse data. This cannot be undone.
+    WriteOtp {
+        /// LoaderBoot binary file.
+        #[arg(long, required = true)]
+        loaderboot: PathBuf,
+
+        /// OTP/eFuse data file.
+        otp_file: PathBuf,
+
+        /// Confirm that OTP/eFuse bits can never be cleared again.
+        #[arg(long = "i-understand-otp-is-permanent")]
+        i_understand_otp_is_permanent: bool,
+    },
+
+    /// Switch the device to DFU mode.
+    Dfu,
+
+    /// Measure flash throughput by writing generated data to a scratch area.
+    Bench {
+        /// LoaderBoot binary file.
+        #[arg(long, required = true)]
+        loaderboot: PathBuf,
+
+        /// Amount of test data (bytes, or with a K/M suffix).
+        #[arg(long, default_value = "1M", value_parser = parse_size)]
+        size: u32,
+
+        /// Flash address of the scratch area; its contents are lost.
+        #[arg(short = 'a', long = "address", value_name = "ADDRESS", value_parser = parse_hex_u32)]
+        scratch_address: u32,
+
+        /// Use late baud rate change.
+        #[arg(long)]
+        late_baud: bool,
+    },
+
     /// Show information about a firmware file.
     Info {
         /// Path to the FWPKG firmware file.
@@ -437,6 +598,14 @@ enum Commands {
         /// Output information as JSON to stdout.
         #[arg(long)]
         json: bool,
+
+        /// Fail if the CRC or any partition bounds check does not pass.
+        #[arg(long)]
+        validate: bool,
+
+        /// Show the flash address layout of all partitions.
+        #[arg(long, visible_alias = "partition-map", conflicts_with = "json")]
+        map: bool,
     },
 
     /// List available serial ports.
@@ -465,13 +634,48 @@ enum Commands {
         #[arg(long, value_name = "FILE")]
         log: Option<PathBuf>,
 
+        /// Log file format.
+        #[arg(long, value_enum, default_value_t = LogFormat::Text, requires = "log")]
+        log_format: LogFormat,
+
         /// Clean output by filtering non-printable control characters.
         #[arg(long = "clean-output", action = clap::ArgAction::Set, default_value_t = true)]
         clean_output: bool,
 
-        /// Show raw serial output without control-character filtering.
+        /// Write received bytes verbatim, with no decoding, line handling or
+        /// timestamps.
         #[arg(long, conflicts_with = "clean_output")]
         raw: bool,
+
+        /// Do not read the keyboard (only with --raw).
+        #[arg(long, requires = "raw")]
+        no_input: bool,
+
+        /// Edit each line locally (arrow keys, Up/Down history) and send it
+        /// on Enter.
+        #[arg(long, conflicts_with = "no_input")]
+        line_input: bool,
+
+        /// Print typed characters locally, for devices that do not echo.
+        #[arg(long, conflicts_with_all = ["no_input", "line_input"])]
+        local_echo: bool,
+
+        /// Only show received lines matching this regex.
+        #[arg(long, value_name = "REGEX", conflicts_with = "raw")]
+        include: Option<regex::Regex>,
+
+        /// Hide received lines matching this regex.
+        #[arg(long, value_name = "REGEX", conflicts_with = "raw")]
+        exclude: Option<regex::Regex>,
+
+        /// Apply --include/--exclude to the log file too (default: log every
+        /// line).
+        #[arg(long, requires = "log")]
+        filter_log: bool,
+
+        /// Close the serial monitor after this many seconds.
+        #[arg(long, value_name = "SECS")]
+        monitor_timeout: Option<u64>,
     },
 
     /// Generate shell completion scripts.
@@ -485,6 +689,48 @@ enum Commands {
         #[arg(long)]
         install: bool,
     },
+
+    /// Manage the configuration file.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Developer utilities.
+    #[command(hide = true)]
+    Debug {
+        #[command(subcommand)]
+        action: DebugAction,
+    },
+}
+
+/// `debug` subcommands.
+#[derive(Subcommand)]
+enum DebugAction {
+    /// Print a command frame as hex without opening a port.
+    Frame {
+        #[command(subcommand)]
+        kind: FrameKind,
+    },
+}
+
+/// `config` subcommands.
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Write an annotated configuration template.
+    Init {
+        /// Output file (default: ./hisiflash.toml).
+        #[arg(conflicts_with = "global")]
+        output: Option<PathBuf>,
+
+        /// Write to the global config file instead of the current directory.
+        #[arg(long)]
+        global: bool,
+
+        /// Overwrite an existing file.
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 /// Parse binary argument in format "file:address".
@@ -507,6 +753,49 @@ fn parse_bin_arg(s: &str) -> Result<(PathBuf, u32), String> {
     Ok((path, addr))
 }
 
+/// Parse named partition argument in format "name=file".
+fn parse_partition_arg(s: &str) -> Result<(String, PathBuf), String> {
+    match s.split_once('=') {
+        Some((name, path)) if !name.is_empty() && !path.is_empty() => {
+            Ok((name.to_string(), PathBuf::from(path)))
+        },
+        _ => Err(format!(
+            "Invalid format: '{s}'. Expected 'name=file' (e.g., 'app=app.bin')"
+        )),
+    }
+}
+
+/// Parse a byte count such as `4096`, `64K` or `1M` (binary units).
+fn parse_size(s: &str) -> Result<u32, String> {
+    let s = s.trim();
+    let upper = s.to_ascii_uppercase();
+    let number = upper
+        .trim_end_matches("IB")
+        .trim_end_matches('B');
+    let (digits, multiplier) = if let Some(digits) = number.strip_suffix('K') {
+        (digits, 1024)
+    } else if let Some(digits) = number.strip_suffix('M') {
+        (digits, 1024 * 1024)
+    } else {
+        (number, 1)
+    };
+    let size = digits
+        .parse::<u32>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("Invalid size: '{s}'. Expected e.g. 4096, 64K or 1M"))?;
+    if size == 0 {
+        return Err("Size must be greater than zero".to_string());
+    }
+    Ok(size)
+}
+
+/// Parse a `--auto-enter` preset name or DTR/RTS sequence.
+fn parse_auto_enter(s: &str) -> Result<BootloaderEntry, String> {
+    s.parse()
+        .map_err(|e: LibError| e.to_string())
+}
+
 /// Parse hexadecimal address (supports 0x prefix and underscores).
 fn parse_hex_u32(s: &str) -> Result<u32, String> {
     let s = s.trim();
@@ -555,7 +844,6 @@ fn main() {
 fn run() -> Result<()> {
     install_signal_handler()?;
     clear_interrupted_flag();
-    clear_interrupt_flag();
 
     let raw_args: Vec<String> = env::args().collect();
     let result = run_with_args(&raw_args);
@@ -668,20 +956,33 @@ fn run_with_args(raw_args: &[String]) -> Result<()> {
 
     apply_config_defaults(&mut cli, &matches, &config)?;
 
+    let started = Instant::now();
     match &cli.command {
         Commands::Flash {
             firmware,
             filter,
             late_baud,
             skip_verify,
+            force_security,
+            progress_file,
+            keep_going,
+            stall_timeout,
+            checksum_fallback,
+            expect,
+            allow_large,
+            list_partitions,
+            pre_erase,
             monitor,
             monitor_baud,
             monitor_port,
             monitor_clean_output,
             monitor_raw,
+            monitor_timeout,
         } => {
-            let firmware = resolve_firmware(firmware.as_ref(), cli.non_interactive, cli.quiet)?;
-            let chip = resolve_effective_chip(&cli, Some(&firmware))?;
+            if *list_partitions {
+                let firmware = resolve_firmware(firmware.as_ref(), cli.non_interactive, cli.quiet)?;
+                return cmd_list_partitions(&firmware);
+            }
             // When `--monitor` is requested without an explicit
             // `--monitor-port`, hand the flasher's still-open serial handle
             // straight over to the monitor. This both avoids a second
@@ -690,19 +991,44 @@ fn run_with_args(raw_args: &[String]) -> Result<()> {
             // otherwise drop those bytes). When `--monitor-port` pins a
             // different port, fall back to the regular open path.
             let want_handoff = *monitor && monitor_port.is_none();
-            let outcome = cmd_flash(
+            let outcome = (|| {
+                let firmware = resolve_firmware(firmware.as_ref(), cli.non_interactive, cli.quiet)?;
+                let chip = resolve_effective_chip(&cli, Some(&firmware))?;
+                cmd_flash(
+                    &cli,
+                    &mut config,
+                    &firmware,
+                    filter.as_ref(),
+                    *late_baud,
+                    *skip_verify,
+                    chip.into(),
+                    want_handoff,
+                    *force_security,
+                    progress_file.as_deref(),
+                    *keep_going,
+                    stall_timeout.map(Duration::from_secs),
+                    *checksum_fallback,
+                    expect.as_ref(),
+                    *allow_large,
+                    *pre_erase,
+                )
+            })();
+            report_quiet_summary(
                 &cli,
-                &mut config,
-                &firmware,
-                filter.as_ref(),
-                *late_baud,
-                *skip_verify,
-                chip.into(),
-                want_handoff,
-            )?;
+                started,
+                outcome
+                    .as_ref()
+                    .map(|o| Some(&o.summary)),
+            );
+            let outcome = outcome?;
             if *monitor {
                 eprintln!();
-                let clean_output = *monitor_clean_output && !*monitor_raw;
+                let output = if *monitor_clean_output && !*monitor_raw {
+                    MonitorOutput::Clean
+                } else {
+                    MonitorOutput::Unfiltered
+                };
+                let monitor_timeout = monitor_timeout.map(Duration::from_secs);
                 if want_handoff {
                     let flasher = outcome
                         .flasher
@@ -714,9 +1040,15 @@ fn run_with_args(raw_args: &[String]) -> Result<()> {
                                 &outcome.port,
                                 *monitor_baud,
                                 false,
-                                clean_output,
+                                output,
                                 None,
+                                LogFormat::Text,
                                 true,
+                                monitor_timeout,
+                                false,
+                                false,
+                                false,
+                                None,
                             )?;
                         },
                         Err(err) => {
@@ -735,7 +1067,13 @@ fn run_with_args(raw_args: &[String]) -> Result<()> {
                                 ),
                                 *monitor_baud,
                                 false,
-                                clean_output,
+                                output,
+                                None,
+                                LogFormat::Text,
+                                monitor_timeout,
+                                false,
+                                false,
+                                false,
                                 None,
                             )?;
                         },
@@ -747,7 +1085,13 @@ fn run_with_args(raw_args: &[String]) -> Result<()> {
                         monitor_port.as_deref(),
                         *monitor_baud,
                         false,
-                        clean_output,
+                        output,
+                        None,
+                        LogFormat::Text,
+                        monitor_timeout,
+                        false,
+                        false,
+                        false,
                         None,
                     )?;
                 }
@@ -756,52 +1100,208 @@ fn run_with_args(raw_args: &[String]) -> Result<()> {
         Commands::Write {
             loaderboot,
             bins,
+            partitions,
+            manifest,
             late_baud,
+            allow_large,
         } => {
-            let chip = resolve_effective_chip(&cli, None)?;
-            cmd_write(&cli, &mut config, loaderboot, bins, *late_baud, chip.into())?;
+            let result = (|| {
+                let chip = resolve_effective_chip(&cli, None)?;
+                match (manifest, loaderboot) {
+                    (Some(dir), _) => cmd_write_manifest(
+                        &cli,
+                        &mut config,
+                        dir,
+                        *late_baud,
+                        *allow_large,
+                        chip.into(),
+                    ),
+                    (None, Some(loaderboot)) => {
+                        let mut bins = bins.clone();
+                        bins.extend(resolve_named_partitions(chip.into(), partitions)?);
+                        cmd_write(
+                            &cli,
+                            &mut config,
+                            loaderboot,
+                            &bins,
+                            *late_baud,
+                            *allow_large,
+                            chip.into(),
+                        )
+                    },
+                    (None, None) => unreachable!("clap requires --loaderboot without --manifest"),
+                }
+            })();
+            report_quiet_summary(
+                &cli,
+                started,
+                result
+                    .as_ref()
+                    .map(Some),
+            );
+            result?;
         },
         Commands::WriteProgram {
             loaderboot,
             program,
             address,
             late_baud,
+            allow_large,
         } => {
-            let chip = resolve_effective_chip(&cli, None)?;
-            cmd_write_program(
+            let result = (|| {
+                let chip = resolve_effective_chip(&cli, None)?;
+                cmd_write_program(
+                    &cli,
+                    &mut config,
+                    loaderboot,
+                    program.clone(),
+                    *address,
+                    *late_baud,
+                    *allow_large,
+                    chip.into(),
+                )
+            })();
+            report_quiet_summary(
                 &cli,
-                &mut config,
-                loaderboot,
-                program.clone(),
-                *address,
-                *late_baud,
-                chip.into(),
-            )?;
+                started,
+                result
+                    .as_ref()
+                    .map(Some),
+            );
+            result?;
+        },
+        Commands::WriteRegion {
+            loaderboot,
+            file,
+            address,
+            late_baud,
+        } => {
+            let result = (|| {
+                let chip = resolve_effective_chip(&cli, None)?;
+                cmd_write_region(
+                    &cli,
+                    &mut config,
+                    loaderboot,
+                    file,
+                    *address,
+                    *late_baud,
+                    chip.into(),
+                )
+            })();
+            report_quiet_summary(
+                &cli,
+                started,
+                result
+                    .as_ref()
+                    .map(Some),
+            );
+            result?;
         },
         Commands::Erase { all } => {
-            let chip = resolve_effective_chip(&cli, None)?;
-            cmd_erase(&cli, &mut config, *all, chip.into())?;
+            let result = (|| {
+                let chip = resolve_effective_chip(&cli, None)?;
+                cmd_erase(&cli, &mut config, *all, chip.into())
+            })();
+            report_quiet_summary(
+                &cli,
+                started,
+                result
+                    .as_ref()
+                    .map(|()| None),
+            );
+            result?;
+        },
+        Commands::WriteOtp {
+            loaderboot,
+            otp_file,
+            i_understand_otp_is_permanent,
+        } => {
+            let result = (|| {
+                let chip = resolve_effective_chip(&cli, None)?;
+                cmd_write_otp(
+                    &cli,
+                    &mut config,
+                    loaderboot,
+                    otp_file,
+                    *i_understand_otp_is_permanent,
+                    chip.into(),
+                )
+            })();
+            report_quiet_summary(
+                &cli,
+                started,
+                result
+                    .as_ref()
+                    .map(|()| None),
+            );
+            result?;
+        },
+        Commands::Dfu => {
+            let result = (|| {
+                let chip = resolve_effective_chip(&cli, None)?;
+                cmd_dfu(&cli, &mut config, chip.into())
+            })();
+            report_quiet_summary(
+                &cli,
+                started,
+                result
+                    .as_ref()
+                    .map(|()| None),
+            );
+            result?;
         },
-        Commands::Info { firmware, json } => {
+        Commands::Bench {
+            loaderboot,
+            size,
+            scratch_address,
+            late_baud,
+        } => {
+            let result = (|| {
+                let chip = resolve_effective_chip(&cli, None)?;
+                cmd_bench(
+                    &cli,
+                    &mut config,
+                    loaderboot,
+                    *size,
+                    *scratch_address,
+                    *late_baud,
+                    chip.into(),
+                )
+            })();
+            report_quiet_summary(
+                &cli,
+                started,
+                result
+                    .as_ref()
+                    .map(Some),
+            );
+            result?;
+        },
+        Commands::Info {
+            firmware,
+            json,
+            validate,
+            map,
+        } => {
             if *json {
-                if let Err(err) = cmd_info(firmware, true) {
+                if let Err(err) = cmd_info(firmware, true, *validate, false) {
                     let code = map_exit_code(&err);
                     emit_structured_json_error("info", code, &err)?;
                     return Err(JsonErrorResponseEmitted { exit_code: code }.into());
                 }
             } else {
-                cmd_info(firmware, false)?;
+                cmd_info(firmware, false, *validate, *map)?;
             }
         },
         Commands::ListPorts { json } => {
             if *json {
-                if let Err(err) = cmd_list_ports(true) {
+                if let Err(err) = cmd_list_ports(true, cli.list_all_ports) {
                     let code = map_exit_code(&err);
                     emit_structured_json_error("list-ports", code, &err)?;
                     return Err(JsonErrorResponseEmitted { exit_code: code }.into());
                 }
             } else {
-                cmd_list_ports(false)?;
+                cmd_list_ports(false, cli.list_all_ports)?;
             }
         },
         Commands::Monitor {
@@ -809,17 +1309,38 @@ fn run_with_args(raw_args: &[String]) -> Result<()> {
             monitor_baud,
             timestamp,
             log,
+            log_format,
             clean_output,
             raw,
+            no_input,
+            line_input,
+            local_echo,
+            include,
+            exclude,
+            filter_log,
+            monitor_timeout,
         } => {
+            let output = if *raw {
+                MonitorOutput::Raw
+            } else if *clean_output {
+                MonitorOutput::Clean
+            } else {
+                MonitorOutput::Unfiltered
+            };
             cmd_monitor(
                 &cli,
                 &mut config,
                 monitor_port.as_deref(),
                 *monitor_baud,
                 *timestamp,
-                *clean_output && !*raw,
+                output,
                 log.as_ref(),
+                *log_format,
+                monitor_timeout.map(Duration::from_secs),
+                *no_input,
+                *line_input,
+                *local_echo,
+                LineFilter::new(include.clone(), exclude.clone(), *filter_log),
             )?;
         },
         Commands::Completions { shell, install } => {
@@ -836,6 +1357,16 @@ fn run_with_args(raw_args: &[String]) -> Result<()> {
                 cmd_completions(shell);
             }
         },
+        Commands::Config { action } => match action {
+            ConfigAction::Init {
+                output,
+                global,
+                force,
+            } => cmd_config_init(output.as_deref(), *global, *force, cli.quiet)?,
+        },
+        Commands::Debug { action } => match action {
+            DebugAction::Frame { kind } => cmd_debug_frame(&cli, kind),
+        },
     }
 
     Ok(())
@@ -866,6 +1397,20 @@ fn apply_config_defaults(cli: &mut Cli, matches: &clap::ArgMatches, config: &Con
             .chip
             .as_deref()
         {
+            if let Some(family) =
+                ChipFamily::from_name(config_chip_name).filter(|family| !family.is_supported())
+            {
+                return Err(CliError::Config(
+                    t!(
+                        "error.config_chip_not_supported",
+                        chip = family
+                            .to_string()
+                            .to_lowercase()
+                    )
+                    .to_string(),
+                )
+                .into());
+            }
             let config_chip = Chip::from_config_name(config_chip_name).ok_or_else(|| {
                 CliError::Config(
                     t!(
@@ -880,10 +1425,20 @@ fn apply_config_defaults(cli: &mut Cli, matches: &clap::ArgMatches, config: &Con
         }
     }
 
+    let from_command_line = |id: &str| {
+        matches!(
+            matches
+                .subcommand()
+                .and_then(|(_, m)| m.value_source(id)),
+            Some(ValueSource::CommandLine)
+        )
+    };
+
     match &mut cli.command {
         Commands::Flash {
             late_baud,
             skip_verify,
+            monitor_baud,
             ..
         } => {
             if !matches!(
@@ -906,8 +1461,38 @@ fn apply_config_defaults(cli: &mut Cli, matches: &clap::ArgMatches, config: &Con
                     .flash
                     .skip_verify;
             }
+            if !from_command_line("monitor_baud") {
+                if let Some(baud) = config
+                    .monitor
+                    .baud
+                {
+                    *monitor_baud = baud;
+                }
+            }
+        },
+        Commands::Monitor {
+            monitor_baud,
+            timestamp,
+            ..
+        } => {
+            if !from_command_line("monitor_baud") {
+                if let Some(baud) = config
+                    .monitor
+                    .baud
+                {
+                    *monitor_baud = baud;
+                }
+            }
+            if !from_command_line("timestamp") {
+                *timestamp = config
+                    .monitor
+                    .timestamp;
+            }
         },
-        Commands::Write { late_baud, .. } | Commands::WriteProgram { late_baud, .. }
+        Commands::Write { late_baud, .. }
+        | Commands::WriteProgram { late_baud, .. }
+        | Commands::WriteRegion { late_baud, .. }
+        | Commands::Bench { late_baud, .. }
             if !matches!(
                 matches
                     .subcommand()
@@ -925,6 +1510,17 @@ fn apply_config_defaults(cli: &mut Cli, matches: &clap::ArgMatches, config: &Con
     Ok(())
 }
 
+/// Print the `--quiet` machine summary line for a device command to stdout.
+fn report_quiet_summary(
+    cli: &Cli,
+    started: Instant,
+    result: std::result::Result<Option<&RunSummary>, &anyhow::Error>,
+) {
+    if cli.quiet {
+        println!("{}", summary_line(result, started.elapsed()));
+    }
+}
+
 fn map_exit_code(err: &anyhow::Error) -> i32 {
     if let Some(json_err) = err.downcast_ref::<JsonErrorResponseEmitted>() {
         return json_err.exit_code;
@@ -935,21 +1531,52 @@ fn map_exit_code(err: &anyhow::Error) -> i32 {
         return cli_err.exit_code();
     }
 
-    // Priority 2: library errors mapped to coarse CLI classes.
-    // Keep this mapping conservative to avoid breaking automation expectations.
+    // Priority 2: library errors mapped by their stable `kind()`.
+    // Codes are part of the script contract: only ever add new ones.
     if let Some(lib_err) = err.downcast_ref::<LibError>() {
-        return match lib_err {
-            LibError::DeviceNotFound => 4,
-            LibError::Config(_) => 3,
-            LibError::Unsupported(_) => 5,
-            _ => 1,
-        };
+        return lib_exit_code(lib_err);
     }
 
     // Fallback: unexpected/unclassified failure.
     1
 }
 
+/// Exit code for a library error.
+///
+/// | code | kinds |
+/// |------|-------|
+/// | 3 | `config` |
+/// | 4 | `device_not_found` |
+/// | 5 | `unsupported` |
+/// | 6 | permission denied (`io`/`serial`) |
+/// | 7 | `timeout` |
+/// | 8 | `crc_mismatch` |
+/// | 9 | `protocol`, `handshake_failed`, `ymodem` |
+/// | 130 | `interrupted` |
+/// | 1 | anything else |
+fn lib_exit_code(err: &LibError) -> i32 {
+    let permission_denied = match err {
+        LibError::Io(e) => e.kind() == std::io::ErrorKind::PermissionDenied,
+        LibError::Serial(e) => {
+            e.kind() == serialport::ErrorKind::Io(std::io::ErrorKind::PermissionDenied)
+        },
+        _ => false,
+    };
+    if permission_denied {
+        return 6;
+    }
+    match err.kind() {
+        "config" => 3,
+        "device_not_found" => 4,
+        "unsupported" => 5,
+        "timeout" => 7,
+        "crc_mismatch" => 8,
+        "protocol" | "handshake_failed" | "ymodem" => 9,
+        "interrupted" => 130,
+        _ => 1,
+    }
+}
+
 fn emit_structured_json_error(command: &str, exit_code: i32, err: &anyhow::Error) -> Result<()> {
     let body = serde_json::json!({
         "ok": false,
@@ -981,6 +1608,25 @@ pub(crate) fn get_port(cli: &Cli, config: &mut Config) -> Result<String> {
         ask_remember_port(&selected.port, config)?;
     }
 
+    // Catch the common first-run permission problem before the OS error does.
+    if let PortAccess::PermissionDenied { hint } = check_port_access(
+        &selected
+            .port
+            .name,
+    ) {
+        return Err(CliError::Config(
+            t!(
+                "error.port_permission_denied",
+                port = selected
+                    .port
+                    .name,
+                hint = hint
+            )
+            .to_string(),
+        )
+        .into());
+    }
+
     Ok(selected
         .port
         .name)
@@ -1153,6 +1799,16 @@ mod cli_tests {
             "app,flashboot",
             "--late-baud",
             "--skip-verify",
+            "--force",
+            "--progress-file",
+            "/run/flash.json",
+            "--keep-going",
+            "--stall-timeout",
+            "90",
+            "--checksum-fallback",
+            "--expect",
+            "app_a,app_b",
+            "--pre-erase",
             "--monitor",
         ])
         .unwrap();
@@ -1161,11 +1817,21 @@ mod cli_tests {
             filter,
             late_baud,
             skip_verify,
+            force_security,
+            progress_file,
+            keep_going,
+            stall_timeout,
+            checksum_fallback,
+            expect,
+            allow_large,
+            list_partitions,
+            pre_erase,
             monitor,
             monitor_baud,
             monitor_port,
             monitor_clean_output,
             monitor_raw,
+            monitor_timeout,
         } = cli.command
         {
             assert_eq!(
@@ -1178,16 +1844,57 @@ mod cli_tests {
             assert_eq!(filter.as_deref(), Some("app,flashboot"));
             assert!(late_baud);
             assert!(skip_verify);
+            assert!(force_security);
+            assert_eq!(progress_file, Some(PathBuf::from("/run/flash.json")));
+            assert!(keep_going);
+            assert_eq!(stall_timeout, Some(90));
+            assert!(checksum_fallback);
+            assert_eq!(expect.as_deref(), Some("app_a,app_b"));
+            assert!(!allow_large);
+            assert!(!list_partitions);
+            assert!(pre_erase);
             assert!(monitor);
             assert_eq!(monitor_baud, 115200);
             assert_eq!(monitor_port, None);
             assert!(monitor_clean_output);
             assert!(!monitor_raw);
+            assert_eq!(monitor_timeout, None);
         } else {
             panic!("Expected Flash command");
         }
     }
 
+    #[test]
+    fn test_cli_parse_monitor_timeout() {
+        let cli = Cli::try_parse_from([
+            "hisiflash",
+            "flash",
+            "fw.fwpkg",
+            "--monitor",
+            "--monitor-timeout",
+            "30",
+        ])
+        .unwrap();
+        if let Commands::Flash {
+            monitor_timeout, ..
+        } = cli.command
+        {
+            assert_eq!(monitor_timeout, Some(30));
+        } else {
+            panic!("Expected Flash command");
+        }
+
+        let cli = Cli::try_parse_from(["hisiflash", "monitor", "--monitor-timeout", "5"]).unwrap();
+        if let Commands::Monitor {
+            monitor_timeout, ..
+        } = cli.command
+        {
+            assert_eq!(monitor_timeout, Some(5));
+        } else {
+            panic!("Expected Monitor command");
+        }
+    }
+
     #[test]
     fn test_cli_parse_write() {
         let cli = Cli::try_parse_from([
@@ -1197,20 +1904,28 @@ mod cli_tests {
             "lb.bin",
             "--bin",
             "app.bin:0x00800000",
+            "--partition",
+            "nv=nv.bin",
+            "--allow-large",
         ])
         .unwrap();
         if let Commands::Write {
             loaderboot,
             bins,
+            partitions,
+            manifest,
             late_baud,
+            allow_large,
         } = cli.command
         {
             assert_eq!(
                 loaderboot
+                    .unwrap()
                     .to_str()
                     .unwrap(),
                 "lb.bin"
             );
+            assert!(manifest.is_none());
             assert_eq!(bins.len(), 1);
             assert_eq!(
                 bins[0]
@@ -1220,12 +1935,40 @@ mod cli_tests {
                 "app.bin"
             );
             assert_eq!(bins[0].1, 0x00800000);
+            assert_eq!(partitions, [("nv".to_string(), PathBuf::from("nv.bin"))]);
             assert!(!late_baud);
+            assert!(allow_large);
         } else {
             panic!("Expected Write command");
         }
     }
 
+    #[test]
+    fn test_cli_parse_write_manifest() {
+        let cli = Cli::try_parse_from(["hisiflash", "write", "--manifest", "out/pkg"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Write {
+                loaderboot: None,
+                manifest: Some(_),
+                ..
+            }
+        ));
+
+        assert!(Cli::try_parse_from(["hisiflash", "write"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "hisiflash",
+                "write",
+                "--manifest",
+                "out/pkg",
+                "--loaderboot",
+                "lb.bin",
+            ])
+            .is_err()
+        );
+    }
+
     #[test]
     fn test_cli_parse_write_program() {
         let cli = Cli::try_parse_from([
@@ -1241,6 +1984,38 @@ mod cli_tests {
         assert!(matches!(cli.command, Commands::WriteProgram { .. }));
     }
 
+    #[test]
+    fn test_cli_parse_write_region() {
+        let cli = Cli::try_parse_from([
+            "hisiflash",
+            "write-region",
+            "--loaderboot",
+            "lb.bin",
+            "-a",
+            "0x003F0100",
+            "config.bin",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::WriteRegion {
+                address: 0x003F_0100,
+                late_baud: false,
+                ..
+            }
+        ));
+        assert!(
+            Cli::try_parse_from([
+                "hisiflash",
+                "write-region",
+                "--loaderboot",
+                "lb.bin",
+                "config.bin"
+            ])
+            .is_err()
+        );
+    }
+
     #[test]
     fn test_cli_parse_erase() {
         let cli = Cli::try_parse_from(["hisiflash", "erase", "--all"]).unwrap();
@@ -1251,6 +2026,90 @@ mod cli_tests {
         }
     }
 
+    #[test]
+    fn test_cli_parse_write_otp() {
+        let cli = Cli::try_parse_from([
+            "hisiflash",
+            "write-otp",
+            "--loaderboot",
+            "lb.bin",
+            "otp.bin",
+            "--i-understand-otp-is-permanent",
+        ])
+        .unwrap();
+        if let Commands::WriteOtp {
+            otp_file,
+            i_understand_otp_is_permanent,
+            ..
+        } = cli.command
+        {
+            assert_eq!(otp_file, PathBuf::from("otp.bin"));
+            assert!(i_understand_otp_is_permanent);
+        } else {
+            panic!("Expected WriteOtp command");
+        }
+
+        let cli = Cli::try_parse_from([
+            "hisiflash",
+            "write-otp",
+            "--loaderboot",
+            "lb.bin",
+            "otp.bin",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::WriteOtp {
+                i_understand_otp_is_permanent: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_dfu() {
+        let cli = Cli::try_parse_from(["hisiflash", "dfu"]).unwrap();
+        assert!(matches!(cli.command, Commands::Dfu));
+    }
+
+    #[test]
+    fn test_cli_parse_bench() {
+        let cli = Cli::try_parse_from([
+            "hisiflash",
+            "bench",
+            "--loaderboot",
+            "lb.bin",
+            "--size",
+            "64K",
+            "-a",
+            "0x00500000",
+        ])
+        .unwrap();
+        if let Commands::Bench {
+            size,
+            scratch_address,
+            ..
+        } = cli.command
+        {
+            assert_eq!(size, 64 * 1024);
+            assert_eq!(scratch_address, 0x0050_0000);
+        } else {
+            panic!("Expected Bench command");
+        }
+        assert!(Cli::try_parse_from(["hisiflash", "bench", "--loaderboot", "lb.bin"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_size_units() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("64k"), Ok(64 * 1024));
+        assert_eq!(parse_size("1M"), Ok(1024 * 1024));
+        assert_eq!(parse_size("2MiB"), Ok(2 * 1024 * 1024));
+        assert!(parse_size("0").is_err());
+        assert!(parse_size("1G").is_err());
+        assert!(parse_size("8192M").is_err());
+    }
+
     #[test]
     fn test_cli_parse_info() {
         let cli = Cli::try_parse_from(["hisiflash", "info", "firmware.fwpkg"]).unwrap();
@@ -1267,6 +2126,35 @@ mod cli_tests {
         }
     }
 
+    #[test]
+    fn test_cli_parse_info_map() {
+        let cli = Cli::try_parse_from(["hisiflash", "info", "--map", "firmware.fwpkg"]).unwrap();
+        assert!(matches!(cli.command, Commands::Info { map: true, .. }));
+
+        let cli = Cli::try_parse_from(["hisiflash", "info", "--partition-map", "firmware.fwpkg"])
+            .unwrap();
+        assert!(matches!(cli.command, Commands::Info { map: true, .. }));
+
+        assert!(
+            Cli::try_parse_from(["hisiflash", "info", "--map", "--json", "firmware.fwpkg"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_info_validate() {
+        let cli =
+            Cli::try_parse_from(["hisiflash", "info", "--validate", "firmware.fwpkg"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Info {
+                validate: true,
+                json: false,
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn test_cli_parse_list_ports() {
         let cli = Cli::try_parse_from(["hisiflash", "list-ports"]).unwrap();
@@ -1356,6 +2244,121 @@ mod cli_tests {
         }
     }
 
+    #[test]
+    fn test_cli_parse_monitor_no_input_requires_raw() {
+        let cli = Cli::try_parse_from(["hisiflash", "monitor", "--raw", "--no-input"]).unwrap();
+        if let Commands::Monitor { raw, no_input, .. } = cli.command {
+            assert!(raw);
+            assert!(no_input);
+        } else {
+            panic!("Expected Monitor command");
+        }
+
+        assert!(Cli::try_parse_from(["hisiflash", "monitor", "--no-input"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_monitor_line_input() {
+        let cli = Cli::try_parse_from(["hisiflash", "monitor", "--line-input"]).unwrap();
+        if let Commands::Monitor { line_input, .. } = cli.command {
+            assert!(line_input);
+        } else {
+            panic!("Expected Monitor command");
+        }
+
+        assert!(
+            Cli::try_parse_from([
+                "hisiflash",
+                "monitor",
+                "--raw",
+                "--no-input",
+                "--line-input"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_monitor_local_echo() {
+        let cli = Cli::try_parse_from(["hisiflash", "monitor", "--local-echo"]).unwrap();
+        if let Commands::Monitor { local_echo, .. } = cli.command {
+            assert!(local_echo);
+        } else {
+            panic!("Expected Monitor command");
+        }
+
+        assert!(
+            Cli::try_parse_from(["hisiflash", "monitor", "--local-echo", "--line-input"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_monitor_line_filters() {
+        let cli = Cli::try_parse_from([
+            "hisiflash",
+            "monitor",
+            "--include",
+            "^app",
+            "--exclude",
+            "tick",
+            "--log",
+            "serial.log",
+            "--filter-log",
+        ])
+        .unwrap();
+        if let Commands::Monitor {
+            include,
+            exclude,
+            filter_log,
+            ..
+        } = cli.command
+        {
+            assert_eq!(
+                include
+                    .map(|re| re.to_string())
+                    .as_deref(),
+                Some("^app")
+            );
+            assert_eq!(
+                exclude
+                    .map(|re| re.to_string())
+                    .as_deref(),
+                Some("tick")
+            );
+            assert!(filter_log);
+        } else {
+            panic!("Expected Monitor command");
+        }
+
+        for args in [
+            &["hisiflash", "monitor", "--include", "("][..],
+            &["hisiflash", "monitor", "--include", "x", "--raw"],
+            &["hisiflash", "monitor", "--exclude", "x", "--filter-log"],
+        ] {
+            assert!(Cli::try_parse_from(args).is_err(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_monitor_log_format() {
+        let cli = Cli::try_parse_from([
+            "hisiflash",
+            "monitor",
+            "--log",
+            "serial.jsonl",
+            "--log-format",
+            "jsonl",
+        ])
+        .unwrap();
+        if let Commands::Monitor { log_format, .. } = cli.command {
+            assert_eq!(log_format, LogFormat::Jsonl);
+        } else {
+            panic!("Expected Monitor command");
+        }
+
+        assert!(Cli::try_parse_from(["hisiflash", "monitor", "--log-format", "jsonl"]).is_err());
+    }
+
     #[test]
     fn test_cli_parse_completions() {
         let cli = Cli::try_parse_from(["hisiflash", "completions", "bash"]).unwrap();
@@ -1408,6 +2411,16 @@ mod cli_tests {
             "--list-all-ports",
             "--config",
             "/tmp/config.toml",
+            "--trace-file",
+            "handshake.log",
+            "--dump-device-errors",
+            "errors.bin",
+            "--handshake-baud",
+            "9600",
+            "--reset-settle-ms",
+            "1500",
+            "--auto-enter",
+            "dtr-boot",
             "list-ports",
         ])
         .unwrap();
@@ -1427,6 +2440,20 @@ mod cli_tests {
         assert!(cli.non_interactive);
         assert!(cli.confirm_port);
         assert!(cli.list_all_ports);
+        assert_eq!(
+            cli.trace_file
+                .as_deref(),
+            Some(Path::new("handshake.log"))
+        );
+        assert_eq!(
+            cli.dump_device_errors
+                .as_deref(),
+            Some(Path::new("errors.bin"))
+        );
+        assert_eq!(cli.handshake_baud, Some(9600));
+        assert_eq!(cli.reset_settle_ms, Some(1500));
+        assert_eq!(cli.auto_enter, BootloaderEntry::preset("dtr-boot"));
+        assert!(Cli::try_parse_from(["hisiflash", "--auto-enter", "X1", "list-ports"]).is_err());
     }
 
     #[test]
@@ -1519,6 +2546,63 @@ mod cli_tests {
         }
     }
 
+    #[test]
+    fn test_apply_config_defaults_for_monitor() {
+        let mut config = Config::default();
+        config
+            .monitor
+            .baud = Some(9600);
+        config
+            .monitor
+            .timestamp = true;
+
+        let cmd = Cli::command();
+        let matches = cmd
+            .try_get_matches_from(["hisiflash", "monitor", "--monitor-baud", "57600"])
+            .unwrap();
+        let mut cli = Cli::from_arg_matches(&matches).unwrap();
+
+        apply_config_defaults(&mut cli, &matches, &config).unwrap();
+
+        if let Commands::Monitor {
+            monitor_baud,
+            timestamp,
+            ..
+        } = cli.command
+        {
+            assert_eq!(monitor_baud, 57600);
+            assert!(timestamp);
+        } else {
+            panic!("Expected Monitor command");
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_config_init() {
+        let cli = Cli::try_parse_from(["hisiflash", "config", "init", "--force"]).unwrap();
+        if let Commands::Config {
+            action:
+                ConfigAction::Init {
+                    output,
+                    global,
+                    force,
+                },
+        } = cli.command
+        {
+            assert!(output.is_none());
+            assert!(!global);
+            assert!(force);
+        } else {
+            panic!("Expected Config command");
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_config_init_global_conflicts_with_output() {
+        let result = Cli::try_parse_from(["hisiflash", "config", "init", "--global", "out.toml"]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_run_no_args_returns_ok() {
         let args = vec!["hisiflash".to_string()];
@@ -1558,6 +2642,26 @@ mod cli_tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_apply_config_defaults_unsupported_chip() {
+        let mut config = Config::default();
+        config
+            .flash
+            .chip = Some("ws53".to_string());
+
+        let cmd = Cli::command();
+        let matches = cmd
+            .try_get_matches_from(["hisiflash", "list-ports"])
+            .unwrap();
+        let mut cli = Cli::from_arg_matches(&matches).unwrap();
+
+        let err = apply_config_defaults(&mut cli, &matches, &config).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CliError>(),
+            Some(CliError::Config(msg)) if msg.contains("ws53")
+        ));
+    }
+
     #[test]
     fn test_guess_chip_from_firmware_path_bs21e() {
         let chip = guess_chip_from_firmware_path(Path::new(
@@ -1599,6 +2703,40 @@ mod cli_tests {
         assert_eq!(map_exit_code(&err), 130);
     }
 
+    #[test]
+    fn test_map_exit_code_library_kinds() {
+        let code = |e: LibError| map_exit_code(&anyhow::Error::new(e));
+        assert_eq!(code(LibError::Config("bad".into())), 3);
+        assert_eq!(code(LibError::DeviceNotFound), 4);
+        assert_eq!(code(LibError::Unsupported("x".into())), 5);
+        assert_eq!(
+            code(LibError::Io(std::io::Error::from(
+                std::io::ErrorKind::PermissionDenied
+            ))),
+            6
+        );
+        assert_eq!(code(LibError::Timeout("ack".into())), 7);
+        assert_eq!(
+            code(LibError::CrcMismatch {
+                expected: 1,
+                actual: 2
+            }),
+            8
+        );
+        assert_eq!(code(LibError::Protocol("nak".into())), 9);
+        assert_eq!(code(LibError::HandshakeFailed("no reply".into())), 9);
+        assert_eq!(
+            code(LibError::Io(std::io::Error::from(
+                std::io::ErrorKind::Interrupted
+            ))),
+            130
+        );
+        assert_eq!(code(LibError::InvalidFwpkg("magic".into())), 1);
+
+        let wrapped = anyhow::Error::new(LibError::Timeout("ack".into())).context("flash failed");
+        assert_eq!(map_exit_code(&wrapped), 7);
+    }
+
     #[test]
     fn test_cli_invalid_chip() {
         let result = Cli::try_parse_from(["hisiflash", "--chip", "invalid_chip", "list-ports"]);
@@ -1762,6 +2900,7 @@ mod cli_tests {
         assert!(subcmd_names.contains(&"flash".to_string()));
         assert!(subcmd_names.contains(&"write".to_string()));
         assert!(subcmd_names.contains(&"erase".to_string()));
+        assert!(subcmd_names.contains(&"dfu".to_string()));
         assert!(subcmd_names.contains(&"info".to_string()));
         assert!(subcmd_names.contains(&"list-ports".to_string()));
         assert!(subcmd_names.contains(&"monitor".to_string()));
@@ -2085,7 +3224,10 @@ mod cli_tests {
             ("flash", "烧录"),
             ("write", "写入"),
             ("write-program", "写入"),
+            ("write-region", "写入"),
             ("erase", "擦除"),
+            ("write-otp", "OTP"),
+            ("dfu", "DFU"),
             ("info", "显示"),
             ("list-ports", "列出"),
             ("monitor", "监视器"),
@@ -0,0 +1,163 @@
+//! Live progress file for `flash --progress-file`.
+//!
+//! Supervisors that poll a file instead of reading a TTY get a small JSON
+//! object describing the partition being flashed:
+//!
+//! ```text
+//! {"partition":"app","done":65536,"total":1048576,"percent":6}
+//! ```
+//!
+//! Each update is written to a temporary file next to the target and renamed
+//! over it, so readers never see a partially written object.
+
+use {
+    log::warn,
+    std::{
+        ffi::OsString,
+        fs, io,
+        path::{Path, PathBuf},
+        time::{Duration, Instant},
+    },
+};
+
+/// Minimum time between two writes of the same partition's progress.
+const MIN_WRITE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Throttled writer for the progress file.
+pub(crate) struct ProgressFile {
+    path: PathBuf,
+    tmp_path: PathBuf,
+    partition: Option<String>,
+    last_write: Option<Instant>,
+    failed: bool,
+}
+
+impl ProgressFile {
+    /// Create the progress file at `path` with an initial, empty state.
+    ///
+    /// Failing here (e.g. the directory does not exist) is reported to the
+    /// caller; later write failures only log a warning so flashing goes on.
+    pub(crate) fn create(path: &Path) -> io::Result<Self> {
+        let mut tmp_name = path
+            .file_name()
+            .map_or_else(|| OsString::from("progress"), OsString::from);
+        tmp_name.push(".tmp");
+
+        let progress = Self {
+            path: path.to_path_buf(),
+            tmp_path: path.with_file_name(tmp_name),
+            partition: None,
+            last_write: None,
+            failed: false,
+        };
+        progress.write(None, 0, 0)?;
+        Ok(progress)
+    }
+
+    /// Record progress from the flash callback.
+    ///
+    /// Writes at most every [`MIN_WRITE_INTERVAL`], except that the first
+    /// update of a partition and its completion are always written.
+    pub(crate) fn update(&mut self, partition: &str, done: usize, total: usize) {
+        let new_partition = self
+            .partition
+            .as_deref()
+            != Some(partition);
+        let due = self
+            .last_write
+            .is_none_or(|at| at.elapsed() >= MIN_WRITE_INTERVAL);
+        if !new_partition && !due && done < total {
+            return;
+        }
+
+        if new_partition {
+            self.partition = Some(partition.to_string());
+        }
+        self.last_write = Some(Instant::now());
+        if let Err(err) = self.write(Some(partition), done, total) {
+            if !self.failed {
+                warn!(
+                    "Failed to write progress file {}: {err}",
+                    self.path
+                        .display()
+                );
+                self.failed = true;
+            }
+        }
+    }
+
+    fn write(&self, partition: Option<&str>, done: usize, total: usize) -> io::Result<()> {
+        let percent = (done * 100)
+            .checked_div(total)
+            .unwrap_or(0);
+        let json = serde_json::json!({
+            "partition": partition,
+            "done": done,
+            "total": total,
+            "percent": percent,
+        });
+        fs::write(&self.tmp_path, json.to_string())?;
+        fs::rename(&self.tmp_path, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(path: &Path) -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_progress_file_writes_state_and_leaves_no_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir
+            .path()
+            .join("flash.json");
+
+        let mut progress = ProgressFile::create(&path).unwrap();
+        assert_eq!(read(&path)["partition"], serde_json::Value::Null);
+
+        progress.update("app", 512, 2048);
+        let state = read(&path);
+        assert_eq!(state["partition"], "app");
+        assert_eq!(state["done"], 512);
+        assert_eq!(state["total"], 2048);
+        assert_eq!(state["percent"], 25);
+        assert!(
+            !dir.path()
+                .join("flash.json.tmp")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn test_progress_file_throttles_but_keeps_completion() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir
+            .path()
+            .join("flash.json");
+        let mut progress = ProgressFile::create(&path).unwrap();
+
+        progress.update("app", 0, 100);
+        progress.update("app", 50, 100);
+        assert_eq!(read(&path)["done"], 0);
+
+        progress.update("app", 100, 100);
+        assert_eq!(read(&path)["percent"], 100);
+
+        progress.update("nv", 0, 10);
+        assert_eq!(read(&path)["partition"], "nv");
+    }
+
+    #[test]
+    fn test_progress_file_create_fails_for_missing_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir
+            .path()
+            .join("missing")
+            .join("flash.json");
+        assert!(ProgressFile::create(&path).is_err());
+    }
+}
@@ -12,10 +12,13 @@ use {
     anyhow::Result,
     console::style,
     dialoguer::{Confirm, Error as DialoguerError, Select, theme::ColorfulTheme},
-    hisiflash::{DetectedPort, Error as LibError, TransportKind, UsbDevice, discover_ports},
+    hisiflash::{
+        DetectedPort, Error as LibError, PortListing, TransportKind, UsbDevice, discover_ports,
+        try_discover_ports,
+    },
     log::{debug, error, info},
     rust_i18n::t,
-    std::{cmp::Ordering, io::IsTerminal},
+    std::{cmp::Ordering, io::IsTerminal, path::Path},
 };
 
 /// Options for serial port selection.
@@ -76,7 +79,25 @@ fn select_non_interactive_port(
 pub fn select_serial_port(options: &SerialOptions, config: &Config) -> Result<SelectedPort> {
     // If port explicitly specified, use it
     if let Some(port_name) = &options.port {
-        return find_port_by_name(port_name).ok_or_else(|| LibError::DeviceNotFound.into());
+        if let Some(selected) = network_port(port_name) {
+            return Ok(selected);
+        }
+        // Enumeration fails in some environments (e.g. containers without
+        // udev) and can miss ports that open fine by name, such as
+        // pseudo-terminals; use an existing device node as given.
+        match try_discover_ports() {
+            Ok(ports) => {
+                if let Some(selected) = find_port_by_name(&ports, port_name) {
+                    return Ok(selected);
+                }
+                if !Path::new(port_name).exists() {
+                    return Err(LibError::DeviceNotFound.into());
+                }
+                debug!("Port {port_name} was not enumerated; using it as given");
+            },
+            Err(e) => debug!("Port enumeration failed ({e}); using {port_name} as given"),
+        }
+        return Ok(unlisted_port(port_name));
     }
 
     // If port in config, use it
@@ -86,23 +107,14 @@ pub fn select_serial_port(options: &SerialOptions, config: &Config) -> Result<Se
         .serial
     {
         debug!("Using port from config: {port_name}");
-        if let Some(selected) = find_port_by_name(port_name) {
+        if let Some(selected) = network_port(port_name) {
+            return Ok(selected);
+        }
+        if let Some(selected) = find_port_by_name(&discover_ports(), port_name) {
             return Ok(selected);
         }
 
-        return Ok(SelectedPort {
-            port: DetectedPort {
-                name: port_name.clone(),
-                transport: TransportKind::Serial,
-                device: UsbDevice::Unknown,
-                vid: None,
-                pid: None,
-                manufacturer: None,
-                product: None,
-                serial: None,
-            },
-            is_known: false,
-        });
+        return Ok(unlisted_port(port_name));
     }
 
     // Detect available ports
@@ -120,11 +132,22 @@ pub fn select_serial_port(options: &SerialOptions, config: &Config) -> Result<Se
         .cloned()
         .collect();
 
-    // Select candidate set: known first unless user asks for all
-    let selection_ports: Vec<DetectedPort> = if options.list_all_ports || known_ports.is_empty() {
+    // Select candidate set: known first, then USB ports, unless user asks for
+    // all. Built-in UARTs (e.g. /dev/ttyS0) are never HiSilicon boards.
+    let selection_ports: Vec<DetectedPort> = if options.list_all_ports {
         ports
-    } else {
+    } else if !known_ports.is_empty() {
         known_ports
+    } else {
+        let total = ports.len();
+        let usb_ports: Vec<DetectedPort> = ports
+            .into_iter()
+            .filter(DetectedPort::is_usb)
+            .collect();
+        if usb_ports.is_empty() {
+            return Err(usage_err(t!("serial.no_usb_ports", count = total).as_ref()));
+        }
+        usb_ports
     };
 
     // Non-interactive mode must never prompt
@@ -185,9 +208,49 @@ fn map_prompt_error(err: DialoguerError) -> anyhow::Error {
 }
 
 /// Find a port by name.
-fn find_port_by_name(name: &str) -> Option<SelectedPort> {
-    let ports = discover_ports();
+/// Accept an `rfc2217://host:port` URL as-is.
+///
+/// Network ports are never enumerated, so they bypass discovery and are
+/// treated as known (there is nothing to remember in the config).
+fn network_port(name: &str) -> Option<SelectedPort> {
+    if !hisiflash::port::rfc2217::is_rfc2217_url(name) {
+        return None;
+    }
+    Some(SelectedPort {
+        port: DetectedPort {
+            name: name.to_string(),
+            transport: TransportKind::Rfc2217,
+            device: UsbDevice::Unknown,
+            vid: None,
+            pid: None,
+            manufacturer: None,
+            product: None,
+            serial: None,
+            usb_location: None,
+        },
+        is_known: true,
+    })
+}
+
+/// A serial port used by name without enumeration metadata.
+fn unlisted_port(name: &str) -> SelectedPort {
+    SelectedPort {
+        port: DetectedPort {
+            name: name.to_string(),
+            transport: TransportKind::Serial,
+            device: UsbDevice::Unknown,
+            vid: None,
+            pid: None,
+            manufacturer: None,
+            product: None,
+            serial: None,
+            usb_location: None,
+        },
+        is_known: false,
+    }
+}
 
+fn find_port_by_name(ports: &[DetectedPort], name: &str) -> Option<SelectedPort> {
     // Try exact match first
     if let Some(port) = ports
         .iter()
@@ -270,18 +333,9 @@ fn select_port_interactive(mut ports: Vec<DetectedPort>, config: &Config) -> Res
                     .clone()
             };
 
-            let device_info = if port
-                .device
-                .is_known()
-            {
-                format!(
-                    " [{}]",
-                    style(
-                        port.device
-                            .name()
-                    )
-                    .yellow()
-                )
+            let port = PortListing::from(port);
+            let device_info = if port.known {
+                format!(" [{}]", style(port.device).yellow())
             } else if let (Some(vid), Some(pid)) = (port.vid, port.pid) {
                 format!(" ({vid:04X}:{pid:04X})")
             } else {
@@ -510,6 +564,7 @@ mod tests {
             manufacturer: None,
             product: None,
             serial: None,
+            usb_location: None,
         };
         let config = Config::default();
         assert!(is_known_device(&port, &config));
@@ -526,6 +581,7 @@ mod tests {
             manufacturer: None,
             product: None,
             serial: None,
+            usb_location: None,
         };
         let config = Config::default();
         assert!(!is_known_device(&port, &config));
@@ -542,6 +598,7 @@ mod tests {
             manufacturer: None,
             product: None,
             serial: None,
+            usb_location: None,
         };
         let mut config = Config::default();
         config
@@ -565,6 +622,7 @@ mod tests {
             manufacturer: None,
             product: None,
             serial: None,
+            usb_location: None,
         };
         let config = Config::default();
         assert!(!is_known_device(&port, &config));
@@ -584,6 +642,7 @@ mod tests {
                 manufacturer: Some("Silicon Labs".to_string()),
                 product: Some("CP2102".to_string()),
                 serial: None,
+                usb_location: None,
             },
             is_known: true,
         };
@@ -614,6 +673,7 @@ mod tests {
                 manufacturer: None,
                 product: None,
                 serial: None,
+                usb_location: None,
             },
             DetectedPort {
                 name: "/dev/ttyUSB1".to_string(),
@@ -624,6 +684,7 @@ mod tests {
                 manufacturer: None,
                 product: None,
                 serial: None,
+                usb_location: None,
             },
         ];
 
@@ -668,6 +729,7 @@ mod tests {
             manufacturer: None,
             product: None,
             serial: None,
+            usb_location: None,
         }];
 
         let selected = select_non_interactive_port(ports, &Config::default()).unwrap();
@@ -678,4 +740,77 @@ mod tests {
             "/dev/ttyUSB0"
         );
     }
+
+    #[test]
+    fn test_select_explicit_rfc2217_url_skips_discovery() {
+        let options = SerialOptions {
+            port: Some("rfc2217://192.168.1.20:4001".to_string()),
+            non_interactive: true,
+            ..Default::default()
+        };
+
+        let selected = select_serial_port(&options, &Config::default()).unwrap();
+        assert_eq!(
+            selected
+                .port
+                .transport,
+            TransportKind::Rfc2217
+        );
+        assert_eq!(
+            selected
+                .port
+                .name,
+            "rfc2217://192.168.1.20:4001"
+        );
+        assert!(selected.is_known);
+    }
+
+    #[test]
+    fn test_select_explicit_port_without_enumeration() {
+        let dir = tempfile::tempdir().unwrap();
+        let node = dir
+            .path()
+            .join("ttyUNLISTED0");
+        std::fs::write(&node, b"").unwrap();
+        let name = node
+            .to_string_lossy()
+            .into_owned();
+
+        let options = SerialOptions {
+            port: Some(name.clone()),
+            non_interactive: true,
+            ..Default::default()
+        };
+        let selected = select_serial_port(&options, &Config::default()).unwrap();
+        assert_eq!(
+            selected
+                .port
+                .name,
+            name
+        );
+        assert_eq!(
+            selected
+                .port
+                .transport,
+            TransportKind::Serial
+        );
+
+        let options = SerialOptions {
+            port: Some(
+                dir.path()
+                    .join("ttyMISSING0")
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            non_interactive: true,
+            ..Default::default()
+        };
+        let err = select_serial_port(&options, &Config::default())
+            .err()
+            .expect("expected error");
+        assert!(matches!(
+            err.downcast_ref::<LibError>(),
+            Some(LibError::DeviceNotFound)
+        ));
+    }
 }
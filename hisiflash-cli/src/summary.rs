@@ -0,0 +1,125 @@
+//! One-line machine-readable result summary for `--quiet` runs.
+//!
+//! Device commands (`flash`, `write`, `write-program`, `write-region`,
+//! `erase`, `dfu`) print exactly one `key=value` line to stdout in quiet mode
+//! so scripts can check the outcome without parsing human-oriented stderr
+//! output:
+//!
+//! ```text
+//! result=ok partitions=8 bytes=1234567 elapsed_ms=4210
+//! result=error kind=timeout elapsed_ms=30012
+//! ```
+
+use {
+    crate::{CliError, was_interrupted},
+    hisiflash::Error as LibError,
+    std::time::Duration,
+};
+
+/// What a successful device command transferred.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct RunSummary {
+    /// Number of images sent to the device. `write` and `write-program`
+    /// include LoaderBoot; `write-region` and `bench`, which only use it to
+    /// reach the flash, do not.
+    pub partitions: usize,
+    /// Total payload bytes sent, counted like `partitions`.
+    pub bytes: u64,
+}
+
+/// Format the summary line for a finished command.
+///
+/// `summary` is `None` for commands that do not transfer images (`erase`,
+/// `dfu`).
+pub(crate) fn summary_line(
+    result: std::result::Result<Option<&RunSummary>, &anyhow::Error>,
+    elapsed: Duration,
+) -> String {
+    let elapsed_ms = elapsed.as_millis();
+    match result {
+        Ok(Some(summary)) => format!(
+            "result=ok partitions={} bytes={} elapsed_ms={elapsed_ms}",
+            summary.partitions, summary.bytes
+        ),
+        Ok(None) => format!("result=ok elapsed_ms={elapsed_ms}"),
+        Err(err) => format!(
+            "result=error kind={} elapsed_ms={elapsed_ms}",
+            error_kind(err)
+        ),
+    }
+}
+
+/// Classify an error into a stable, machine-readable kind.
+pub(crate) fn error_kind(err: &anyhow::Error) -> &'static str {
+    if was_interrupted() {
+        return "cancelled";
+    }
+
+    for cause in err.chain() {
+        if let Some(cli_err) = cause.downcast_ref::<CliError>() {
+            return match cli_err {
+                CliError::Usage(_) => "usage",
+                CliError::Config(_) => "config",
+                CliError::Cancelled(_) => "cancelled",
+            };
+        }
+        if let Some(lib_err) = cause.downcast_ref::<LibError>() {
+            return match lib_err.kind() {
+                "interrupted" => "cancelled",
+                kind => kind,
+            };
+        }
+        if cause
+            .downcast_ref::<std::io::Error>()
+            .is_some()
+        {
+            return "io";
+        }
+    }
+
+    "other"
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, anyhow::Context};
+
+    #[test]
+    fn test_summary_line_ok() {
+        let summary = RunSummary {
+            partitions: 8,
+            bytes: 1_234_567,
+        };
+        assert_eq!(
+            summary_line(Ok(Some(&summary)), Duration::from_millis(4210)),
+            "result=ok partitions=8 bytes=1234567 elapsed_ms=4210"
+        );
+        assert_eq!(
+            summary_line(Ok(None), Duration::from_millis(15)),
+            "result=ok elapsed_ms=15"
+        );
+    }
+
+    #[test]
+    fn test_summary_line_error_uses_library_kind_through_context() {
+        let err = Err::<(), _>(LibError::Timeout("no ACK".into()))
+            .context("flashing app")
+            .unwrap_err();
+        assert_eq!(
+            summary_line(Err(&err), Duration::from_millis(7)),
+            "result=error kind=timeout elapsed_ms=7"
+        );
+    }
+
+    #[test]
+    fn test_error_kind_cli_errors() {
+        let err = anyhow::Error::from(CliError::Usage("bad".into()));
+        assert_eq!(error_kind(&err), "usage");
+
+        let err = anyhow::Error::from(CliError::Config("bad".into()));
+        assert_eq!(error_kind(&err), "config");
+
+        let err = anyhow::anyhow!("something else");
+        assert_eq!(error_kind(&err), "other");
+    }
+}
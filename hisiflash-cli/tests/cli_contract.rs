@@ -102,6 +102,64 @@ fn info_json_success_returns_structured_json() {
     assert!(parsed["data"]["partitions"].is_array());
 }
 
+#[test]
+fn flash_list_partitions_exits_without_connecting() {
+    let dir = tempdir().expect("tempdir should be created");
+    let fwpkg = dir
+        .path()
+        .join("ok.fwpkg");
+    let valid_header: Vec<u8> = vec![
+        0xDF, 0xAD, 0xBE, 0xEF, // magic (FWPKG V1)
+        0x00, 0x00, // crc
+        0x00, 0x00, // cnt = 0
+        0x0C, 0x00, 0x00, 0x00, // len = 12 bytes total
+    ];
+    fs::write(&fwpkg, valid_header).expect("write fwpkg");
+
+    let mut cmd = cli_cmd();
+    cmd.args(["--lang", "en", "-p", "/dev/hisiflash-no-such-port", "flash"])
+        .arg("--list-partitions")
+        .arg(fwpkg)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("nothing was flashed"));
+}
+
+#[test]
+fn info_validate_json_fails_on_crc_mismatch() {
+    let dir = tempdir().expect("tempdir should be created");
+    let fwpkg = dir
+        .path()
+        .join("bad_crc.fwpkg");
+    let header: Vec<u8> = vec![
+        0xDF, 0xAD, 0xBE, 0xEF, // magic (FWPKG V1)
+        0x00, 0x00, // crc (deliberately wrong)
+        0x00, 0x00, // cnt = 0
+        0x0C, 0x00, 0x00, 0x00, // len = 12 bytes total
+    ];
+    fs::write(&fwpkg, header).expect("write fwpkg");
+
+    let mut cmd = cli_cmd();
+    let output = cmd
+        .args(["info", "--json", "--validate"])
+        .arg(fwpkg)
+        .output()
+        .expect("command should execute");
+
+    assert_eq!(
+        output
+            .status
+            .code(),
+        Some(8)
+    );
+    let stdout = String::from_utf8(output.stdout).expect("stdout should be utf-8");
+    let parsed: serde_json::Value =
+        serde_json::from_str(&stdout).expect("info --validate failure must be valid JSON");
+    assert_eq!(parsed["ok"], serde_json::Value::Bool(false));
+    assert_eq!(parsed["error"]["exit_code"], 8);
+}
+
 #[test]
 fn info_json_error_keeps_stdout_clean() {
     // Use temp dir for non-existent file path
@@ -278,6 +336,132 @@ fn exit_code_four_for_device_not_found() {
         .code(4);
 }
 
+/// `--bin -:ADDR` reads the payload from stdin before opening the port.
+#[test]
+fn write_reads_bin_from_stdin() {
+    let dir = tempdir().expect("tempdir should be created");
+    let loaderboot = dir
+        .path()
+        .join("loaderboot.bin");
+    fs::write(&loaderboot, b"lb").expect("write loaderboot");
+
+    let mut cmd = cli_cmd();
+    cmd.arg("-p")
+        .arg("INVALID_PORT_NAME_XYZ")
+        .arg("--chip")
+        .arg("ws63")
+        .arg("write")
+        .arg("--loaderboot")
+        .arg(&loaderboot)
+        .arg("--bin")
+        .arg("-:0x00800000")
+        .write_stdin(b"app".as_slice())
+        .assert()
+        .failure()
+        .code(4);
+
+    let mut cmd = cli_cmd();
+    cmd.arg("-p")
+        .arg("INVALID_PORT_NAME_XYZ")
+        .arg("write")
+        .arg("--loaderboot")
+        .arg(&loaderboot)
+        .arg("--bin")
+        .arg("-:0x00800000")
+        .arg("--bin")
+        .arg("-:0x00900000")
+        .write_stdin(b"app".as_slice())
+        .assert()
+        .failure()
+        .code(2);
+}
+
+/// Quiet mode prints exactly one machine-readable result line to stdout.
+#[test]
+fn quiet_write_failure_prints_single_summary_line() {
+    let dir = tempdir().expect("tempdir should be created");
+    let loaderboot = dir
+        .path()
+        .join("loaderboot.bin");
+    let app = dir
+        .path()
+        .join("app.bin");
+    fs::write(&loaderboot, b"lb").expect("write loaderboot");
+    fs::write(&app, b"app").expect("write app bin");
+
+    let output = cli_cmd()
+        .arg("--quiet")
+        .arg("-p")
+        .arg("INVALID_PORT_NAME_XYZ")
+        .arg("--chip")
+        .arg("ws63")
+        .arg("write")
+        .arg("--loaderboot")
+        .arg(&loaderboot)
+        .arg("--bin")
+        .arg(format!("{}:0x00800000", app.display()))
+        .output()
+        .expect("command should run");
+
+    assert_eq!(
+        output
+            .status
+            .code(),
+        Some(4)
+    );
+    let stdout = String::from_utf8(output.stdout).expect("stdout should be utf-8");
+    let lines: Vec<&str> = stdout
+        .lines()
+        .collect();
+    assert_eq!(lines.len(), 1, "expected one summary line, got {stdout:?}");
+    assert!(
+        lines[0].starts_with("result=error kind=device_not_found elapsed_ms="),
+        "unexpected summary line: {}",
+        lines[0]
+    );
+}
+
+/// `--skip-verify` still reports a CRC mismatch, even in quiet mode.
+#[test]
+fn skip_verify_warns_about_crc_mismatch() {
+    let dir = tempdir().expect("tempdir should be created");
+    let fwpkg = dir
+        .path()
+        .join("bad_crc.fwpkg");
+    let header: Vec<u8> = vec![
+        0xDF, 0xAD, 0xBE, 0xEF, // magic (FWPKG V1)
+        0x00, 0x00, // crc (deliberately wrong)
+        0x00, 0x00, // cnt = 0
+        0x0C, 0x00, 0x00, 0x00, // len = 12 bytes total
+    ];
+    fs::write(&fwpkg, header).expect("write fwpkg");
+
+    let output = cli_cmd()
+        .arg("--quiet")
+        .arg("-p")
+        .arg("INVALID_PORT_NAME_XYZ")
+        .arg("--chip")
+        .arg("ws63")
+        .arg("flash")
+        .arg("--skip-verify")
+        .arg(&fwpkg)
+        .output()
+        .expect("command should run");
+
+    assert_eq!(
+        output
+            .status
+            .code(),
+        Some(4),
+        "mismatch must not abort before opening the port"
+    );
+    let stderr = String::from_utf8(output.stderr).expect("stderr should be utf-8");
+    assert!(
+        stderr.contains("0x0000") && stderr.contains("CRC"),
+        "expected CRC mismatch warning, got {stderr:?}"
+    );
+}
+
 /// Exit code 130: cancelled (Ctrl+C)
 #[test]
 fn exit_code_130_for_cancelled_operation() {
@@ -367,6 +551,24 @@ fn erase_command_invalid_args_writes_to_stderr_only() {
         .stderr(predicate::str::is_empty().not());
 }
 
+#[test]
+fn write_otp_requires_explicit_confirmation() {
+    // Refused before the files are read or a port is opened.
+    let mut cmd = cli_cmd();
+    cmd.args([
+        "--chip",
+        "ws63",
+        "write-otp",
+        "--loaderboot",
+        "missing-lb.bin",
+        "missing-otp.bin",
+    ])
+    .assert()
+    .code(2)
+    .stdout(predicate::str::is_empty())
+    .stderr(predicate::str::contains("--i-understand-otp-is-permanent"));
+}
+
 #[test]
 fn info_command_with_valid_file_writes_to_stdout() {
     // Create a minimal valid fwpkg for testing - this tests the command executes
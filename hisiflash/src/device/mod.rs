@@ -5,15 +5,23 @@
 //! designed to support future transports (TCP, BLE, USB-HID, etc.).
 
 #[cfg(feature = "native")]
-use log::{debug, info, trace};
+use {
+    crate::port::{NativePortEnumerator, PortEnumerator},
+    log::{info, trace},
+};
 
-use crate::error::{Error, Result};
+use crate::{
+    error::{Error, Result},
+    port::PortInfo,
+};
 
 /// Transport type for discovered endpoints.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TransportKind {
     /// Serial transport (UART/USB CDC).
     Serial,
+    /// Network serial port reached over RFC 2217 (`rfc2217://host:port`).
+    Rfc2217,
     /// Unknown or unclassified transport.
     Unknown,
 }
@@ -88,6 +96,32 @@ impl DeviceKind {
         }
     }
 
+    /// Get the chip vendor for the device kind.
+    pub fn vendor(&self) -> &'static str {
+        match self {
+            Self::Ch340 => "WCH",
+            Self::Cp210x => "Silicon Labs",
+            Self::Ftdi => "FTDI",
+            Self::Prolific => "Prolific",
+            Self::HiSilicon => "HiSilicon",
+            Self::Unknown => "Unknown",
+        }
+    }
+
+    /// Get a short reliability hint for adapters with known quirks.
+    pub fn notes(&self) -> Option<&'static str> {
+        match self {
+            Self::Ch340 => {
+                Some("may be unreliable above 460800 baud; lower --baud if transfers stall")
+            },
+            Self::Prolific => Some(
+                "counterfeit PL2303 chips are common and often fail with current drivers; \
+                 prefer a CH340 or CP210x adapter",
+            ),
+            Self::Cp210x | Self::Ftdi | Self::HiSilicon | Self::Unknown => None,
+        }
+    }
+
     /// Check if this is a known/expected device kind.
     pub fn is_known(&self) -> bool {
         !matches!(self, Self::Unknown)
@@ -118,6 +152,13 @@ pub struct DetectedPort {
     pub product: Option<String>,
     /// Serial number (if available).
     pub serial: Option<String>,
+    /// Physical USB location as a bus-port path, e.g. `"1-1.2"` (if
+    /// available).
+    ///
+    /// Identifies the USB port an adapter is plugged into, which tells
+    /// identical adapters without serial numbers apart. Currently read from
+    /// sysfs on Linux only.
+    pub usb_location: Option<String>,
 }
 
 impl DetectedPort {
@@ -126,58 +167,242 @@ impl DetectedPort {
         self.device
             .is_known()
     }
+
+    /// Check if this endpoint is a USB serial port (it reports a VID).
+    pub fn is_usb(&self) -> bool {
+        self.vid
+            .is_some()
+    }
+
+    /// Identifier that survives re-plugging and device node renumbering.
+    ///
+    /// Combines VID/PID with the USB serial number, or with
+    /// [`Self::usb_location`] for adapters without one. `None` for ports
+    /// that are not USB or offer neither.
+    pub fn stable_id(&self) -> Option<String> {
+        let (Some(vid), Some(pid)) = (self.vid, self.pid) else {
+            return None;
+        };
+        if let Some(serial) = self
+            .serial
+            .as_deref()
+            .filter(|serial| !serial.is_empty())
+        {
+            return Some(format!("{vid:04x}:{pid:04x}:{serial}"));
+        }
+        self.usb_location
+            .as_ref()
+            .map(|location| format!("{vid:04x}:{pid:04x}@{location}"))
+    }
+
+    /// Convert to the [`PortInfo`] used by the port layer.
+    pub fn to_port_info(&self) -> PortInfo {
+        PortInfo {
+            name: self
+                .name
+                .clone(),
+            vid: self.vid,
+            pid: self.pid,
+            manufacturer: self
+                .manufacturer
+                .clone(),
+            product: self
+                .product
+                .clone(),
+            serial_number: self
+                .serial
+                .clone(),
+        }
+    }
 }
 
-/// Detect all available endpoints with metadata.
-#[cfg(feature = "native")]
-pub fn detect_ports() -> Vec<DetectedPort> {
-    let mut result = Vec::new();
+impl From<PortInfo> for DetectedPort {
+    /// Classify an enumerated serial port by its VID/PID.
+    fn from(info: PortInfo) -> Self {
+        let device = match (info.vid, info.pid) {
+            (Some(vid), Some(pid)) => DeviceKind::from_vid_pid(vid, pid),
+            _ => DeviceKind::Unknown,
+        };
+        Self {
+            name: info.name,
+            transport: TransportKind::Serial,
+            device,
+            vid: info.vid,
+            pid: info.pid,
+            manufacturer: info.manufacturer,
+            product: info.product,
+            serial: info.serial_number,
+            usb_location: None,
+        }
+    }
+}
 
-    match serialport::available_ports() {
-        Ok(ports) => {
-            for port_info in ports {
-                let mut detected = DetectedPort {
-                    name: port_info
-                        .port_name
-                        .clone(),
-                    transport: TransportKind::Serial,
-                    device: DeviceKind::Unknown,
-                    vid: None,
-                    pid: None,
-                    manufacturer: None,
-                    product: None,
-                    serial: None,
-                };
-
-                if let serialport::SerialPortType::UsbPort(usb_info) = port_info.port_type {
-                    detected.vid = Some(usb_info.vid);
-                    detected.pid = Some(usb_info.pid);
-                    detected.manufacturer = usb_info.manufacturer;
-                    detected.product = usb_info.product;
-                    detected.serial = usb_info.serial_number;
-                    detected.device = DeviceKind::from_vid_pid(usb_info.vid, usb_info.pid);
-
-                    trace!(
-                        "Found USB port: {} (VID: {:04X}, PID: {:04X}, Device: {:?})",
-                        port_info.port_name, usb_info.vid, usb_info.pid, detected.device
-                    );
-                }
-
-                result.push(detected);
-            }
-        },
-        Err(e) => {
-            debug!("Failed to enumerate serial ports: {e}");
-        },
+/// Flat view of a [`DetectedPort`] for listings.
+///
+/// Carries the derived fields (device name, vendor, notes, stable id) next
+/// to the raw USB metadata, so JSON output and interactive menus show the
+/// same information.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PortListing {
+    /// Endpoint name/path (e.g., "/dev/ttyUSB0" or "COM3").
+    pub name: String,
+    /// Device kind name, see [`DeviceKind::name`].
+    pub device: &'static str,
+    /// Bridge chip vendor, see [`DeviceKind::vendor`].
+    pub vendor: &'static str,
+    /// Reliability hint for the adapter, see [`DeviceKind::notes`].
+    pub notes: Option<&'static str>,
+    /// Whether the device kind is recognized.
+    pub known: bool,
+    /// USB Vendor ID (if available).
+    pub vid: Option<u16>,
+    /// USB Product ID (if available).
+    pub pid: Option<u16>,
+    /// Device manufacturer string (if available).
+    pub manufacturer: Option<String>,
+    /// Device product string (if available).
+    pub product: Option<String>,
+    /// Serial number (if available).
+    pub serial: Option<String>,
+    /// Physical USB location (if available).
+    pub usb_location: Option<String>,
+    /// See [`DetectedPort::stable_id`].
+    pub stable_id: Option<String>,
+}
+
+impl PortListing {
+    /// Check if this endpoint is a USB serial port (it reports a VID).
+    pub fn is_usb(&self) -> bool {
+        self.vid
+            .is_some()
     }
+}
 
-    result
+impl From<&DetectedPort> for PortListing {
+    fn from(port: &DetectedPort) -> Self {
+        Self {
+            name: port
+                .name
+                .clone(),
+            device: port
+                .device
+                .name(),
+            vendor: port
+                .device
+                .vendor(),
+            notes: port
+                .device
+                .notes(),
+            known: port
+                .device
+                .is_known(),
+            vid: port.vid,
+            pid: port.pid,
+            manufacturer: port
+                .manufacturer
+                .clone(),
+            product: port
+                .product
+                .clone(),
+            serial: port
+                .serial
+                .clone(),
+            usb_location: port
+                .usb_location
+                .clone(),
+            stable_id: port.stable_id(),
+        }
+    }
+}
+
+/// Extract the USB bus-port path from a resolved sysfs device path.
+///
+/// The path of a USB serial device runs through its interface directory,
+/// named `<bus>-<port path>:<config>.<interface>`, e.g.
+/// `/sys/devices/pci0000:00/0000:00:14.0/usb1/1-1/1-1.2/1-1.2:1.0/ttyUSB0`
+/// gives `1-1.2`.
+#[cfg_attr(not(all(feature = "native", target_os = "linux")), allow(dead_code))]
+fn usb_location_from_sysfs(path: &std::path::Path) -> Option<String> {
+    path.components()
+        .rev()
+        .filter_map(|component| {
+            component
+                .as_os_str()
+                .to_str()
+        })
+        .find_map(|component| {
+            let (location, interface) = component.split_once(':')?;
+            let (bus, ports) = location.split_once('-')?;
+            let is_digits = |s: &str| {
+                !s.is_empty()
+                    && s.bytes()
+                        .all(|b| b.is_ascii_digit())
+            };
+            (is_digits(bus)
+                && ports
+                    .split('.')
+                    .all(is_digits)
+                && interface
+                    .split('.')
+                    .all(is_digits))
+            .then(|| location.to_string())
+        })
+}
+
+/// Look up the USB location of a serial device node (Linux only).
+#[cfg(all(feature = "native", target_os = "linux"))]
+fn usb_location(port_name: &str) -> Option<String> {
+    let node = port_name.strip_prefix("/dev/")?;
+    let device = std::fs::canonicalize(format!("/sys/class/tty/{node}/device")).ok()?;
+    usb_location_from_sysfs(&device)
+}
+
+/// Look up the USB location of a serial device node (Linux only).
+#[cfg(all(feature = "native", not(target_os = "linux")))]
+fn usb_location(_port_name: &str) -> Option<String> {
+    None
+}
+
+/// Detect all available endpoints with metadata, reporting enumeration
+/// failures.
+///
+/// Enumeration fails in some environments (e.g. containers without udev),
+/// while ports opened by name still work.
+#[cfg(feature = "native")]
+pub fn try_detect_ports() -> Result<Vec<DetectedPort>> {
+    Ok(NativePortEnumerator::list_ports()?
+        .into_iter()
+        .map(|info| {
+            let mut detected = DetectedPort::from(info);
+            if detected.is_usb() {
+                detected.usb_location = usb_location(&detected.name);
+            }
+            if let (Some(vid), Some(pid)) = (detected.vid, detected.pid) {
+                trace!(
+                    "Found USB port: {} (VID: {vid:04X}, PID: {pid:04X}, Device: {:?})",
+                    detected.name, detected.device
+                );
+            }
+            detected
+        })
+        .collect())
 }
 
 /// Detect all available endpoints (WASM stub - always returns empty).
 #[cfg(not(feature = "native"))]
+pub fn try_detect_ports() -> Result<Vec<DetectedPort>> {
+    Ok(Vec::new())
+}
+
+/// Detect all available endpoints with metadata.
+///
+/// Returns an empty list when enumeration fails; see [`try_detect_ports`].
 pub fn detect_ports() -> Vec<DetectedPort> {
-    Vec::new()
+    try_detect_ports().unwrap_or_else(|e| {
+        log::debug!("Failed to enumerate serial ports: {e}");
+        Vec::new()
+    })
 }
 
 /// Detect endpoints that are likely HiSilicon development boards.
@@ -188,60 +413,61 @@ pub fn detect_hisilicon_ports() -> Vec<DetectedPort> {
         .collect()
 }
 
-/// Auto-detect a single HiSilicon endpoint.
-#[cfg(feature = "native")]
-pub fn auto_detect_port() -> Result<DetectedPort> {
-    let ports = detect_ports();
-
-    if let Some(port) = ports
+/// Detect all available endpoints as [`PortListing`]s.
+pub fn detect_port_listings() -> Vec<PortListing> {
+    detect_ports()
         .iter()
-        .find(|p| p.device == DeviceKind::HiSilicon)
-    {
-        info!("Auto-detected HiSilicon USB device: {}", port.name);
-        return Ok(port.clone());
-    }
+        .map(PortListing::from)
+        .collect()
+}
 
-    if let Some(port) = ports
-        .iter()
-        .find(|p| {
-            p.device
-                .is_high_priority()
-        })
+/// Preference of a port during auto-selection: HiSilicon devices first,
+/// then high-priority bridges, other known bridges and anything else.
+#[cfg_attr(not(feature = "native"), allow(dead_code))]
+fn selection_rank(port: &DetectedPort) -> u8 {
+    if port.device == DeviceKind::HiSilicon {
+        0
+    } else if port
+        .device
+        .is_high_priority()
     {
-        info!(
-            "Auto-detected {} USB-UART bridge: {}",
-            port.device
-                .name(),
-            port.name
-        );
-        return Ok(port.clone());
+        1
+    } else if port
+        .device
+        .is_known()
+    {
+        2
+    } else {
+        3
     }
+}
 
-    if let Some(port) = ports
+/// The preferred port in `ports` (see [`selection_rank`]); ties go to the
+/// first one enumerated.
+#[cfg_attr(not(feature = "native"), allow(dead_code))]
+fn best_port(ports: &[DetectedPort]) -> Option<&DetectedPort> {
+    ports
         .iter()
-        .find(|p| {
-            p.device
-                .is_known()
-        })
-    {
-        info!(
+        .min_by_key(|port| selection_rank(port))
+}
+
+/// Auto-detect a single HiSilicon endpoint.
+#[cfg(feature = "native")]
+pub fn auto_detect_port() -> Result<DetectedPort> {
+    let ports = detect_ports();
+    let port = best_port(&ports).ok_or(Error::DeviceNotFound)?;
+
+    match selection_rank(port) {
+        0 => info!("Auto-detected HiSilicon USB device: {}", port.name),
+        1 | 2 => info!(
             "Auto-detected {} USB-UART bridge: {}",
             port.device
                 .name(),
             port.name
-        );
-        return Ok(port.clone());
-    }
-
-    if let Some(port) = ports
-        .into_iter()
-        .next()
-    {
-        info!("Using first available port: {}", port.name);
-        return Ok(port);
+        ),
+        _ => info!("Using first available port: {}", port.name),
     }
-
-    Err(Error::DeviceNotFound)
+    Ok(port.clone())
 }
 
 /// Auto-detect a single HiSilicon endpoint (WASM stub - not supported).
@@ -253,17 +479,23 @@ pub fn auto_detect_port() -> Result<DetectedPort> {
     ))
 }
 
-/// Find an endpoint by name pattern.
+/// Find an endpoint whose name contains `pattern`.
+///
+/// When several match, the one [`auto_detect_port`] would prefer is
+/// returned, so a pattern such as `ttyUSB` picks a HiSilicon device or a
+/// CH340/CP210x bridge over other adapters.
 #[cfg(feature = "native")]
 pub fn find_port_by_pattern(pattern: &str) -> Result<DetectedPort> {
-    let ports = detect_ports();
-
-    ports
+    let matching: Vec<DetectedPort> = detect_ports()
         .into_iter()
-        .find(|p| {
+        .filter(|p| {
             p.name
                 .contains(pattern)
         })
+        .collect();
+
+    best_port(&matching)
+        .cloned()
         .ok_or(Error::DeviceNotFound)
 }
 
@@ -276,6 +508,159 @@ pub fn find_port_by_pattern(_pattern: &str) -> Result<DetectedPort> {
     ))
 }
 
+/// Whether the current user can open a serial port, as far as can be told
+/// without opening it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortAccess {
+    /// The port exists and looks accessible, or access cannot be determined.
+    Ok,
+    /// The port exists but the current user may not read and write it.
+    PermissionDenied {
+        /// How to get access, e.g. which group to join.
+        hint: String,
+    },
+    /// No device node with this name exists.
+    NotFound,
+}
+
+/// Check whether the current user can access the serial port `name`.
+///
+/// Opening a port may toggle DTR/RTS and reset the board, so only the device
+/// node is inspected. On Linux its mode and owner are compared with the
+/// process's user and groups; where that is not possible (other platforms,
+/// `COM` names, network ports) the result is [`PortAccess::Ok`] and opening
+/// the port reports any error.
+#[cfg(feature = "native")]
+pub fn check_port_access(name: &str) -> PortAccess {
+    if !name.starts_with('/') {
+        return PortAccess::Ok;
+    }
+
+    match std::fs::metadata(name) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => PortAccess::NotFound,
+        Err(_) => PortAccess::Ok,
+        #[cfg(target_os = "linux")]
+        Ok(metadata) => linux_access::check(name, &metadata),
+        #[cfg(not(target_os = "linux"))]
+        Ok(_) => PortAccess::Ok,
+    }
+}
+
+/// Check whether the current user can access a port (WASM stub - always
+/// [`PortAccess::Ok`]; the browser grants access through Web Serial).
+#[cfg(not(feature = "native"))]
+pub fn check_port_access(_name: &str) -> PortAccess {
+    PortAccess::Ok
+}
+
+#[cfg(all(feature = "native", target_os = "linux"))]
+mod linux_access {
+    use {
+        super::PortAccess,
+        std::{fs, os::unix::fs::MetadataExt},
+    };
+
+    /// Effective user and group IDs of the current process.
+    #[derive(Debug, PartialEq, Eq)]
+    pub(super) struct Credentials {
+        pub uid: u32,
+        pub gids: Vec<u32>,
+    }
+
+    pub(super) fn check(name: &str, metadata: &fs::Metadata) -> PortAccess {
+        let Some(credentials) = fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| parse_credentials(&status))
+        else {
+            return PortAccess::Ok;
+        };
+
+        if allows_read_write(
+            metadata.mode(),
+            metadata.uid(),
+            metadata.gid(),
+            &credentials,
+        ) {
+            return PortAccess::Ok;
+        }
+
+        let group = fs::read_to_string("/etc/group")
+            .ok()
+            .and_then(|groups| group_name(&groups, metadata.gid()).map(str::to_string));
+        PortAccess::PermissionDenied {
+            hint: permission_hint(name, group.as_deref()),
+        }
+    }
+
+    /// Read the effective UID and all GIDs from `/proc/self/status`.
+    pub(super) fn parse_credentials(status: &str) -> Option<Credentials> {
+        let field = |key: &str| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix(key))
+                .map(|rest| {
+                    rest.split_whitespace()
+                        .filter_map(|id| {
+                            id.parse::<u32>()
+                                .ok()
+                        })
+                        .collect::<Vec<_>>()
+                })
+        };
+
+        let uid = *field("Uid:")?.get(1)?;
+        let mut gids = field("Groups:").unwrap_or_default();
+        gids.push(*field("Gid:")?.get(1)?);
+        Some(Credentials { uid, gids })
+    }
+
+    /// Whether `mode` grants read and write access to `credentials`.
+    pub(super) fn allows_read_write(
+        mode: u32,
+        owner: u32,
+        group: u32,
+        credentials: &Credentials,
+    ) -> bool {
+        const RW: u32 = 0o6;
+
+        if credentials.uid == 0 || mode & RW == RW {
+            return true;
+        }
+        if credentials.uid == owner {
+            return (mode >> 6) & RW == RW;
+        }
+        credentials
+            .gids
+            .contains(&group)
+            && (mode >> 3) & RW == RW
+    }
+
+    /// Look up the name of `gid` in `/etc/group` contents.
+    pub(super) fn group_name(groups: &str, gid: u32) -> Option<&str> {
+        groups
+            .lines()
+            .find_map(|line| {
+                let mut fields = line.split(':');
+                let name = fields.next()?;
+                let id = fields.nth(1)?;
+                (id.parse() == Ok(gid)).then_some(name)
+            })
+    }
+
+    pub(super) fn permission_hint(name: &str, group: Option<&str>) -> String {
+        match group {
+            Some(group) => format!(
+                "{name} belongs to group `{group}`; add your user to it with `sudo usermod -aG \
+                 {group} $USER`, then log out and back in"
+            ),
+            None => format!(
+                "no permission to open {name}; add your user to the `dialout` (or `uucp`) group \
+                 with `sudo usermod -aG dialout $USER`, then log out and back in"
+            ),
+        }
+    }
+}
+
 /// Format a list of detected endpoints for display.
 pub fn format_port_list(ports: &[DetectedPort]) -> Vec<String> {
     let mut result = Vec::new();
@@ -337,6 +722,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_device_kind_vendor_and_notes() {
+        assert_eq!(DeviceKind::Ch340.vendor(), "WCH");
+        assert_eq!(DeviceKind::Cp210x.vendor(), "Silicon Labs");
+        assert_eq!(DeviceKind::Prolific.vendor(), "Prolific");
+        assert!(
+            DeviceKind::Prolific
+                .notes()
+                .is_some()
+        );
+        assert!(
+            DeviceKind::Cp210x
+                .notes()
+                .is_none()
+        );
+        assert!(
+            DeviceKind::Unknown
+                .notes()
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_device_kind_is_known() {
         assert!(DeviceKind::Ch340.is_known());
@@ -354,8 +761,10 @@ mod tests {
             manufacturer: None,
             product: None,
             serial: None,
+            usb_location: None,
         };
         assert!(known.is_likely_hisilicon());
+        assert!(known.is_usb());
 
         let unknown = DetectedPort {
             name: "/dev/ttyS0".to_string(),
@@ -366,8 +775,176 @@ mod tests {
             manufacturer: None,
             product: None,
             serial: None,
+            usb_location: None,
         };
         assert!(!unknown.is_likely_hisilicon());
+        assert!(!unknown.is_usb());
+    }
+
+    #[test]
+    fn test_best_port_prefers_known_devices() {
+        let port = |name: &str, device| DetectedPort {
+            name: name.to_string(),
+            transport: TransportKind::Serial,
+            device,
+            vid: None,
+            pid: None,
+            manufacturer: None,
+            product: None,
+            serial: None,
+            usb_location: None,
+        };
+        let mut ports = vec![
+            port("/dev/ttyS0", DeviceKind::Unknown),
+            port("/dev/ttyUSB0", DeviceKind::Prolific),
+            port("/dev/ttyUSB1", DeviceKind::Ch340),
+            port("/dev/ttyUSB2", DeviceKind::Cp210x),
+            port("/dev/ttyACM0", DeviceKind::HiSilicon),
+        ];
+        let best = |ports: &[DetectedPort]| {
+            best_port(ports).map(|p| {
+                p.name
+                    .clone()
+            })
+        };
+
+        assert_eq!(best(&ports).as_deref(), Some("/dev/ttyACM0"));
+        ports.pop();
+        assert_eq!(best(&ports).as_deref(), Some("/dev/ttyUSB1"));
+        ports.truncate(2);
+        assert_eq!(best(&ports).as_deref(), Some("/dev/ttyUSB0"));
+        ports.truncate(1);
+        assert_eq!(best(&ports).as_deref(), Some("/dev/ttyS0"));
+        assert_eq!(best(&[]), None);
+    }
+
+    #[test]
+    fn test_port_info_detected_port_conversion() {
+        let info = PortInfo {
+            name: "/dev/ttyUSB0".to_string(),
+            vid: Some(0x10C4),
+            pid: Some(0xEA60),
+            manufacturer: Some("Silicon Labs".to_string()),
+            product: Some("CP2102".to_string()),
+            serial_number: Some("0001".to_string()),
+        };
+        let detected = DetectedPort::from(info.clone());
+        assert_eq!(detected.device, DeviceKind::Cp210x);
+        assert_eq!(detected.transport, TransportKind::Serial);
+        assert_eq!(
+            detected
+                .serial
+                .as_deref(),
+            Some("0001")
+        );
+
+        let back = detected.to_port_info();
+        assert_eq!(back.name, info.name);
+        assert_eq!((back.vid, back.pid), (info.vid, info.pid));
+        assert_eq!(back.manufacturer, info.manufacturer);
+        assert_eq!(back.product, info.product);
+        assert_eq!(back.serial_number, info.serial_number);
+
+        let uart = DetectedPort::from(PortInfo {
+            name: "/dev/ttyS0".to_string(),
+            vid: None,
+            pid: None,
+            manufacturer: None,
+            product: None,
+            serial_number: None,
+        });
+        assert_eq!(uart.device, DeviceKind::Unknown);
+    }
+
+    #[test]
+    fn test_port_listing_from_detected_port() {
+        let mut port = DetectedPort::from(PortInfo {
+            name: "/dev/ttyUSB0".to_string(),
+            vid: Some(0x1A86),
+            pid: Some(0x7523),
+            manufacturer: None,
+            product: Some("USB Serial".to_string()),
+            serial_number: None,
+        });
+        port.usb_location = Some("1-1.2".to_string());
+
+        let listing = PortListing::from(&port);
+        assert_eq!(listing.name, "/dev/ttyUSB0");
+        assert_eq!(listing.device, "CH340/CH341");
+        assert_eq!(listing.vendor, "WCH");
+        assert!(
+            listing
+                .notes
+                .is_some()
+        );
+        assert!(listing.known && listing.is_usb());
+        assert_eq!(listing.product, port.product);
+        assert_eq!(
+            listing
+                .stable_id
+                .as_deref(),
+            Some("1a86:7523@1-1.2")
+        );
+
+        let uart = PortListing::from(&DetectedPort::from(PortInfo {
+            name: "/dev/ttyS0".to_string(),
+            vid: None,
+            pid: None,
+            manufacturer: None,
+            product: None,
+            serial_number: None,
+        }));
+        assert!(!uart.known && !uart.is_usb());
+        assert_eq!(uart.stable_id, None);
+    }
+
+    #[test]
+    fn test_usb_location_and_stable_id() {
+        use std::path::Path;
+
+        assert_eq!(
+            usb_location_from_sysfs(Path::new(
+                "/sys/devices/pci0000:00/0000:00:14.0/usb1/1-1/1-1.2/1-1.2:1.0/ttyUSB0"
+            ))
+            .as_deref(),
+            Some("1-1.2")
+        );
+        assert_eq!(
+            usb_location_from_sysfs(Path::new(
+                "/sys/devices/pci0000:00/0000:00:14.0/usb3/3-4/3-4:1.0/tty/ttyACM0"
+            ))
+            .as_deref(),
+            Some("3-4")
+        );
+        assert_eq!(
+            usb_location_from_sysfs(Path::new("/sys/devices/platform/serial8250/tty/ttyS0")),
+            None
+        );
+
+        let mut port = DetectedPort {
+            name: "/dev/ttyUSB0".to_string(),
+            transport: TransportKind::Serial,
+            device: DeviceKind::Ch340,
+            vid: Some(0x1A86),
+            pid: Some(0x7523),
+            manufacturer: None,
+            product: None,
+            serial: None,
+            usb_location: None,
+        };
+        assert_eq!(port.stable_id(), None);
+        port.usb_location = Some("1-1.2".to_string());
+        assert_eq!(
+            port.stable_id()
+                .as_deref(),
+            Some("1a86:7523@1-1.2")
+        );
+        port.serial = Some("A5069RR4".to_string());
+        assert_eq!(
+            port.stable_id()
+                .as_deref(),
+            Some("1a86:7523:A5069RR4")
+        );
     }
 
     #[test]
@@ -382,6 +959,7 @@ mod tests {
                 manufacturer: Some("WCH".to_string()),
                 product: Some("USB-Serial".to_string()),
                 serial: None,
+                usb_location: None,
             },
             DetectedPort {
                 name: "/dev/ttyUSB1".to_string(),
@@ -392,6 +970,7 @@ mod tests {
                 manufacturer: None,
                 product: None,
                 serial: None,
+                usb_location: None,
             },
         ];
 
@@ -401,4 +980,50 @@ mod tests {
         assert!(formatted[0].contains("CH340/CH341"));
         assert!(formatted[1].contains("/dev/ttyUSB1"));
     }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_check_port_access_missing_node_and_non_path_names() {
+        assert_eq!(
+            check_port_access("/dev/hisiflash-does-not-exist"),
+            PortAccess::NotFound
+        );
+        assert_eq!(check_port_access("COM3"), PortAccess::Ok);
+        assert_eq!(check_port_access("rfc2217://host:4000"), PortAccess::Ok);
+    }
+
+    #[cfg(all(feature = "native", target_os = "linux"))]
+    #[test]
+    fn test_linux_access_credentials_and_mode() {
+        use linux_access::{Credentials, allows_read_write, group_name, parse_credentials};
+
+        let status = "Name:\thisiflash\nUid:\t1000\t1000\t1000\t1000\nGid:\t1000\t1000\t1000\t1000\nGroups:\t4 24 27 \n";
+        let user = parse_credentials(status).unwrap();
+        assert_eq!(
+            user,
+            Credentials {
+                uid: 1000,
+                gids: vec![4, 24, 27, 1000],
+            }
+        );
+
+        // crw-rw---- root:dialout
+        assert!(!allows_read_write(0o20660, 0, 20, &user));
+        assert!(allows_read_write(0o20660, 0, 27, &user));
+        assert!(allows_read_write(0o20666, 0, 20, &user));
+        assert!(allows_read_write(
+            0o20660,
+            0,
+            20,
+            &Credentials {
+                uid: 0,
+                gids: vec![0],
+            }
+        ));
+
+        let groups = "root:x:0:\ndialout:x:20:\nuucp:x:14:alice\n";
+        assert_eq!(group_name(groups, 20), Some("dialout"));
+        assert_eq!(group_name(groups, 14), Some("uucp"));
+        assert_eq!(group_name(groups, 99), None);
+    }
 }
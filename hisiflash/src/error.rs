@@ -56,6 +56,82 @@ pub enum Error {
     /// Configuration error.
     #[error("Configuration error: {0}")]
     Config(String),
+
+    /// A partition image is larger than the flasher's size limit.
+    #[error("{name} is {size} bytes, larger than the {limit}-byte partition size limit")]
+    PartitionTooLarge {
+        /// Partition name.
+        name: String,
+        /// Image size in bytes.
+        size: usize,
+        /// Limit in effect, in bytes.
+        limit: usize,
+    },
+
+    /// Some partitions failed while flashing with keep-going enabled.
+    #[error("Flashing incomplete: {0}")]
+    PartitionsFailed(FlashReport),
+}
+
+/// Per-partition outcome of a flash that kept going past failures.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlashReport {
+    /// Partitions flashed successfully, in order.
+    pub flashed: Vec<String>,
+    /// Partitions that failed, with the error of their last attempt.
+    pub failed: Vec<(String, String)>,
+}
+
+impl std::fmt::Display for FlashReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} partitions failed",
+            self.failed
+                .len(),
+            self.failed
+                .len()
+                + self
+                    .flashed
+                    .len()
+        )?;
+        for (i, (name, error)) in self
+            .failed
+            .iter()
+            .enumerate()
+        {
+            let sep = if i == 0 { ": " } else { "; " };
+            write!(f, "{sep}{name} ({error})")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error {
+    /// Stable, machine-readable name of the error category.
+    ///
+    /// Intended for scripts and structured output; unlike the `Display`
+    /// message it does not change between releases or locales.
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io(e) if e.kind() == io::ErrorKind::Interrupted => "interrupted",
+            Self::Io(e) if e.kind() == io::ErrorKind::TimedOut => "timeout",
+            Self::Io(_) => "io",
+            Self::Serial(_) => "serial",
+            Self::InvalidFwpkg(_) => "invalid_fwpkg",
+            Self::CrcMismatch { .. } => "crc_mismatch",
+            Self::Timeout(_) => "timeout",
+            Self::DeviceNotFound => "device_not_found",
+            Self::HandshakeFailed(_) => "handshake_failed",
+            Self::Protocol(_) => "protocol",
+            Self::Ymodem(_) => "ymodem",
+            Self::Unsupported(_) => "unsupported",
+            Self::Config(_) => "config",
+            Self::PartitionTooLarge { .. } => "partition_too_large",
+            Self::PartitionsFailed(_) => "partitions_failed",
+        }
+    }
 }
 
 #[cfg(test)]
@@ -121,6 +197,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_error_kind_names() {
+        assert_eq!(Error::Timeout("x".into()).kind(), "timeout");
+        assert_eq!(Error::DeviceNotFound.kind(), "device_not_found");
+        assert_eq!(
+            Error::CrcMismatch {
+                expected: 1,
+                actual: 2,
+            }
+            .kind(),
+            "crc_mismatch"
+        );
+        assert_eq!(
+            Error::Io(io::Error::new(io::ErrorKind::Interrupted, "cancelled")).kind(),
+            "interrupted"
+        );
+        assert_eq!(
+            Error::Io(io::Error::new(io::ErrorKind::TimedOut, "slow")).kind(),
+            "timeout"
+        );
+        assert_eq!(Error::Io(io::Error::other("disk")).kind(), "io");
+        assert_eq!(
+            Error::PartitionsFailed(FlashReport::default()).kind(),
+            "partitions_failed"
+        );
+        assert_eq!(
+            Error::PartitionTooLarge {
+                name: "app".into(),
+                size: 2,
+                limit: 1,
+            }
+            .kind(),
+            "partition_too_large"
+        );
+    }
+
+    #[test]
+    fn test_flash_report_display() {
+        let report = FlashReport {
+            flashed: vec!["app".into()],
+            failed: vec![
+                ("nv".into(), "Timeout: no ACK".into()),
+                ("rom".into(), "YMODEM error: NAK".into()),
+            ],
+        };
+        assert_eq!(
+            Error::PartitionsFailed(report).to_string(),
+            "Flashing incomplete: 2 of 3 partitions failed: nv (Timeout: no ACK); rom (YMODEM \
+             error: NAK)"
+        );
+    }
+
     #[test]
     fn test_error_from_io() {
         let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "file not found");
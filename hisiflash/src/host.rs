@@ -8,6 +8,11 @@ pub fn discover_ports() -> Vec<DetectedPort> {
     crate::device::detect_ports()
 }
 
+/// Discover all available serial ports, failing if enumeration fails.
+pub fn try_discover_ports() -> crate::Result<Vec<DetectedPort>> {
+    crate::device::try_detect_ports()
+}
+
 /// Discover serial ports that are likely HiSilicon devices.
 #[must_use]
 pub fn discover_hisilicon_ports() -> Vec<DetectedPort> {
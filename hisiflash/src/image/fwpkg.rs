@@ -58,18 +58,24 @@
 
 use {
     crate::{
+        CancelContext,
         error::{Error, Result},
-        protocol::crc::crc16_xmodem,
+        protocol::crc::{crc16_xmodem, crc16_xmodem_update},
     },
-    byteorder::{LittleEndian, ReadBytesExt},
-    log::debug,
+    byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt},
+    log::{debug, warn},
     std::{
+        collections::BTreeMap,
         fs::File,
-        io::{BufReader, Read},
+        io::{BufReader, Read, Write},
         path::Path,
     },
 };
 
+/// Bytes checksummed between cancellation checks in
+/// [`Fwpkg::verify_crc_cancellable`].
+const CRC_CHUNK_SIZE: usize = 64 * 1024;
+
 /// FWPKG V1 magic number (little-endian).
 /// Stored as 0xDFADBEEF, reads as 0xEFBEADDF.
 pub const FWPKG_MAGIC_V1: u32 = 0xEFBEADDF;
@@ -120,6 +126,15 @@ pub enum FwpkgVersion {
     V2,
 }
 
+impl std::fmt::Display for FwpkgVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::V1 => write!(f, "V1"),
+            Self::V2 => write!(f, "V2"),
+        }
+    }
+}
+
 /// FWPKG file header.
 ///
 /// V1: 12 bytes (no name field)
@@ -193,21 +208,20 @@ impl FwpkgHeader {
         let len = reader.read_u32::<LittleEndian>()?;
 
         // Detect version based on magic
-        let (name, version) = if magic == FWPKG_MAGIC_V1 {
-            (String::new(), FwpkgVersion::V1)
-        } else if (FWPKG_MAGIC_V2_MIN..=FWPKG_MAGIC_V2_MAX).contains(&magic) {
-            // V2: read the 260-byte name field
-            let mut name_bytes = [0u8; NAME_SIZE_V2];
-            reader.read_exact(&mut name_bytes)?;
-            let name_end = name_bytes
-                .iter()
-                .position(|&c| c == 0)
-                .unwrap_or(NAME_SIZE_V2);
-            let name = String::from_utf8_lossy(&name_bytes[..name_end]).to_string();
-            (name, FwpkgVersion::V2)
-        } else {
-            // Invalid magic, but still return for error reporting
-            (String::new(), FwpkgVersion::V1)
+        let (name, version) = match magic_version(magic) {
+            Some(FwpkgVersion::V2) => {
+                // V2: read the 260-byte name field
+                let mut name_bytes = [0u8; NAME_SIZE_V2];
+                reader.read_exact(&mut name_bytes)?;
+                let name_end = name_bytes
+                    .iter()
+                    .position(|&c| c == 0)
+                    .unwrap_or(NAME_SIZE_V2);
+                let name = String::from_utf8_lossy(&name_bytes[..name_end]).to_string();
+                (name, FwpkgVersion::V2)
+            },
+            // V1, or an invalid magic that is still returned for error reporting
+            Some(FwpkgVersion::V1) | None => (String::new(), FwpkgVersion::V1),
         };
 
         Ok(Self {
@@ -220,11 +234,23 @@ impl FwpkgHeader {
         })
     }
 
+    /// Write the header to a writer (12 bytes for V1, 272 bytes for V2).
+    ///
+    /// The name field is only written for V2.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u32::<LittleEndian>(self.magic)?;
+        writer.write_u16::<LittleEndian>(self.crc)?;
+        writer.write_u16::<LittleEndian>(self.cnt)?;
+        writer.write_u32::<LittleEndian>(self.len)?;
+        if self.version == FwpkgVersion::V2 {
+            write_name(writer, &self.name, NAME_SIZE_V2)?;
+        }
+        Ok(())
+    }
+
     /// Check if the magic number is valid.
     pub fn is_valid(&self) -> bool {
-        let valid_magic = self.magic == FWPKG_MAGIC_V1
-            || (FWPKG_MAGIC_V2_MIN..=FWPKG_MAGIC_V2_MAX).contains(&self.magic);
-        valid_magic && (self.cnt as usize) <= MAX_PARTITIONS
+        magic_version(self.magic).is_some() && (self.cnt as usize) <= MAX_PARTITIONS
     }
 
     /// Get the header size based on version.
@@ -242,12 +268,56 @@ impl FwpkgHeader {
             FwpkgVersion::V2 => BIN_INFO_SIZE_V2,
         }
     }
+
+    /// Offset just past the partition table, where partition data may start.
+    pub fn expected_data_start(&self) -> usize {
+        self.header_size() + self.cnt as usize * self.bin_info_size()
+    }
+}
+
+/// Expected size in bytes of the whole package file, as recorded in the
+/// header's `len` field.
+///
+/// Lets a downloader check that a file is complete from its first
+/// [`FwpkgHeader::expected_data_start`] bytes alone.
+pub fn expected_total_from_header(header: &FwpkgHeader) -> u64 {
+    u64::from(header.len)
+}
+
+/// Classify a magic number as V1, V2 or neither.
+fn magic_version(magic: u32) -> Option<FwpkgVersion> {
+    if magic == FWPKG_MAGIC_V1 {
+        Some(FwpkgVersion::V1)
+    } else if (FWPKG_MAGIC_V2_MIN..=FWPKG_MAGIC_V2_MAX).contains(&magic) {
+        Some(FwpkgVersion::V2)
+    } else {
+        None
+    }
+}
+
+/// Detect the format version from the first bytes of a package.
+///
+/// Only the 4-byte magic is read, so this can route files to a parser
+/// without building a [`Fwpkg`]. Returns `None` when fewer than 4 bytes are
+/// given or the magic is not a FWPKG magic.
+pub fn detect_version(first_bytes: &[u8]) -> Option<FwpkgVersion> {
+    let magic = first_bytes.get(..4)?;
+    magic_version(u32::from_le_bytes([magic[0], magic[1], magic[2], magic[3]]))
+}
+
+/// Find the offset of the first V1/V2 FWPKG magic in `data`.
+///
+/// Useful for packages embedded in a larger container. Only the magic is
+/// checked; see [`Fwpkg::from_bytes_at`] to also parse what follows.
+pub fn find_magic(data: &[u8]) -> Option<usize> {
+    data.windows(4)
+        .position(|window| detect_version(window).is_some())
 }
 
 /// Partition/Image type.
 ///
 /// Based on HiSilicon's IMAGE_TYPE enum from fbb_burntool.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum PartitionType {
     /// LoaderBoot (first-stage bootloader).
@@ -343,6 +413,109 @@ impl PartitionType {
     pub const LoaderBoot: Self = Self::Loader;
 }
 
+impl std::fmt::Display for PartitionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Loader => write!(f, "Loader"),
+            Self::Normal => write!(f, "Normal"),
+            Self::KvNv => write!(f, "KV-NV"),
+            Self::Efuse => write!(f, "eFuse"),
+            Self::Otp => write!(f, "OTP"),
+            Self::Flashboot => write!(f, "FlashBoot"),
+            Self::Factory => write!(f, "Factory"),
+            Self::Version => write!(f, "Version"),
+            Self::SecurityA => write!(f, "Security-A"),
+            Self::SecurityB => write!(f, "Security-B"),
+            Self::SecurityC => write!(f, "Security-C"),
+            Self::ProtocolA => write!(f, "Protocol-A"),
+            Self::AppsA => write!(f, "Apps-A"),
+            Self::RadioConfig => write!(f, "RadioConfig"),
+            Self::Rom => write!(f, "ROM"),
+            Self::Emmc => write!(f, "eMMC"),
+            Self::Database => write!(f, "Database"),
+            Self::Unknown(v) => write!(f, "Unknown({v})"),
+        }
+    }
+}
+
+/// Selects partitions of a FWPKG by name or by role.
+///
+/// Parsed from strings such as `app` (name substring) or `type:security`
+/// (partition type). Type keywords are the [`PartitionType`] names in
+/// lowercase (`loader`, `normal`, `kvnv`, `flashboot`, `security_a`, ...),
+/// the groups `security` (A, B and C), `protocol` and `app`, or a numeric
+/// type value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartitionFilter {
+    /// Partition name contains this substring.
+    Name(String),
+    /// Partition type is one of these.
+    Type(Vec<PartitionType>),
+}
+
+impl PartitionFilter {
+    /// Check whether a partition is selected by this filter.
+    pub fn matches(&self, bin: &FwpkgBinInfo) -> bool {
+        match self {
+            Self::Name(name) => bin
+                .name
+                .contains(name.as_str()),
+            Self::Type(types) => types.contains(&bin.partition_type),
+        }
+    }
+
+    /// Map a `type:` keyword to the partition types it selects.
+    fn parse_type(kind: &str) -> Option<Vec<PartitionType>> {
+        use PartitionType::{
+            AppsA, Database, Efuse, Emmc, Factory, Flashboot, KvNv, Loader, Normal, Otp, ProtocolA,
+            RadioConfig, Rom, SecurityA, SecurityB, SecurityC, Version,
+        };
+
+        let types = match kind
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "loader" | "loaderboot" => vec![Loader],
+            "normal" => vec![Normal],
+            "kvnv" | "nv" => vec![KvNv],
+            "efuse" => vec![Efuse],
+            "otp" => vec![Otp],
+            "flashboot" => vec![Flashboot],
+            "factory" => vec![Factory],
+            "version" => vec![Version],
+            "security" => vec![SecurityA, SecurityB, SecurityC],
+            "security_a" => vec![SecurityA],
+            "security_b" => vec![SecurityB],
+            "security_c" => vec![SecurityC],
+            "protocol" | "protocol_a" => vec![ProtocolA],
+            "app" | "apps" | "apps_a" => vec![AppsA],
+            "radio" | "radio_config" => vec![RadioConfig],
+            "rom" => vec![Rom],
+            "emmc" => vec![Emmc],
+            "database" => vec![Database],
+            other => vec![PartitionType::from(
+                other
+                    .parse::<u32>()
+                    .ok()?,
+            )],
+        };
+        Some(types)
+    }
+}
+
+impl std::str::FromStr for PartitionFilter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.strip_prefix("type:") {
+            Some(kind) => Self::parse_type(kind)
+                .map(Self::Type)
+                .ok_or_else(|| Error::Config(format!("Unknown partition type: {kind}"))),
+            None => Ok(Self::Name(s.to_string())),
+        }
+    }
+}
+
 /// FWPKG partition information.
 ///
 /// V1: 52 bytes (32-byte name)
@@ -361,6 +534,11 @@ pub struct FwpkgBinInfo {
     pub burn_size: u32,
     /// Partition type.
     pub partition_type: PartitionType,
+    /// The 4 bytes after the type field in a V2 entry (always zero for V1).
+    ///
+    /// The vendor tools leave them zero; some packages carry vendor-specific
+    /// flags here. They are kept as-is and written back when repacking.
+    pub reserved: [u8; 4],
 }
 
 impl FwpkgBinInfo {
@@ -389,6 +567,7 @@ impl FwpkgBinInfo {
             burn_addr,
             burn_size,
             partition_type: type_value.into(),
+            reserved: [0; 4],
         })
     }
 
@@ -410,9 +589,9 @@ impl FwpkgBinInfo {
         let burn_size = reader.read_u32::<LittleEndian>()?;
         let type_value = reader.read_u32::<LittleEndian>()?;
 
-        // V2 BinInfo: 260 + 4*5 = 280 bytes, so 4 bytes padding
-        let mut padding = [0u8; 4];
-        reader.read_exact(&mut padding)?;
+        // V2 BinInfo: 260 + 4*5 = 280 bytes, followed by 4 reserved bytes
+        let mut reserved = [0u8; 4];
+        reader.read_exact(&mut reserved)?;
 
         Ok(Self {
             name,
@@ -421,6 +600,7 @@ impl FwpkgBinInfo {
             burn_addr,
             burn_size,
             partition_type: type_value.into(),
+            reserved,
         })
     }
 
@@ -432,12 +612,53 @@ impl FwpkgBinInfo {
         }
     }
 
+    /// Write BinInfo to a writer (52 bytes for V1, 284 bytes for V2).
+    pub fn write_to<W: Write>(&self, writer: &mut W, version: FwpkgVersion) -> Result<()> {
+        let name_size = match version {
+            FwpkgVersion::V1 => NAME_SIZE_V1,
+            FwpkgVersion::V2 => NAME_SIZE_V2,
+        };
+        write_name(writer, &self.name, name_size)?;
+        writer.write_u32::<LittleEndian>(self.offset)?;
+        writer.write_u32::<LittleEndian>(self.length)?;
+        writer.write_u32::<LittleEndian>(self.burn_addr)?;
+        writer.write_u32::<LittleEndian>(self.burn_size)?;
+        writer.write_u32::<LittleEndian>(
+            self.partition_type
+                .as_u32(),
+        )?;
+        match version {
+            FwpkgVersion::V1 if self.reserved != [0; 4] => {
+                return Err(Error::InvalidFwpkg(format!(
+                    "Partition {} has reserved bytes, which V1 packages cannot store",
+                    self.name
+                )));
+            },
+            FwpkgVersion::V1 => {},
+            FwpkgVersion::V2 => writer.write_all(&self.reserved)?,
+        }
+        Ok(())
+    }
+
     /// Check if this is the LoaderBoot partition.
     pub fn is_loaderboot(&self) -> bool {
         self.partition_type == PartitionType::Loader
     }
 }
 
+/// Write a NUL-padded fixed-size name field.
+fn write_name<W: Write>(writer: &mut W, name: &str, size: usize) -> Result<()> {
+    let bytes = name.as_bytes();
+    if bytes.len() > size {
+        return Err(Error::InvalidFwpkg(format!(
+            "Name {name:?} is longer than {size} bytes"
+        )));
+    }
+    writer.write_all(bytes)?;
+    writer.write_all(&vec![0u8; size - bytes.len()])?;
+    Ok(())
+}
+
 /// Parsed FWPKG firmware package.
 pub struct Fwpkg {
     /// File header.
@@ -466,11 +687,67 @@ impl Fwpkg {
 
     /// Parse a FWPKG from raw bytes.
     pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        let (header, bins) = Self::parse_tables(&data)?;
+        let fwpkg = Self { header, bins, data };
+        fwpkg.warn_duplicate_names();
+        Ok(fwpkg)
+    }
+
+    /// Parse a FWPKG embedded in `data`, searching from `start_offset`.
+    ///
+    /// `start_offset` is a hint: parsing starts at the first magic at or
+    /// after it whose header and partition table are valid, and the bytes
+    /// before the package are dropped.
+    pub fn from_bytes_at(mut data: Vec<u8>, start_offset: usize) -> Result<Self> {
+        let mut offset = start_offset;
+        loop {
+            let Some(pos) = data
+                .get(offset..)
+                .and_then(find_magic)
+                .map(|pos| offset + pos)
+            else {
+                return Err(Error::InvalidFwpkg(format!(
+                    "No FWPKG found at or after offset {start_offset:#X}"
+                )));
+            };
+            if Self::parse_tables(&data[pos..]).is_ok() {
+                data.drain(..pos);
+                return Self::from_bytes(data);
+            }
+            offset = pos + 1;
+        }
+    }
+
+    /// Parse a FWPKG from a borrowed byte slice.
+    ///
+    /// The slice is copied once after the header and partition table have
+    /// been validated, which is convenient for memory-mapped files or
+    /// embedded assets where the caller does not own a `Vec<u8>`.
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        let (header, bins) = Self::parse_tables(data)?;
+        let fwpkg = Self {
+            header,
+            bins,
+            data: data.to_vec(),
+        };
+        fwpkg.warn_duplicate_names();
+        Ok(fwpkg)
+    }
+
+    /// Log a warning for every partition name used more than once.
+    fn warn_duplicate_names(&self) {
+        for name in self.duplicate_names() {
+            warn!("FWPKG contains more than one partition named {name:?}");
+        }
+    }
+
+    /// Parse the header and partition table from raw bytes.
+    fn parse_tables(data: &[u8]) -> Result<(FwpkgHeader, Vec<FwpkgBinInfo>)> {
         if data.len() < HEADER_SIZE_V1 {
             return Err(Error::InvalidFwpkg("File too small for header".into()));
         }
 
-        let mut cursor = std::io::Cursor::new(&data);
+        let mut cursor = std::io::Cursor::new(data);
 
         // Read header (auto-detects version)
         let header = FwpkgHeader::read_from(&mut cursor)?;
@@ -499,7 +776,7 @@ impl Fwpkg {
 
         // Read partition info
         let bin_count = header.cnt as usize;
-        let expected_size = header.header_size() + bin_count * header.bin_info_size();
+        let expected_size = header.expected_data_start();
 
         if data.len() < expected_size {
             return Err(Error::InvalidFwpkg(format!(
@@ -525,7 +802,7 @@ impl Fwpkg {
             bins.push(bin_info);
         }
 
-        Ok(Self { header, bins, data })
+        Ok((header, bins))
     }
 
     /// Get the format version.
@@ -555,15 +832,33 @@ impl Fwpkg {
             .filter(|b| !b.is_loaderboot())
     }
 
+    /// Get all partitions of the given type.
+    pub fn bins_of_type(
+        &self,
+        partition_type: PartitionType,
+    ) -> impl Iterator<Item = &FwpkgBinInfo> {
+        self.bins
+            .iter()
+            .filter(move |b| b.partition_type == partition_type)
+    }
+
     /// Get the binary data for a partition.
     pub fn bin_data(&self, bin: &FwpkgBinInfo) -> Result<&[u8]> {
+        self.verify_partition_bounds(bin)?;
+
         let start = bin.offset as usize;
         let end = start + bin.length as usize;
+        Ok(&self.data[start..end])
+    }
+
+    /// Check that a partition's data lies entirely within the package.
+    pub fn verify_partition_bounds(&self, bin: &FwpkgBinInfo) -> Result<()> {
+        let end = u64::from(bin.offset) + u64::from(bin.length);
 
         if end
             > self
                 .data
-                .len()
+                .len() as u64
         {
             return Err(Error::InvalidFwpkg(format!(
                 "Partition {} data out of bounds (offset {}, length {}, file size {})",
@@ -575,7 +870,18 @@ impl Fwpkg {
             )));
         }
 
-        Ok(&self.data[start..end])
+        Ok(())
+    }
+
+    /// Check every partition's bounds up front.
+    ///
+    /// [`Fwpkg::bin_data`] only checks a partition when it is accessed, so a
+    /// malformed package would otherwise fail partway through flashing.
+    /// Returns the error for the first partition that does not fit.
+    pub fn verify_all_partition_bounds(&self) -> Result<()> {
+        self.bins
+            .iter()
+            .try_for_each(|bin| self.verify_partition_bounds(bin))
     }
 
     /// Verify the CRC checksum.
@@ -585,6 +891,14 @@ impl Fwpkg {
     /// 6 bytes) For V2: covers cnt(2) + len(4) + name(260) + BinInfo[]
     /// (header total - 6 bytes)
     pub fn verify_crc(&self) -> Result<()> {
+        self.verify_crc_cancellable(&CancelContext::none())
+    }
+
+    /// Verify the CRC checksum, checking `cancel` between chunks.
+    ///
+    /// Same as [`Self::verify_crc`], but the checksummed span is processed in
+    /// chunks so that very large packages can be interrupted.
+    pub fn verify_crc_cancellable(&self, cancel: &CancelContext) -> Result<()> {
         let header_size = self
             .header
             .header_size();
@@ -618,8 +932,11 @@ impl Fwpkg {
             ));
         }
 
-        let crc_data = &self.data[crc_start..crc_end];
-        let calculated_crc = crc16_xmodem(crc_data);
+        let mut calculated_crc = 0;
+        for chunk in self.data[crc_start..crc_end].chunks(CRC_CHUNK_SIZE) {
+            cancel.check()?;
+            calculated_crc = crc16_xmodem_update(calculated_crc, chunk);
+        }
 
         if calculated_crc
             != self
@@ -648,12 +965,307 @@ impl Fwpkg {
             .len()
     }
 
+    /// Get the partition at `index` in table order, or `None` if `index` is
+    /// not below [`Self::partition_count`].
+    pub fn partition(&self, index: usize) -> Option<&FwpkgBinInfo> {
+        self.bins
+            .get(index)
+    }
+
+    /// Flash space the package occupies: the sum of every partition's
+    /// `burn_size`.
+    pub fn total_flash_bytes(&self) -> u64 {
+        self.bins
+            .iter()
+            .map(|bin| u64::from(bin.burn_size))
+            .sum()
+    }
+
+    /// [`Self::total_flash_bytes`] broken down by partition type.
+    pub fn size_by_type(&self) -> BTreeMap<PartitionType, u64> {
+        let mut sizes = BTreeMap::new();
+        for bin in &self.bins {
+            *sizes
+                .entry(bin.partition_type)
+                .or_insert(0) += u64::from(bin.burn_size);
+        }
+        sizes
+    }
+
+    /// Iterate over the partitions in table order.
+    pub fn iter(&self) -> std::slice::Iter<'_, FwpkgBinInfo> {
+        self.bins
+            .iter()
+    }
+
     /// Find a partition by name.
+    ///
+    /// Returns the first match; see [`Self::duplicate_names`].
     pub fn find_by_name(&self, name: &str) -> Option<&FwpkgBinInfo> {
         self.bins
             .iter()
             .find(|b| b.name == name)
     }
+
+    /// Partition names that appear more than once, in table order.
+    pub fn duplicate_names(&self) -> Vec<String> {
+        let mut duplicates: Vec<String> = Vec::new();
+        for (i, bin) in self
+            .bins
+            .iter()
+            .enumerate()
+        {
+            if self.bins[..i]
+                .iter()
+                .any(|b| b.name == bin.name)
+                && !duplicates.contains(&bin.name)
+            {
+                duplicates.push(
+                    bin.name
+                        .clone(),
+                );
+            }
+        }
+        duplicates
+    }
+
+    /// Write the package to `path`, rebuilt from [`Self::header`] and
+    /// [`Self::bins`] with [`FwpkgBuilder`].
+    ///
+    /// Changes to the header or partition table are written out; each
+    /// partition's image is read from its current `offset` and `length`.
+    /// The header `len` and CRC are recomputed, so an unmodified package
+    /// round-trips to the same partition table and CRC.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut builder = FwpkgBuilder::new(
+            self.header
+                .version,
+        )
+        .with_magic(
+            self.header
+                .magic,
+        )
+        .with_name(
+            self.header
+                .name
+                .clone(),
+        );
+        for bin in &self.bins {
+            builder = builder.with_bin(
+                bin.clone(),
+                self.bin_data(bin)?
+                    .to_vec(),
+            );
+        }
+        let bytes = builder.build()?;
+
+        let path = path.as_ref();
+        debug!("Writing FWPKG to: {}", path.display());
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+}
+
+/// Assembles a FWPKG package from partition images.
+///
+/// Each partition is written at the `offset` recorded in its
+/// [`FwpkgBinInfo`]; its `length` is taken from the image data. Bytes not
+/// covered by the header, the partition table or an image are zero-filled, and
+/// the header CRC is always recomputed.
+///
+/// # Example
+///
+/// ```rust
+/// use hisiflash::{FwpkgBinInfo, FwpkgBuilder, FwpkgVersion, PartitionType};
+///
+/// let app = FwpkgBinInfo {
+///     name: "app".into(),
+///     offset: 64,
+///     length: 0,
+///     burn_addr: 0x0023_0000,
+///     burn_size: 4,
+///     partition_type: PartitionType::Normal,
+///     reserved: [0; 4],
+/// };
+/// let bytes = FwpkgBuilder::new(FwpkgVersion::V1)
+///     .with_bin(app, vec![1, 2, 3, 4])
+///     .build()?;
+/// assert_eq!(bytes.len(), 68);
+/// # Ok::<(), hisiflash::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct FwpkgBuilder {
+    version: FwpkgVersion,
+    magic: u32,
+    name: String,
+    len: Option<u32>,
+    bins: Vec<(FwpkgBinInfo, Vec<u8>)>,
+}
+
+impl FwpkgBuilder {
+    /// Create an empty builder for the given format version.
+    ///
+    /// The magic defaults to [`FWPKG_MAGIC_V1`] or [`FWPKG_MAGIC_V2_MIN`].
+    pub fn new(version: FwpkgVersion) -> Self {
+        let magic = match version {
+            FwpkgVersion::V1 => FWPKG_MAGIC_V1,
+            FwpkgVersion::V2 => FWPKG_MAGIC_V2_MIN,
+        };
+        Self {
+            version,
+            magic,
+            name: String::new(),
+            len: None,
+            bins: Vec::new(),
+        }
+    }
+
+    /// Set the header magic (must match the format version).
+    #[must_use]
+    pub fn with_magic(mut self, magic: u32) -> Self {
+        self.magic = magic;
+        self
+    }
+
+    /// Set the package name (V2 only).
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Override the header `len` field.
+    ///
+    /// Defaults to the size of the built package.
+    #[must_use]
+    pub fn with_total_len(mut self, len: u32) -> Self {
+        self.len = Some(len);
+        self
+    }
+
+    /// Append a partition and its image data.
+    #[must_use]
+    pub fn with_bin(mut self, info: FwpkgBinInfo, data: Vec<u8>) -> Self {
+        self.bins
+            .push((info, data));
+        self
+    }
+
+    /// Build the package bytes.
+    pub fn build(&self) -> Result<Vec<u8>> {
+        let mut header = FwpkgHeader {
+            magic: self.magic,
+            crc: 0,
+            cnt: u16::try_from(
+                self.bins
+                    .len(),
+            )
+            .unwrap_or(u16::MAX),
+            len: 0,
+            name: self
+                .name
+                .clone(),
+            version: self.version,
+        };
+
+        let v1_magic = self.magic == FWPKG_MAGIC_V1;
+        if !header.is_valid() || v1_magic != (self.version == FwpkgVersion::V1) {
+            return Err(Error::InvalidFwpkg(format!(
+                "Magic {:#010X} is not valid for a {:?} package ({} partitions)",
+                self.magic,
+                self.version,
+                self.bins
+                    .len()
+            )));
+        }
+        if self.version == FwpkgVersion::V1
+            && !self
+                .name
+                .is_empty()
+        {
+            return Err(Error::InvalidFwpkg(
+                "V1 packages have no package name".into(),
+            ));
+        }
+
+        let tables_end = header.header_size()
+            + self
+                .bins
+                .len()
+                * header.bin_info_size();
+        let mut infos = Vec::with_capacity(
+            self.bins
+                .len(),
+        );
+        let mut ranges = Vec::with_capacity(
+            self.bins
+                .len(),
+        );
+        for (info, data) in &self.bins {
+            let length = u32::try_from(data.len()).map_err(|_| {
+                Error::InvalidFwpkg(format!("Partition {} is larger than 4 GiB", info.name))
+            })?;
+            let start = info.offset as usize;
+            if start < tables_end {
+                return Err(Error::InvalidFwpkg(format!(
+                    "Partition {} at offset {start} overlaps the partition table (ends at \
+                     {tables_end})",
+                    info.name
+                )));
+            }
+            ranges.push((start, start + data.len(), &info.name));
+            infos.push(FwpkgBinInfo {
+                length,
+                ..info.clone()
+            });
+        }
+
+        ranges.sort_unstable();
+        for pair in ranges.windows(2) {
+            if pair[0].1 > pair[1].0 {
+                return Err(Error::InvalidFwpkg(format!(
+                    "Partitions {} and {} overlap",
+                    pair[0].2, pair[1].2
+                )));
+            }
+        }
+
+        let end = ranges
+            .iter()
+            .map(|range| range.1)
+            .max()
+            .unwrap_or(tables_end)
+            .max(tables_end);
+        header.len = match self.len {
+            Some(len) => len,
+            None => u32::try_from(end)
+                .map_err(|_| Error::InvalidFwpkg("Package is larger than 4 GiB".into()))?,
+        };
+
+        let mut out = Vec::with_capacity(end);
+        header.write_to(&mut out)?;
+        for info in &infos {
+            info.write_to(&mut out, self.version)?;
+        }
+        out.resize(end, 0);
+        for (info, data) in &self.bins {
+            let start = info.offset as usize;
+            out[start..start + data.len()].copy_from_slice(data);
+        }
+
+        let crc = crc16_xmodem(&out[6..tables_end]);
+        out[4..6].copy_from_slice(&crc.to_le_bytes());
+        Ok(out)
+    }
+}
+
+impl<'a> IntoIterator for &'a Fwpkg {
+    type Item = &'a FwpkgBinInfo;
+    type IntoIter = std::slice::Iter<'a, FwpkgBinInfo>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
 impl std::fmt::Debug for Fwpkg {
@@ -738,6 +1350,15 @@ mod tests {
         assert_eq!(PartitionType::LoaderBoot.as_u32(), 0);
     }
 
+    #[test]
+    fn test_display_impls() {
+        assert_eq!(PartitionType::KvNv.to_string(), "KV-NV");
+        assert_eq!(PartitionType::SecurityB.to_string(), "Security-B");
+        assert_eq!(PartitionType::Unknown(42).to_string(), "Unknown(42)");
+        assert_eq!(FwpkgVersion::V1.to_string(), "V1");
+        assert_eq!(FwpkgVersion::V2.to_string(), "V2");
+    }
+
     #[test]
     fn test_magic_constants() {
         assert_eq!(FWPKG_MAGIC_V1, 0xEFBEADDF);
@@ -780,6 +1401,50 @@ mod tests {
         assert_eq!(v2_header.bin_info_size(), BIN_INFO_SIZE_V2);
     }
 
+    #[test]
+    fn test_detect_version_from_magic() {
+        assert_eq!(
+            detect_version(&FWPKG_MAGIC_V1.to_le_bytes()),
+            Some(FwpkgVersion::V1)
+        );
+        assert_eq!(
+            detect_version(&FWPKG_MAGIC_V2_MIN.to_le_bytes()),
+            Some(FwpkgVersion::V2)
+        );
+        let mut v2 = FWPKG_MAGIC_V2_MAX
+            .to_le_bytes()
+            .to_vec();
+        v2.extend_from_slice(&[0; 8]);
+        assert_eq!(detect_version(&v2), Some(FwpkgVersion::V2));
+
+        assert_eq!(detect_version(&0xEFBE_ADCFu32.to_le_bytes()), None);
+        assert_eq!(detect_version(&FWPKG_MAGIC_V1.to_le_bytes()[..3]), None);
+        assert_eq!(detect_version(&[]), None);
+    }
+
+    #[test]
+    fn test_expected_sizes_from_header() {
+        let mut header = FwpkgHeader {
+            magic: FWPKG_MAGIC_V2_MIN,
+            crc: 0,
+            cnt: 3,
+            len: 0x0021_5140,
+            name: String::new(),
+            version: FwpkgVersion::V2,
+        };
+        assert_eq!(
+            header.expected_data_start(),
+            HEADER_SIZE_V2 + 3 * BIN_INFO_SIZE_V2
+        );
+        assert_eq!(expected_total_from_header(&header), 0x0021_5140);
+
+        header.version = FwpkgVersion::V1;
+        assert_eq!(
+            header.expected_data_start(),
+            HEADER_SIZE_V1 + 3 * BIN_INFO_SIZE_V1
+        );
+    }
+
     #[test]
     fn test_header_is_valid() {
         // Valid V1
@@ -1005,6 +1670,7 @@ mod tests {
             burn_addr: 0,
             burn_size: 100,
             partition_type: PartitionType::Loader,
+            reserved: [0; 4],
         };
         assert!(info.is_loaderboot());
 
@@ -1015,10 +1681,45 @@ mod tests {
             burn_addr: 0,
             burn_size: 100,
             partition_type: PartitionType::Normal,
+            reserved: [0; 4],
         };
         assert!(!info.is_loaderboot());
     }
 
+    #[test]
+    fn test_fwpkg_from_bytes_at_skips_container_prefix() {
+        let package = build_test_fwpkg_v1(&[("app", 0, 64, 0x800000, 64, 1)]);
+        let mut container = b"WRAPPER-HEADER".to_vec();
+        // A stray magic whose partition table does not fit is skipped.
+        let decoy = container.len();
+        container.extend_from_slice(&FWPKG_MAGIC_V1.to_le_bytes());
+        container.extend_from_slice(&[0xFF; 8]);
+        let start = container.len();
+        container.extend_from_slice(&package);
+        container.extend_from_slice(b"TRAILER");
+
+        assert_eq!(find_magic(&container), Some(decoy));
+        assert_eq!(find_magic(&container[decoy + 1..]), Some(start - decoy - 1));
+        assert_eq!(find_magic(b"no magic here"), None);
+
+        let fwpkg = Fwpkg::from_bytes_at(container.clone(), 0).unwrap();
+        fwpkg
+            .verify_crc()
+            .unwrap();
+        let app = fwpkg
+            .find_by_name("app")
+            .unwrap();
+        assert_eq!(
+            fwpkg
+                .bin_data(app)
+                .unwrap(),
+            &package[package.len() - 64..]
+        );
+
+        assert!(Fwpkg::from_bytes_at(container.clone(), start + 1).is_err());
+        assert!(Fwpkg::from_bytes_at(container, usize::MAX).is_err());
+    }
+
     #[test]
     fn test_fwpkg_from_bytes_valid_v1() {
         let data = build_test_fwpkg_v1(&[
@@ -1054,6 +1755,15 @@ mod tests {
             .normal_bins()
             .collect();
         assert_eq!(normals.len(), 2);
+        assert_eq!(
+            fwpkg
+                .bins_of_type(PartitionType::Flashboot)
+                .map(|b| b
+                    .name
+                    .as_str())
+                .collect::<Vec<_>>(),
+            ["flashboot"]
+        );
 
         // Check find_by_name
         assert!(
@@ -1068,6 +1778,136 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fwpkg_duplicate_names() {
+        let data = build_test_fwpkg_v1(&[
+            ("loaderboot", 0, 16, 0x0, 16, 0),
+            ("app", 0, 16, 0x800000, 16, 1),
+            ("nv", 0, 16, 0x900000, 16, 1),
+            ("app", 0, 16, 0xA00000, 16, 1),
+            ("app", 0, 16, 0xB00000, 16, 1),
+        ]);
+        let fwpkg = Fwpkg::from_bytes(data).unwrap();
+        assert_eq!(fwpkg.duplicate_names(), vec!["app".to_string()]);
+        assert_eq!(
+            fwpkg
+                .find_by_name("app")
+                .unwrap()
+                .burn_addr,
+            0x800000
+        );
+
+        let data = build_test_fwpkg_v1(&[("loaderboot", 0, 16, 0x0, 16, 0)]);
+        assert!(
+            Fwpkg::from_bytes(data)
+                .unwrap()
+                .duplicate_names()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_fwpkg_into_iterator() {
+        let data = build_test_fwpkg_v1(&[
+            ("loaderboot", 0, 16, 0x0, 16, 0),
+            ("app", 0, 16, 0x800000, 16, 1),
+        ]);
+        let fwpkg = Fwpkg::from_bytes(data).unwrap();
+
+        let mut names = Vec::new();
+        for bin in &fwpkg {
+            names.push(
+                bin.name
+                    .as_str(),
+            );
+        }
+        assert_eq!(names, ["loaderboot", "app"]);
+        assert_eq!(
+            fwpkg
+                .iter()
+                .count(),
+            fwpkg.partition_count()
+        );
+    }
+
+    #[test]
+    fn test_fwpkg_partition_by_index() {
+        let data = build_test_fwpkg_v1(&[
+            ("loaderboot", 0, 16, 0x0, 16, 0),
+            ("app", 0, 16, 0x800000, 16, 1),
+        ]);
+        let fwpkg = Fwpkg::from_bytes(data).unwrap();
+
+        assert_eq!(
+            fwpkg
+                .partition(1)
+                .map(|bin| bin
+                    .name
+                    .as_str()),
+            Some("app")
+        );
+        assert!(
+            fwpkg
+                .partition(fwpkg.partition_count())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_fwpkg_flash_usage_by_type() {
+        let data = build_test_fwpkg_v1(&[
+            ("loaderboot", 0, 16, 0x0, 0x8000, 0),
+            ("app", 0, 16, 0x230000, 0x10_0000, 1),
+            ("ext", 0, 16, 0x330000, 0x2000, 1),
+            ("nv", 0, 16, 0x5FC000, 0x4000, 2),
+        ]);
+        let fwpkg = Fwpkg::from_bytes(data).unwrap();
+
+        assert_eq!(fwpkg.total_flash_bytes(), 0x10_E000);
+        let sizes = fwpkg.size_by_type();
+        assert_eq!(
+            sizes
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![
+                (PartitionType::Loader, 0x8000),
+                (PartitionType::Normal, 0x10_2000),
+                (PartitionType::KvNv, 0x4000),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fwpkg_from_slice_matches_from_bytes() {
+        let data = build_test_fwpkg_v1(&[
+            ("loaderboot", 0, 16, 0x0, 16, 0),
+            ("app", 0, 64, 0x800000, 64, 1),
+        ]);
+        let borrowed = Fwpkg::from_slice(&data).unwrap();
+        let owned = Fwpkg::from_bytes(data.clone()).unwrap();
+
+        assert_eq!(borrowed.partition_count(), owned.partition_count());
+        assert_eq!(borrowed.bins[1].name, owned.bins[1].name);
+        assert!(
+            borrowed
+                .verify_crc()
+                .is_ok()
+        );
+        assert_eq!(
+            borrowed
+                .bin_data(&borrowed.bins[1])
+                .unwrap(),
+            owned
+                .bin_data(&owned.bins[1])
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fwpkg_from_slice_too_small() {
+        assert!(Fwpkg::from_slice(&[0u8; 4]).is_err());
+    }
+
     #[test]
     fn test_fwpkg_from_bytes_too_small() {
         let data = vec![0u8; 4]; // Too small for header
@@ -1102,6 +1942,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fwpkg_verify_crc_cancellable() {
+        let data = build_test_fwpkg_v1(&[("app", 0, 64, 0x800000, 64, 1)]);
+        let fwpkg = Fwpkg::from_bytes(data).unwrap();
+        assert!(
+            fwpkg
+                .verify_crc_cancellable(&CancelContext::none())
+                .is_ok()
+        );
+
+        let err = fwpkg
+            .verify_crc_cancellable(&CancelContext::new(|| true))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Io(ref io) if io.kind() == std::io::ErrorKind::Interrupted
+        ));
+    }
+
     #[test]
     fn test_fwpkg_verify_crc_mismatch() {
         let mut data = build_test_fwpkg_v1(&[("app", 0, 8, 0x800000, 8, 1)]);
@@ -1143,6 +2002,7 @@ mod tests {
             burn_addr: 0,
             burn_size: 100,
             partition_type: PartitionType::Normal,
+            reserved: [0; 4],
         };
         assert!(
             fwpkg
@@ -1151,6 +2011,298 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fwpkg_verify_all_partition_bounds_ok() {
+        let data = build_test_fwpkg_v1(&[
+            ("loaderboot", 0, 16, 0x0, 16, 0),
+            ("app", 0, 64, 0x800000, 64, 1),
+        ]);
+        let fwpkg = Fwpkg::from_bytes(data).unwrap();
+        assert!(
+            fwpkg
+                .verify_all_partition_bounds()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_fwpkg_verify_all_partition_bounds_reports_first_violator() {
+        let mut data = build_test_fwpkg_v1(&[
+            ("loaderboot", 0, 16, 0x0, 16, 0),
+            ("app", 0, 64, 0x800000, 64, 1),
+        ]);
+        // Truncate the payload so only the first partition still fits.
+        data.truncate(data.len() - 1);
+        let fwpkg = Fwpkg::from_bytes(data).unwrap();
+
+        let err = fwpkg
+            .verify_all_partition_bounds()
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidFwpkg(ref msg) if msg.contains("app")));
+    }
+
+    #[test]
+    fn test_fwpkg_verify_partition_bounds_no_overflow() {
+        let data = build_test_fwpkg_v1(&[("app", 0, 8, 0x800000, 8, 1)]);
+        let fwpkg = Fwpkg::from_bytes(data).unwrap();
+        let bin = FwpkgBinInfo {
+            name: "huge".into(),
+            offset: u32::MAX,
+            length: u32::MAX,
+            burn_addr: 0,
+            burn_size: 0,
+            partition_type: PartitionType::Normal,
+            reserved: [0; 4],
+        };
+        assert!(
+            fwpkg
+                .verify_partition_bounds(&bin)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_partition_filter_parse_and_match() {
+        let bin = |name: &str, partition_type| FwpkgBinInfo {
+            name: name.into(),
+            offset: 0,
+            length: 0,
+            burn_addr: 0,
+            burn_size: 0,
+            partition_type,
+            reserved: [0; 4],
+        };
+        let security: PartitionFilter = "type:security"
+            .parse()
+            .unwrap();
+        assert!(security.matches(&bin("sec_b", PartitionType::SecurityB)));
+        assert!(!security.matches(&bin("security_app", PartitionType::AppsA)));
+
+        let app: PartitionFilter = "type:APP"
+            .parse()
+            .unwrap();
+        assert_eq!(app, PartitionFilter::Type(vec![PartitionType::AppsA]));
+
+        let numeric: PartitionFilter = "type:42"
+            .parse()
+            .unwrap();
+        assert!(numeric.matches(&bin("x", PartitionType::Unknown(42))));
+
+        let name: PartitionFilter = "flashboot"
+            .parse()
+            .unwrap();
+        assert!(name.matches(&bin("ws63_flashboot", PartitionType::Flashboot)));
+
+        assert!(matches!(
+            "type:bogus".parse::<PartitionFilter>(),
+            Err(Error::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_builder_matches_reference_v1_layout() {
+        let reference = build_test_fwpkg_v1(&[
+            ("loaderboot", 0, 16, 0x0, 16, 0),
+            ("app", 0, 64, 0x800000, 64, 1),
+        ]);
+        let fwpkg = Fwpkg::from_slice(&reference).unwrap();
+
+        let builder = fwpkg
+            .bins
+            .iter()
+            .fold(FwpkgBuilder::new(FwpkgVersion::V1), |builder, bin| {
+                builder.with_bin(
+                    bin.clone(),
+                    fwpkg
+                        .bin_data(bin)
+                        .unwrap()
+                        .to_vec(),
+                )
+            });
+
+        assert_eq!(
+            builder
+                .build()
+                .unwrap(),
+            reference
+        );
+    }
+
+    #[test]
+    fn test_builder_v2_roundtrip() {
+        let app = FwpkgBinInfo {
+            name: "应用".into(),
+            offset: 0,
+            length: 0,
+            burn_addr: 0x0023_0000,
+            burn_size: 0x1000,
+            partition_type: PartitionType::Normal,
+            reserved: [0xAA, 0x00, 0x00, 0x01],
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let offset = (HEADER_SIZE_V2 + BIN_INFO_SIZE_V2) as u32;
+        let bytes = FwpkgBuilder::new(FwpkgVersion::V2)
+            .with_magic(FWPKG_MAGIC_V2_MAX)
+            .with_name("ws63-app")
+            .with_bin(FwpkgBinInfo { offset, ..app }, vec![0x5A; 10])
+            .build()
+            .unwrap();
+
+        let fwpkg = Fwpkg::from_bytes(bytes).unwrap();
+        assert!(
+            fwpkg
+                .verify_crc()
+                .is_ok()
+        );
+        assert_eq!(fwpkg.version(), FwpkgVersion::V2);
+        assert_eq!(fwpkg.package_name(), "ws63-app");
+        assert_eq!(fwpkg.bins[0].name, "应用");
+        assert_eq!(fwpkg.bins[0].length, 10);
+        assert_eq!(fwpkg.bins[0].reserved, [0xAA, 0x00, 0x00, 0x01]);
+        assert_eq!(
+            fwpkg
+                .bin_data(&fwpkg.bins[0])
+                .unwrap(),
+            &[0x5A; 10]
+        );
+    }
+
+    #[test]
+    fn test_write_to_file_roundtrips() {
+        let app = FwpkgBinInfo {
+            name: "app".into(),
+            offset: 0,
+            length: 0,
+            burn_addr: 0x0023_0000,
+            burn_size: 0x1000,
+            partition_type: PartitionType::Normal,
+            reserved: [0; 4],
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let offset = (HEADER_SIZE_V2 + 2 * BIN_INFO_SIZE_V2) as u32;
+        let bytes = FwpkgBuilder::new(FwpkgVersion::V2)
+            .with_name("ws63-app")
+            .with_bin(
+                FwpkgBinInfo {
+                    offset,
+                    ..app.clone()
+                },
+                vec![0x11; 16],
+            )
+            .with_bin(
+                FwpkgBinInfo {
+                    name: "nv".into(),
+                    offset: offset + 16,
+                    partition_type: PartitionType::KvNv,
+                    ..app
+                },
+                vec![0x22; 8],
+            )
+            .build()
+            .unwrap();
+        let fwpkg = Fwpkg::from_bytes(bytes.clone()).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir
+            .path()
+            .join("out.fwpkg");
+        fwpkg
+            .write_to_file(&path)
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+
+        let mut reread = Fwpkg::from_file(&path).unwrap();
+        assert!(
+            reread
+                .verify_crc()
+                .is_ok()
+        );
+        assert_eq!(
+            reread
+                .header
+                .crc,
+            fwpkg
+                .header
+                .crc
+        );
+        assert_eq!(format!("{:?}", reread.bins), format!("{:?}", fwpkg.bins));
+
+        // Table edits are persisted with a fresh CRC.
+        reread.bins[1].burn_addr = 0x0040_0000;
+        reread
+            .write_to_file(&path)
+            .unwrap();
+        let edited = Fwpkg::from_file(&path).unwrap();
+        assert!(
+            edited
+                .verify_crc()
+                .is_ok()
+        );
+        assert_eq!(edited.bins[1].burn_addr, 0x0040_0000);
+        assert_eq!(
+            edited
+                .bin_data(&edited.bins[1])
+                .unwrap(),
+            &[0x22; 8]
+        );
+    }
+
+    #[test]
+    fn test_builder_rejects_bad_layouts() {
+        let bin = |name: &str, offset| FwpkgBinInfo {
+            name: name.into(),
+            offset,
+            length: 0,
+            burn_addr: 0,
+            burn_size: 0,
+            partition_type: PartitionType::Normal,
+            reserved: [0; 4],
+        };
+
+        // Inside the partition table
+        assert!(
+            FwpkgBuilder::new(FwpkgVersion::V1)
+                .with_bin(bin("a", 20), vec![0; 4])
+                .build()
+                .is_err()
+        );
+        // Overlapping payloads
+        assert!(
+            FwpkgBuilder::new(FwpkgVersion::V1)
+                .with_bin(bin("a", 200), vec![0; 8])
+                .with_bin(bin("b", 204), vec![0; 8])
+                .build()
+                .is_err()
+        );
+        // V1 names are limited to 32 bytes
+        assert!(
+            FwpkgBuilder::new(FwpkgVersion::V1)
+                .with_bin(bin(&"x".repeat(33), 200), vec![0; 8])
+                .build()
+                .is_err()
+        );
+        // V1 entries cannot store reserved bytes
+        assert!(
+            FwpkgBuilder::new(FwpkgVersion::V1)
+                .with_bin(
+                    FwpkgBinInfo {
+                        reserved: [1, 0, 0, 0],
+                        ..bin("a", 200)
+                    },
+                    vec![0; 8]
+                )
+                .build()
+                .is_err()
+        );
+        // Magic must match the version
+        assert!(
+            FwpkgBuilder::new(FwpkgVersion::V2)
+                .with_magic(FWPKG_MAGIC_V1)
+                .build()
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_fwpkg_debug_format() {
         let data = build_test_fwpkg_v1(&[("app", 0, 4, 0, 4, 1)]);
@@ -0,0 +1,324 @@
+//! Rebuildable FWPKG manifests.
+//!
+//! [`Fwpkg::export_manifest`] unpacks a package into a directory holding one
+//! `.bin` file per partition plus a `manifest.toml` describing the header and
+//! partition table. [`FwpkgBuilder::from_manifest`] packs such a directory
+//! back into FWPKG bytes.
+//!
+//! Unchanged inputs rebuild byte-identically (and therefore with the same
+//! CRC) as long as every byte of the original package belongs to the header,
+//! the partition table or a partition image, which is how packages produced
+//! by the vendor tools are laid out. Gaps between images are zero-filled.
+//!
+//! ```toml
+//! version = 2
+//! magic = 0xEFBEADD0
+//! name = "ws63-liteos-app"
+//! len = 2183488
+//!
+//! [[partition]]
+//! name = "loaderboot"
+//! file = "00_loaderboot.bin"
+//! type = 0
+//! offset = 0x00000C50
+//! burn_addr = 0x00000000
+//! burn_size = 0x00008B50
+//! ```
+//!
+//! A V2 entry whose reserved bytes are not all zero also gets a
+//! `reserved = 0x...` key holding them as a little-endian integer.
+
+use {
+    crate::{
+        error::{Error, Result},
+        image::fwpkg::{Fwpkg, FwpkgBinInfo, FwpkgBuilder, FwpkgVersion},
+    },
+    log::debug,
+    std::{
+        fmt::Write as _,
+        fs,
+        path::{Component, Path},
+    },
+    toml::{Table, Value},
+};
+
+/// File name of the manifest inside an export directory.
+pub const MANIFEST_FILE: &str = "manifest.toml";
+
+impl Fwpkg {
+    /// Export the package to `dir` as a [`MANIFEST_FILE`] plus one `.bin`
+    /// file per partition.
+    ///
+    /// The directory is created if needed; existing files with the same names
+    /// are overwritten. Use [`FwpkgBuilder::from_manifest`] to rebuild.
+    pub fn export_manifest(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)?;
+
+        let mut manifest = String::new();
+        let version = match self.version() {
+            FwpkgVersion::V1 => 1,
+            FwpkgVersion::V2 => 2,
+        };
+        let _ = writeln!(manifest, "version = {version}");
+        let _ = writeln!(
+            manifest,
+            "magic = {:#010X}",
+            self.header
+                .magic
+        );
+        if self.version() == FwpkgVersion::V2 {
+            let _ = writeln!(manifest, "name = {}", quote(self.package_name()));
+        }
+        let _ = writeln!(
+            manifest,
+            "len = {}",
+            self.header
+                .len
+        );
+
+        for (index, bin) in self
+            .bins
+            .iter()
+            .enumerate()
+        {
+            let file = format!("{index:02}_{}.bin", file_stem(&bin.name));
+            fs::write(dir.join(&file), self.bin_data(bin)?)?;
+            debug!("Exported {} to {file}", bin.name);
+
+            let _ = writeln!(manifest);
+            let _ = writeln!(manifest, "[[partition]]");
+            let _ = writeln!(manifest, "name = {}", quote(&bin.name));
+            let _ = writeln!(manifest, "file = {}", quote(&file));
+            let _ = writeln!(
+                manifest,
+                "type = {}",
+                bin.partition_type
+                    .as_u32()
+            );
+            let _ = writeln!(manifest, "offset = {:#010X}", bin.offset);
+            let _ = writeln!(manifest, "burn_addr = {:#010X}", bin.burn_addr);
+            let _ = writeln!(manifest, "burn_size = {:#010X}", bin.burn_size);
+            if bin.reserved != [0; 4] {
+                let _ = writeln!(
+                    manifest,
+                    "reserved = {:#010X}",
+                    u32::from_le_bytes(bin.reserved)
+                );
+            }
+        }
+
+        fs::write(dir.join(MANIFEST_FILE), manifest)?;
+        Ok(())
+    }
+}
+
+impl FwpkgBuilder {
+    /// Rebuild a package from a directory written by
+    /// [`Fwpkg::export_manifest`].
+    ///
+    /// Partition images are read from the files named in the manifest, so
+    /// they may be replaced before rebuilding; offsets are kept as recorded.
+    pub fn from_manifest(dir: &Path) -> Result<Vec<u8>> {
+        let text = fs::read_to_string(dir.join(MANIFEST_FILE))?;
+        let table: Table = text
+            .parse()
+            .map_err(|e| manifest_error(format!("{e}")))?;
+
+        let version = match get_u32(&table, "version")? {
+            1 => FwpkgVersion::V1,
+            2 => FwpkgVersion::V2,
+            other => return Err(manifest_error(format!("unsupported version {other}"))),
+        };
+        let mut builder = FwpkgBuilder::new(version)
+            .with_magic(get_u32(&table, "magic")?)
+            .with_total_len(get_u32(&table, "len")?);
+        if table.contains_key("name") {
+            builder = builder.with_name(get_str(&table, "name")?);
+        }
+
+        let partitions = match table.get("partition") {
+            None => &Vec::new(),
+            Some(Value::Array(partitions)) => partitions,
+            Some(_) => return Err(manifest_error("`partition` must be an array of tables")),
+        };
+        for partition in partitions {
+            let Value::Table(partition) = partition else {
+                return Err(manifest_error("`partition` must be an array of tables"));
+            };
+            let file = get_str(partition, "file")?;
+            if !Path::new(file)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+            {
+                return Err(manifest_error(format!(
+                    "partition file {file:?} must be a relative path inside the manifest directory"
+                )));
+            }
+
+            let info = FwpkgBinInfo {
+                name: get_str(partition, "name")?.to_string(),
+                offset: get_u32(partition, "offset")?,
+                length: 0,
+                burn_addr: get_u32(partition, "burn_addr")?,
+                burn_size: get_u32(partition, "burn_size")?,
+                partition_type: get_u32(partition, "type")?.into(),
+                reserved: get_reserved(partition)?,
+            };
+            builder = builder.with_bin(info, fs::read(dir.join(file))?);
+        }
+
+        builder.build()
+    }
+}
+
+fn manifest_error(msg: impl std::fmt::Display) -> Error {
+    Error::InvalidFwpkg(format!("{MANIFEST_FILE}: {msg}"))
+}
+
+fn get_u32(table: &Table, key: &str) -> Result<u32> {
+    match table.get(key) {
+        Some(Value::Integer(value)) => u32::try_from(*value)
+            .map_err(|_| manifest_error(format!("`{key}` = {value} is out of range"))),
+        Some(_) => Err(manifest_error(format!("`{key}` must be an integer"))),
+        None => Err(manifest_error(format!("missing `{key}`"))),
+    }
+}
+
+/// Read the optional `reserved` key, stored as a little-endian integer.
+fn get_reserved(table: &Table) -> Result<[u8; 4]> {
+    if table.contains_key("reserved") {
+        Ok(get_u32(table, "reserved")?.to_le_bytes())
+    } else {
+        Ok([0; 4])
+    }
+}
+
+fn get_str<'a>(table: &'a Table, key: &str) -> Result<&'a str> {
+    match table.get(key) {
+        Some(Value::String(value)) => Ok(value),
+        Some(_) => Err(manifest_error(format!("`{key}` must be a string"))),
+        None => Err(manifest_error(format!("missing `{key}`"))),
+    }
+}
+
+/// Quote a string as a TOML basic string.
+fn quote(s: &str) -> String {
+    Value::String(s.to_string()).to_string()
+}
+
+/// Turn a partition name into a portable file name stem.
+fn file_stem(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if stem.is_empty() {
+        "partition".to_string()
+    } else {
+        stem
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::image::fwpkg::{
+            BIN_INFO_SIZE_V2, FWPKG_MAGIC_V2_MAX, HEADER_SIZE_V2, PartitionType,
+        },
+    };
+
+    fn sample_v2() -> Vec<u8> {
+        #[allow(clippy::cast_possible_truncation)]
+        let first = (HEADER_SIZE_V2 + 2 * BIN_INFO_SIZE_V2) as u32;
+        let bin = |name: &str, offset, ptype| FwpkgBinInfo {
+            name: name.into(),
+            offset,
+            length: 0,
+            burn_addr: 0x0020_0000,
+            burn_size: 0x1000,
+            partition_type: ptype,
+            reserved: [0; 4],
+        };
+        let mut signed = bin("loaderboot", first, PartitionType::Loader);
+        signed.reserved = [0x01, 0x00, 0x00, 0x80];
+        FwpkgBuilder::new(FwpkgVersion::V2)
+            .with_magic(FWPKG_MAGIC_V2_MAX)
+            .with_name("ws63 \"demo\"")
+            .with_bin(signed, vec![0x11; 16])
+            .with_bin(
+                bin("app/主程序", first + 16, PartitionType::Unknown(42)),
+                vec![0x22; 32],
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_manifest_roundtrip_is_byte_identical() {
+        let original = sample_v2();
+        let dir = tempfile::tempdir().unwrap();
+
+        Fwpkg::from_slice(&original)
+            .unwrap()
+            .export_manifest(dir.path())
+            .unwrap();
+
+        assert!(
+            dir.path()
+                .join("01_app____.bin")
+                .is_file()
+        );
+        let rebuilt = FwpkgBuilder::from_manifest(dir.path()).unwrap();
+        assert_eq!(rebuilt, original);
+    }
+
+    #[test]
+    fn test_manifest_rebuild_with_replaced_image() {
+        let dir = tempfile::tempdir().unwrap();
+        Fwpkg::from_slice(&sample_v2())
+            .unwrap()
+            .export_manifest(dir.path())
+            .unwrap();
+        fs::write(
+            dir.path()
+                .join("01_app____.bin"),
+            [0x33; 32],
+        )
+        .unwrap();
+
+        let rebuilt = Fwpkg::from_bytes(FwpkgBuilder::from_manifest(dir.path()).unwrap()).unwrap();
+        assert!(
+            rebuilt
+                .verify_crc()
+                .is_ok()
+        );
+        assert_eq!(
+            rebuilt
+                .bin_data(&rebuilt.bins[1])
+                .unwrap(),
+            &[0x33; 32]
+        );
+    }
+
+    #[test]
+    fn test_manifest_rejects_escaping_file_path() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path()
+                .join(MANIFEST_FILE),
+            "version = 1\nmagic = 0xEFBEADDF\nlen = 0\n\n[[partition]]\nname = \"app\"\nfile = \
+             \"../app.bin\"\ntype = 1\noffset = 64\nburn_addr = 0\nburn_size = 0\n",
+        )
+        .unwrap();
+
+        let err = FwpkgBuilder::from_manifest(dir.path()).unwrap_err();
+        assert!(matches!(err, Error::InvalidFwpkg(ref msg) if msg.contains("relative path")));
+    }
+}
@@ -1,3 +1,4 @@
 //! Firmware image formats.
 
 pub mod fwpkg;
+pub mod manifest;
@@ -41,6 +41,9 @@
 //!
 //! // Option 3: No cancellation (always returns "not cancelled")
 //! let cancel = CancelContext::none();
+//!
+//! // Option 4: Custom context that also honors the global flag
+//! let cancel = CancelContext::new(move || flag.load(Ordering::SeqCst)).or_global();
 //! ```
 //!
 //! ### Integration with Flasher
@@ -147,6 +150,27 @@ impl CancelContext {
             .is_some_and(|c| c())
     }
 
+    /// Combine two contexts: cancelled when either one is.
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        match (self.checker, other.checker) {
+            (None, None) => Self::none(),
+            (Some(checker), None) | (None, Some(checker)) => Self {
+                checker: Some(checker),
+            },
+            (Some(a), Some(b)) => Self::new(move || a() || b()),
+        }
+    }
+
+    /// Extend this context to also honor the global interrupt flag.
+    ///
+    /// Flashers only consult the context they were given, so use this when
+    /// an explicit context should still react to [`set_interrupt_flag`].
+    #[must_use]
+    pub fn or_global(self) -> Self {
+        self.or(cancel_context_from_global())
+    }
+
     /// Check and return an Interrupted error if cancelled.
     pub fn check(&self) -> crate::Result<()> {
         if self.is_cancelled() {
@@ -166,6 +190,25 @@ impl From<fn() -> bool> for CancelContext {
     }
 }
 
+/// What a flash operation should do after a progress report.
+///
+/// Returned by the callback of [`Flasher::flash_fwpkg_controlled`], which is
+/// called after every acknowledged data block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProgressControl {
+    /// Keep transferring.
+    #[default]
+    Continue,
+    /// Hold the transfer after the current block. The callback is called
+    /// again with the same position about every 50 ms until it returns
+    /// something else; the connection stays open meanwhile, but a device
+    /// left waiting too long may give up on the transfer.
+    Pause,
+    /// Stop with an [`std::io::ErrorKind::Interrupted`] error, like a
+    /// cancellation.
+    Abort,
+}
+
 /// Create a CancelContext that bridges to the global interrupt flag.
 ///
 /// This is used internally by native implementations to check for Ctrl-C.
@@ -190,6 +233,10 @@ pub fn is_interrupted_requested() -> bool {
     INTERRUPT_FLAG.load(Ordering::SeqCst)
 }
 
+/// Serializes tests that set the global interrupt flag.
+#[cfg(test)]
+pub(crate) static INTERRUPT_TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
 #[cfg(test)]
 pub(crate) fn test_set_interrupted(value: bool) {
     INTERRUPT_FLAG.store(value, Ordering::SeqCst);
@@ -200,15 +247,25 @@ pub(crate) fn test_set_interrupted(value: bool) {
 #[cfg(feature = "native")]
 pub use port::{NativePort, NativePortEnumerator};
 // Ws63Flasher 不直接导出，只通过 Flasher trait 访问
-pub use target::{ChipConfig, ChipFamily, ChipOps, Flasher};
-// CancelContext is already defined in this module, no need to re-export
+pub use target::{
+    AbortHandle, BootloaderEntry, ChipConfig, ChipFamily, ChipOps, DEFAULT_MAX_PARTITION_BYTES,
+    Flasher, OtpConfirmation,
+};
+// CancelContext and ProgressControl are defined in this module, no need to re-export
 pub use {
-    device::{DetectedPort, DeviceKind, TransportKind, UsbDevice},
-    error::{Error, Result},
-    host::{auto_detect_port, discover_hisilicon_ports, discover_ports},
-    image::fwpkg::{Fwpkg, FwpkgBinInfo, FwpkgHeader, FwpkgVersion, PartitionType},
+    device::{
+        DetectedPort, DeviceKind, PortAccess, PortListing, TransportKind, UsbDevice,
+        check_port_access, detect_port_listings,
+    },
+    error::{Error, FlashReport, Result},
+    host::{auto_detect_port, discover_hisilicon_ports, discover_ports, try_discover_ports},
+    image::fwpkg::{
+        Fwpkg, FwpkgBinInfo, FwpkgBuilder, FwpkgHeader, FwpkgVersion, PartitionFilter,
+        PartitionType,
+    },
     monitor::{
-        MonitorSession, clean_monitor_text, drain_utf8_lossy, format_monitor_output, split_utf8,
+        MonitorSession, clean_monitor_text, drain_utf8_lossy, format_monitor_output,
+        format_monitor_output_since, split_utf8,
     },
     port::{Port, PortEnumerator, PortInfo, SerialConfig},
     protocol::seboot::{CommandType, ImageType, SebootAck, SebootFrame, contains_handshake_ack},
@@ -218,14 +275,44 @@ pub use {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_cancel_context_or_combines_checkers() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let own = Arc::clone(&flag);
+        let combined =
+            CancelContext::new(move || own.load(Ordering::SeqCst)).or(CancelContext::none());
+        assert!(!combined.is_cancelled());
+
+        flag.store(true, Ordering::SeqCst);
+        assert!(combined.is_cancelled());
+
+        let always = CancelContext::new(|| true);
+        assert!(
+            CancelContext::none()
+                .or(always)
+                .is_cancelled()
+        );
+        assert!(
+            !CancelContext::none()
+                .or(CancelContext::none())
+                .is_cancelled()
+        );
+    }
+
     #[test]
     fn test_interrupt_checker_default_false() {
+        let _guard = INTERRUPT_TEST_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
         test_set_interrupted(false);
         assert!(!is_interrupted_requested());
     }
 
     #[test]
     fn test_interrupt_checker_toggle_true_false() {
+        let _guard = INTERRUPT_TEST_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
         test_set_interrupted(true);
         assert!(is_interrupted_requested());
 
@@ -67,6 +67,18 @@ impl MonitorSession {
             .write_request_to_send(enabled)?;
         Ok(())
     }
+
+    /// Start (`true`) or stop (`false`) sending a break condition on TX.
+    pub fn set_break(&mut self, enabled: bool) -> crate::Result<()> {
+        if enabled {
+            self.port
+                .set_break()?;
+        } else {
+            self.port
+                .clear_break()?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(not(feature = "native"))]
@@ -161,6 +173,19 @@ pub fn clean_monitor_text(text: &str) -> String {
 
 /// Format monitor output with optional timestamps.
 pub fn format_monitor_output(text: &str, timestamp: bool, at_line_start: &mut bool) -> String {
+    format_monitor_output_since(text, timestamp, None, at_line_start)
+}
+
+/// Format monitor output with optional timestamps relative to `origin`.
+///
+/// With `origin`, each line is prefixed with the time elapsed since then
+/// (`[+S.mmm]`) instead of the wall-clock time.
+pub fn format_monitor_output_since(
+    text: &str,
+    timestamp: bool,
+    origin: Option<std::time::Instant>,
+    at_line_start: &mut bool,
+) -> String {
     let normalized = text
         .replace("\r\n", "\n")
         .replace('\r', "\n");
@@ -183,14 +208,20 @@ pub fn format_monitor_output(text: &str, timestamp: bool, at_line_start: &mut bo
     }
 
     let mut out = String::with_capacity(normalized.len() + 128);
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default();
-    let total_secs = now.as_secs();
-    let millis = now.subsec_millis();
-    let hours = (total_secs / 3600) % 24;
-    let minutes = (total_secs / 60) % 60;
-    let seconds = total_secs % 60;
+    let prefix = if let Some(origin) = origin {
+        let elapsed = origin.elapsed();
+        format!("[+{}.{:03}]", elapsed.as_secs(), elapsed.subsec_millis())
+    } else {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let total_secs = now.as_secs();
+        let millis = now.subsec_millis();
+        let hours = (total_secs / 3600) % 24;
+        let minutes = (total_secs / 60) % 60;
+        let seconds = total_secs % 60;
+        format!("[{hours:02}:{minutes:02}:{seconds:02}.{millis:03}]")
+    };
 
     for c in normalized.chars() {
         match c {
@@ -201,10 +232,7 @@ pub fn format_monitor_output(text: &str, timestamp: bool, at_line_start: &mut bo
             _ => {
                 if *at_line_start {
                     use std::fmt::Write;
-                    let _ = write!(
-                        out,
-                        "\x1b[90m[{hours:02}:{minutes:02}:{seconds:02}.{millis:03}]\x1b[0m "
-                    );
+                    let _ = write!(out, "\x1b[90m{prefix}\x1b[0m ");
                     *at_line_start = false;
                 }
                 out.push(c);
@@ -217,7 +245,9 @@ pub fn format_monitor_output(text: &str, timestamp: bool, at_line_start: &mut bo
 
 #[cfg(test)]
 mod tests {
-    use super::{clean_monitor_text, drain_utf8_lossy, format_monitor_output};
+    use super::{
+        clean_monitor_text, drain_utf8_lossy, format_monitor_output, format_monitor_output_since,
+    };
 
     #[test]
     fn test_drain_utf8_lossy_replaces_invalid_bytes_and_continues() {
@@ -240,6 +270,89 @@ mod tests {
         assert!(buf.is_empty());
     }
 
+    /// Feed `stream` to [`drain_utf8_lossy`] in chunks ending at `splits`,
+    /// then decode whatever is still buffered at the end of the stream.
+    fn decode_in_chunks(stream: &[u8], splits: &[usize]) -> String {
+        let mut buf = Vec::new();
+        let mut out = String::new();
+        let mut start = 0;
+        for &end in splits
+            .iter()
+            .chain(std::iter::once(&stream.len()))
+        {
+            buf.extend_from_slice(&stream[start..end]);
+            out.push_str(&drain_utf8_lossy(&mut buf));
+            start = end;
+        }
+        out + &String::from_utf8_lossy(&buf)
+    }
+
+    #[test]
+    fn test_drain_utf8_lossy_malformed_sequences_at_every_split() {
+        let cases: [&[u8]; 6] = [
+            b"\xC0\xAFA",             // overlong '/'
+            b"\xE0\x80\xAFB",         // overlong '/', three bytes
+            b"\xED\xA0\x80C",         // UTF-16 surrogate
+            b"\x80\xBFD\x80",         // lone continuation bytes
+            b"\xF0\x9F\x98E\xF4\x90", // truncated 4-byte char, then out of range
+            "你好🙂".as_bytes(),
+        ];
+        for stream in cases {
+            let expected = String::from_utf8_lossy(stream);
+            for split in 0..=stream.len() {
+                assert_eq!(
+                    decode_in_chunks(stream, &[split]),
+                    expected,
+                    "{stream:02X?} split at {split}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_drain_utf8_lossy_matches_whole_stream_for_random_chunks() {
+        // Bytes that stress the decoder: ASCII, continuation bytes, overlong
+        // and out-of-range lead bytes, and leads of valid multibyte chars.
+        const ALPHABET: &[u8] = &[
+            b'a', b'\n', 0x80, 0x9F, 0xBF, 0xC0, 0xC1, 0xC2, 0xDF, 0xE0, 0xE4, 0xED, 0xEF, 0xF0,
+            0xF4, 0xF5, 0xFF,
+        ];
+        let mut state: u32 = 0x2545_F491;
+        let mut next = move |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as usize % bound
+        };
+
+        for _ in 0..2000 {
+            let len = next(48);
+            let mut stream = Vec::with_capacity(len);
+            while stream.len() < len {
+                if next(4) == 0 {
+                    stream.extend_from_slice("é你🙂".as_bytes());
+                } else {
+                    stream.push(ALPHABET[next(ALPHABET.len())]);
+                }
+            }
+            let mut splits = Vec::new();
+            let mut at = 0;
+            loop {
+                at += 1 + next(6);
+                if at >= stream.len() {
+                    break;
+                }
+                splits.push(at);
+            }
+
+            assert_eq!(
+                decode_in_chunks(&stream, &splits),
+                String::from_utf8_lossy(&stream),
+                "{stream:02X?} split at {splits:?}"
+            );
+        }
+    }
+
     #[test]
     fn test_clean_monitor_text_filters_control_chars() {
         let text = "A\x07B\x1BC\tD\nE\rF";
@@ -265,4 +378,19 @@ mod tests {
         assert_eq!(result2, "\r\n");
         assert!(at_line_start);
     }
+
+    #[test]
+    fn test_format_output_since_origin_is_relative() {
+        let origin = std::time::Instant::now()
+            .checked_sub(std::time::Duration::from_millis(1500))
+            .unwrap();
+        let mut at_line_start = true;
+        let result = format_monitor_output_since("a\nb", true, Some(origin), &mut at_line_start);
+        let lines: Vec<&str> = result
+            .split("\r\n")
+            .collect();
+        assert!(lines[0].starts_with("\x1b[90m[+1."));
+        assert!(lines[0].ends_with("]\x1b[0m a"));
+        assert!(lines[1].starts_with("\x1b[90m[+1."));
+    }
 }
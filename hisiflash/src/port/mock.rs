@@ -0,0 +1,676 @@
+//! Transcript-driven mock port for protocol regression tests.
+//!
+//! A [`Transcript`] records one session on the wire: the bytes the host must
+//! send (`tx`), the device's canned answers (`rx`) and the points where the
+//! host switches its local baud rate (`baud`). [`TranscriptPort`] plays the
+//! device side of it. Each `rx` step is released once every `tx` and `baud`
+//! step before it has happened, and is delivered as one packet: a read never
+//! returns bytes of two `rx` steps, as a real device pauses between replies.
+//! Everything the host writes is compared against the `tx` steps in order,
+//! regardless of how it is split into writes, and must wait until the host
+//! has read every `rx` step before it: a reply left in the buffer would be
+//! taken for the answer to the next command.
+//!
+//! Transcripts are TOML:
+//!
+//! ```toml
+//! # Local baud rate when the port is opened (default 115200).
+//! baud = 115200
+//!
+//! [[step]]
+//! note = "handshake"
+//! tx = "EF BE AD DE 12 00 F0 0F 00 10 0E 00 08 01 00 00 6E 80"
+//!
+//! [[step]]
+//! rx = "EF BE AD DE 0C 00 E1 1E 5A 00 95 22"
+//!
+//! [[step]]
+//! baud = 921600
+//! ```
+//!
+//! Byte strings are whitespace-separated hex; `XX*N` repeats byte `XX` `N`
+//! times, which keeps YMODEM padding readable. Only bytes the host is meant
+//! to read belong in `rx` steps: [`Port::clear_buffers`] does not discard
+//! them.
+//!
+//! After the first mismatch no more `rx` is released and reads fail with
+//! [`std::io::ErrorKind::Interrupted`], which ends the session without
+//! retries. [`TranscriptPort::assert_complete`] then reports where the host
+//! left the script.
+
+use {
+    crate::{
+        error::{Error, Result},
+        port::Port,
+    },
+    std::{
+        collections::VecDeque,
+        fmt::Write as _,
+        io::{ErrorKind, Read, Write},
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    },
+    toml::{Table, Value},
+};
+
+/// Baud rate a transcript starts at unless it sets `baud`.
+const DEFAULT_BAUD: u32 = 115_200;
+
+/// What happens in one transcript step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Step {
+    /// Bytes the host must send.
+    Tx(Vec<u8>),
+    /// Bytes the device answers with.
+    Rx(Vec<u8>),
+    /// The host switches its local baud rate.
+    Baud(u32),
+}
+
+/// One step plus its optional `note`, quoted in mismatch reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TranscriptStep {
+    pub(crate) step: Step,
+    pub(crate) note: Option<String>,
+}
+
+/// A recorded protocol session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Transcript {
+    pub(crate) baud: u32,
+    pub(crate) steps: Vec<TranscriptStep>,
+}
+
+impl Transcript {
+    /// Parse a transcript from TOML.
+    pub(crate) fn parse(text: &str) -> Result<Self> {
+        let table: Table = text
+            .parse()
+            .map_err(|e| transcript_error(&format!("{e}")))?;
+
+        let baud = match table.get("baud") {
+            None => DEFAULT_BAUD,
+            Some(value) => baud_value(value, "baud")?,
+        };
+        let entries = match table.get("step") {
+            None => return Err(transcript_error("no [[step]] entries")),
+            Some(Value::Array(entries)) => entries,
+            Some(_) => return Err(transcript_error("`step` must be an array of tables")),
+        };
+
+        let mut steps = Vec::with_capacity(entries.len());
+        for (index, entry) in entries
+            .iter()
+            .enumerate()
+        {
+            let context = format!("step {}", index + 1);
+            let Value::Table(entry) = entry else {
+                return Err(transcript_error(&format!("{context} is not a table")));
+            };
+            if let Some(key) = entry
+                .keys()
+                .find(|key| !matches!(key.as_str(), "tx" | "rx" | "baud" | "note"))
+            {
+                return Err(transcript_error(&format!("{context}: unknown key `{key}`")));
+            }
+
+            let mut actions = Vec::new();
+            if let Some(value) = entry.get("tx") {
+                actions.push(Step::Tx(hex_value(value, &context)?));
+            }
+            if let Some(value) = entry.get("rx") {
+                actions.push(Step::Rx(hex_value(value, &context)?));
+            }
+            if let Some(value) = entry.get("baud") {
+                actions.push(Step::Baud(baud_value(value, &context)?));
+            }
+            let Ok([step]) = <[Step; 1]>::try_from(actions) else {
+                return Err(transcript_error(&format!(
+                    "{context} needs exactly one of `tx`, `rx` or `baud`"
+                )));
+            };
+            let note = match entry.get("note") {
+                None => None,
+                Some(Value::String(note)) => Some(note.clone()),
+                Some(_) => {
+                    return Err(transcript_error(&format!(
+                        "{context}: `note` must be a string"
+                    )));
+                },
+            };
+            steps.push(TranscriptStep { step, note });
+        }
+
+        Ok(Self { baud, steps })
+    }
+
+    /// Concatenation of all `tx` steps: the complete expected host output.
+    pub(crate) fn expected_tx(&self) -> Vec<u8> {
+        self.steps
+            .iter()
+            .filter_map(|step| match &step.step {
+                Step::Tx(bytes) => Some(bytes.as_slice()),
+                _ => None,
+            })
+            .flatten()
+            .copied()
+            .collect()
+    }
+}
+
+fn transcript_error(message: &str) -> Error {
+    Error::Config(format!("Invalid transcript: {message}"))
+}
+
+fn baud_value(value: &Value, context: &str) -> Result<u32> {
+    value
+        .as_integer()
+        .and_then(|baud| u32::try_from(baud).ok())
+        .filter(|baud| *baud > 0)
+        .ok_or_else(|| transcript_error(&format!("{context}: `baud` must be a positive integer")))
+}
+
+fn hex_value(value: &Value, context: &str) -> Result<Vec<u8>> {
+    let Value::String(text) = value else {
+        return Err(transcript_error(&format!(
+            "{context}: bytes must be a string"
+        )));
+    };
+    let bytes = parse_hex(text)
+        .map_err(|token| transcript_error(&format!("{context}: bad byte `{token}`")))?;
+    if bytes.is_empty() {
+        return Err(transcript_error(&format!("{context}: no bytes")));
+    }
+    Ok(bytes)
+}
+
+/// Parse whitespace-separated hex, where `XX*N` repeats `XX` `N` times.
+///
+/// On failure the offending token is returned.
+fn parse_hex(text: &str) -> std::result::Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    for token in text.split_whitespace() {
+        let bad = || token.to_string();
+        if let Some((byte, count)) = token.split_once('*') {
+            let byte = u8::from_str_radix(byte, 16).map_err(|_| bad())?;
+            let count: usize = count
+                .parse()
+                .map_err(|_| bad())?;
+            bytes.resize(bytes.len() + count, byte);
+            continue;
+        }
+        if token.len() % 2 != 0 {
+            return Err(bad());
+        }
+        for pair in token
+            .as_bytes()
+            .chunks(2)
+        {
+            let pair = std::str::from_utf8(pair).map_err(|_| bad())?;
+            bytes.push(u8::from_str_radix(pair, 16).map_err(|_| bad())?);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Progress through a transcript, shared between [`TranscriptPort`] clones.
+#[derive(Debug)]
+struct State {
+    steps: Vec<TranscriptStep>,
+    /// Next step the host has to perform.
+    cursor: usize,
+    /// Bytes of the current `tx` step already matched.
+    offset: usize,
+    /// Released `rx` packets not yet read.
+    pending_rx: VecDeque<Vec<u8>>,
+    /// Everything the host wrote.
+    tx: Vec<u8>,
+    /// First mismatch, if any.
+    divergence: Option<String>,
+}
+
+impl State {
+    /// Queue the `rx` steps at the cursor.
+    fn release_rx(&mut self) {
+        while let Some(Step::Rx(bytes)) = self
+            .steps
+            .get(self.cursor)
+            .map(|step| &step.step)
+        {
+            self.pending_rx
+                .push_back(bytes.clone());
+            self.cursor += 1;
+        }
+    }
+
+    /// Describe the step at the cursor for a mismatch report.
+    fn expected(&self) -> String {
+        let Some(step) = self
+            .steps
+            .get(self.cursor)
+        else {
+            return "end of transcript".to_string();
+        };
+        let mut text = match &step.step {
+            Step::Tx(bytes) => format!(
+                "step {} tx byte {} of {} ({:02X})",
+                self.cursor + 1,
+                self.offset + 1,
+                bytes.len(),
+                bytes[self.offset]
+            ),
+            Step::Rx(_) => format!("step {} rx", self.cursor + 1),
+            Step::Baud(baud) => format!("step {} baud {baud}", self.cursor + 1),
+        };
+        if let Some(note) = &step.note {
+            let _ = write!(text, " [{note}]");
+        }
+        text
+    }
+
+    fn diverge(&mut self, actual: &str) {
+        if self
+            .divergence
+            .is_none()
+        {
+            self.divergence = Some(format!(
+                "host sent {actual} at TX offset {}, expected {}",
+                self.tx
+                    .len(),
+                self.expected()
+            ));
+        }
+    }
+
+    fn record_tx(&mut self, data: &[u8]) {
+        for &byte in data {
+            let unread: usize = self
+                .pending_rx
+                .iter()
+                .map(Vec::len)
+                .sum();
+            if unread > 0 {
+                self.diverge(&format!("{byte:02X} with {unread} rx bytes unread"));
+            }
+            if self
+                .divergence
+                .is_none()
+            {
+                match self
+                    .steps
+                    .get(self.cursor)
+                    .map(|step| &step.step)
+                {
+                    Some(Step::Tx(expected)) if expected[self.offset] == byte => {
+                        self.offset += 1;
+                        if self.offset == expected.len() {
+                            self.offset = 0;
+                            self.cursor += 1;
+                            self.release_rx();
+                        }
+                    },
+                    _ => self.diverge(&format!("{byte:02X}")),
+                }
+            }
+            self.tx
+                .push(byte);
+        }
+    }
+
+    fn record_baud(&mut self, baud: u32) {
+        if self
+            .divergence
+            .is_some()
+        {
+            return;
+        }
+        match self
+            .steps
+            .get(self.cursor)
+            .map(|step| &step.step)
+        {
+            Some(Step::Baud(expected)) if *expected == baud => {
+                self.cursor += 1;
+                self.release_rx();
+            },
+            _ => self.diverge(&format!("a baud rate change to {baud}")),
+        }
+    }
+}
+
+/// Mock port that plays the device side of a [`Transcript`].
+///
+/// Clones share the session, so a test can keep one to inspect after the
+/// flasher has taken the other.
+#[derive(Debug, Clone)]
+pub(crate) struct TranscriptPort {
+    state: Arc<Mutex<State>>,
+    baud_rate: u32,
+    timeout: Duration,
+}
+
+impl TranscriptPort {
+    pub(crate) fn new(transcript: Transcript) -> Self {
+        let mut state = State {
+            steps: transcript.steps,
+            cursor: 0,
+            offset: 0,
+            pending_rx: VecDeque::new(),
+            tx: Vec::new(),
+            divergence: None,
+        };
+        state.release_rx();
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            baud_rate: transcript.baud,
+            timeout: Duration::from_millis(50),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Everything the host has written so far.
+    pub(crate) fn tx(&self) -> Vec<u8> {
+        self.state()
+            .tx
+            .clone()
+    }
+
+    /// Check that the host followed the whole transcript and read every
+    /// `rx` step.
+    ///
+    /// # Panics
+    ///
+    /// With a description of the first mismatch, the first step not
+    /// reached, or the amount of unread `rx`.
+    pub(crate) fn assert_complete(&self) {
+        let state = self.state();
+        if let Some(divergence) = &state.divergence {
+            panic!("transcript diverged: {divergence}");
+        }
+        assert!(
+            state.cursor
+                == state
+                    .steps
+                    .len(),
+            "transcript incomplete: host stopped before {}",
+            state.expected()
+        );
+        let unread: usize = state
+            .pending_rx
+            .iter()
+            .map(Vec::len)
+            .sum();
+        assert!(
+            unread == 0,
+            "transcript complete but {unread} rx bytes were never read"
+        );
+    }
+}
+
+impl Port for TranscriptPort {
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        if baud_rate != self.baud_rate {
+            self.baud_rate = baud_rate;
+            self.state()
+                .record_baud(baud_rate);
+        }
+        Ok(())
+    }
+
+    fn baud_rate(&self) -> u32 {
+        self.baud_rate
+    }
+
+    fn clear_buffers(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "transcript"
+    }
+
+    fn set_dtr(&mut self, _level: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_rts(&mut self, _level: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_cts(&mut self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn read_dsr(&mut self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Read for TranscriptPort {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut state = self.state();
+        if state
+            .divergence
+            .is_some()
+        {
+            return Err(std::io::Error::new(
+                ErrorKind::Interrupted,
+                "transcript diverged",
+            ));
+        }
+        let Some(packet) = state
+            .pending_rx
+            .front_mut()
+        else {
+            drop(state);
+            thread::sleep(self.timeout);
+            return Err(ErrorKind::TimedOut.into());
+        };
+        let n = packet
+            .len()
+            .min(buf.len());
+        buf[..n].copy_from_slice(&packet[..n]);
+        packet.drain(..n);
+        if packet.is_empty() {
+            state
+                .pending_rx
+                .pop_front();
+        }
+        Ok(n)
+    }
+}
+
+impl Write for TranscriptPort {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.state()
+            .record_tx(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"
+        baud = 115200
+
+        [[step]]
+        note = "request"
+        tx = "01 02"
+
+        [[step]]
+        rx = "AA"
+
+        [[step]]
+        baud = 921600
+
+        [[step]]
+        rx = "BB CC"
+
+        [[step]]
+        tx = "0304 00*3"
+    "#;
+
+    #[test]
+    fn test_transcript_parse() {
+        let transcript = Transcript::parse(SCRIPT).unwrap();
+        assert_eq!(transcript.baud, 115_200);
+        let steps: Vec<_> = transcript
+            .steps
+            .iter()
+            .map(|step| {
+                step.step
+                    .clone()
+            })
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                Step::Tx(vec![1, 2]),
+                Step::Rx(vec![0xAA]),
+                Step::Baud(921_600),
+                Step::Rx(vec![0xBB, 0xCC]),
+                Step::Tx(vec![3, 4, 0, 0, 0]),
+            ]
+        );
+        assert_eq!(
+            transcript.steps[0]
+                .note
+                .as_deref(),
+            Some("request")
+        );
+        assert_eq!(transcript.expected_tx(), [1, 2, 3, 4, 0, 0, 0]);
+
+        for bad in [
+            "baud = 9600",
+            "[[step]]\ntx = \"0\"",
+            "[[step]]\ntx = \"GG\"",
+            "[[step]]\ntx = \"00*x\"",
+            "[[step]]\ntx = \"00\"\nrx = \"00\"",
+            "[[step]]\nnote = \"nothing\"",
+            "[[step]]\nwait = 1",
+            "[[step]]\nbaud = 0",
+            "[[step]]\nrx = \"\"",
+        ] {
+            assert!(Transcript::parse(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn test_transcript_port_releases_rx_on_cue() {
+        let mut port = TranscriptPort::new(Transcript::parse(SCRIPT).unwrap());
+        port.set_timeout(Duration::ZERO)
+            .unwrap();
+        let mut buf = [0u8; 8];
+        assert!(
+            port.read(&mut buf)
+                .is_err()
+        );
+
+        // Split writes are matched as one stream.
+        port.write_all(&[1])
+            .unwrap();
+        assert!(
+            port.read(&mut buf)
+                .is_err()
+        );
+        port.write_all(&[2])
+            .unwrap();
+        assert_eq!(
+            port.read(&mut buf)
+                .unwrap(),
+            1
+        );
+        assert_eq!(buf[0], 0xAA);
+
+        port.set_baud_rate(921_600)
+            .unwrap();
+        // Packets are delivered whole but never merged.
+        assert_eq!(
+            port.read(&mut buf[..1])
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            port.read(&mut buf)
+                .unwrap(),
+            1
+        );
+        assert_eq!(buf[0], 0xCC);
+
+        port.write_all(&[3, 4, 0, 0, 0])
+            .unwrap();
+        port.assert_complete();
+        assert_eq!(port.tx(), [1, 2, 3, 4, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_transcript_port_reports_divergence() {
+        let mut port = TranscriptPort::new(Transcript::parse(SCRIPT).unwrap());
+        port.write_all(&[1, 9])
+            .unwrap();
+        let err = port
+            .read(&mut [0u8; 4])
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Interrupted);
+
+        let message = std::panic::catch_unwind(|| port.assert_complete())
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert!(
+            message.contains(
+                "host sent 09 at TX offset 1, expected step 1 tx byte 2 of 2 (02) [request]"
+            ),
+            "{message}"
+        );
+
+        // Replies must be read before the host sends the next command.
+        let mut port = TranscriptPort::new(Transcript::parse(SCRIPT).unwrap());
+        port.write_all(&[1, 2])
+            .unwrap();
+        port.set_baud_rate(921_600)
+            .unwrap();
+        port.write_all(&[3])
+            .unwrap();
+        let message = std::panic::catch_unwind(|| port.assert_complete())
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert!(
+            message.contains("host sent 03 with 3 rx bytes unread at TX offset 2"),
+            "{message}"
+        );
+
+        let port = TranscriptPort::new(Transcript::parse(SCRIPT).unwrap());
+        let message = std::panic::catch_unwind(|| port.assert_complete())
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert!(message.contains("stopped before step 1 tx"), "{message}");
+    }
+}
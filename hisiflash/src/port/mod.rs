@@ -4,6 +4,8 @@
 //! serial port implementations:
 //!
 //! - **Native platforms** (Linux, macOS, Windows): Uses the `serialport` crate
+//! - **RFC 2217**: Network serial ports (`rfc2217://host:port`) on native
+//!   platforms
 //! - **WASM/Web**: Uses Web Serial API via `web-sys` (feature-gated)
 //!
 //! ## Architecture
@@ -49,14 +51,20 @@
 #[cfg(feature = "native")]
 pub mod native;
 
+#[cfg(feature = "native")]
+pub mod rfc2217;
+
 #[cfg(feature = "wasm")]
 pub mod wasm;
 
+#[cfg(test)]
+pub(crate) mod mock;
+
 use {
     crate::error::Result,
     std::{
-        io::{Read, Write},
-        time::Duration,
+        io::{ErrorKind, Read, Write},
+        time::{Duration, Instant},
     },
 };
 
@@ -185,9 +193,12 @@ pub struct PortInfo {
 /// - WASM/Web via the Web Serial API
 pub trait Port: Read + Write + Send {
     /// Set the read/write timeout.
+    ///
+    /// Implementations may clamp very small values to a minimum the platform
+    /// can honour; [`Port::timeout`] reports the value actually in effect.
     fn set_timeout(&mut self, timeout: Duration) -> Result<()>;
 
-    /// Get the current timeout.
+    /// Get the current (effective) timeout.
     fn timeout(&self) -> Duration;
 
     /// Set the baud rate.
@@ -214,6 +225,16 @@ pub trait Port: Read + Write + Send {
     /// Read DSR (Data Set Ready) pin state.
     fn read_dsr(&mut self) -> Result<bool>;
 
+    /// Start (`true`) or stop (`false`) sending a break condition on TX.
+    ///
+    /// The default implementation returns
+    /// [`crate::error::Error::Unsupported`].
+    fn set_break(&mut self, _enabled: bool) -> Result<()> {
+        Err(crate::error::Error::Unsupported(
+            "Port does not support break signals".into(),
+        ))
+    }
+
     /// Close the port and release resources.
     ///
     /// After calling this method, the port cannot be used for further I/O.
@@ -226,6 +247,38 @@ pub trait Port: Read + Write + Send {
         Ok(())
     }
 
+    /// Read everything that arrives within `window`, up to `max` bytes.
+    ///
+    /// Returns early once `max` bytes have been read; read timeouts inside
+    /// the window are not errors, so the result may be empty. Each read waits
+    /// at most the remaining window (and never longer than
+    /// [`Port::timeout`]), and the timeout is restored afterwards.
+    fn read_for(&mut self, window: Duration, max: usize) -> Result<Vec<u8>> {
+        let saved_timeout = self.timeout();
+        let start = Instant::now();
+        let mut received = Vec::new();
+        let mut buf = [0u8; 256];
+        let result = loop {
+            let remaining = window.saturating_sub(start.elapsed());
+            if remaining.is_zero() || received.len() >= max {
+                break Ok(());
+            }
+            if let Err(e) = self.set_timeout(remaining.min(saved_timeout)) {
+                break Err(e);
+            }
+            let len = buf
+                .len()
+                .min(max - received.len());
+            match self.read(&mut buf[..len]) {
+                Ok(n) => received.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::TimedOut => {},
+                Err(e) => break Err(e.into()),
+            }
+        };
+        self.set_timeout(saved_timeout)?;
+        result.map(|()| received)
+    }
+
     /// Hand off the underlying transport to a serial monitor session.
     ///
     /// Implementations that own a real platform serial handle should override
@@ -246,6 +299,68 @@ pub trait Port: Read + Write + Send {
     }
 }
 
+/// Boxed ports forward to the inner port, so transports chosen at runtime
+/// can be used wherever a `P: Port` is expected.
+///
+/// Monitor handoff is not forwarded: it needs the concrete port type.
+impl Port for Box<dyn Port> {
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        (**self).set_timeout(timeout)
+    }
+
+    fn timeout(&self) -> Duration {
+        (**self).timeout()
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        (**self).set_baud_rate(baud_rate)
+    }
+
+    fn baud_rate(&self) -> u32 {
+        (**self).baud_rate()
+    }
+
+    fn clear_buffers(&mut self) -> Result<()> {
+        (**self).clear_buffers()
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn set_dtr(&mut self, level: bool) -> Result<()> {
+        (**self).set_dtr(level)
+    }
+
+    fn set_rts(&mut self, level: bool) -> Result<()> {
+        (**self).set_rts(level)
+    }
+
+    fn read_cts(&mut self) -> Result<bool> {
+        (**self).read_cts()
+    }
+
+    fn read_dsr(&mut self) -> Result<bool> {
+        (**self).read_dsr()
+    }
+
+    fn set_break(&mut self, enabled: bool) -> Result<()> {
+        (**self).set_break(enabled)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        (**self).close()
+    }
+
+    fn write_all_bytes(&mut self, buf: &[u8]) -> Result<()> {
+        (**self).write_all_bytes(buf)
+    }
+
+    fn read_for(&mut self, window: Duration, max: usize) -> Result<Vec<u8>> {
+        (**self).read_for(window, max)
+    }
+}
+
 /// Trait for listing available serial ports.
 ///
 /// This is separated from `Port` because it's a static operation that
@@ -364,6 +479,117 @@ mod tests {
         assert_eq!(info.name, cloned.name);
     }
 
+    /// Port returning queued chunks, then timing out.
+    struct ChunkPort {
+        chunks: std::collections::VecDeque<Vec<u8>>,
+        timeout: Duration,
+    }
+
+    impl Read for ChunkPort {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some(mut chunk) = self
+                .chunks
+                .pop_front()
+            else {
+                std::thread::sleep(self.timeout);
+                return Err(ErrorKind::TimedOut.into());
+            };
+            let n = chunk
+                .len()
+                .min(buf.len());
+            buf[..n].copy_from_slice(&chunk[..n]);
+            if n < chunk.len() {
+                self.chunks
+                    .push_front(chunk.split_off(n));
+            }
+            Ok(n)
+        }
+    }
+
+    impl Write for ChunkPort {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Port for ChunkPort {
+        fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+            self.timeout = timeout;
+            Ok(())
+        }
+
+        fn timeout(&self) -> Duration {
+            self.timeout
+        }
+
+        fn set_baud_rate(&mut self, _baud_rate: u32) -> Result<()> {
+            Ok(())
+        }
+
+        fn baud_rate(&self) -> u32 {
+            115200
+        }
+
+        fn clear_buffers(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn name(&self) -> &'static str {
+            "chunks"
+        }
+
+        fn set_dtr(&mut self, _level: bool) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_rts(&mut self, _level: bool) -> Result<()> {
+            Ok(())
+        }
+
+        fn read_cts(&mut self) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn read_dsr(&mut self) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn close(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_read_for_collects_burst_until_window_or_max() {
+        let mut port = ChunkPort {
+            chunks: vec![vec![1, 2], vec![3], vec![4, 5, 6]].into(),
+            timeout: Duration::from_secs(1),
+        };
+
+        let started = Instant::now();
+        let data = port
+            .read_for(Duration::from_millis(50), 64)
+            .unwrap();
+        assert_eq!(data, [1, 2, 3, 4, 5, 6]);
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(port.timeout(), Duration::from_secs(1));
+
+        port.chunks = vec![vec![7, 8, 9, 10]].into();
+        let data = port
+            .read_for(Duration::from_secs(5), 3)
+            .unwrap();
+        assert_eq!(data, [7, 8, 9]);
+        assert!(
+            port.read_for(Duration::ZERO, 64)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
     #[test]
     fn test_enums_are_copy() {
         let db = DataBits::Eight;
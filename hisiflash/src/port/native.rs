@@ -18,6 +18,28 @@ use {
     },
 };
 
+/// Smallest read/write timeout applied to a native port.
+///
+/// Some platforms round very small timeouts down to zero, which turns a
+/// paced read into a non-blocking poll (or an infinite wait, depending on
+/// the backend). Requested timeouts below this value are clamped up to it.
+pub const MIN_TIMEOUT: Duration = Duration::from_millis(1);
+
+/// Clamp a requested timeout to the platform-safe minimum.
+fn effective_timeout(timeout: Duration) -> Duration {
+    timeout.max(MIN_TIMEOUT)
+}
+
+/// sysfs latency timer of an FTDI device node (Linux `ftdi_sio` driver).
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn latency_timer_path(port_name: &str) -> Option<std::path::PathBuf> {
+    let node = port_name.strip_prefix("/dev/")?;
+    if node.is_empty() || node.contains('/') {
+        return None;
+    }
+    Some(format!("/sys/class/tty/{node}/device/latency_timer").into())
+}
+
 /// Native serial port implementation.
 pub struct NativePort {
     port: Option<Box<dyn serialport::SerialPort>>,
@@ -29,8 +51,9 @@ pub struct NativePort {
 impl NativePort {
     /// Open a serial port with the given configuration.
     pub fn open(config: &SerialConfig) -> Result<Self> {
+        let timeout = effective_timeout(config.timeout);
         let port = serialport::new(&config.port_name, config.baud_rate)
-            .timeout(config.timeout)
+            .timeout(timeout)
             .data_bits(
                 config
                     .data_bits
@@ -58,7 +81,7 @@ impl NativePort {
             name: config
                 .port_name
                 .clone(),
-            timeout: config.timeout,
+            timeout,
             baud_rate: config.baud_rate,
         })
     }
@@ -68,10 +91,27 @@ impl NativePort {
         let config = SerialConfig::new(port_name, baud_rate);
         Self::open(&config)
     }
+
+    /// Set the FTDI latency timer to 1 ms.
+    ///
+    /// FTDI adapters hold received bytes for up to 16 ms by default, which
+    /// slows the handshake's fast polling. Only Linux exposes the timer (in
+    /// sysfs); elsewhere, and for ports without one, this does nothing.
+    /// Writing the timer may need root.
+    pub fn set_low_latency(&mut self) -> Result<()> {
+        #[cfg(target_os = "linux")]
+        if let Some(path) = latency_timer_path(&self.name).filter(|path| path.exists()) {
+            std::fs::write(&path, "1")?;
+            trace!("Set {} to 1 ms", path.display());
+        }
+        Ok(())
+    }
 }
 
 impl Port for NativePort {
+    /// Set the read/write timeout, clamped to at least [`MIN_TIMEOUT`].
     fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        let timeout = effective_timeout(timeout);
         if let Some(ref mut p) = self.port {
             p.set_timeout(timeout)?;
         }
@@ -146,6 +186,18 @@ impl Port for NativePort {
         }
     }
 
+    fn set_break(&mut self, enabled: bool) -> Result<()> {
+        trace!("Setting break to {enabled}");
+        if let Some(ref p) = self.port {
+            if enabled {
+                p.set_break()?;
+            } else {
+                p.clear_break()?;
+            }
+        }
+        Ok(())
+    }
+
     fn close(&mut self) -> Result<()> {
         // Take ownership of the port and let it drop (close)
         self.port
@@ -224,6 +276,22 @@ impl PortEnumerator for NativePortEnumerator {
     }
 }
 
+impl NativePortEnumerator {
+    /// List only USB serial ports, i.e. those reporting a VID/PID.
+    ///
+    /// Leaves out built-in UARTs such as `/dev/ttyS0`, which are never
+    /// HiSilicon boards.
+    pub fn list_usb_ports() -> Result<Vec<PortInfo>> {
+        Ok(Self::list_ports()?
+            .into_iter()
+            .filter(|p| {
+                p.vid
+                    .is_some()
+            })
+            .collect())
+    }
+}
+
 // Type conversions from our types to serialport types
 
 impl From<DataBits> for serialport::DataBits {
@@ -270,12 +338,35 @@ impl From<FlowControl> for serialport::FlowControl {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_latency_timer_path() {
+        assert_eq!(
+            latency_timer_path("/dev/ttyUSB0"),
+            Some("/sys/class/tty/ttyUSB0/device/latency_timer".into())
+        );
+        assert_eq!(latency_timer_path("/dev/serial/by-id/usb-FTDI"), None);
+        assert_eq!(latency_timer_path("COM3"), None);
+    }
+
     #[test]
     fn test_list_ports() {
         // This test just verifies that list_ports doesn't panic
         let _ = NativePortEnumerator::list_ports();
     }
 
+    #[test]
+    fn test_list_usb_ports_only_returns_usb_ports() {
+        if let Ok(ports) = NativePortEnumerator::list_usb_ports() {
+            assert!(
+                ports
+                    .iter()
+                    .all(|p| p
+                        .vid
+                        .is_some())
+            );
+        }
+    }
+
     #[test]
     fn test_serial_config_default() {
         let config = SerialConfig::default();
@@ -286,6 +377,28 @@ mod tests {
         assert_eq!(config.flow_control, FlowControl::None);
     }
 
+    #[test]
+    fn test_set_timeout_clamps_to_minimum() {
+        let mut port = NativePort {
+            port: None,
+            name: "test".to_string(),
+            timeout: Duration::from_secs(1),
+            baud_rate: 115200,
+        };
+
+        port.set_timeout(Duration::ZERO)
+            .unwrap();
+        assert_eq!(port.timeout(), MIN_TIMEOUT);
+
+        port.set_timeout(Duration::from_micros(200))
+            .unwrap();
+        assert_eq!(port.timeout(), MIN_TIMEOUT);
+
+        port.set_timeout(Duration::from_millis(20))
+            .unwrap();
+        assert_eq!(port.timeout(), Duration::from_millis(20));
+    }
+
     #[test]
     fn test_serial_config_builder() {
         let config = SerialConfig::new("/dev/ttyUSB0", 921600).with_timeout(Duration::from_secs(5));
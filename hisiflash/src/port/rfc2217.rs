@@ -0,0 +1,557 @@
+//! RFC 2217 (Telnet COM-PORT-CONTROL) network serial port.
+//!
+//! Terminal servers that speak RFC 2217 tunnel a remote UART over a telnet
+//! connection and accept out-of-band commands for line settings and modem
+//! control. Unlike a raw TCP bridge, this lets baud rate changes and DTR/RTS
+//! toggles reach the remote UART, so the full flash flow (including the
+//! post-LoaderBoot baud switch) works over the network.
+//!
+//! Ports are addressed as `rfc2217://host:port`.
+
+use {
+    crate::{
+        error::{Error, Result},
+        port::{Port, native::MIN_TIMEOUT},
+    },
+    log::{debug, trace},
+    std::{
+        io::{Read, Write},
+        net::{TcpStream, ToSocketAddrs},
+        time::Duration,
+    },
+};
+
+/// URL scheme prefix for RFC 2217 ports.
+pub const RFC2217_SCHEME: &str = "rfc2217://";
+
+/// Timeout for establishing the TCP connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Telnet protocol bytes.
+mod telnet {
+    pub const IAC: u8 = 255;
+    pub const DONT: u8 = 254;
+    pub const DO: u8 = 253;
+    pub const WONT: u8 = 252;
+    pub const WILL: u8 = 251;
+    pub const SB: u8 = 250;
+    pub const SE: u8 = 240;
+
+    pub const BINARY: u8 = 0;
+    pub const SUPPRESS_GO_AHEAD: u8 = 3;
+    pub const COM_PORT_OPTION: u8 = 44;
+}
+
+/// COM-PORT-OPTION client commands (server replies add 100).
+mod cpc {
+    pub const SET_BAUDRATE: u8 = 1;
+    pub const SET_DATASIZE: u8 = 2;
+    pub const SET_PARITY: u8 = 3;
+    pub const SET_STOPSIZE: u8 = 4;
+    pub const SET_CONTROL: u8 = 5;
+    pub const PURGE_DATA: u8 = 12;
+
+    pub const NOTIFY_MODEMSTATE: u8 = 7 + 100;
+
+    pub const CONTROL_NO_FLOW: u8 = 1;
+    pub const CONTROL_BREAK_ON: u8 = 5;
+    pub const CONTROL_BREAK_OFF: u8 = 6;
+    pub const CONTROL_DTR_ON: u8 = 8;
+    pub const CONTROL_DTR_OFF: u8 = 9;
+    pub const CONTROL_RTS_ON: u8 = 11;
+    pub const CONTROL_RTS_OFF: u8 = 12;
+
+    pub const PARITY_NONE: u8 = 1;
+    pub const STOPSIZE_1: u8 = 1;
+    pub const PURGE_BOTH: u8 = 3;
+
+    pub const MODEMSTATE_CTS: u8 = 0x10;
+    pub const MODEMSTATE_DSR: u8 = 0x20;
+}
+
+/// Return the `host:port` part of an `rfc2217://` URL.
+pub fn parse_rfc2217_url(url: &str) -> Option<&str> {
+    let addr = url.strip_prefix(RFC2217_SCHEME)?;
+    let addr = addr.trim_end_matches('/');
+    (!addr.is_empty()).then_some(addr)
+}
+
+/// Whether a port name refers to an RFC 2217 endpoint.
+pub fn is_rfc2217_url(name: &str) -> bool {
+    parse_rfc2217_url(name).is_some()
+}
+
+/// Incremental telnet stream decoder state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DecodeState {
+    Data,
+    Iac,
+    Negotiate(u8),
+    Sub,
+    SubIac,
+}
+
+/// Serial port tunnelled over an RFC 2217 telnet connection.
+pub struct Rfc2217Port {
+    stream: Option<TcpStream>,
+    name: String,
+    timeout: Duration,
+    baud_rate: u32,
+    state: DecodeState,
+    subneg: Vec<u8>,
+    modem_state: u8,
+}
+
+impl Rfc2217Port {
+    /// Connect to an `rfc2217://host:port` endpoint and configure the remote
+    /// UART for 8N1 at `baud_rate` with no flow control.
+    pub fn open(url: &str, baud_rate: u32) -> Result<Self> {
+        let addr = parse_rfc2217_url(url)
+            .ok_or_else(|| Error::Config(format!("Invalid RFC 2217 URL: {url}")))?;
+        let socket_addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::Config(format!("Could not resolve {addr}")))?;
+
+        debug!("Connecting to RFC 2217 server {socket_addr}");
+        let stream = TcpStream::connect_timeout(&socket_addr, CONNECT_TIMEOUT)?;
+        stream.set_nodelay(true)?;
+
+        let mut port = Self {
+            stream: Some(stream),
+            name: url.to_string(),
+            timeout: Duration::from_secs(1),
+            baud_rate,
+            state: DecodeState::Data,
+            subneg: Vec::new(),
+            modem_state: 0,
+        };
+        port.set_timeout(port.timeout)?;
+        port.negotiate()?;
+        port.set_baud_rate(baud_rate)?;
+        Ok(port)
+    }
+
+    fn stream(&mut self) -> std::io::Result<&mut TcpStream> {
+        self.stream
+            .as_mut()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "port closed"))
+    }
+
+    fn send_raw(&mut self, bytes: &[u8]) -> Result<()> {
+        self.stream()?
+            .write_all(bytes)?;
+        Ok(())
+    }
+
+    /// Announce the telnet options we need and set the fixed line settings.
+    fn negotiate(&mut self) -> Result<()> {
+        use telnet::{BINARY, COM_PORT_OPTION, DO, IAC, SUPPRESS_GO_AHEAD, WILL};
+
+        self.send_raw(&[
+            IAC,
+            WILL,
+            BINARY,
+            IAC,
+            DO,
+            BINARY,
+            IAC,
+            WILL,
+            SUPPRESS_GO_AHEAD,
+            IAC,
+            DO,
+            SUPPRESS_GO_AHEAD,
+            IAC,
+            WILL,
+            COM_PORT_OPTION,
+        ])?;
+        self.send_com_port_command(cpc::SET_DATASIZE, &[8])?;
+        self.send_com_port_command(cpc::SET_PARITY, &[cpc::PARITY_NONE])?;
+        self.send_com_port_command(cpc::SET_STOPSIZE, &[cpc::STOPSIZE_1])?;
+        self.send_com_port_command(cpc::SET_CONTROL, &[cpc::CONTROL_NO_FLOW])
+    }
+
+    fn send_com_port_command(&mut self, command: u8, value: &[u8]) -> Result<()> {
+        trace!("RFC 2217 command {command} {value:02X?}");
+        let frame = encode_com_port_command(command, value);
+        self.send_raw(&frame)
+    }
+
+    /// Decode raw telnet bytes into `out`, handling commands in-line.
+    ///
+    /// Returns the number of data bytes written to `out` and any reply that
+    /// must be sent back to the server.
+    fn decode(&mut self, raw: &[u8], out: &mut [u8]) -> (usize, Vec<u8>) {
+        use telnet::{
+            BINARY, COM_PORT_OPTION, DO, DONT, IAC, SB, SE, SUPPRESS_GO_AHEAD, WILL, WONT,
+        };
+
+        let mut written = 0;
+        let mut reply = Vec::new();
+
+        for &byte in raw {
+            self.state = match self.state {
+                DecodeState::Data if byte == IAC => DecodeState::Iac,
+                DecodeState::Data => {
+                    out[written] = byte;
+                    written += 1;
+                    DecodeState::Data
+                },
+                DecodeState::Iac => match byte {
+                    IAC => {
+                        out[written] = IAC;
+                        written += 1;
+                        DecodeState::Data
+                    },
+                    WILL | WONT | DO | DONT => DecodeState::Negotiate(byte),
+                    SB => {
+                        self.subneg
+                            .clear();
+                        DecodeState::Sub
+                    },
+                    _ => DecodeState::Data,
+                },
+                DecodeState::Negotiate(verb) => {
+                    let supported = matches!(byte, BINARY | SUPPRESS_GO_AHEAD | COM_PORT_OPTION);
+                    match verb {
+                        DO if !supported => reply.extend_from_slice(&[IAC, WONT, byte]),
+                        WILL if !supported => reply.extend_from_slice(&[IAC, DONT, byte]),
+                        _ => {},
+                    }
+                    DecodeState::Data
+                },
+                DecodeState::Sub if byte == IAC => DecodeState::SubIac,
+                DecodeState::Sub => {
+                    self.subneg
+                        .push(byte);
+                    DecodeState::Sub
+                },
+                DecodeState::SubIac => match byte {
+                    SE => {
+                        self.handle_subnegotiation();
+                        DecodeState::Data
+                    },
+                    _ => {
+                        self.subneg
+                            .push(byte);
+                        DecodeState::Sub
+                    },
+                },
+            };
+        }
+
+        (written, reply)
+    }
+
+    fn handle_subnegotiation(&mut self) {
+        if let [telnet::COM_PORT_OPTION, cpc::NOTIFY_MODEMSTATE, state, ..] = self
+            .subneg
+            .as_slice()
+        {
+            self.modem_state = *state;
+        }
+        trace!("RFC 2217 subnegotiation {:02X?}", self.subneg);
+    }
+}
+
+/// Build an `IAC SB COM-PORT-OPTION <command> <value> IAC SE` frame.
+fn encode_com_port_command(command: u8, value: &[u8]) -> Vec<u8> {
+    use telnet::{COM_PORT_OPTION, IAC, SB, SE};
+
+    let mut frame = vec![IAC, SB, COM_PORT_OPTION, command];
+    for &byte in value {
+        frame.push(byte);
+        if byte == IAC {
+            frame.push(IAC);
+        }
+    }
+    frame.extend_from_slice(&[IAC, SE]);
+    frame
+}
+
+/// Escape data bytes for transmission over telnet.
+fn escape_data(buf: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(buf.len());
+    for &byte in buf {
+        out.push(byte);
+        if byte == telnet::IAC {
+            out.push(telnet::IAC);
+        }
+    }
+    out
+}
+
+impl Port for Rfc2217Port {
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        let timeout = timeout.max(MIN_TIMEOUT);
+        if let Some(ref stream) = self.stream {
+            stream.set_read_timeout(Some(timeout))?;
+            stream.set_write_timeout(Some(timeout.max(CONNECT_TIMEOUT)))?;
+        }
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        self.send_com_port_command(cpc::SET_BAUDRATE, &baud_rate.to_be_bytes())?;
+        self.baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn baud_rate(&self) -> u32 {
+        self.baud_rate
+    }
+
+    fn clear_buffers(&mut self) -> Result<()> {
+        self.send_com_port_command(cpc::PURGE_DATA, &[cpc::PURGE_BOTH])
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_dtr(&mut self, level: bool) -> Result<()> {
+        trace!("Setting DTR to {level}");
+        let value = if level {
+            cpc::CONTROL_DTR_ON
+        } else {
+            cpc::CONTROL_DTR_OFF
+        };
+        self.send_com_port_command(cpc::SET_CONTROL, &[value])
+    }
+
+    fn set_rts(&mut self, level: bool) -> Result<()> {
+        trace!("Setting RTS to {level}");
+        let value = if level {
+            cpc::CONTROL_RTS_ON
+        } else {
+            cpc::CONTROL_RTS_OFF
+        };
+        self.send_com_port_command(cpc::SET_CONTROL, &[value])
+    }
+
+    fn read_cts(&mut self) -> Result<bool> {
+        Ok(self.modem_state & cpc::MODEMSTATE_CTS != 0)
+    }
+
+    fn read_dsr(&mut self) -> Result<bool> {
+        Ok(self.modem_state & cpc::MODEMSTATE_DSR != 0)
+    }
+
+    fn set_break(&mut self, enabled: bool) -> Result<()> {
+        trace!("Setting break to {enabled}");
+        let value = if enabled {
+            cpc::CONTROL_BREAK_ON
+        } else {
+            cpc::CONTROL_BREAK_OFF
+        };
+        self.send_com_port_command(cpc::SET_CONTROL, &[value])
+    }
+
+    fn close(&mut self) -> Result<()> {
+        if let Some(stream) = self
+            .stream
+            .take()
+        {
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+        }
+        Ok(())
+    }
+}
+
+impl Read for Rfc2217Port {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut raw = vec![0u8; buf.len()];
+        loop {
+            let n = match self
+                .stream()?
+                .read(&mut raw)
+            {
+                Ok(0) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "RFC 2217 server closed the connection",
+                    ));
+                },
+                Ok(n) => n,
+                // Socket read timeouts surface as WouldBlock on Unix; report
+                // them like a serial port would so callers can keep polling.
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, e));
+                },
+                Err(e) => return Err(e),
+            };
+
+            let (written, reply) = self.decode(&raw[..n], buf);
+            if !reply.is_empty() {
+                self.stream()?
+                    .write_all(&reply)?;
+            }
+            if written > 0 {
+                return Ok(written);
+            }
+        }
+    }
+}
+
+impl Write for Rfc2217Port {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let escaped = escape_data(buf);
+        self.stream()?
+            .write_all(&escaped)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream()?
+            .flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::net::TcpListener};
+
+    fn detached_port() -> Rfc2217Port {
+        Rfc2217Port {
+            stream: None,
+            name: "rfc2217://test:2217".to_string(),
+            timeout: Duration::from_millis(100),
+            baud_rate: 115200,
+            state: DecodeState::Data,
+            subneg: Vec::new(),
+            modem_state: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_rfc2217_url() {
+        assert_eq!(
+            parse_rfc2217_url("rfc2217://10.0.0.5:4001"),
+            Some("10.0.0.5:4001")
+        );
+        assert_eq!(parse_rfc2217_url("rfc2217://host:2217/"), Some("host:2217"));
+        assert_eq!(parse_rfc2217_url("rfc2217://"), None);
+        assert_eq!(parse_rfc2217_url("/dev/ttyUSB0"), None);
+        assert!(is_rfc2217_url("rfc2217://host:1"));
+        assert!(!is_rfc2217_url("COM3"));
+    }
+
+    #[test]
+    fn test_encode_set_baudrate_escapes_iac() {
+        let frame = encode_com_port_command(cpc::SET_BAUDRATE, &[0x00, 0x00, 0x00, 0xFF]);
+        assert_eq!(
+            frame,
+            [255, 250, 44, 1, 0x00, 0x00, 0x00, 0xFF, 0xFF, 255, 240]
+        );
+    }
+
+    #[test]
+    fn test_escape_data_doubles_iac() {
+        assert_eq!(escape_data(&[0x01, 0xFF, 0x02]), [0x01, 0xFF, 0xFF, 0x02]);
+    }
+
+    #[test]
+    fn test_decode_strips_telnet_commands() {
+        let mut port = detached_port();
+        let mut out = [0u8; 16];
+        // data, escaped IAC, WILL BINARY, data
+        let (n, reply) = port.decode(&[0x41, 0xFF, 0xFF, 0xFF, 251, 0, 0x42], &mut out);
+        assert_eq!(&out[..n], &[0x41, 0xFF, 0x42]);
+        assert!(reply.is_empty());
+    }
+
+    #[test]
+    fn test_decode_refuses_unknown_options() {
+        let mut port = detached_port();
+        let mut out = [0u8; 4];
+        // DO ECHO (1), WILL TERMINAL-TYPE (24)
+        let (n, reply) = port.decode(&[255, 253, 1, 255, 251, 24], &mut out);
+        assert_eq!(n, 0);
+        assert_eq!(reply, [255, 252, 1, 255, 254, 24]);
+    }
+
+    #[test]
+    fn test_decode_tracks_modem_state_across_chunks() {
+        let mut port = detached_port();
+        let mut out = [0u8; 4];
+        let (n, _) = port.decode(&[255, 250, 44, 107], &mut out);
+        assert_eq!(n, 0);
+        let (n, _) = port.decode(&[0x30, 255, 240, 0x55], &mut out);
+        assert_eq!(&out[..n], &[0x55]);
+        assert!(
+            port.read_cts()
+                .unwrap()
+        );
+        assert!(
+            port.read_dsr()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_open_negotiates_and_exchanges_data() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener
+            .local_addr()
+            .unwrap();
+
+        let server = std::thread::spawn(move || {
+            let (mut conn, _) = listener
+                .accept()
+                .unwrap();
+            conn.set_read_timeout(Some(Duration::from_secs(2)))
+                .unwrap();
+            // Reply with an escaped 0xFF followed by a plain byte.
+            conn.write_all(&[0xFF, 0xFF, 0x10])
+                .unwrap();
+
+            let mut received = Vec::new();
+            let mut buf = [0u8; 256];
+            while !received.ends_with(&[0xAA, 0xFF, 0xFF]) {
+                let n = conn
+                    .read(&mut buf)
+                    .unwrap();
+                if n == 0 {
+                    break;
+                }
+                received.extend_from_slice(&buf[..n]);
+            }
+            received
+        });
+
+        let mut port = Rfc2217Port::open(&format!("rfc2217://{addr}"), 921_600).unwrap();
+        let mut buf = [0u8; 8];
+        let mut data = Vec::new();
+        while data.len() < 2 {
+            let n = port
+                .read(&mut buf)
+                .unwrap();
+            data.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(data, [0xFF, 0x10]);
+
+        port.write_all(&[0xAA, 0xFF])
+            .unwrap();
+        let received = server
+            .join()
+            .unwrap();
+
+        let set_baud = encode_com_port_command(cpc::SET_BAUDRATE, &921_600u32.to_be_bytes());
+        assert!(
+            received
+                .windows(set_baud.len())
+                .any(|w| w == set_baud.as_slice())
+        );
+        assert!(received.ends_with(&[0xAA, 0xFF, 0xFF]));
+        assert_eq!(port.baud_rate(), 921_600);
+    }
+}
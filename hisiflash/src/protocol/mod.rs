@@ -5,4 +5,6 @@ pub mod seboot;
 pub mod ymodem;
 
 // Re-export common types
-pub use seboot::{CommandType, ImageType, SebootAck, SebootFrame, contains_handshake_ack};
+pub use seboot::{
+    CommandType, ImageType, SebootAck, SebootFrame, contains_handshake_ack, describe_frame,
+};
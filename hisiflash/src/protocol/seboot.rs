@@ -24,6 +24,7 @@
 use {
     crate::protocol::crc::crc16_xmodem,
     byteorder::{LittleEndian, WriteBytesExt},
+    std::fmt::Write as _,
 };
 
 /// Frame magic number (0xDEADBEEF stored as little-endian).
@@ -85,6 +86,67 @@ pub enum CommandType {
 }
 
 impl CommandType {
+    /// Look up the command type for a frame type byte.
+    pub fn from_byte(value: u8) -> Option<Self> {
+        let cmd = match value {
+            0xF0 => Self::Handshake,
+            0xE1 => Self::Ack,
+            0xD2 => Self::DownloadFlashImage,
+            0xC3 => Self::DownloadOtpEfuse,
+            0xB4 => Self::UploadData,
+            0xA5 => Self::ReadOtpEfuse,
+            0x96 => Self::FlashLock,
+            0x87 => Self::Reset,
+            0x78 => Self::DownloadFactoryBin,
+            0x69 => Self::DownloadVersion,
+            0x5A => Self::SetBaudRate,
+            0x4B => Self::DownloadNv,
+            0x1E => Self::SwitchDfu,
+            _ => return None,
+        };
+        Some(cmd)
+    }
+
+    /// Payload fields of this command as `(name, offset, width, hex)`, in
+    /// the order [`SebootFrame`] writes them.
+    fn payload_fields(self) -> &'static [(&'static str, usize, usize, bool)] {
+        const DOWNLOAD: &[(&str, usize, usize, bool)] = &[
+            ("addr", 0, 4, true),
+            ("len", 4, 4, false),
+            ("erase_size", 8, 4, true),
+        ];
+        match self {
+            Self::Handshake => &[
+                ("baud", 0, 4, false),
+                ("data_bits", 4, 1, false),
+                ("stop_bits", 5, 1, false),
+                ("parity", 6, 1, false),
+                ("flow_ctrl", 7, 1, false),
+            ],
+            Self::Ack => &[("result", 0, 1, true), ("error", 1, 1, true)],
+            Self::DownloadFlashImage => &[
+                ("addr", 0, 4, true),
+                ("len", 4, 4, false),
+                ("erase_size", 8, 4, true),
+                ("formal", 12, 1, false),
+            ],
+            Self::DownloadFactoryBin => DOWNLOAD,
+            Self::DownloadNv => &[
+                ("addr", 0, 4, true),
+                ("len", 4, 4, false),
+                ("erase_size", 8, 4, true),
+                ("enc_items", 12, 2, false),
+                ("flag", 14, 2, false),
+            ],
+            Self::DownloadOtpEfuse | Self::DownloadVersion => &[("len", 0, 4, false)],
+            Self::UploadData => &[("len", 0, 4, false), ("addr", 4, 4, true)],
+            Self::ReadOtpEfuse => &[("start_bit", 0, 2, false), ("bit_width", 2, 2, false)],
+            Self::FlashLock => &[("param", 0, 2, true)],
+            Self::SetBaudRate => &[("baud", 0, 4, false), ("magic", 4, 4, true)],
+            Self::Reset | Self::SwitchDfu => &[],
+        }
+    }
+
     /// Get the reversed/complement frame type byte (~cmd).
     pub fn reversed(self) -> u8 {
         !(self as u8)
@@ -446,6 +508,11 @@ impl SebootFrame {
     pub fn command_type(&self) -> CommandType {
         self.frame_type
     }
+
+    /// Describe the built frame; see [`describe_frame`].
+    pub fn describe(&self) -> String {
+        describe_frame(&self.build())
+    }
 }
 
 /// SEBOOT ACK frame parser.
@@ -510,6 +577,69 @@ impl SebootAck {
     }
 }
 
+/// Decode a raw SEBOOT frame into a one-line, human-readable summary.
+///
+/// Shows the command type, the length, the payload fields known for the
+/// command and whether the CRC matches, e.g.
+/// `SetBaudRate (0x5A) len=18 baud=921600 magic=0x108 crc=ok`. Bad magic and
+/// truncated frames are described instead of rejected; bytes after the frame
+/// are ignored.
+pub fn describe_frame(bytes: &[u8]) -> String {
+    if bytes.len() < 10 {
+        return format!("truncated frame ({} bytes)", bytes.len());
+    }
+    let magic = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    if magic != FRAME_MAGIC {
+        return format!("not a SEBOOT frame (magic {magic:#010X})");
+    }
+
+    let len = usize::from(u16::from_le_bytes([bytes[4], bytes[5]]));
+    let type_byte = bytes[6];
+    let cmd = CommandType::from_byte(type_byte);
+    let mut out = match cmd {
+        Some(cmd) => format!("{cmd:?} ({type_byte:#04X})"),
+        None => format!("unknown type {type_byte:#04X}"),
+    };
+    if bytes[7] != !type_byte {
+        let _ = write!(out, " ~type mismatch ({:#04X})", bytes[7]);
+    }
+    let _ = write!(out, " len={len}");
+    if len < 10 || len > bytes.len() {
+        let _ = write!(out, " (invalid, {} bytes available)", bytes.len());
+        return out;
+    }
+
+    let payload = &bytes[8..len - 2];
+    let mut described = 0;
+    for &(name, offset, width, hex) in cmd.map_or(&[][..], CommandType::payload_fields) {
+        let Some(field) = payload.get(offset..offset + width) else {
+            break;
+        };
+        let value = field
+            .iter()
+            .rev()
+            .fold(0u32, |value, &byte| value << 8 | u32::from(byte));
+        if hex {
+            let _ = write!(out, " {name}={value:#X}");
+        } else {
+            let _ = write!(out, " {name}={value}");
+        }
+        described = offset + width;
+    }
+    if described < payload.len() {
+        let _ = write!(out, " data={:02X?}", &payload[described..]);
+    }
+
+    let crc = u16::from_le_bytes([bytes[len - 2], bytes[len - 1]]);
+    let expected = crc16_xmodem(&bytes[..len - 2]);
+    if crc == expected {
+        out.push_str(" crc=ok");
+    } else {
+        let _ = write!(out, " crc={crc:#06X} (expected {expected:#06X})");
+    }
+    out
+}
+
 /// Check if data contains a valid handshake ACK pattern.
 pub fn contains_handshake_ack(data: &[u8]) -> bool {
     // Look for the pattern: Magic + Length(12) + Type(E1) + ~Type(1E) + Result(5A)
@@ -786,6 +916,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_describe_frame() {
+        assert_eq!(
+            SebootFrame::set_baud_rate(921_600).describe(),
+            "SetBaudRate (0x5A) len=18 baud=921600 magic=0x108 crc=ok"
+        );
+        assert_eq!(
+            describe_frame(
+                &SebootFrame::download_flash_image(0x0023_0000, 4096, 0x2000, false).build()
+            ),
+            "DownloadFlashImage (0xD2) len=24 addr=0x230000 len=4096 erase_size=0x2000 formal=0 \
+             data=[FF] crc=ok"
+        );
+
+        let mut corrupted = SebootFrame::reset().build();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0xFF;
+        assert!(describe_frame(&corrupted).starts_with("Reset (0x87) len=12 data=[00, 00] crc=0x"));
+        assert!(describe_frame(&corrupted).contains("expected"));
+
+        assert_eq!(describe_frame(&[0xEF, 0xBE]), "truncated frame (2 bytes)");
+        assert!(describe_frame(&[0u8; 12]).starts_with("not a SEBOOT frame"));
+        assert!(
+            describe_frame(&SebootFrame::reset().build()[..11])
+                .ends_with("(invalid, 11 bytes available)")
+        );
+    }
+
+    #[test]
+    fn test_command_type_from_byte_roundtrip() {
+        for cmd in [
+            CommandType::Handshake,
+            CommandType::Ack,
+            CommandType::DownloadNv,
+        ] {
+            assert_eq!(CommandType::from_byte(cmd as u8), Some(cmd));
+        }
+        assert_eq!(CommandType::from_byte(0x00), None);
+    }
+
     #[test]
     fn test_image_type_from_u32() {
         assert_eq!(ImageType::from(0), ImageType::Loader);
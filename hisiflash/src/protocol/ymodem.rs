@@ -15,16 +15,22 @@
 //! | 1   | 1   | 1    |     1024      | 2      |
 //! +-----+-----+------+---------------+--------+
 //! ```
+//!
+//! With [`YmodemConfig::checksum_fallback`], a receiver that starts the
+//! session with NAK instead of 'C' gets blocks with a one-byte arithmetic
+//! checksum in place of the CRC16.
 
 use {
     crate::{
-        CancelContext,
+        CancelContext, ProgressControl,
         error::{Error, Result},
+        port::Port,
         protocol::crc::crc16_xmodem,
     },
     log::{debug, trace},
     std::{
         io::{Read, Write},
+        thread,
         time::{Duration, Instant},
     },
 };
@@ -53,6 +59,9 @@ pub const SOH_BLOCK_SIZE: usize = 128;
 /// Block size for STX packets (YMODEM-1K).
 pub const STX_BLOCK_SIZE: usize = 1024;
 
+/// How often a paused transfer polls its progress callback.
+pub const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 /// Grace period before treating a standalone 'C' as a retransmission request.
 ///
 /// fbb_burntool does not immediately resend a data block when it sees a lone
@@ -66,6 +75,17 @@ const SEBOOT_MAGIC: [u8; 4] = [0xEF, 0xBE, 0xAD, 0xDE];
 /// session should end without a finish block.
 const POST_EOT_C_TIMEOUT: Duration = Duration::from_millis(2500);
 
+/// Longest single read while waiting for 'C' when the port timeout is
+/// controlled (see [`YmodemTransfer::with_port_timeout_control`]), so
+/// cancellation stays responsive during the long wait.
+const C_WAIT_READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Getter and setter for the port timeout.
+type TimeoutControl<P> = (fn(&P) -> Duration, fn(&mut P, Duration) -> Result<()>);
+
+/// Prefix of the [`Error::Timeout`] message raised by the stall watchdog.
+pub(crate) const STALL_MESSAGE: &str = "flash stalled";
+
 /// YMODEM configuration options.
 #[derive(Debug, Clone)]
 pub struct YmodemConfig {
@@ -79,7 +99,36 @@ pub struct YmodemConfig {
     /// 'C' request.
     pub finish_without_c: bool,
     /// Verbose output level.
+    ///
+    /// At 2 and above, every block sent is logged at `trace` level with its
+    /// sequence number, payload size, CRC and a hex preview of the payload,
+    /// and every retry is logged with the attempt number and its reason.
     pub verbose: u8,
+    /// Accept a transfer whose data blocks were all ACKed even if the EOT is
+    /// never acknowledged.
+    ///
+    /// The receiver state is unknown in that case, so no finish block is
+    /// sent; callers can check [`YmodemTransfer::eot_confirmed`] and decide
+    /// whether the payload needs to be re-sent.
+    pub tolerate_lost_eot: bool,
+    /// File name sent in block 0 instead of the one passed to
+    /// [`YmodemTransfer::transfer`], for receivers that expect a fixed name.
+    ///
+    /// The file length is always sent after the name, so the receiver can
+    /// strip the padding of the last block.
+    pub filename_override: Option<String>,
+    /// Abort the transfer with [`Error::Timeout`] when no data block has
+    /// been acknowledged for this long, even if retries remain.
+    ///
+    /// `None` disables the watchdog; a stalled block then fails only after
+    /// [`max_retries`](Self::max_retries) attempts.
+    pub stall_timeout: Option<Duration>,
+    /// Accept a NAK instead of 'C' as the receiver's start request and then
+    /// send blocks with an 8-bit checksum instead of CRC16, for legacy
+    /// loaders that do not speak CRC mode.
+    ///
+    /// Off by default, so stray NAKs before the session starts are ignored.
+    pub checksum_fallback: bool,
 }
 
 impl Default for YmodemConfig {
@@ -90,6 +139,10 @@ impl Default for YmodemConfig {
             max_retries: 10,
             finish_without_c: true,
             verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
         }
     }
 }
@@ -101,6 +154,12 @@ pub struct YmodemTransfer<'a, P: Read + Write> {
     cancel: &'a CancelContext,
     prefetched_input: Vec<u8>,
     trailing_data: Vec<u8>,
+    eot_confirmed: bool,
+    /// When the last data block was ACKed (or the data phase started).
+    last_progress: Option<Instant>,
+    /// The receiver asked for checksum mode (NAK) instead of CRC ('C').
+    checksum_mode: bool,
+    timeout_control: Option<TimeoutControl<P>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -115,6 +174,22 @@ enum ControlResponse {
 enum EotOutcome {
     SendFinish,
     Complete,
+    /// No ACK for EOT after all retries.
+    Unconfirmed,
+}
+
+impl<P: Port> YmodemTransfer<'_, P> {
+    /// Let [`Self::wait_for_c`] manage the port timeout.
+    ///
+    /// Reads while waiting for 'C' then time out after at most
+    /// [`YmodemConfig::c_timeout`] (and no later than 100 ms), whatever
+    /// timeout the port was opened with, so a long port timeout cannot
+    /// overshoot the wait. The previous timeout is restored afterwards.
+    #[must_use]
+    pub fn with_port_timeout_control(mut self) -> Self {
+        self.timeout_control = Some((P::timeout, P::set_timeout));
+        self
+    }
 }
 
 impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
@@ -131,6 +206,10 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
             cancel,
             prefetched_input: Vec::new(),
             trailing_data: Vec::new(),
+            eot_confirmed: false,
+            last_progress: None,
+            checksum_mode: false,
+            timeout_control: None,
         }
     }
 
@@ -142,6 +221,10 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
             cancel,
             prefetched_input: Vec::new(),
             trailing_data: Vec::new(),
+            eot_confirmed: false,
+            last_progress: None,
+            checksum_mode: false,
+            timeout_control: None,
         }
     }
 
@@ -158,6 +241,24 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
         std::mem::take(&mut self.trailing_data)
     }
 
+    /// Whether the receiver acknowledged the EOT of the last [`transfer`].
+    ///
+    /// `false` after a successful transfer means every data block was ACKed
+    /// but the end of transmission was never confirmed.
+    ///
+    /// [`transfer`]: Self::transfer
+    pub fn eot_confirmed(&self) -> bool {
+        self.eot_confirmed
+    }
+
+    /// Whether the receiver requested checksum mode, so blocks end in an
+    /// 8-bit checksum instead of CRC16.
+    ///
+    /// Only possible with [`YmodemConfig::checksum_fallback`].
+    pub fn checksum_mode(&self) -> bool {
+        self.checksum_mode
+    }
+
     fn extend_trailing_data(&mut self, data: &[u8], debug_message: &str) {
         if data.is_empty() {
             return;
@@ -274,7 +375,30 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
     }
 
     /// Wait for the receiver to send 'C' (CRC mode request).
+    ///
+    /// With [`YmodemConfig::checksum_fallback`], a NAK is accepted as well
+    /// and switches the transfer to checksum mode.
+    ///
+    /// With [`Self::with_port_timeout_control`], the port timeout is set from
+    /// [`YmodemConfig::c_timeout`] for the wait and restored afterwards.
     pub fn wait_for_c(&mut self) -> Result<()> {
+        let Some((get_timeout, set_timeout)) = self.timeout_control else {
+            return self.wait_for_c_inner();
+        };
+
+        let saved_timeout = get_timeout(self.port);
+        set_timeout(
+            self.port,
+            self.config
+                .c_timeout
+                .min(C_WAIT_READ_TIMEOUT),
+        )?;
+        let result = self.wait_for_c_inner();
+        set_timeout(self.port, saved_timeout)?;
+        result
+    }
+
+    fn wait_for_c_inner(&mut self) -> Result<()> {
         debug!("Waiting for 'C' from receiver...");
         let start = Instant::now();
 
@@ -293,6 +417,16 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
                     let chunk = &buf[..n];
                     if chunk.contains(&control::C) {
                         debug!("Received 'C', starting transfer");
+                        self.checksum_mode = false;
+                        return Ok(());
+                    }
+                    if self
+                        .config
+                        .checksum_fallback
+                        && chunk.contains(&control::NAK)
+                    {
+                        debug!("Received NAK, starting transfer in checksum mode");
+                        self.checksum_mode = true;
                         return Ok(());
                     }
 
@@ -306,7 +440,20 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
         Err(Error::Timeout("Timeout waiting for 'C'".into()))
     }
 
-    /// Build a YMODEM block.
+    /// Build a YMODEM block with the trailer the receiver asked for.
+    fn block(&self, seq: u8, data: &[u8], use_stx: bool) -> Vec<u8> {
+        let mut block = Self::build_block(seq, data, use_stx);
+        if self.checksum_mode {
+            block.truncate(block.len() - 2);
+            let sum = block[3..]
+                .iter()
+                .fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+            block.push(sum);
+        }
+        block
+    }
+
+    /// Build a YMODEM block with a CRC16 trailer.
     fn build_block(seq: u8, data: &[u8], use_stx: bool) -> Vec<u8> {
         let block_size = if use_stx {
             STX_BLOCK_SIZE
@@ -338,6 +485,73 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
         block
     }
 
+    /// Log a block about to be sent when `verbose >= 2`.
+    ///
+    /// Nothing is formatted unless `trace` logging is enabled, as this runs
+    /// for every block.
+    fn trace_block(&self, block: &[u8], attempt: u32) {
+        if self
+            .config
+            .verbose
+            < 2
+            || block.len() < 5
+            || !log::log_enabled!(log::Level::Trace)
+        {
+            return;
+        }
+        let (payload, check) = if self.checksum_mode {
+            let (payload, sum) = block[3..].split_at(block.len() - 4);
+            (payload, format!("checksum=0x{:02X}", sum[0]))
+        } else {
+            let (payload, crc) = block[3..].split_at(block.len() - 5);
+            (
+                payload,
+                format!("crc=0x{:04X}", u16::from_be_bytes([crc[0], crc[1]])),
+            )
+        };
+        trace!(
+            "YMODEM block seq={} size={} {check} attempt {attempt}/{}: {}",
+            block[1],
+            payload.len(),
+            self.config
+                .max_retries,
+            hex_preview(payload)
+        );
+    }
+
+    /// Log why a block or EOT is being resent when `verbose >= 2`.
+    fn trace_retry(&self, what: &dyn std::fmt::Display, attempt: u32, reason: &str) {
+        if self
+            .config
+            .verbose
+            >= 2
+        {
+            trace!(
+                "YMODEM {what} retry after attempt {attempt}/{}: {reason}",
+                self.config
+                    .max_retries
+            );
+        }
+    }
+
+    /// Fail with [`Error::Timeout`] if the stall watchdog has expired.
+    fn check_stalled(&self) -> Result<()> {
+        let (Some(limit), Some(since)) = (
+            self.config
+                .stall_timeout,
+            self.last_progress,
+        ) else {
+            return Ok(());
+        };
+        if since.elapsed() >= limit {
+            return Err(Error::Timeout(format!(
+                "{STALL_MESSAGE}: no block acknowledged for {}s",
+                limit.as_secs_f32()
+            )));
+        }
+        Ok(())
+    }
+
     /// Send a block and wait for ACK.
     fn send_block(&mut self, block: &[u8]) -> Result<()> {
         for retry in 0..self
@@ -345,7 +559,9 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
             .max_retries
         {
             self.check_interrupted()?;
+            self.check_stalled()?;
             trace!("Sending block (attempt {})", retry + 1);
+            self.trace_block(block, retry + 1);
 
             self.port
                 .write_all(block)?;
@@ -363,15 +579,30 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
                 },
                 Ok(ControlResponse::Nak) => {
                     debug!("Block NAKed, retrying...");
+                    self.trace_retry(
+                        &format_args!("block seq={}", block[1]),
+                        retry + 1,
+                        "NAK from receiver",
+                    );
                 },
                 Ok(ControlResponse::RetryRequested) => {
                     debug!("Receiver requested block retransmission with 'C'");
+                    self.trace_retry(
+                        &format_args!("block seq={}", block[1]),
+                        retry + 1,
+                        "receiver requested retransmission ('C')",
+                    );
                 },
                 Ok(ControlResponse::Cancel) => {
                     return Err(Error::Ymodem("Transfer cancelled by receiver".into()));
                 },
                 Err(Error::Timeout(_)) => {
                     debug!("Timeout waiting for ACK, retrying...");
+                    self.trace_retry(
+                        &format_args!("block seq={}", block[1]),
+                        retry + 1,
+                        "timed out waiting for ACK",
+                    );
                 },
                 Err(e) => return Err(e),
             }
@@ -384,6 +615,56 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
         )))
     }
 
+    /// Act on a [`ProgressControl`], holding the transfer while it says
+    /// [`ProgressControl::Pause`] and re-polling with `poll`.
+    fn follow_progress_control(
+        &mut self,
+        mut action: ProgressControl,
+        mut poll: impl FnMut() -> ProgressControl,
+    ) -> Result<()> {
+        let mut paused = false;
+        loop {
+            match action {
+                ProgressControl::Continue => break,
+                ProgressControl::Abort => {
+                    return Err(Error::Io(std::io::Error::new(
+                        std::io::ErrorKind::Interrupted,
+                        "transfer aborted by progress callback",
+                    )));
+                },
+                ProgressControl::Pause => {},
+            }
+            if !paused {
+                debug!("Transfer paused by progress callback");
+                paused = true;
+            }
+            self.check_interrupted()?;
+
+            let mut buf = [0u8; 64];
+            match self.read_input(&mut buf) {
+                Ok(0) => thread::sleep(PAUSE_POLL_INTERVAL),
+                Ok(n) => {
+                    if buf[..n].contains(&control::CAN) {
+                        return Err(Error::Ymodem(
+                            "Transfer cancelled by receiver while paused".into(),
+                        ));
+                    }
+                    trace!("Discarding input received while paused: {:02X?}", &buf[..n]);
+                },
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {},
+                Err(e) => return Err(Error::Io(e)),
+            }
+            action = poll();
+        }
+
+        if paused {
+            debug!("Transfer resumed");
+            // Time spent paused is not a stall.
+            self.last_progress = Some(Instant::now());
+        }
+        Ok(())
+    }
+
     /// Send file information block (block 0).
     ///
     /// Format: `filename\0filesize\0`
@@ -401,7 +682,7 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
         );
         data.push(0x00);
 
-        let block = Self::build_block(0, &data, false);
+        let block = self.block(0, &data, false);
         self.send_block(&block)
             .map_err(|err| {
                 Self::add_transfer_context(
@@ -415,7 +696,7 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
     fn send_eot(&mut self) -> Result<EotOutcome> {
         debug!("Sending EOT");
 
-        for _retry in 0..self
+        for retry in 0..self
             .config
             .max_retries
         {
@@ -474,6 +755,12 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
                             return Err(Error::Ymodem("Transfer cancelled by receiver".into()));
                         }
                         if chunk.contains(&control::NAK) {
+                            // In checksum mode the receiver asks for the next
+                            // header with NAK where CRC mode would use 'C'.
+                            if self.checksum_mode && (saw_ack || saw_ack_in_chunk) {
+                                return Ok(EotOutcome::SendFinish);
+                            }
+                            self.trace_retry(&"EOT", retry + 1, "NAK from receiver");
                             break;
                         }
                         if saw_c && (saw_ack || saw_ack_in_chunk) {
@@ -529,14 +816,14 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
             }
         }
 
-        // Consider EOT sent even without ACK
-        Ok(EotOutcome::Complete)
+        // Consider EOT sent even without ACK; the caller decides how to treat it.
+        Ok(EotOutcome::Unconfirmed)
     }
 
     /// Send finish block (empty block 0 to end session).
     pub fn send_finish(&mut self) -> Result<()> {
         debug!("Sending finish block");
-        let block = Self::build_block(0, &[], false);
+        let block = self.block(0, &[], false);
 
         for retry in 0..self
             .config
@@ -544,6 +831,7 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
         {
             self.check_interrupted()?;
             trace!("Sending finish block (attempt {})", retry + 1);
+            self.trace_block(&block, retry + 1);
 
             self.port
                 .write_all(&block)?;
@@ -571,6 +859,7 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
                         }
                         if chunk.contains(&control::NAK) {
                             debug!("Finish block NAKed, retrying...");
+                            self.trace_retry(&"finish block", retry + 1, "NAK from receiver");
                             break;
                         }
                         if let Some(ack_index) = chunk
@@ -598,6 +887,7 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
             }
 
             debug!("Timeout waiting for finish block ACK, retrying...");
+            self.trace_retry(&"finish block", retry + 1, "no ACK before timeout");
         }
 
         Err(Error::Ymodem(format!(
@@ -617,6 +907,27 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
     pub fn transfer<F>(&mut self, filename: &str, data: &[u8], mut progress: F) -> Result<()>
     where
         F: FnMut(usize, usize),
+    {
+        self.transfer_controlled(filename, data, |current, total| {
+            progress(current, total);
+            ProgressControl::Continue
+        })
+    }
+
+    /// Transfer file data, letting `progress` pause or abort the transfer
+    /// after each data block.
+    ///
+    /// While paused, input from the receiver (typically NAKs asking for the
+    /// next block) is discarded and `progress` is polled again every
+    /// [`PAUSE_POLL_INTERVAL`]. A CAN from the receiver ends the transfer.
+    pub fn transfer_controlled<F>(
+        &mut self,
+        filename: &str,
+        data: &[u8],
+        mut progress: F,
+    ) -> Result<()>
+    where
+        F: FnMut(usize, usize) -> ProgressControl,
     {
         self.check_interrupted()?;
 
@@ -636,7 +947,12 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
             })?;
 
         // Send file info (block 0)
-        self.send_file_info(filename, data.len())?;
+        let block0_name = self
+            .config
+            .filename_override
+            .clone()
+            .unwrap_or_else(|| filename.to_string());
+        self.send_file_info(&block0_name, data.len())?;
 
         // Note: WS63 device does NOT send a second 'C' after block 0 ACK.
         // Proceed directly to data blocks (confirmed by fbb_burntool and ws63flash).
@@ -645,6 +961,7 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
         let mut seq: u8 = 1;
         let mut offset = 0;
         let total = data.len();
+        self.last_progress = Some(Instant::now());
 
         while offset < total {
             self.check_interrupted()?;
@@ -652,21 +969,26 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
             let chunk_end = (offset + STX_BLOCK_SIZE).min(total);
             let chunk = &data[offset..chunk_end];
 
-            let block = Self::build_block(seq, chunk, true);
+            let block = self.block(seq, chunk, true);
             self.send_block(&block)
-                .map_err(|err| {
-                    Self::add_transfer_context(
+                .map_err(|err| match err {
+                    // Only the stall watchdog lets a timeout escape send_block;
+                    // keep it a timeout so callers can tell it apart.
+                    Error::Timeout(_) => err,
+                    err => Self::add_transfer_context(
                         err,
                         format!(
                             "while sending YMODEM data block {seq} for {filename} at offset 0x{offset:08X}"
                         ),
-                    )
+                    ),
                 })?;
+            self.last_progress = Some(Instant::now());
 
             offset = chunk_end;
             seq = seq.wrapping_add(1);
 
-            progress(offset, total);
+            let action = progress(offset, total);
+            self.follow_progress_control(action, || progress(offset, total))?;
         }
 
         // Send EOT
@@ -679,10 +1001,25 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
                 )
             })?;
 
-        if matches!(eot_outcome, EotOutcome::SendFinish)
-            || self
+        self.eot_confirmed = eot_outcome != EotOutcome::Unconfirmed;
+        if !self.eot_confirmed {
+            debug!(
+                "EOT for {filename} was not acknowledged after {} attempts; all data blocks were \
+                 ACKed",
+                self.config
+                    .max_retries
+            );
+        }
+
+        let skip_finish = !self.eot_confirmed
+            && self
                 .config
-                .finish_without_c
+                .tolerate_lost_eot;
+        if !skip_finish
+            && (matches!(eot_outcome, EotOutcome::SendFinish)
+                || self
+                    .config
+                    .finish_without_c)
         {
             let _ = self
                 .send_finish()
@@ -699,10 +1036,206 @@ impl<'a, P: Read + Write> YmodemTransfer<'a, P> {
     }
 }
 
+/// Number of leading and trailing bytes shown by [`hex_preview`].
+const HEX_PREVIEW_EDGE: usize = 8;
+
+/// Render the first and last few bytes of `data` as hex for trace logs.
+fn hex_preview(data: &[u8]) -> String {
+    let hex = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    if data.len() <= HEX_PREVIEW_EDGE * 2 {
+        return hex(data);
+    }
+    format!(
+        "{} .. {}",
+        hex(&data[..HEX_PREVIEW_EDGE]),
+        hex(&data[data.len() - HEX_PREVIEW_EDGE..])
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_transfer_tolerates_lost_eot_without_finish_block() {
+        let response = vec![
+            control::C,   // Initial 'C'
+            control::ACK, // ACK for block 0
+            control::ACK, // ACK for data block 1
+                          // EOT never acknowledged
+        ];
+
+        let mut port = MockSerial::new(&response);
+        let config = YmodemConfig {
+            char_timeout: Duration::from_millis(20),
+            c_timeout: Duration::from_millis(200),
+            max_retries: 2,
+            finish_without_c: true,
+            verbose: 0,
+            tolerate_lost_eot: true,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
+        };
+
+        let cancel = crate::CancelContext::none();
+        let mut ymodem = YmodemTransfer::with_config(&mut port, config, &cancel);
+        ymodem
+            .transfer("lost_eot.bin", &[0x11; 10], |_, _| {})
+            .unwrap();
+        assert!(!ymodem.eot_confirmed());
+
+        // Block 0 (SOH) + data block (STX) + one EOT per retry, no finish block.
+        let expected_len = (3 + SOH_BLOCK_SIZE + 2) + (3 + STX_BLOCK_SIZE + 2) + 2;
+        assert_eq!(
+            port.write_buf
+                .len(),
+            expected_len
+        );
+        assert_eq!(
+            port.write_buf[expected_len - 2..],
+            [control::EOT, control::EOT]
+        );
+    }
+
+    #[test]
+    fn test_block0_uses_filename_override_and_length() {
+        let response = vec![control::C, control::ACK, control::ACK, control::ACK];
+
+        let mut port = MockSerial::new(&response);
+        let config = YmodemConfig {
+            char_timeout: Duration::from_millis(20),
+            c_timeout: Duration::from_millis(200),
+            max_retries: 1,
+            finish_without_c: false,
+            verbose: 0,
+            tolerate_lost_eot: true,
+            filename_override: Some("boot.bin".into()),
+            stall_timeout: None,
+            checksum_fallback: false,
+        };
+
+        let cancel = crate::CancelContext::none();
+        let mut ymodem = YmodemTransfer::with_config(&mut port, config, &cancel);
+        ymodem
+            .transfer("loaderboot", &[0x11; 1234], |_, _| {})
+            .unwrap();
+
+        let block0 = &port.write_buf[..3 + SOH_BLOCK_SIZE + 2];
+        assert_eq!(block0[..3], [control::SOH, 0x00, 0xFF]);
+        let info = b"boot.bin\x001234\x00";
+        assert_eq!(&block0[3..3 + info.len()], info);
+        assert!(
+            block0[3 + info.len()..3 + SOH_BLOCK_SIZE]
+                .iter()
+                .all(|&b| b == 0)
+        );
+    }
+
+    #[test]
+    fn test_transfer_reports_confirmed_eot() {
+        let response = vec![control::C, control::ACK, control::ACK, control::ACK];
+
+        let mut port = MockSerial::new(&response);
+        let config = YmodemConfig {
+            char_timeout: Duration::from_millis(20),
+            c_timeout: Duration::from_millis(200),
+            max_retries: 1,
+            finish_without_c: false,
+            verbose: 0,
+            tolerate_lost_eot: true,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
+        };
+
+        let cancel = crate::CancelContext::none();
+        let mut ymodem = YmodemTransfer::with_config(&mut port, config, &cancel);
+        ymodem
+            .transfer("ok.bin", &[0x22; 10], |_, _| {})
+            .unwrap();
+        assert!(ymodem.eot_confirmed());
+    }
+
+    #[test]
+    fn test_transfer_uses_checksum_for_nak_first_receiver() {
+        // NAK requests checksum mode; then ACKs for block 0, the data block,
+        // EOT, and finally NAK to request the closing block 0.
+        let response = vec![
+            control::NAK,
+            control::ACK,
+            control::ACK,
+            control::ACK,
+            control::NAK,
+            control::ACK,
+        ];
+
+        let mut port = MockSerial::new(&response);
+        let config = YmodemConfig {
+            char_timeout: Duration::from_millis(20),
+            c_timeout: Duration::from_millis(200),
+            max_retries: 1,
+            finish_without_c: false,
+            verbose: 2,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: true,
+        };
+
+        let cancel = crate::CancelContext::none();
+        let mut ymodem = YmodemTransfer::with_config(&mut port, config, &cancel);
+        ymodem
+            .transfer("legacy.bin", &[0x01; 10], |_, _| {})
+            .unwrap();
+        assert!(ymodem.checksum_mode());
+
+        let block0_len = 3 + SOH_BLOCK_SIZE + 1;
+        let data_len = 3 + STX_BLOCK_SIZE + 1;
+        assert_eq!(
+            port.write_buf
+                .len(),
+            block0_len + data_len + 1 + block0_len
+        );
+        let data_block = &port.write_buf[block0_len..block0_len + data_len];
+        assert_eq!(data_block[0], control::STX);
+        // Ten 0x01 bytes followed by zero padding.
+        assert_eq!(data_block[data_len - 1], 10);
+        assert_eq!(port.write_buf[block0_len + data_len], control::EOT);
+    }
+
+    #[test]
+    fn test_wait_for_c_ignores_nak_without_checksum_fallback() {
+        let mut port = MockSerial::new(&[control::NAK]);
+        let config = YmodemConfig {
+            c_timeout: Duration::from_millis(50),
+            ..YmodemConfig::default()
+        };
+
+        let cancel = crate::CancelContext::none();
+        let mut ymodem = YmodemTransfer::with_config(&mut port, config, &cancel);
+        assert!(matches!(ymodem.wait_for_c(), Err(Error::Timeout(_))));
+        assert!(!ymodem.checksum_mode());
+    }
+
+    #[test]
+    fn test_hex_preview_short_and_long() {
+        assert_eq!(hex_preview(&[]), "");
+        assert_eq!(hex_preview(&[0x01, 0xAB]), "01 AB");
+
+        let data: Vec<u8> = (0..=0x20).collect();
+        assert_eq!(
+            hex_preview(&data),
+            "00 01 02 03 04 05 06 07 .. 19 1A 1B 1C 1D 1E 1F 20"
+        );
+    }
+
     #[test]
     fn test_build_block_soh() {
         let data = [0x01, 0x02, 0x03];
@@ -737,6 +1270,9 @@ mod tests {
     struct MockSerial {
         read_chunks: std::collections::VecDeque<Vec<u8>>,
         write_buf: Vec<u8>,
+        timeout: Duration,
+        /// Port timeout in effect at each read.
+        read_timeouts: Vec<Duration>,
     }
 
     impl MockSerial {
@@ -758,12 +1294,16 @@ mod tests {
                     .into_iter()
                     .collect(),
                 write_buf: Vec::new(),
+                timeout: Duration::from_secs(1),
+                read_timeouts: Vec::new(),
             }
         }
     }
 
     impl std::io::Read for MockSerial {
         fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.read_timeouts
+                .push(self.timeout);
             if self
                 .read_chunks
                 .is_empty()
@@ -800,6 +1340,53 @@ mod tests {
         }
     }
 
+    impl Port for MockSerial {
+        fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+            self.timeout = timeout;
+            Ok(())
+        }
+
+        fn timeout(&self) -> Duration {
+            self.timeout
+        }
+
+        fn set_baud_rate(&mut self, _baud_rate: u32) -> Result<()> {
+            Ok(())
+        }
+
+        fn baud_rate(&self) -> u32 {
+            115_200
+        }
+
+        fn clear_buffers(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+
+        fn set_dtr(&mut self, _level: bool) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_rts(&mut self, _level: bool) -> Result<()> {
+            Ok(())
+        }
+
+        fn read_cts(&mut self) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn read_dsr(&mut self) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn close(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
     /// Regression: YMODEM transfer must only call wait_for_c ONCE at the start.
     ///
     /// WS63 device sends a single 'C' after acknowledging the download command
@@ -831,6 +1418,10 @@ mod tests {
             max_retries: 1,
             finish_without_c: true,
             verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
         };
 
         let cancel = crate::CancelContext::none();
@@ -870,6 +1461,10 @@ mod tests {
             max_retries: 1,
             finish_without_c: true,
             verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
         };
 
         let cancel = crate::CancelContext::none();
@@ -903,6 +1498,10 @@ mod tests {
             max_retries: 1,
             finish_without_c: true,
             verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
         };
 
         let cancel = crate::CancelContext::none();
@@ -936,6 +1535,10 @@ mod tests {
             max_retries: 1,
             finish_without_c: true,
             verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
         };
 
         let cancel = crate::CancelContext::none();
@@ -960,6 +1563,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ymodem_transfer_controlled_pauses_and_aborts() {
+        let config = YmodemConfig {
+            char_timeout: Duration::from_millis(100),
+            c_timeout: Duration::from_millis(200),
+            max_retries: 1,
+            finish_without_c: true,
+            verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
+        };
+        let cancel = crate::CancelContext::none();
+        let data = vec![0xA5; STX_BLOCK_SIZE * 2];
+
+        // The NAK arrives while paused and must not trigger a resend.
+        let mut port = MockSerial::new(&[
+            control::C,
+            control::ACK,
+            control::ACK,
+            control::NAK,
+            control::ACK,
+            control::ACK,
+            control::ACK,
+        ]);
+        let mut ymodem = YmodemTransfer::with_config(&mut port, config.clone(), &cancel);
+        let mut calls = Vec::new();
+        ymodem
+            .transfer_controlled("pause.bin", &data, |current, _| {
+                calls.push(current);
+                if calls.len() == 1 {
+                    ProgressControl::Pause
+                } else {
+                    ProgressControl::Continue
+                }
+            })
+            .unwrap();
+        assert_eq!(calls, [STX_BLOCK_SIZE, STX_BLOCK_SIZE, STX_BLOCK_SIZE * 2]);
+
+        let mut port = MockSerial::new(&[control::C, control::ACK, control::ACK]);
+        let mut ymodem = YmodemTransfer::with_config(&mut port, config, &cancel);
+        let err = ymodem
+            .transfer_controlled("abort.bin", &data, |_, _| ProgressControl::Abort)
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(ref e) if e.kind() == std::io::ErrorKind::Interrupted));
+        // Block 0 and the first data block only.
+        assert_eq!(
+            port.write_buf
+                .len(),
+            (SOH_BLOCK_SIZE + 5) + (STX_BLOCK_SIZE + 5)
+        );
+    }
+
     #[test]
     fn test_ymodem_transfer_accepts_ack_amid_noise() {
         let mut port = MockSerial::with_chunks([
@@ -975,6 +1632,10 @@ mod tests {
             max_retries: 2,
             finish_without_c: true,
             verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
         };
 
         let cancel = crate::CancelContext::none();
@@ -1003,6 +1664,10 @@ mod tests {
             max_retries: 1,
             finish_without_c: false,
             verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
         };
 
         let cancel = crate::CancelContext::none();
@@ -1033,6 +1698,10 @@ mod tests {
             max_retries: 2,
             finish_without_c: true,
             verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
         };
 
         let cancel = crate::CancelContext::none();
@@ -1062,6 +1731,10 @@ mod tests {
             max_retries: 1,
             finish_without_c: false,
             verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
         };
 
         let cancel = crate::CancelContext::none();
@@ -1090,6 +1763,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_wait_for_c_sets_and_restores_port_timeout() {
+        let config = YmodemConfig {
+            c_timeout: Duration::from_millis(60),
+            ..YmodemConfig::default()
+        };
+        let cancel = crate::CancelContext::none();
+
+        let mut port = MockSerial::new(&[0x00, control::C]);
+        port.timeout = Duration::from_secs(5);
+        YmodemTransfer::with_config(&mut port, config.clone(), &cancel)
+            .with_port_timeout_control()
+            .wait_for_c()
+            .unwrap();
+        assert_eq!(port.read_timeouts, [Duration::from_millis(60); 2]);
+        assert_eq!(port.timeout, Duration::from_secs(5));
+
+        // Restored after a failed wait as well.
+        let mut port = MockSerial::new(&[]);
+        port.timeout = Duration::from_secs(5);
+        let result = YmodemTransfer::with_config(&mut port, config, &cancel)
+            .with_port_timeout_control()
+            .wait_for_c();
+        assert!(matches!(result, Err(Error::Timeout(_))));
+        assert_eq!(port.timeout, Duration::from_secs(5));
+    }
+
     #[test]
     fn test_wait_for_c_interrupted_immediate() {
         let mut port = MockSerial::new(&[]);
@@ -1099,6 +1799,10 @@ mod tests {
             max_retries: 1,
             finish_without_c: true,
             verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
         };
 
         let cancel = crate::CancelContext::new(|| true);
@@ -1120,6 +1824,10 @@ mod tests {
             max_retries: 1,
             finish_without_c: true,
             verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
         };
 
         let cancel = crate::CancelContext::new(|| true);
@@ -1136,4 +1844,72 @@ mod tests {
             "Interrupted transfer should not write any YMODEM data"
         );
     }
+
+    #[test]
+    fn test_transfer_stall_timeout_aborts_before_retries_run_out() {
+        // 'C' to start and ACK for block 0, then silence for the data block.
+        let mut port = MockSerial::new(&[control::C, control::ACK]);
+        let config = YmodemConfig {
+            char_timeout: Duration::from_millis(50),
+            c_timeout: Duration::from_millis(100),
+            max_retries: 100,
+            finish_without_c: true,
+            verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: Some(Duration::from_millis(200)),
+            checksum_fallback: false,
+        };
+
+        let cancel = crate::CancelContext::none();
+        let mut ymodem = YmodemTransfer::with_config(&mut port, config, &cancel);
+        let start = Instant::now();
+        let result = ymodem.transfer("app.bin", &[0x11; 16], |_, _| {});
+
+        assert!(matches!(result, Err(Error::Timeout(ref msg)) if msg.contains("flash stalled")));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_transfer_interrupted_between_data_blocks() {
+        use std::sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        };
+
+        // 'C' to start, ACK for block 0, ACK for the first data block only.
+        let mut port = MockSerial::new(&[control::C, control::ACK, control::ACK]);
+        let config = YmodemConfig {
+            char_timeout: Duration::from_millis(50),
+            c_timeout: Duration::from_millis(100),
+            max_retries: 1,
+            finish_without_c: true,
+            verbose: 0,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: false,
+        };
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancelled);
+        let cancel = crate::CancelContext::new(move || flag.load(Ordering::SeqCst));
+        let data = vec![0x5A; STX_BLOCK_SIZE * 3];
+
+        let mut ymodem = YmodemTransfer::with_config(&mut port, config, &cancel);
+        let result = ymodem.transfer("app.bin", &data, |_, _| {
+            cancelled.store(true, Ordering::SeqCst);
+        });
+
+        assert!(matches!(
+            result,
+            Err(Error::Io(ref io)) if io.kind() == std::io::ErrorKind::Interrupted
+        ));
+        assert_eq!(
+            port.write_buf
+                .len(),
+            (3 + SOH_BLOCK_SIZE + 2) + (3 + STX_BLOCK_SIZE + 2),
+            "Only block 0 and the first data block should be written before cancelling"
+        );
+    }
 }
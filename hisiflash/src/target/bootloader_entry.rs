@@ -0,0 +1,197 @@
+//! Automatic entry into download mode via the DTR/RTS lines.
+//!
+//! Many boards wire the USB-serial adapter's RTS to the chip's reset and DTR
+//! to a boot-select pin, so a tool can restart the chip into its boot ROM
+//! without a button press. A [`BootloaderEntry`] is the pin sequence for one
+//! such wiring, written in the same notation as esptool's custom reset
+//! sequences:
+//!
+//! ```text
+//! D1|R1|W0.1|R0|W0.05|D0
+//! ```
+//!
+//! `D0`/`D1` and `R0`/`R1` deassert/assert DTR and RTS, `W<seconds>` waits.
+//! Asserting a line drives it low on common adapters.
+
+use {
+    crate::{
+        CancelContext,
+        error::{Error, Result},
+        port::Port,
+    },
+    std::{fmt, str::FromStr, thread, time::Duration},
+};
+
+/// Longest wait accepted in a sequence.
+const MAX_WAIT: Duration = Duration::from_secs(10);
+
+/// One step of a [`BootloaderEntry`] sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinStep {
+    /// Assert (`true`) or deassert (`false`) DTR.
+    Dtr(bool),
+    /// Assert (`true`) or deassert (`false`) RTS.
+    Rts(bool),
+    /// Wait before the next step.
+    Wait(Duration),
+}
+
+/// DTR/RTS sequence that puts a board into download mode.
+///
+/// Parse one from a preset name (see [`Self::PRESETS`]) or a sequence such
+/// as `R1|W0.1|R0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootloaderEntry {
+    steps: Vec<PinStep>,
+}
+
+impl BootloaderEntry {
+    /// Names accepted by [`Self::preset`] and [`FromStr`].
+    pub const PRESETS: &'static [&'static str] = &["reset", "dtr-boot"];
+
+    /// Create a sequence from its steps.
+    pub fn new(steps: Vec<PinStep>) -> Self {
+        Self { steps }
+    }
+
+    /// Look up a named preset.
+    ///
+    /// - `reset`: pulse RTS (wired to reset). WS63 boots into its ROM,
+    ///   which enters download mode when the handshake arrives in time.
+    /// - `dtr-boot`: hold DTR (wired to boot-select) while pulsing RTS,
+    ///   for boards that must be strapped into download mode.
+    pub fn preset(name: &str) -> Option<Self> {
+        let sequence = match name {
+            "reset" => "R1|W0.1|R0",
+            "dtr-boot" => "D1|R1|W0.1|R0|W0.1|D0",
+            _ => return None,
+        };
+        sequence
+            .parse()
+            .ok()
+    }
+
+    /// The steps of this sequence.
+    pub fn steps(&self) -> &[PinStep] {
+        &self.steps
+    }
+
+    /// Drive the port's control lines through the sequence.
+    ///
+    /// `cancel` is checked before every step.
+    pub fn apply<P: Port + ?Sized>(&self, port: &mut P, cancel: &CancelContext) -> Result<()> {
+        for step in &self.steps {
+            cancel.check()?;
+            match *step {
+                PinStep::Dtr(level) => port.set_dtr(level)?,
+                PinStep::Rts(level) => port.set_rts(level)?,
+                PinStep::Wait(duration) => thread::sleep(duration),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for BootloaderEntry {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some(preset) = Self::preset(s) {
+            return Ok(preset);
+        }
+
+        let invalid = |token: &str| {
+            Error::Config(format!(
+                "Invalid bootloader entry step {token:?} in {s:?}; expected D0, D1, R0, R1 or \
+                 W<seconds>, or one of the presets {}",
+                Self::PRESETS.join(", ")
+            ))
+        };
+        let mut steps = Vec::new();
+        for token in s.split('|') {
+            let token = token.trim();
+            let step = match token {
+                "D0" | "d0" => PinStep::Dtr(false),
+                "D1" | "d1" => PinStep::Dtr(true),
+                "R0" | "r0" => PinStep::Rts(false),
+                "R1" | "r1" => PinStep::Rts(true),
+                _ => {
+                    let seconds = token
+                        .strip_prefix(['W', 'w'])
+                        .and_then(|secs| {
+                            secs.parse::<f64>()
+                                .ok()
+                        })
+                        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                        .filter(|wait| *wait <= MAX_WAIT)
+                        .ok_or_else(|| invalid(token))?;
+                    PinStep::Wait(seconds)
+                },
+            };
+            steps.push(step);
+        }
+        Ok(Self::new(steps))
+    }
+}
+
+impl fmt::Display for BootloaderEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, step) in self
+            .steps
+            .iter()
+            .enumerate()
+        {
+            if i > 0 {
+                f.write_str("|")?;
+            }
+            match step {
+                PinStep::Dtr(level) => write!(f, "D{}", u8::from(*level))?,
+                PinStep::Rts(level) => write!(f, "R{}", u8::from(*level))?,
+                PinStep::Wait(duration) => write!(f, "W{}", duration.as_secs_f64())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bootloader_entry_parses_sequences_and_presets() {
+        let entry: BootloaderEntry = "D1|R1|W0.1|r0|w0.05|D0"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            entry.steps(),
+            [
+                PinStep::Dtr(true),
+                PinStep::Rts(true),
+                PinStep::Wait(Duration::from_millis(100)),
+                PinStep::Rts(false),
+                PinStep::Wait(Duration::from_millis(50)),
+                PinStep::Dtr(false),
+            ]
+        );
+        assert_eq!(entry.to_string(), "D1|R1|W0.1|R0|W0.05|D0");
+
+        for name in BootloaderEntry::PRESETS {
+            let preset = BootloaderEntry::preset(name).unwrap();
+            assert_eq!(
+                name.parse::<BootloaderEntry>()
+                    .unwrap(),
+                preset
+            );
+        }
+
+        for bad in ["", "X1", "D2", "W", "W-1", "W60", "R1||R0"] {
+            assert!(
+                bad.parse::<BootloaderEntry>()
+                    .is_err(),
+                "{bad:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,975 @@
+//! Chip/target abstraction for supporting multiple HiSilicon chips.
+//!
+//! This module provides a trait-based abstraction for different chip families,
+//! allowing the same codebase to support WS63, BS2X, and other HiSilicon chips.
+
+use {
+    crate::{
+        error::{Error, Result},
+        image::fwpkg::{Fwpkg, PartitionFilter, PartitionType},
+        port::{Port, SerialConfig},
+    },
+    std::{
+        fmt,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+    },
+};
+
+/// Default limit for a single partition image, see
+/// [`Flasher::set_max_partition_bytes`].
+pub const DEFAULT_MAX_PARTITION_BYTES: usize = 64 * 1024 * 1024;
+
+/// Supported chip families.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ChipFamily {
+    /// WS63 series (WiFi + BLE).
+    #[default]
+    Ws63,
+    /// BS2X series (BS21, BS25, etc. - BLE only).
+    Bs2x,
+    /// BS25 specific.
+    Bs25,
+    /// WS53 series.
+    Ws53,
+    /// SW39 series.
+    Sw39,
+    /// Generic HiSilicon (unknown specific type).
+    Generic,
+}
+
+impl ChipFamily {
+    /// Get default baud rate for this chip family.
+    ///
+    /// This is the rate the boot ROM listens on, i.e. the
+    /// [`handshake_baud`](Self::handshake_baud).
+    #[must_use]
+    pub fn default_baud(&self) -> u32 {
+        self.handshake_baud()
+    }
+
+    /// Get the baud rate the boot ROM expects for the initial handshake.
+    ///
+    /// Flashers created by this family connect at this rate before switching
+    /// to the data transfer baud rate.
+    #[must_use]
+    pub fn handshake_baud(&self) -> u32 {
+        // All chips currently handshake at 115200
+        115_200
+    }
+
+    /// Get the baud rates a flasher can handshake at, for boards whose boot
+    /// ROM UART is not at the default [`handshake_baud`](Self::handshake_baud).
+    ///
+    /// See [`Flasher::set_handshake_baud`].
+    #[must_use]
+    pub fn supported_handshake_bauds(&self) -> &'static [u32] {
+        &[
+            9_600, 19_200, 38_400, 57_600, 115_200, 230_400, 460_800, 921_600,
+        ]
+    }
+
+    /// Get high-speed baud rate for this chip family.
+    #[must_use]
+    pub fn high_speed_baud(&self) -> u32 {
+        match self {
+            Self::Bs2x | Self::Bs25 => 2_000_000,
+            _ => 921_600,
+        }
+    }
+
+    /// Get recommended flash baud rate for this chip family.
+    ///
+    /// BS2X/BS25 chips use 460800 as the recommended rate because CH340/CH341
+    /// USB-serial adapters (commonly used with these chips) are unreliable at
+    /// 921600 baud, causing YMODEM transfer failures around the 2KB mark.
+    /// WS63 and other chips use 921600 for maximum throughput.
+    #[must_use]
+    pub fn recommended_flash_baud(&self) -> u32 {
+        match self {
+            Self::Bs2x | Self::Bs25 => 460_800,
+            _ => 921_600,
+        }
+    }
+
+    /// Get supported baud rates for this chip family.
+    #[must_use]
+    pub fn supported_bauds(&self) -> &'static [u32] {
+        match self {
+            Self::Bs2x | Self::Bs25 => &[115_200, 230_400, 460_800, 921_600, 2_000_000],
+            _ => &[115_200, 230_400, 460_800, 921_600],
+        }
+    }
+
+    /// Check if flashing is implemented for this chip family.
+    ///
+    /// WS63, BS2X and BS25 share the SEBOOT serial path; the other families
+    /// are recognized but [`create_flasher`](Self::create_flasher) rejects
+    /// them with [`Error::Unsupported`].
+    pub fn is_supported(&self) -> bool {
+        matches!(self, Self::Ws63 | Self::Bs2x | Self::Bs25)
+    }
+
+    /// Check if this chip family supports USB DFU mode.
+    pub fn supports_usb_dfu(&self) -> bool {
+        matches!(self, Self::Bs2x | Self::Bs25)
+    }
+
+    /// Check if this chip family supports eFuse operations.
+    pub fn supports_efuse(&self) -> bool {
+        true // All HiSilicon chips support eFuse
+    }
+
+    /// Groups of partition types that must be flashed together.
+    ///
+    /// Flashing only some members of a group leaves the device in an
+    /// inconsistent state; e.g. the SEBOOT chips verify the security
+    /// partitions against each other. Unknown families have no groups.
+    #[must_use]
+    pub fn coupled_partitions(&self) -> &'static [&'static [PartitionType]] {
+        match self {
+            Self::Ws63 | Self::Bs2x | Self::Bs25 => &[&[
+                PartitionType::SecurityA,
+                PartitionType::SecurityB,
+                PartitionType::SecurityC,
+            ]],
+            _ => &[],
+        }
+    }
+
+    /// Check if this chip family requires signed firmware.
+    pub fn requires_signed_firmware(&self) -> bool {
+        // Some chips require signed firmware for security
+        matches!(self, Self::Ws63 | Self::Bs2x | Self::Bs25)
+    }
+
+    /// Get the chip family from a string name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name
+            .to_lowercase()
+            .as_str()
+        {
+            "ws63" => Some(Self::Ws63),
+            "bs2x" | "bs21" => Some(Self::Bs2x),
+            "bs25" => Some(Self::Bs25),
+            "ws53" => Some(Self::Ws53),
+            "sw39" => Some(Self::Sw39),
+            "generic" | "auto" => Some(Self::Generic),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ChipFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ws63 => write!(f, "WS63"),
+            Self::Bs2x => write!(f, "BS2X"),
+            Self::Bs25 => write!(f, "BS25"),
+            Self::Ws53 => write!(f, "WS53"),
+            Self::Sw39 => write!(f, "SW39"),
+            Self::Generic => write!(f, "Generic"),
+        }
+    }
+}
+
+/// Chip configuration parameters.
+#[derive(Debug, Clone)]
+pub struct ChipConfig {
+    /// Chip family.
+    pub family: ChipFamily,
+    /// Initial baud rate for handshake.
+    pub init_baud: u32,
+    /// Target baud rate for data transfer.
+    pub target_baud: u32,
+    /// Use late baud rate switch (after loaderboot).
+    pub late_baud_switch: bool,
+    /// Handshake timeout in seconds.
+    pub handshake_timeout_secs: u32,
+    /// Data transfer timeout in seconds.
+    pub transfer_timeout_secs: u32,
+}
+
+impl ChipConfig {
+    /// Create a new chip configuration for the given family.
+    pub fn new(family: ChipFamily) -> Self {
+        Self {
+            family,
+            init_baud: family.handshake_baud(),
+            target_baud: family.high_speed_baud(),
+            late_baud_switch: false,
+            handshake_timeout_secs: 30,
+            transfer_timeout_secs: 60,
+        }
+    }
+
+    /// Set the target baud rate.
+    #[must_use]
+    pub fn with_baud(mut self, baud: u32) -> Self {
+        self.target_baud = baud;
+        self
+    }
+
+    /// Enable late baud rate switching.
+    #[must_use]
+    pub fn with_late_baud(mut self, late: bool) -> Self {
+        self.late_baud_switch = late;
+        self
+    }
+
+    /// Groups of partition types that must be flashed together, see
+    /// [`ChipFamily::coupled_partitions`].
+    #[must_use]
+    pub fn coupled_partitions(&self) -> &'static [&'static [PartitionType]] {
+        self.family
+            .coupled_partitions()
+    }
+
+    /// Set handshake timeout.
+    #[must_use]
+    pub fn with_handshake_timeout(mut self, secs: u32) -> Self {
+        self.handshake_timeout_secs = secs;
+        self
+    }
+}
+
+impl Default for ChipConfig {
+    fn default() -> Self {
+        Self::new(ChipFamily::default())
+    }
+}
+
+/// Explicit consent to program OTP/eFuse bits, required by
+/// [`Flasher::write_otp`].
+///
+/// OTP/eFuse bits can be set but never cleared, so a bad write can disable
+/// secure boot keys or brick the chip. The token has no [`Default`] and is
+/// not [`Clone`]: every write needs one built on purpose, right where the
+/// user confirmed it.
+#[derive(Debug)]
+pub struct OtpConfirmation {
+    _private: (),
+}
+
+impl OtpConfirmation {
+    /// Confirm that the caller knows OTP/eFuse writes cannot be undone.
+    pub fn i_understand_otp_is_permanent() -> Self {
+        Self { _private: () }
+    }
+}
+
+/// Stops a flasher's operation from another thread; see
+/// [`Flasher::abort_handle`].
+///
+/// Cloning the handle shares the same flag. A handle keeps working after the
+/// flasher reconnects, but [`Flasher::connect`] clears an abort requested
+/// before it.
+#[derive(Debug, Clone, Default)]
+pub struct AbortHandle {
+    aborted: Arc<AtomicBool>,
+}
+
+impl AbortHandle {
+    /// Create a handle that sets `aborted`.
+    pub(crate) fn new(aborted: Arc<AtomicBool>) -> Self {
+        Self { aborted }
+    }
+
+    /// Make the flasher's cancellation checks fail, so the running operation
+    /// stops with an interrupted error at its next check point.
+    pub fn abort(&self) {
+        self.aborted
+            .store(true, Ordering::SeqCst);
+    }
+}
+
+/// Trait for flashing operations across all chip families.
+///
+/// This trait provides a unified interface for flashing firmware,
+/// allowing the CLI to work with any chip family through a common API.
+pub trait Flasher {
+    /// Connect to the device and perform handshake.
+    fn connect(&mut self) -> Result<()>;
+
+    /// Flash a complete FWPKG firmware package.
+    ///
+    /// # Arguments
+    ///
+    /// * `fwpkg` - The firmware package to flash
+    /// * `filter` - Optional partition filters; a partition is flashed if any
+    ///   filter matches (None = flash all). LoaderBoot is always sent.
+    /// * `progress` - Progress callback (partition_name, current_bytes,
+    ///   total_bytes)
+    fn flash_fwpkg(
+        &mut self,
+        fwpkg: &Fwpkg,
+        filter: Option<&[PartitionFilter]>,
+        progress: &mut dyn FnMut(&str, usize, usize),
+    ) -> Result<()>;
+
+    /// Like [`Self::flash_fwpkg`], but `progress` decides after every data
+    /// block whether to continue, pause or abort (see
+    /// [`crate::ProgressControl`]).
+    ///
+    /// For frontends with a pause button. The default implementation returns
+    /// [`Error::Unsupported`].
+    fn flash_fwpkg_controlled(
+        &mut self,
+        _fwpkg: &Fwpkg,
+        _filter: Option<&[PartitionFilter]>,
+        _progress: &mut dyn FnMut(&str, usize, usize) -> crate::ProgressControl,
+    ) -> Result<()> {
+        Err(Error::Unsupported(
+            "Flasher does not support pausable progress".into(),
+        ))
+    }
+
+    /// Flash raw binary files.
+    fn write_bins(&mut self, loaderboot: &[u8], bins: &[(&[u8], u32)]) -> Result<()>;
+
+    /// Flash raw binary files without re-sending LoaderBoot.
+    ///
+    /// Must follow a successful [`Self::flash_fwpkg`] or [`Self::write_bins`]
+    /// on the same connection, so that LoaderBoot is already running and the
+    /// baud rate is already switched. The default implementation fails with
+    /// [`Error::Unsupported`].
+    fn download_bins_only(
+        &mut self,
+        _bins: &[(&[u8], u32)],
+        _progress: &mut dyn FnMut(&str, usize, usize),
+    ) -> Result<()> {
+        Err(Error::Unsupported(
+            "Writing without LoaderBoot is not supported by this flasher".to_string(),
+        ))
+    }
+
+    /// Write `data` at `flash_addr`, erasing only the sectors it touches.
+    ///
+    /// For patching a region inside a partition. Like
+    /// [`Self::download_bins_only`], this must follow a LoaderBoot transfer
+    /// on the same connection. The default implementation fails with
+    /// [`Error::Unsupported`].
+    fn write_at(
+        &mut self,
+        _flash_addr: u32,
+        _data: &[u8],
+        _progress: &mut dyn FnMut(&str, usize, usize),
+    ) -> Result<()> {
+        Err(Error::Unsupported(
+            "Region writes are not supported by this flasher".to_string(),
+        ))
+    }
+
+    /// Permit the next destructive operation ([`Self::erase_all`]) once the
+    /// user has confirmed it. The default implementation does nothing.
+    fn arm_destructive(&mut self) {}
+
+    /// Erase entire flash.
+    ///
+    /// Implementations may refuse with [`crate::Error::Config`] unless
+    /// [`Self::arm_destructive`] was called first.
+    fn erase_all(&mut self) -> Result<()>;
+
+    /// Program OTP/eFuse data. This cannot be undone.
+    ///
+    /// Like [`Self::download_bins_only`], this must follow a LoaderBoot
+    /// transfer on the same connection. The default implementation fails
+    /// with [`Error::Unsupported`].
+    fn write_otp(&mut self, _data: &[u8], _confirm: OtpConfirmation) -> Result<()> {
+        Err(Error::Unsupported(
+            "OTP/eFuse writes are not supported by this flasher".to_string(),
+        ))
+    }
+
+    /// Abort and discard pending serial data.
+    ///
+    /// This needs `&mut self`, so it runs between operations, e.g. after a
+    /// progress callback asked to stop; use [`Self::abort_handle`] to stop
+    /// an operation still running on another thread. Operations fail with an
+    /// interrupted error until [`Self::connect`] is called again. The
+    /// default implementation trips [`Self::abort_handle`].
+    fn abort(&mut self) {
+        self.abort_handle()
+            .abort();
+    }
+
+    /// Get a handle that aborts this flasher's operations from any thread,
+    /// including the one currently running.
+    ///
+    /// The default implementation returns a handle connected to nothing, so
+    /// aborting through it has no effect.
+    fn abort_handle(&self) -> AbortHandle {
+        AbortHandle::default()
+    }
+
+    /// Keep a partition whose data blocks were all ACKed even when the end of
+    /// the transfer is never confirmed, instead of erasing and re-sending it.
+    ///
+    /// See [`crate::protocol::ymodem::YmodemConfig::tolerate_lost_eot`]. The
+    /// default implementation ignores the setting.
+    fn set_tolerate_lost_eot(&mut self, _tolerate: bool) {}
+
+    /// Accept a NAK as the YMODEM start request and send blocks with an
+    /// 8-bit checksum, for loaders that do not speak CRC mode.
+    ///
+    /// See [`crate::protocol::ymodem::YmodemConfig::checksum_fallback`]. The
+    /// default implementation ignores the setting.
+    fn set_checksum_fallback(&mut self, _fallback: bool) {}
+
+    /// Continue with the next partition when one fails in
+    /// [`Self::flash_fwpkg`], reporting all failures at the end as
+    /// [`crate::Error::PartitionsFailed`]. The default implementation ignores
+    /// the setting.
+    fn set_keep_going(&mut self, _keep_going: bool) {}
+
+    /// Abort a transfer when no data block has been acknowledged for
+    /// `timeout`, even if retries remain; `None` disables the watchdog.
+    ///
+    /// See [`crate::protocol::ymodem::YmodemConfig::stall_timeout`]. The
+    /// default implementation ignores the setting.
+    fn set_stall_timeout(&mut self, _timeout: Option<std::time::Duration>) {}
+
+    /// Fail with [`crate::Error::Timeout`] at the next retry boundary once
+    /// `deadline` has passed since [`Self::connect`] started; `None` removes
+    /// the bound. The default implementation ignores the setting.
+    fn set_total_deadline(&mut self, _deadline: Option<std::time::Duration>) {}
+
+    /// Wait `settle` after the reset command before [`Self::reset`]
+    /// returns. The default implementation ignores the setting.
+    fn set_reset_settle(&mut self, _settle: std::time::Duration) {}
+
+    /// Refuse to transfer a partition image larger than `max` bytes, failing
+    /// with [`crate::Error::PartitionTooLarge`] before anything is sent;
+    /// `None` removes the limit.
+    ///
+    /// Guards against flashing a wrongly selected file. Flashers start with
+    /// [`DEFAULT_MAX_PARTITION_BYTES`]. The default implementation ignores
+    /// the setting.
+    fn set_max_partition_bytes(&mut self, _max: Option<usize>) {}
+
+    /// Handshake at `baud` instead of the chip family's default
+    /// [`ChipFamily::handshake_baud`], before switching to the data rate.
+    ///
+    /// Callers should check the rate against
+    /// [`ChipFamily::supported_handshake_bauds`]. The default implementation
+    /// ignores the setting.
+    fn set_handshake_baud(&mut self, _baud: u32) {}
+
+    /// Never send LoaderBoot, for devices known to run it already (e.g.
+    /// after an aborted flash). Flashing fails if the device is still in
+    /// first-stage boot. The default implementation ignores the setting.
+    fn set_skip_loaderboot(&mut self, _skip: bool) {}
+
+    /// Erase the whole flash while flashing a FWPKG, after LoaderBoot and
+    /// before the first partition. Needs [`Self::arm_destructive`] like
+    /// [`Self::erase_all`]. The default implementation ignores the setting.
+    fn set_pre_erase(&mut self, _pre_erase: bool) {}
+
+    /// Clear the global interrupt flag (see [`crate::clear_interrupt_flag`])
+    /// when [`Self::connect`] starts, so a Ctrl-C that ended an earlier
+    /// operation does not cancel this one. The default implementation
+    /// ignores the setting.
+    fn set_clear_interrupt_on_start(&mut self, _clear: bool) {}
+
+    /// Drive DTR/RTS through `entry` at the start of [`Self::connect`] to put
+    /// the board into download mode, instead of waiting for a manual reset;
+    /// `None` turns this off. The default implementation ignores the setting.
+    fn set_auto_bootloader(&mut self, _entry: Option<crate::target::BootloaderEntry>) {}
+
+    /// Reset the board through the auto bootloader sequence (or the `reset`
+    /// preset) when the handshake receives application output instead of
+    /// waiting for a manual reset. The default implementation ignores the
+    /// setting.
+    fn set_auto_reset_on_app_mode(&mut self, _enabled: bool) {}
+
+    /// Treat the device as running its application once `bytes` of non-ACK
+    /// data arrive during one handshake attempt (50 by default), for
+    /// [`Self::set_auto_reset_on_app_mode`]. The default implementation
+    /// ignores the setting.
+    fn set_app_detect_threshold(&mut self, _bytes: usize) {}
+
+    /// Write a timestamped hex log of the bytes exchanged during
+    /// [`Self::connect`] to `sink`, for sharing handshake problems.
+    ///
+    /// Each line holds the time since `connect()` started, `TX` or `RX`, the
+    /// length and the bytes in hex; lines with `#` mark attempts. The default
+    /// implementation ignores the sink.
+    fn set_trace_sink(&mut self, _sink: Box<dyn std::io::Write + Send>) {}
+
+    /// Capture error responses from the device: the raw frame of each failed
+    /// ACK is appended to `sink`. The command fails with [`Error::Protocol`]
+    /// whether or not a sink is set. The default implementation ignores the
+    /// sink.
+    fn set_device_error_sink(&mut self, _sink: Box<dyn std::io::Write + Send>) {}
+
+    /// Switch the device to DFU mode.
+    ///
+    /// The device leaves the serial download protocol and waits for a DFU
+    /// tool; only [`Self::close`] is meaningful afterwards. The default
+    /// implementation fails with [`Error::Unsupported`].
+    fn switch_to_dfu(&mut self) -> Result<()> {
+        Err(Error::Unsupported(
+            "Switching to DFU mode is not supported by this flasher".to_string(),
+        ))
+    }
+
+    /// Reset the device.
+    fn reset(&mut self) -> Result<()>;
+
+    /// Get the connection baud rate.
+    fn connection_baud(&self) -> u32;
+
+    /// Get the target transfer baud rate (if different from connection).
+    fn target_baud(&self) -> Option<u32>;
+
+    /// Get the chip configuration in effect (family, baud rates, late baud
+    /// switch), reflecting the setters applied so far.
+    fn chip_config(&self) -> &ChipConfig;
+
+    /// Close the flasher and release resources.
+    ///
+    /// This method ensures the serial port is properly closed.
+    /// It is safe to call even if the connection is not active.
+    /// After calling this method, the flasher cannot be used.
+    fn close(&mut self);
+
+    /// Hand off the underlying serial port to a [`crate::monitor::MonitorSession`].
+    ///
+    /// Consumes the flasher and re-purposes its open serial handle for the
+    /// monitor without going through close/reopen, which would otherwise
+    /// drop the early bootlog the chip emits right after [`Self::reset`].
+    ///
+    /// `baud_rate` is the operating-mode baud rate (typically 115200) the
+    /// device will speak after reboot, and it will be applied to the handle
+    /// before returning.
+    ///
+    /// The default implementation returns [`Error::Unsupported`]. Concrete
+    /// flashers backed by a real serial port should override.
+    ///
+    /// Only available with the `native` feature.
+    #[cfg(feature = "native")]
+    fn into_monitor(self: Box<Self>, _baud_rate: u32) -> Result<crate::monitor::MonitorSession> {
+        Err(crate::error::Error::Unsupported(
+            "Flasher does not support monitor handoff".into(),
+        ))
+    }
+}
+
+impl ChipFamily {
+    /// Create a flasher instance for this chip family (native platforms).
+    ///
+    /// This is the main entry point for creating chip-specific flashers.
+    ///
+    /// # Arguments
+    ///
+    /// * `port_name` - Serial port name (e.g., "/dev/ttyUSB0"), or an RFC 2217
+    ///   URL (`rfc2217://host:port`) for a network serial server
+    /// * `target_baud` - Target baud rate for data transfer
+    /// * `late_baud` - Use late baud rate switch (after LoaderBoot)
+    /// * `verbose` - Verbose output level
+    ///
+    /// # Returns
+    ///
+    /// A boxed flasher instance implementing the `Flasher` trait
+    #[cfg(feature = "native")]
+    pub fn create_flasher(
+        &self,
+        port_name: &str,
+        target_baud: u32,
+        late_baud: bool,
+        verbose: u8,
+    ) -> Result<Box<dyn Flasher>> {
+        match self {
+            Self::Ws63 | Self::Bs2x | Self::Bs25
+                if crate::port::rfc2217::is_rfc2217_url(port_name) =>
+            {
+                let port =
+                    crate::port::rfc2217::Rfc2217Port::open(port_name, self.handshake_baud())?;
+                self.create_flasher_with_port_and_cancel(
+                    port,
+                    target_baud,
+                    late_baud,
+                    verbose,
+                    crate::cancel_context_from_global(),
+                )
+            },
+            Self::Ws63 | Self::Bs2x | Self::Bs25 => {
+                // WS63/BS2X/BS25 currently share the same serial SEBOOT/YMODEM
+                // transport implementation. Chip-specific quirks are handled in
+                // the shared protocol layer.
+                let flasher = super::ws63::flasher::Ws63Flasher::open(port_name, target_baud)?
+                    .with_family(*self)
+                    .with_handshake_baud(self.handshake_baud())
+                    .with_late_baud(late_baud)
+                    .with_finish_without_c(!matches!(self, Self::Bs2x | Self::Bs25))
+                    .with_verbose(verbose);
+                Ok(Box::new(flasher))
+            },
+            Self::Ws53 | Self::Sw39 => Err(Error::Unsupported(format!(
+                "{self} series support coming soon"
+            ))),
+            Self::Generic => Err(Error::Unsupported(
+                "Cannot create flasher for generic chip family".into(),
+            )),
+        }
+    }
+
+    /// Create a flasher with an existing port (generic, works for any Port
+    /// type).
+    ///
+    /// This is useful for testing or custom port implementations.
+    #[cfg(feature = "native")]
+    pub fn create_flasher_with_port<P: Port + 'static>(
+        &self,
+        port: P,
+        target_baud: u32,
+        late_baud: bool,
+        verbose: u8,
+    ) -> Result<Box<dyn Flasher>> {
+        self.create_flasher_with_port_and_cancel(
+            port,
+            target_baud,
+            late_baud,
+            verbose,
+            crate::CancelContext::none(),
+        )
+    }
+
+    /// Create a flasher with a boxed port.
+    ///
+    /// For transports selected at runtime (e.g. a local serial port or
+    /// RFC2217), where the concrete port type cannot be named. Like the
+    /// other port-based constructors, this needs the `native` feature.
+    #[cfg(feature = "native")]
+    pub fn create_flasher_from_boxed(
+        &self,
+        port: Box<dyn Port>,
+        target_baud: u32,
+        late_baud: bool,
+        verbose: u8,
+    ) -> Result<Box<dyn Flasher>> {
+        self.create_flasher_with_port(port, target_baud, late_baud, verbose)
+    }
+
+    /// Create a flasher with an existing port and explicit cancel context.
+    ///
+    /// This is the recommended way to create a flasher when you want to
+    /// support cancellation (Ctrl-C) from the embedding application.
+    #[cfg(feature = "native")]
+    pub fn create_flasher_with_port_and_cancel<P: Port + 'static>(
+        &self,
+        port: P,
+        target_baud: u32,
+        late_baud: bool,
+        verbose: u8,
+        cancel: crate::CancelContext,
+    ) -> Result<Box<dyn Flasher>> {
+        match self {
+            Self::Ws63 | Self::Bs2x | Self::Bs25 => {
+                let flasher =
+                    super::ws63::flasher::Ws63Flasher::with_cancel(port, target_baud, cancel)
+                        .with_family(*self)
+                        .with_handshake_baud(self.handshake_baud())
+                        .with_late_baud(late_baud)
+                        .with_finish_without_c(!matches!(self, Self::Bs2x | Self::Bs25))
+                        .with_verbose(verbose);
+                Ok(Box::new(flasher))
+            },
+            _ => Err(Error::Unsupported(format!(
+                "Unsupported chip family for generic port: {self}"
+            ))),
+        }
+    }
+
+    /// Create a flasher with full serial configuration (P0: 完整配置支持).
+    ///
+    /// This allows customization of all serial port parameters including
+    /// baud rate, data bits, parity, stop bits, and flow control.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Serial port configuration
+    /// * `late_baud` - Use late baud rate switch (after LoaderBoot)
+    /// * `verbose` - Verbose output level
+    ///
+    /// # Returns
+    ///
+    /// A boxed flasher instance implementing the `Flasher` trait
+    #[cfg(feature = "native")]
+    pub fn create_flasher_with_config(
+        &self,
+        config: SerialConfig,
+        late_baud: bool,
+        verbose: u8,
+    ) -> Result<Box<dyn Flasher>> {
+        match self {
+            Self::Ws63 | Self::Bs2x | Self::Bs25 => {
+                let flasher = super::ws63::flasher::Ws63Flasher::open_with_config(config)?
+                    .with_family(*self)
+                    .with_handshake_baud(self.handshake_baud())
+                    .with_late_baud(late_baud)
+                    .with_finish_without_c(!matches!(self, Self::Bs2x | Self::Bs25))
+                    .with_verbose(verbose);
+                Ok(Box::new(flasher))
+            },
+            Self::Ws53 | Self::Sw39 => Err(Error::Unsupported(format!(
+                "{self} series support coming soon"
+            ))),
+            Self::Generic => Err(Error::Unsupported(
+                "Cannot create flasher for generic chip family".into(),
+            )),
+        }
+    }
+}
+
+/// Trait for chip-specific implementations.
+///
+/// This trait allows different chip families to have custom behavior
+/// while sharing common flashing logic.
+pub trait ChipOps {
+    /// Get the chip family.
+    fn family(&self) -> ChipFamily;
+
+    /// Get the chip configuration.
+    fn config(&self) -> &ChipConfig;
+
+    /// Prepare a binary for flashing (e.g., add signing header).
+    fn prepare_binary(&self, data: &[u8], _addr: u32) -> Result<Vec<u8>> {
+        // Default: return data unchanged
+        Ok(data.to_vec())
+    }
+
+    /// Check if a binary needs signing.
+    fn needs_signing(&self, _addr: u32) -> bool {
+        false
+    }
+
+    /// Get the flash base address for this chip.
+    fn flash_base(&self) -> u32 {
+        0x00000000
+    }
+
+    /// Get the maximum flash size for this chip.
+    fn flash_size(&self) -> u32 {
+        0x00800000 // 8MB default
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supported_handshake_bauds_include_default() {
+        for chip in [ChipFamily::Ws63, ChipFamily::Bs2x, ChipFamily::Bs25] {
+            let bauds = chip.supported_handshake_bauds();
+            assert!(bauds.contains(&chip.handshake_baud()));
+            assert!(bauds.contains(&9_600));
+        }
+    }
+
+    #[test]
+    fn test_chip_family_from_name() {
+        assert_eq!(ChipFamily::from_name("ws63"), Some(ChipFamily::Ws63));
+        assert_eq!(ChipFamily::from_name("BS2X"), Some(ChipFamily::Bs2x));
+        assert_eq!(ChipFamily::from_name("bs21"), Some(ChipFamily::Bs2x));
+        assert_eq!(ChipFamily::from_name("bs25"), Some(ChipFamily::Bs25));
+        assert_eq!(ChipFamily::from_name("ws53"), Some(ChipFamily::Ws53));
+        assert_eq!(ChipFamily::from_name("sw39"), Some(ChipFamily::Sw39));
+        assert_eq!(ChipFamily::from_name("generic"), Some(ChipFamily::Generic));
+        assert_eq!(ChipFamily::from_name("auto"), Some(ChipFamily::Generic));
+        assert_eq!(ChipFamily::from_name("unknown"), None);
+        assert_eq!(ChipFamily::from_name(""), None);
+    }
+
+    #[test]
+    fn test_chip_family_from_name_case_insensitive() {
+        assert_eq!(ChipFamily::from_name("WS63"), Some(ChipFamily::Ws63));
+        assert_eq!(ChipFamily::from_name("Ws63"), Some(ChipFamily::Ws63));
+        assert_eq!(ChipFamily::from_name("BS25"), Some(ChipFamily::Bs25));
+    }
+
+    #[test]
+    fn test_chip_config_defaults() {
+        let config = ChipConfig::new(ChipFamily::Ws63);
+        assert_eq!(config.init_baud, 115200);
+        assert_eq!(config.target_baud, 921600);
+        assert!(!config.late_baud_switch);
+        assert_eq!(config.handshake_timeout_secs, 30);
+        assert_eq!(config.transfer_timeout_secs, 60);
+    }
+
+    #[test]
+    fn test_chip_config_bs2x_defaults() {
+        let config = ChipConfig::new(ChipFamily::Bs2x);
+        assert_eq!(config.init_baud, 115200);
+        assert_eq!(config.target_baud, 2_000_000);
+    }
+
+    #[test]
+    fn test_chip_config_builder() {
+        let config = ChipConfig::new(ChipFamily::Ws63)
+            .with_baud(460800)
+            .with_late_baud(true)
+            .with_handshake_timeout(10);
+        assert_eq!(config.target_baud, 460800);
+        assert!(config.late_baud_switch);
+        assert_eq!(config.handshake_timeout_secs, 10);
+    }
+
+    #[test]
+    fn test_coupled_partitions() {
+        let groups = ChipConfig::new(ChipFamily::Ws63).coupled_partitions();
+        assert!(
+            groups
+                .iter()
+                .any(|group| group.contains(&PartitionType::SecurityA)
+                    && group.contains(&PartitionType::SecurityB))
+        );
+        assert!(
+            ChipFamily::Generic
+                .coupled_partitions()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_chip_config_default_trait() {
+        let config = ChipConfig::default();
+        assert_eq!(config.family, ChipFamily::Ws63); // Default is Ws63
+    }
+
+    #[test]
+    fn test_chip_family_default() {
+        let family = ChipFamily::default();
+        assert_eq!(family, ChipFamily::Ws63);
+    }
+
+    #[test]
+    fn test_chip_family_display() {
+        assert_eq!(ChipFamily::Ws63.to_string(), "WS63");
+        assert_eq!(ChipFamily::Bs2x.to_string(), "BS2X");
+        assert_eq!(ChipFamily::Bs25.to_string(), "BS25");
+        assert_eq!(ChipFamily::Ws53.to_string(), "WS53");
+        assert_eq!(ChipFamily::Sw39.to_string(), "SW39");
+        assert_eq!(ChipFamily::Generic.to_string(), "Generic");
+    }
+
+    #[test]
+    fn test_chip_family_default_baud() {
+        // All chips use 115200 as default
+        for family in [
+            ChipFamily::Ws63,
+            ChipFamily::Bs2x,
+            ChipFamily::Bs25,
+            ChipFamily::Generic,
+        ] {
+            assert_eq!(family.default_baud(), 115200, "Failed for {family}");
+        }
+    }
+
+    #[test]
+    fn test_chip_family_handshake_baud_matches_config() {
+        for family in [ChipFamily::Ws63, ChipFamily::Bs2x, ChipFamily::Bs25] {
+            assert_eq!(family.handshake_baud(), 115_200, "Failed for {family}");
+            assert_eq!(ChipConfig::new(family).init_baud, family.handshake_baud());
+        }
+    }
+
+    #[test]
+    fn test_chip_family_high_speed_baud() {
+        assert_eq!(ChipFamily::Ws63.high_speed_baud(), 921_600);
+        assert_eq!(ChipFamily::Bs2x.high_speed_baud(), 2_000_000);
+        assert_eq!(ChipFamily::Bs25.high_speed_baud(), 2_000_000);
+        assert_eq!(ChipFamily::Generic.high_speed_baud(), 921_600);
+    }
+
+    #[test]
+    fn test_chip_family_supported_bauds() {
+        let ws63_bauds = ChipFamily::Ws63.supported_bauds();
+        assert!(ws63_bauds.contains(&115_200));
+        assert!(ws63_bauds.contains(&921_600));
+        assert!(!ws63_bauds.contains(&2_000_000));
+
+        let bs2x_bauds = ChipFamily::Bs2x.supported_bauds();
+        assert!(bs2x_bauds.contains(&2_000_000));
+    }
+
+    #[test]
+    fn test_chip_family_is_supported() {
+        assert!(ChipFamily::Ws63.is_supported());
+        assert!(ChipFamily::Bs2x.is_supported());
+        assert!(ChipFamily::Bs25.is_supported());
+        assert!(!ChipFamily::Ws53.is_supported());
+        assert!(!ChipFamily::Sw39.is_supported());
+        assert!(!ChipFamily::Generic.is_supported());
+    }
+
+    #[test]
+    fn test_chip_family_usb_dfu() {
+        assert!(!ChipFamily::Ws63.supports_usb_dfu());
+        assert!(ChipFamily::Bs2x.supports_usb_dfu());
+        assert!(ChipFamily::Bs25.supports_usb_dfu());
+        assert!(!ChipFamily::Generic.supports_usb_dfu());
+    }
+
+    #[test]
+    fn test_chip_family_efuse() {
+        // All chips support eFuse
+        for family in [
+            ChipFamily::Ws63,
+            ChipFamily::Bs2x,
+            ChipFamily::Bs25,
+            ChipFamily::Generic,
+        ] {
+            assert!(family.supports_efuse());
+        }
+    }
+
+    #[test]
+    fn test_chip_family_signed_firmware() {
+        assert!(ChipFamily::Ws63.requires_signed_firmware());
+        assert!(ChipFamily::Bs2x.requires_signed_firmware());
+        assert!(ChipFamily::Bs25.requires_signed_firmware());
+        assert!(!ChipFamily::Generic.requires_signed_firmware());
+    }
+
+    #[test]
+    fn test_chip_family_clone_eq() {
+        let a = ChipFamily::Ws63;
+        let b = a;
+        assert_eq!(a, b);
+
+        let c = ChipFamily::Bs2x;
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_chip_family_hash() {
+        use std::collections::HashSet;
+        let mut set = HashSet::new();
+        set.insert(ChipFamily::Ws63);
+        set.insert(ChipFamily::Bs2x);
+        set.insert(ChipFamily::Ws63); // duplicate
+        assert_eq!(set.len(), 2);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_create_flasher_supported_shared_seboot_chips() {
+        let result = ChipFamily::Bs2x.create_flasher("/dev/null", 115200, false, 0);
+        assert!(!matches!(result, Err(Error::Unsupported(_))));
+
+        let result = ChipFamily::Bs25.create_flasher("/dev/null", 115200, false, 0);
+        assert!(!matches!(result, Err(Error::Unsupported(_))));
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_create_flasher_unsupported_chip() {
+        let result = ChipFamily::Generic.create_flasher("/dev/null", 115200, false, 0);
+        assert!(result.is_err());
+    }
+}
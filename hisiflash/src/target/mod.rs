@@ -0,0 +1,88 @@
+//! Target-specific implementations.
+
+mod bootloader_entry;
+mod chip;
+pub mod ws63;
+
+#[cfg(feature = "native")]
+use crate::{device::DetectedPort, error::Result};
+pub use {
+    bootloader_entry::{BootloaderEntry, PinStep},
+    chip::{
+        AbortHandle, ChipConfig, ChipFamily, ChipOps, DEFAULT_MAX_PARTITION_BYTES, Flasher,
+        OtpConfirmation,
+    },
+};
+
+/// Open a flasher for every connected board that looks like a HiSilicon
+/// device (see [`crate::device::detect_hisilicon_ports`]).
+///
+/// Each port is paired with its own creation result, so ports that could not
+/// be opened (e.g. busy) are reported instead of aborting the others. The
+/// flashers are not connected yet; call [`Flasher::connect`] on each.
+#[cfg(feature = "native")]
+pub fn flashers_for_detected(
+    chip: ChipFamily,
+    baud: u32,
+) -> Vec<(DetectedPort, Result<Box<dyn Flasher>>)> {
+    flashers_for_ports(chip, baud, crate::device::detect_hisilicon_ports())
+}
+
+#[cfg(feature = "native")]
+fn flashers_for_ports(
+    chip: ChipFamily,
+    baud: u32,
+    ports: Vec<DetectedPort>,
+) -> Vec<(DetectedPort, Result<Box<dyn Flasher>>)> {
+    ports
+        .into_iter()
+        .map(|port| {
+            let flasher = chip.create_flasher(&port.name, baud, false, 0);
+            (port, flasher)
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            device::{DeviceKind, TransportKind},
+            error::Error,
+        },
+    };
+
+    #[test]
+    fn test_flashers_for_ports_reports_each_port() {
+        let port = |name: &str| DetectedPort {
+            name: name.into(),
+            transport: TransportKind::Serial,
+            device: DeviceKind::Ch340,
+            vid: Some(0x1A86),
+            pid: Some(0x7523),
+            manufacturer: None,
+            product: None,
+            serial: None,
+            usb_location: None,
+        };
+
+        let results = flashers_for_ports(
+            ChipFamily::Generic,
+            921_600,
+            vec![port("/dev/ttyUSB0"), port("/dev/ttyUSB1")],
+        );
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[1]
+                .0
+                .name,
+            "/dev/ttyUSB1"
+        );
+        assert!(
+            results
+                .iter()
+                .all(|(_, flasher)| matches!(flasher, Err(Error::Unsupported(_))))
+        );
+    }
+}
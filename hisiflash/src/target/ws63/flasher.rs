@@ -0,0 +1,3883 @@
+//! WS63 flasher implementation.
+//!
+//! This module provides the main flasher interface for the WS63 chip.
+//!
+//! ## Generic Port Support
+//!
+//! The flasher uses a generic `Port` trait, allowing it to work with different
+//! serial port implementations:
+//!
+//! - **Native platforms**: Uses the `serialport` crate via `NativePort`
+//! - **WASM/Web**: Can use Web Serial API via `WebSerialPort` (experimental)
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use hisiflash::{ChipFamily, Fwpkg};
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     // Create flasher using chip abstraction
+//!     let mut flasher = ChipFamily::Ws63.create_flasher("/dev/ttyUSB0", 921600, false, 0)?;
+//!
+//!     // Connect to device
+//!     flasher.connect()?;
+//!
+//!     // Flash firmware
+//!     let fwpkg = Fwpkg::from_file("firmware.fwpkg")?;
+//!     flasher.flash_fwpkg(&fwpkg, None, &mut |name, current, total| {
+//!         println!("Flashing {}: {}/{}", name, current, total);
+//!     })?;
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use {
+    crate::{
+        CancelContext, ProgressControl,
+        error::{Error, FlashReport, Result},
+        image::fwpkg::{Fwpkg, PartitionFilter, PartitionType},
+        port::Port,
+        protocol::{
+            seboot::{CommandType, SebootAck, describe_frame},
+            ymodem::{STALL_MESSAGE, YmodemConfig, YmodemTransfer, control},
+        },
+        target::{
+            AbortHandle, BootloaderEntry, ChipConfig, ChipFamily, DEFAULT_MAX_PARTITION_BYTES,
+            OtpConfirmation,
+            ws63::{
+                layout::{SECTOR_SIZE, region_erase_range},
+                protocol::{CommandFrame, DEFAULT_BAUD, HANDSHAKE_ACK, contains_handshake_ack},
+            },
+        },
+    },
+    log::{debug, info, trace, warn},
+    std::{
+        fmt::Write as _,
+        io::Write,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// Timeout for waiting for handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default number of non-ACK bytes received during one handshake attempt
+/// after which the device is assumed to be running its application rather
+/// than the boot ROM; see [`Ws63Flasher::set_app_detect_threshold`].
+const APP_DETECT_THRESHOLD_BYTES: usize = 50;
+
+/// Delay after changing baud rate.
+///
+/// Increased to 300ms to give CH340/CH341 adapters enough time to stabilize
+/// after a baud rate switch before YMODEM transfers begin.
+const BAUD_CHANGE_DELAY: Duration = Duration::from_millis(300);
+
+/// How long to listen at the new rate after an early baud rate switch.
+///
+/// The boot ROM keeps requesting LoaderBoot with YMODEM 'C's, which arrive
+/// garbled if it missed the switch command.
+const BAUD_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Baud rate switch attempts before giving up.
+const MAX_BAUD_SWITCH_ATTEMPTS: usize = 2;
+
+/// Delay between partition transfers to prevent serial data stale.
+const PARTITION_DELAY: Duration = Duration::from_millis(100);
+
+/// Timeout for waiting for SEBOOT magic response.
+const MAGIC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// BurnTool uses a longer common timeout after each YMODEM transfer completes,
+/// because the target may spend noticeable time finalizing the flashed image
+/// before emitting the next SEBOOT ACK.
+const POST_TRANSFER_MAGIC_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How long to watch for the boot ROM's YMODEM 'C' before deciding whether
+/// LoaderBoot is already running.
+const LOADERBOOT_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Read timeout while probing for a running LoaderBoot.
+const LOADERBOOT_PROBE_READ_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// SEBOOT frame magic (0xDEADBEEF, little-endian).
+const SEBOOT_MAGIC: [u8; 4] = [0xEF, 0xBE, 0xAD, 0xDE];
+
+/// Time allowed for a full-chip erase to finish after the command is sent.
+const ERASE_ALL_WAIT: Duration = Duration::from_secs(5);
+
+/// Timeout for the ACK of a full-chip erase before flashing partitions.
+/// The ACK only comes once the whole flash is erased, which takes far
+/// longer than any other command.
+const ERASE_ALL_ACK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Default wait after the reset command before [`Ws63Flasher::reset`]
+/// returns, so the port is not reused while the device is still resetting.
+const DEFAULT_RESET_SETTLE: Duration = Duration::from_millis(200);
+
+/// Delay between connection retry attempts.
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Maximum number of connection attempts.
+const MAX_CONNECT_ATTEMPTS: usize = 7;
+
+/// Silence on the line that ends the pre-handshake drain.
+const DRAIN_QUIET_PERIOD: Duration = Duration::from_millis(20);
+
+/// Stale bytes discarded before the handshake starts regardless of traffic,
+/// so a chatty application cannot stall the connection.
+const DRAIN_MAX_BYTES: usize = 4096;
+
+/// Maximum number of download retry attempts.
+const MAX_DOWNLOAD_RETRIES: usize = 3;
+
+/// Whether `data` holds a SEBOOT frame that is not a handshake ACK.
+fn contains_seboot_reply(data: &[u8]) -> bool {
+    data.windows(SEBOOT_MAGIC.len())
+        .enumerate()
+        .any(|(pos, window)| window == SEBOOT_MAGIC && !data[pos..].starts_with(&HANDSHAKE_ACK))
+}
+
+/// Whether `e` comes from the YMODEM stall watchdog, which must not be retried.
+fn is_stall_error(e: &Error) -> bool {
+    matches!(e, Error::Timeout(msg) if msg.starts_with(STALL_MESSAGE))
+}
+
+/// Prefix of the [`Error::Timeout`] message raised once the total deadline
+/// has passed.
+const DEADLINE_MESSAGE: &str = "total deadline";
+
+/// Whether `e` reports an exceeded total deadline, which ends the operation.
+fn is_deadline_error(e: &Error) -> bool {
+    matches!(e, Error::Timeout(msg) if msg.starts_with(DEADLINE_MESSAGE))
+}
+
+fn is_interrupted_error(e: &Error) -> bool {
+    match e {
+        Error::Io(io) => {
+            io.kind() == std::io::ErrorKind::Interrupted
+                || io.raw_os_error() == Some(4)
+                || io
+                    .to_string()
+                    .to_ascii_lowercase()
+                    .contains("interrupted")
+        },
+        Error::Serial(serial) => {
+            matches!(
+                serial.kind(),
+                serialport::ErrorKind::Io(std::io::ErrorKind::Interrupted)
+            ) || serial
+                .to_string()
+                .to_ascii_lowercase()
+                .contains("interrupted")
+        },
+        _ => e
+            .to_string()
+            .to_ascii_lowercase()
+            .contains("interrupted"),
+    }
+}
+
+fn sleep_interruptible(cancel: &CancelContext, total: Duration) -> Result<()> {
+    const CHUNK: Duration = Duration::from_millis(20);
+
+    let start = Instant::now();
+    while start.elapsed() < total {
+        cancel.check()?;
+        let elapsed = start.elapsed();
+        let remain = total.saturating_sub(elapsed);
+        thread::sleep(remain.min(CHUNK));
+    }
+
+    Ok(())
+}
+
+/// WS63 flasher.
+///
+/// Generic over the port type `P`, which must implement the `Port` trait.
+/// This allows the flasher to work with different serial port implementations.
+#[allow(clippy::struct_excessive_bools)]
+pub struct Ws63Flasher<P: Port> {
+    port: P,
+    config: ChipConfig,
+    finish_without_c: bool,
+    tolerate_lost_eot: bool,
+    checksum_fallback: bool,
+    keep_going: bool,
+    skip_loaderboot: bool,
+    pre_erase: bool,
+    clear_interrupt_on_start: bool,
+    destructive_armed: bool,
+    auto_bootloader: Option<BootloaderEntry>,
+    auto_reset_on_app_mode: bool,
+    app_detect_threshold: usize,
+    stall_timeout: Option<Duration>,
+    total_deadline: Option<Duration>,
+    deadline_started: Option<Instant>,
+    reset_settle: Duration,
+    max_partition_bytes: Option<usize>,
+    prefetched_magic_bytes: Vec<u8>,
+    prefetched_ymodem_bytes: Vec<u8>,
+    verbose: u8,
+    trace_sink: Option<Box<dyn Write + Send>>,
+    device_error_sink: Option<Box<dyn Write + Send>>,
+    trace_started: Option<Instant>,
+    cancel: CancelContext,
+    aborted: Arc<AtomicBool>,
+}
+
+// Implementation for any Port type
+impl<P: Port> Ws63Flasher<P> {
+    /// Create a new WS63 flasher with an existing port.
+    ///
+    /// This flasher will NOT respond to Ctrl-C interrupts.
+    /// For interruptible flasher, use [`with_cancel`](Self::with_cancel).
+    ///
+    /// # Arguments
+    ///
+    /// * `port` - An opened serial port implementing the `Port` trait
+    /// * `target_baud` - Target baud rate for data transfer
+    #[allow(dead_code)]
+    pub fn new(port: P, target_baud: u32) -> Self {
+        Self::with_cancel(port, target_baud, CancelContext::none())
+    }
+
+    /// Create a new WS63 flasher with custom cancel context.
+    ///
+    /// Use this when you need custom cancellation behavior (e.g., Ctrl-C support).
+    /// Every interrupt check consults only `cancel`; the global interrupt flag
+    /// is ignored unless the context includes it (see
+    /// [`CancelContext::or_global`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `port` - An opened serial port implementing the `Port` trait
+    /// * `target_baud` - Target baud rate for data transfer
+    /// * `cancel` - Cancellation context for interruptible operations
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use hisiflash::CancelContext;
+    ///
+    /// // Create with global interrupt support
+    /// let cancel = hisiflash::cancel_context_from_global();
+    /// let flasher = Ws63Flasher::with_cancel(port, 921600, cancel);
+    ///
+    /// // Own flag, still honoring Ctrl-C from the CLI
+    /// let cancel = CancelContext::new(move || stop.load(Ordering::SeqCst)).or_global();
+    /// let flasher = Ws63Flasher::with_cancel(port, 921600, cancel);
+    /// ```
+    pub fn with_cancel(port: P, target_baud: u32, cancel: CancelContext) -> Self {
+        let aborted = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&aborted);
+        Self {
+            port,
+            config: ChipConfig {
+                init_baud: DEFAULT_BAUD,
+                ..ChipConfig::new(ChipFamily::Ws63).with_baud(target_baud)
+            },
+            finish_without_c: true,
+            tolerate_lost_eot: false,
+            checksum_fallback: false,
+            keep_going: false,
+            skip_loaderboot: false,
+            pre_erase: false,
+            clear_interrupt_on_start: false,
+            destructive_armed: false,
+            auto_bootloader: None,
+            auto_reset_on_app_mode: false,
+            app_detect_threshold: APP_DETECT_THRESHOLD_BYTES,
+            stall_timeout: None,
+            total_deadline: None,
+            deadline_started: None,
+            reset_settle: DEFAULT_RESET_SETTLE,
+            max_partition_bytes: Some(DEFAULT_MAX_PARTITION_BYTES),
+            prefetched_magic_bytes: Vec::new(),
+            prefetched_ymodem_bytes: Vec::new(),
+            verbose: 0,
+            trace_sink: None,
+            device_error_sink: None,
+            trace_started: None,
+            cancel: cancel.or(CancelContext::new(move || flag.load(Ordering::SeqCst))),
+            aborted,
+        }
+    }
+
+    /// Set the baud rate used for the initial handshake.
+    ///
+    /// Defaults to [`DEFAULT_BAUD`]. The port is switched to this rate before
+    /// each handshake attempt, and the baud rate switch to `target_baud` is
+    /// skipped when both are equal.
+    #[must_use]
+    pub fn with_handshake_baud(mut self, handshake_baud: u32) -> Self {
+        self.config
+            .init_baud = handshake_baud;
+        self
+    }
+
+    /// Set the chip family reported by [`Self::chip_config`].
+    ///
+    /// The flasher speaks the same protocol to every family it supports;
+    /// this only labels the configuration.
+    #[must_use]
+    pub fn with_family(mut self, family: ChipFamily) -> Self {
+        self.config
+            .family = family;
+        self
+    }
+
+    /// The chip configuration in effect: family, handshake and target baud
+    /// rates and the late baud switch.
+    pub fn chip_config(&self) -> &ChipConfig {
+        &self.config
+    }
+
+    /// Set late baud rate change mode.
+    ///
+    /// In late baud mode, the baud rate is changed after LoaderBoot is loaded,
+    /// which may be necessary for some firmware configurations.
+    #[must_use]
+    pub fn with_late_baud(mut self, late_baud: bool) -> Self {
+        self.config
+            .late_baud_switch = late_baud;
+        self
+    }
+
+    /// Control whether YMODEM should send the finish block when EOT is ACKed
+    /// without a trailing 'C'.
+    #[must_use]
+    pub fn with_finish_without_c(mut self, finish_without_c: bool) -> Self {
+        self.finish_without_c = finish_without_c;
+        self
+    }
+
+    /// Accept a partition whose data blocks were all ACKed even if the
+    /// YMODEM EOT and the following SEBOOT ACK are lost.
+    ///
+    /// Without this, such a partition is treated as failed and re-sent from
+    /// scratch (including the erase). The protocol has no read-back, so the
+    /// partition is assumed written and a warning is logged instead.
+    pub fn set_tolerate_lost_eot(&mut self, tolerate: bool) {
+        self.tolerate_lost_eot = tolerate;
+    }
+
+    /// Accept a NAK as the YMODEM start request and fall back to 8-bit
+    /// checksum blocks, for loaders that do not speak CRC mode.
+    ///
+    /// Applies to LoaderBoot and partition transfers alike. Off by default,
+    /// so a stray NAK before a transfer starts is ignored.
+    pub fn set_checksum_fallback(&mut self, fallback: bool) {
+        self.checksum_fallback = fallback;
+    }
+
+    /// Continue with the next partition when one fails in
+    /// [`Self::flash_fwpkg`].
+    ///
+    /// Failures are collected and returned together as
+    /// [`Error::PartitionsFailed`] once every partition has been tried.
+    /// LoaderBoot failures and cancellation still stop immediately.
+    pub fn set_keep_going(&mut self, keep_going: bool) {
+        self.keep_going = keep_going;
+    }
+
+    /// Never send LoaderBoot; assume the device already runs it.
+    ///
+    /// Without this, LoaderBoot is skipped only when the device does not
+    /// behave like the boot ROM after the handshake (see
+    /// [`Self::flash_fwpkg`]). Flashing fails if the device is actually still
+    /// in first-stage boot.
+    pub fn set_skip_loaderboot(&mut self, skip: bool) {
+        self.skip_loaderboot = skip;
+    }
+
+    /// Erase the whole flash in [`Self::flash_fwpkg`], after LoaderBoot and
+    /// before the first partition is downloaded.
+    ///
+    /// Like [`Self::erase_all`], the erase must be armed with
+    /// [`Self::arm_destructive`] first. Flashing continues once the device
+    /// acknowledges the erase.
+    pub fn set_pre_erase(&mut self, pre_erase: bool) {
+        self.pre_erase = pre_erase;
+    }
+
+    /// Put the board into download mode with `entry` when
+    /// [`Self::connect`] starts, for boards with DTR/RTS wired to
+    /// boot-select and reset; `None` clears it.
+    pub fn set_auto_bootloader(&mut self, entry: Option<BootloaderEntry>) {
+        self.auto_bootloader = entry;
+    }
+
+    /// Reset the board when the handshake sees application output.
+    ///
+    /// Once the app-detect threshold (see
+    /// [`Self::set_app_detect_threshold`]) of non-ACK data arrive during an
+    /// attempt, the auto bootloader sequence (or the `reset` preset when
+    /// none is set) is applied and handshaking continues.
+    pub fn set_auto_reset_on_app_mode(&mut self, enabled: bool) {
+        self.auto_reset_on_app_mode = enabled;
+    }
+
+    /// Treat the device as running its application once `bytes` of non-ACK
+    /// data arrive during one handshake attempt.
+    ///
+    /// Defaults to [`APP_DETECT_THRESHOLD_BYTES`]. Raise it for boards whose
+    /// boot ROM prints a banner before it answers the handshake.
+    pub fn set_app_detect_threshold(&mut self, bytes: usize) {
+        self.app_detect_threshold = bytes;
+    }
+
+    /// Clear the global interrupt flag when [`Self::connect`] starts.
+    ///
+    /// A Ctrl-C that ended an earlier operation in the same process leaves
+    /// [`crate::set_interrupt_flag`] in effect, which would otherwise cancel
+    /// the new connection right away.
+    pub fn set_clear_interrupt_on_start(&mut self, clear: bool) {
+        self.clear_interrupt_on_start = clear;
+    }
+
+    /// Permit the next destructive operation ([`Self::erase_all`]).
+    ///
+    /// Call this after the user has confirmed the operation. The permission
+    /// is used up by that operation, whether it succeeds or not.
+    pub fn arm_destructive(&mut self) {
+        self.destructive_armed = true;
+    }
+
+    /// Consume the permission granted by [`Self::arm_destructive`].
+    fn take_destructive_permission(&mut self) -> Result<()> {
+        let armed = std::mem::take(&mut self.destructive_armed);
+        if armed {
+            Ok(())
+        } else {
+            Err(Error::Config("destructive operation not armed".to_string()))
+        }
+    }
+
+    /// Abort a partition transfer when no YMODEM block has been acknowledged
+    /// for `timeout`; `None` disables the watchdog.
+    ///
+    /// See [`YmodemConfig::stall_timeout`].
+    pub fn set_stall_timeout(&mut self, timeout: Option<Duration>) {
+        self.stall_timeout = timeout;
+    }
+
+    /// Wait `settle` after sending the reset command before
+    /// [`Self::reset`] returns (default 200 ms).
+    ///
+    /// Boards whose USB-serial bridge glitches during reset need longer
+    /// before the port can be reused, e.g. by a monitor.
+    pub fn set_reset_settle(&mut self, settle: Duration) {
+        self.reset_settle = settle;
+    }
+
+    /// Bound the whole operation, from [`Self::connect`] on, to `deadline`;
+    /// `None` removes the bound.
+    ///
+    /// Connection, baud switch and download retries each have their own
+    /// attempt limit; once `deadline` has passed, the next retry boundary in
+    /// any of them fails with [`Error::Timeout`] instead. An attempt already
+    /// in progress is not cut short.
+    pub fn set_total_deadline(&mut self, deadline: Option<Duration>) {
+        self.total_deadline = deadline;
+    }
+
+    /// Fail with [`Error::Timeout`] once the total deadline has passed.
+    fn check_total_deadline(&self) -> Result<()> {
+        let (Some(deadline), Some(started)) = (self.total_deadline, self.deadline_started) else {
+            return Ok(());
+        };
+        if started.elapsed() >= deadline {
+            return Err(Error::Timeout(format!(
+                "{DEADLINE_MESSAGE} of {:.1}s exceeded",
+                deadline.as_secs_f64()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Refuse partition images larger than `max` bytes; `None` removes the
+    /// limit. Defaults to [`DEFAULT_MAX_PARTITION_BYTES`].
+    pub fn set_max_partition_bytes(&mut self, max: Option<usize>) {
+        self.max_partition_bytes = max;
+    }
+
+    /// Set verbose output level.
+    #[must_use]
+    pub fn with_verbose(mut self, verbose: u8) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Record the handshake traffic of [`Self::connect`] to `sink`.
+    ///
+    /// Every frame sent and every chunk received while connecting is written
+    /// as one line holding the time since `connect()` started, the direction,
+    /// the length and the bytes in hex:
+    ///
+    /// ```text
+    /// [  0.012345] TX   18: ef be ad de 12 00 f0 0f ...
+    /// [  0.023456] RX   10: ef be ad de 0c 00 e1 1e 5a 00
+    /// ```
+    ///
+    /// Lines starting with `#` mark attempts and baud rate changes. Writing
+    /// stops with a warning if the sink fails; the handshake goes on.
+    pub fn set_trace_sink(&mut self, sink: Box<dyn Write + Send>) {
+        self.trace_sink = Some(sink);
+    }
+
+    /// Capture error responses from the device.
+    ///
+    /// A command the device answers with a failed ACK always fails with an
+    /// [`Error::Protocol`] holding the frame in hex; with a sink set, the
+    /// raw frame is also appended to `sink`.
+    pub fn set_device_error_sink(&mut self, sink: Box<dyn Write + Send>) {
+        self.device_error_sink = Some(sink);
+    }
+
+    /// Abort and discard any pending serial data.
+    ///
+    /// Every cancellation check fails from now on. This needs `&mut self`,
+    /// so it cannot reach an operation that is still running; trip an
+    /// [`Self::abort_handle`] from another thread for that. The device is
+    /// left in an unknown state; call [`Self::connect`] again to reuse the
+    /// flasher, which also clears the abort.
+    pub fn abort(&mut self) {
+        info!("Aborting...");
+        self.aborted
+            .store(true, Ordering::SeqCst);
+        self.prefetched_magic_bytes
+            .clear();
+        self.prefetched_ymodem_bytes
+            .clear();
+        let _ = self
+            .port
+            .clear_buffers();
+    }
+
+    /// Get a handle that aborts this flasher from any thread.
+    ///
+    /// Tripping it makes every cancellation check fail, so a running
+    /// operation stops with an interrupted error at its next check point.
+    /// Unlike [`Self::abort`], pending serial data is left alone.
+    pub fn abort_handle(&self) -> AbortHandle {
+        AbortHandle::new(Arc::clone(&self.aborted))
+    }
+
+    /// Connect to the device.
+    ///
+    /// This waits for the device to boot into download mode and performs
+    /// the initial handshake with retry mechanism. With
+    /// [`Self::set_auto_bootloader`], the board is reset into download mode
+    /// first.
+    pub fn connect(&mut self) -> Result<()> {
+        self.aborted
+            .store(false, Ordering::SeqCst);
+        if self.clear_interrupt_on_start {
+            crate::clear_interrupt_flag();
+        }
+        info!(
+            "Waiting for device on {}...",
+            self.port
+                .name()
+        );
+
+        self.deadline_started = Some(Instant::now());
+        self.trace_started = Some(Instant::now());
+        let result = self
+            .enter_bootloader()
+            .and_then(|()| self.connect_attempts());
+        self.trace_line(&match &result {
+            Ok(()) => "# connected".to_string(),
+            Err(e) => format!("# failed: {e}"),
+        });
+        if let Some(sink) = self
+            .trace_sink
+            .as_mut()
+        {
+            let _ = sink.flush();
+        }
+        self.trace_started = None;
+        result
+    }
+
+    /// Apply the automatic bootloader entry sequence, if any.
+    fn enter_bootloader(&mut self) -> Result<()> {
+        let Some(entry) = self
+            .auto_bootloader
+            .clone()
+        else {
+            info!("Please reset the device to enter download mode.");
+            return Ok(());
+        };
+        info!("Resetting the device into download mode ({entry})");
+        self.trace_line(&format!("# bootloader entry {entry}"));
+        entry.apply(&mut self.port, &self.cancel)
+    }
+
+    /// React to application output during a handshake attempt: reset the
+    /// board if enabled, otherwise ask for a manual reset.
+    fn handle_app_mode(&mut self) -> Result<()> {
+        if !self.auto_reset_on_app_mode {
+            warn!("Device appears to be running its application; reset it to enter download mode");
+            return Ok(());
+        }
+        let entry = self
+            .auto_bootloader
+            .clone()
+            .unwrap_or_else(|| BootloaderEntry::preset("reset").expect("built-in preset"));
+        info!("Device is running its application; resetting it ({entry})");
+        self.trace_line(&format!("# app mode, reset {entry}"));
+        entry.apply(&mut self.port, &self.cancel)?;
+        self.port
+            .clear_buffers()
+    }
+
+    /// Run handshake attempts until one succeeds or all have failed.
+    fn connect_attempts(&mut self) -> Result<()> {
+        for attempt in 1..=MAX_CONNECT_ATTEMPTS {
+            self.cancel
+                .check()?;
+            self.check_total_deadline()?;
+
+            if attempt > 1 {
+                info!("Connection attempt {attempt}/{MAX_CONNECT_ATTEMPTS}");
+            }
+            self.trace_line(&format!(
+                "# attempt {attempt}/{MAX_CONNECT_ATTEMPTS} at {} baud",
+                self.config
+                    .init_baud
+            ));
+
+            match self.try_connect() {
+                Ok(()) => {
+                    return Ok(());
+                },
+                Err(e) => {
+                    if is_interrupted_error(&e) {
+                        return Err(e);
+                    }
+
+                    if attempt < MAX_CONNECT_ATTEMPTS {
+                        warn!("Connection failed (attempt {attempt}/{MAX_CONNECT_ATTEMPTS}): {e}");
+                        sleep_interruptible(&self.cancel, CONNECT_RETRY_DELAY)?;
+                        self.port
+                            .clear_buffers()?;
+                    } else {
+                        return Err(e);
+                    }
+                },
+            }
+        }
+
+        Err(Error::Timeout(format!(
+            "Connection failed after {MAX_CONNECT_ATTEMPTS} attempts"
+        )))
+    }
+
+    /// Single connection attempt.
+    fn try_connect(&mut self) -> Result<()> {
+        self.cancel
+            .check()?;
+
+        if self
+            .port
+            .baud_rate()
+            != self
+                .config
+                .init_baud
+        {
+            self.port
+                .set_baud_rate(
+                    self.config
+                        .init_baud,
+                )?;
+        }
+        self.port
+            .clear_buffers()?;
+        let drained = self.drain_stale_input()?;
+        if drained > 0 {
+            debug!("Discarded {drained} stale bytes before handshake");
+        }
+
+        let start = Instant::now();
+        let handshake_frame = CommandFrame::handshake(
+            self.config
+                .target_baud,
+        );
+        let handshake_data = handshake_frame.build();
+        let mut app_bytes = 0usize;
+        let mut app_mode_handled = false;
+
+        // Send handshake frames repeatedly until we get a response
+        while start.elapsed() < HANDSHAKE_TIMEOUT {
+            self.cancel
+                .check()?;
+
+            // Send handshake
+            self.trace_io("TX", &handshake_data);
+            if let Err(e) = self
+                .port
+                .write_all(&handshake_data)
+            {
+                if e.kind() == std::io::ErrorKind::Interrupted {
+                    return Err(Error::Io(e));
+                }
+                trace!("Write error (ignoring): {e}");
+            }
+            if let Err(e) = self
+                .port
+                .flush()
+            {
+                if e.kind() == std::io::ErrorKind::Interrupted {
+                    return Err(Error::Io(e));
+                }
+            }
+
+            // Small delay
+            sleep_interruptible(&self.cancel, Duration::from_millis(10))?;
+
+            // Check for response
+            let mut buf = [0u8; 256];
+            match self
+                .port
+                .read(&mut buf)
+            {
+                Ok(n) if n > 0 => {
+                    trace!("Received {n} bytes");
+                    self.trace_io("RX", &buf[..n]);
+                    if contains_handshake_ack(&buf[..n]) {
+                        info!("Handshake successful!");
+                        self.trace_line("# handshake ACK");
+
+                        // Change baud rate if not in late mode
+                        if !self
+                            .config
+                            .late_baud_switch
+                            && self
+                                .config
+                                .target_baud
+                                != self
+                                    .config
+                                    .init_baud
+                        {
+                            self.change_baud_rate(
+                                self.config
+                                    .target_baud,
+                            )?;
+                        }
+
+                        return Ok(());
+                    }
+                    app_bytes += n;
+                    if !app_mode_handled && app_bytes >= self.app_detect_threshold {
+                        app_mode_handled = true;
+                        self.handle_app_mode()?;
+                    }
+                },
+                Ok(_) => {},
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {},
+                Err(e) => {
+                    if e.kind() == std::io::ErrorKind::Interrupted {
+                        return Err(Error::Io(e));
+                    }
+                    trace!("Read error (ignoring): {e}");
+                },
+            }
+        }
+
+        Err(Error::Timeout(format!(
+            "No response after {} seconds",
+            HANDSHAKE_TIMEOUT.as_secs()
+        )))
+    }
+
+    /// Read and discard input until the line has been quiet for
+    /// [`DRAIN_QUIET_PERIOD`] or [`DRAIN_MAX_BYTES`] have been dropped.
+    ///
+    /// `clear_buffers` only empties what the OS has already queued; bytes
+    /// still in flight from a previous attempt (such as a late handshake ACK)
+    /// would otherwise be taken for a reply to the new handshake. Prefetched
+    /// protocol bytes are dropped too. Returns the number of bytes discarded.
+    fn drain_stale_input(&mut self) -> Result<usize> {
+        self.prefetched_magic_bytes
+            .clear();
+        self.prefetched_ymodem_bytes
+            .clear();
+
+        let saved_timeout = self
+            .port
+            .timeout();
+        self.port
+            .set_timeout(DRAIN_QUIET_PERIOD)?;
+
+        let mut drained = 0;
+        let mut buf = [0u8; 256];
+        let result = loop {
+            if drained >= DRAIN_MAX_BYTES {
+                break Ok(());
+            }
+            match self
+                .port
+                .read(&mut buf)
+            {
+                Ok(0) => break Ok(()),
+                Ok(n) => {
+                    self.trace_io("RX", &buf[..n]);
+                    drained += n;
+                },
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => break Ok(()),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => break Err(Error::Io(e)),
+                Err(e) => {
+                    trace!("Read error while draining (ignoring): {e}");
+                    break Ok(());
+                },
+            }
+        };
+
+        self.port
+            .set_timeout(saved_timeout)?;
+        result.map(|()| drained)
+    }
+
+    /// Change the baud rate.
+    ///
+    /// Before LoaderBoot runs, the switch is confirmed by listening at the
+    /// new rate (see [`Self::probe_baud_switch`]); if only garbage arrives,
+    /// the local rate is reverted and the command sent once more.
+    fn change_baud_rate(&mut self, baud: u32) -> Result<()> {
+        let previous = self
+            .port
+            .baud_rate();
+        for attempt in 1..=MAX_BAUD_SWITCH_ATTEMPTS {
+            self.check_total_deadline()?;
+            self.switch_baud_rate(baud)?;
+            // LoaderBoot stays silent until it gets a command, so there is
+            // nothing to listen for in late baud mode.
+            if self
+                .config
+                .late_baud_switch
+                || self.probe_baud_switch()?
+            {
+                debug!("Baud rate changed to {baud}");
+                return Ok(());
+            }
+
+            warn!(
+                "Device is not responding cleanly at {baud} baud (attempt \
+                 {attempt}/{MAX_BAUD_SWITCH_ATTEMPTS}), reverting to {previous}"
+            );
+            self.port
+                .set_baud_rate(previous)?;
+            self.trace_line(&format!("# local baud rate reverted to {previous}"));
+            sleep_interruptible(&self.cancel, BAUD_CHANGE_DELAY)?;
+            self.port
+                .clear_buffers()?;
+        }
+
+        Err(Error::Protocol(format!(
+            "device did not switch to {baud} baud after {MAX_BAUD_SWITCH_ATTEMPTS} attempts"
+        )))
+    }
+
+    /// Send the baud rate command and switch the local port to `baud`.
+    fn switch_baud_rate(&mut self, baud: u32) -> Result<()> {
+        self.cancel
+            .check()?;
+
+        info!("Changing baud rate to {baud}");
+
+        // Send baud rate change command
+        let frame = CommandFrame::set_baud_rate(baud);
+        self.send_command(&frame)?;
+
+        // Wait for command to be processed
+        sleep_interruptible(&self.cancel, BAUD_CHANGE_DELAY)?;
+
+        // Change local baud rate
+        self.port
+            .set_baud_rate(baud)?;
+        self.trace_line(&format!("# local baud rate set to {baud}"));
+
+        // Clear buffers
+        sleep_interruptible(&self.cancel, BAUD_CHANGE_DELAY)?;
+        self.port
+            .clear_buffers()
+    }
+
+    /// Listen for up to [`BAUD_PROBE_TIMEOUT`] after a baud rate switch.
+    ///
+    /// Returns `false` only when bytes arrived but none was a YMODEM 'C' or
+    /// the start of a SEBOOT frame, i.e. the device is still talking at the
+    /// old rate. Silence is accepted, since not every device state sends
+    /// anything unprompted.
+    fn probe_baud_switch(&mut self) -> Result<bool> {
+        let saved_timeout = self
+            .port
+            .timeout();
+        self.port
+            .set_timeout(LOADERBOOT_PROBE_READ_TIMEOUT)?;
+
+        let start = Instant::now();
+        let mut received = Vec::new();
+        let mut buf = [0u8; 64];
+        let result = loop {
+            if received.contains(&control::C)
+                || received
+                    .windows(SEBOOT_MAGIC.len())
+                    .any(|window| window == SEBOOT_MAGIC)
+            {
+                break Ok(true);
+            }
+            if start.elapsed() >= BAUD_PROBE_TIMEOUT {
+                break Ok(received.is_empty());
+            }
+            if let Err(e) = self
+                .cancel
+                .check()
+            {
+                break Err(e);
+            }
+            match self
+                .port
+                .read(&mut buf)
+            {
+                Ok(n) => {
+                    self.trace_io("RX", &buf[..n]);
+                    received.extend_from_slice(&buf[..n]);
+                },
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {},
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => break Err(Error::Io(e)),
+                Err(e) => trace!("Read error while probing the baud rate (ignoring): {e}"),
+            }
+        };
+        self.port
+            .set_timeout(saved_timeout)?;
+        if let Ok(false) = result {
+            debug!("Unexpected bytes after baud rate switch: {received:02X?}");
+        }
+        result
+    }
+
+    /// Send a command frame.
+    fn send_command(&mut self, frame: &CommandFrame) -> Result<()> {
+        let data = frame.build();
+        trace!(
+            "Sending command {:?}: {} bytes",
+            frame.command(),
+            data.len()
+        );
+
+        self.trace_io("TX", &data);
+        self.port
+            .write_all(&data)?;
+        self.port
+            .flush()?;
+
+        Ok(())
+    }
+
+    /// Add `data` to the handshake trace as one timestamped hex line.
+    fn trace_io(&mut self, direction: &str, data: &[u8]) {
+        if self
+            .trace_sink
+            .is_none()
+        {
+            return;
+        }
+        let mut line = format!("{direction} {:>4}:", data.len());
+        for byte in data {
+            let _ = write!(line, " {byte:02x}");
+        }
+        self.trace_line(&line);
+        if data.starts_with(&SEBOOT_MAGIC) {
+            self.trace_line(&format!("#   {}", describe_frame(data)));
+        }
+    }
+
+    /// Write one line to the handshake trace while [`Self::connect`] runs.
+    fn trace_line(&mut self, text: &str) {
+        let (Some(sink), Some(started)) = (
+            self.trace_sink
+                .as_mut(),
+            self.trace_started,
+        ) else {
+            return;
+        };
+        let elapsed = started
+            .elapsed()
+            .as_secs_f64();
+        if let Err(e) = writeln!(sink, "[{elapsed:>11.6}] {text}") {
+            warn!("Failed to write handshake trace, disabling it: {e}");
+            self.trace_sink = None;
+        }
+    }
+
+    /// Wait for SEBOOT magic (0xDEADBEEF) response from device.
+    ///
+    /// After LoaderBoot YMODEM transfer or after sending a download command,
+    /// the device responds with a SEBOOT frame starting with the magic bytes.
+    /// This function reads bytes until the magic sequence is found, then
+    /// drains the remaining frame data.
+    fn wait_for_magic(&mut self, timeout: Duration) -> Result<()> {
+        let magic = SEBOOT_MAGIC;
+        let start = Instant::now();
+        let mut collected = std::mem::take(&mut self.prefetched_magic_bytes);
+
+        debug!("Waiting for SEBOOT magic...");
+
+        while start.elapsed() < timeout {
+            self.cancel
+                .check()?;
+
+            if let Some(pos) = collected
+                .windows(magic.len())
+                .position(|window| window == magic)
+            {
+                if collected.len() >= pos + 6 {
+                    let len = u16::from_le_bytes([collected[pos + 4], collected[pos + 5]]) as usize;
+                    if collected.len() >= pos + len {
+                        let remainder = collected[pos + len..].to_vec();
+                        if !remainder.is_empty() {
+                            trace!("wait_for_magic remainder: {remainder:02X?}");
+                            self.prefetched_ymodem_bytes
+                                .extend(remainder);
+                        }
+                        debug!("Received SEBOOT magic response");
+                        return self.check_device_error(&collected[pos..pos + len]);
+                    }
+                }
+            }
+
+            let mut buf = [0u8; 64];
+            match self
+                .port
+                .read(&mut buf)
+            {
+                Ok(n) if n > 0 => {
+                    trace!("wait_for_magic chunk: {:02X?}", &buf[..n]);
+                    collected.extend_from_slice(&buf[..n]);
+                    if collected.len() > 512 {
+                        let keep_from = collected
+                            .len()
+                            .saturating_sub(64);
+                        collected.drain(..keep_from);
+                    }
+                },
+                Ok(_) => {},
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {},
+                Err(e) => {
+                    if e.kind() == std::io::ErrorKind::Interrupted {
+                        return Err(Error::Io(e));
+                    }
+                    return Err(Error::Io(e));
+                },
+            }
+        }
+
+        Err(Error::Timeout("Timeout waiting for SEBOOT magic".into()))
+    }
+
+    /// Report a failed ACK in `frame` through the device error sink, if set.
+    fn check_device_error(&mut self, frame: &[u8]) -> Result<()> {
+        let Some(ack) = SebootAck::parse(frame) else {
+            return Ok(());
+        };
+        if ack.frame_type != CommandType::Ack as u8 || ack.is_success() {
+            return Ok(());
+        }
+        let mut hex = String::new();
+        for byte in frame {
+            let _ = write!(hex, " {byte:02x}");
+        }
+        if let Some(sink) = self
+            .device_error_sink
+            .as_mut()
+        {
+            if let Err(e) = sink
+                .write_all(frame)
+                .and_then(|()| sink.flush())
+            {
+                warn!("Failed to write device error response: {e}");
+            }
+        }
+        Err(Error::Protocol(format!(
+            "device rejected the command (result {:#04x}, error code {:#04x}); response:{hex}",
+            ack.result, ack.error_code
+        )))
+    }
+
+    /// Transfer LoaderBoot via YMODEM without sending a download command.
+    ///
+    /// After handshake, the device enters YMODEM mode directly for LoaderBoot.
+    /// No download command (0xD2) should be sent. This matches the official
+    /// fbb_burntool behavior where LOADER type partitions skip the download
+    /// command and go straight to YMODEM transfer.
+    fn transfer_loaderboot<F>(&mut self, name: &str, data: &[u8], progress: &mut F) -> Result<()>
+    where
+        F: FnMut(&str, usize, usize) -> ProgressControl,
+    {
+        self.cancel
+            .check()?;
+
+        debug!(
+            "Transferring LoaderBoot {} ({} bytes) via YMODEM",
+            name,
+            data.len()
+        );
+
+        let config = YmodemConfig {
+            char_timeout: Duration::from_secs(1),
+            c_timeout: Duration::from_secs(30),
+            max_retries: 10,
+            finish_without_c: self.finish_without_c,
+            verbose: self.verbose,
+            tolerate_lost_eot: false,
+            filename_override: None,
+            stall_timeout: None,
+            checksum_fallback: self.checksum_fallback,
+        };
+
+        let prefetched_input = std::mem::take(&mut self.prefetched_ymodem_bytes);
+        let mut ymodem = YmodemTransfer::with_config(&mut self.port, config, &self.cancel)
+            .with_prefetched_input(prefetched_input)
+            .with_port_timeout_control();
+        ymodem.transfer_controlled(name, data, |current, total| progress(name, current, total))?;
+        self.prefetched_magic_bytes = ymodem.take_trailing_data();
+
+        debug!("LoaderBoot transfer complete");
+        Ok(())
+    }
+
+    /// Send LoaderBoot and wait for it to start, unless the device already
+    /// runs it (see [`Self::loaderboot_running`]).
+    fn start_loaderboot<F>(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        magic_timeout: Duration,
+        progress: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(&str, usize, usize) -> ProgressControl,
+    {
+        if self.loaderboot_running()? {
+            info!("LoaderBoot is already running, skipping {name}");
+            return Ok(());
+        }
+
+        info!("Flashing LoaderBoot: {name} ({} bytes)", data.len());
+        self.transfer_loaderboot(name, data, progress)?;
+
+        // Wait for LoaderBoot to initialize (device sends SEBOOT magic when ready)
+        self.wait_for_magic(magic_timeout)
+    }
+
+    /// Check whether the device answered the handshake from a running
+    /// LoaderBoot instead of the boot ROM.
+    ///
+    /// The boot ROM requests the LoaderBoot image with YMODEM 'C'. Reading
+    /// stops as soon as one arrives, and the bytes read are kept for the
+    /// transfer. A running LoaderBoot sends SEBOOT frames instead; repeated
+    /// handshake ACKs are ignored because the boot ROM sends those too.
+    fn loaderboot_running(&mut self) -> Result<bool> {
+        let saved_timeout = self
+            .port
+            .timeout();
+        self.port
+            .set_timeout(LOADERBOOT_PROBE_READ_TIMEOUT)?;
+
+        let start = Instant::now();
+        let mut received = std::mem::take(&mut self.prefetched_ymodem_bytes);
+        let mut buf = [0u8; 64];
+        let result = loop {
+            if received.contains(&control::C) || start.elapsed() >= LOADERBOOT_PROBE_TIMEOUT {
+                break Ok(());
+            }
+            if let Err(e) = self
+                .cancel
+                .check()
+            {
+                break Err(e);
+            }
+            match self
+                .port
+                .read(&mut buf)
+            {
+                Ok(n) => received.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {},
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => break Err(Error::Io(e)),
+                Err(e) => trace!("Read error while probing for LoaderBoot (ignoring): {e}"),
+            }
+        };
+        self.port
+            .set_timeout(saved_timeout)?;
+        result?;
+
+        let running = !received.contains(&control::C) && contains_seboot_reply(&received);
+        if running {
+            debug!("SEBOOT frame without YMODEM request after handshake: {received:02X?}");
+        } else {
+            self.prefetched_ymodem_bytes = received;
+        }
+        Ok(running)
+    }
+
+    /// Flash a FWPKG firmware package.
+    ///
+    /// LoaderBoot is not sent again when the device already runs it, e.g.
+    /// after an aborted flash: if no YMODEM 'C' follows the handshake within
+    /// a second but a SEBOOT frame other than the handshake ACK does, the
+    /// partitions are downloaded straight away. See also
+    /// [`Self::set_skip_loaderboot`].
+    ///
+    /// # Arguments
+    ///
+    /// * `fwpkg` - The firmware package to flash
+    /// * `filter` - Optional partition filters; a partition is flashed if any
+    ///   filter matches (None = flash all). LoaderBoot is always sent.
+    /// * `progress` - Progress callback (partition_name, current_bytes,
+    ///   total_bytes)
+    pub fn flash_fwpkg<F>(
+        &mut self,
+        fwpkg: &Fwpkg,
+        filter: Option<&[PartitionFilter]>,
+        mut progress: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&str, usize, usize),
+    {
+        self.flash_fwpkg_controlled(fwpkg, filter, |name, current, total| {
+            progress(name, current, total);
+            ProgressControl::Continue
+        })
+    }
+
+    /// Flash a FWPKG firmware package, letting `progress` pause or abort
+    /// the transfer after each data block.
+    ///
+    /// Behaves like [`Self::flash_fwpkg`] otherwise. See
+    /// [`ProgressControl`] for what the callback can return.
+    pub fn flash_fwpkg_controlled<F>(
+        &mut self,
+        fwpkg: &Fwpkg,
+        filter: Option<&[PartitionFilter]>,
+        mut progress: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&str, usize, usize) -> ProgressControl,
+    {
+        self.cancel
+            .check()?;
+
+        if self.skip_loaderboot {
+            info!("Skipping LoaderBoot transfer");
+        } else {
+            let loaderboot = fwpkg
+                .loaderboot()
+                .ok_or_else(|| Error::InvalidFwpkg("No LoaderBoot partition found".into()))?;
+
+            // LoaderBoot: NO download command. After handshake ACK, the device
+            // enters YMODEM mode directly. This matches fbb_burntool and ws63flash.
+            let lb_data = fwpkg.bin_data(loaderboot)?;
+            self.start_loaderboot(
+                &loaderboot.name,
+                lb_data,
+                POST_TRANSFER_MAGIC_TIMEOUT,
+                &mut progress,
+            )?;
+        }
+
+        // Change baud rate if in late mode
+        if self
+            .config
+            .late_baud_switch
+            && self
+                .config
+                .target_baud
+                != self
+                    .config
+                    .init_baud
+        {
+            self.change_baud_rate(
+                self.config
+                    .target_baud,
+            )?;
+        }
+
+        if self.pre_erase {
+            // The erase ACK must be read here: left in the buffer, it would
+            // be taken for the ACK of the first download command.
+            self.send_erase_all()?;
+            self.wait_for_magic(ERASE_ALL_ACK_TIMEOUT)?;
+            info!("Flash erased");
+        }
+
+        // Flash remaining partitions
+        let mut report = FlashReport::default();
+        for bin in fwpkg.normal_bins() {
+            self.cancel
+                .check()?;
+
+            // Apply filter if provided
+            if let Some(filters) = filter {
+                if !filters
+                    .iter()
+                    .any(|f| f.matches(bin))
+                {
+                    debug!("Skipping partition: {}", bin.name);
+                    continue;
+                }
+            }
+
+            info!(
+                "Flashing partition: {} -> 0x{:08X}",
+                bin.name, bin.burn_addr
+            );
+
+            let bin_data = fwpkg.bin_data(bin)?;
+            let is_rom = bin.partition_type == PartitionType::Rom;
+            match self.download_binary(
+                &bin.name,
+                bin_data,
+                bin.burn_addr,
+                is_rom,
+                None,
+                &mut progress,
+            ) {
+                Ok(()) => {
+                    report
+                        .flashed
+                        .push(
+                            bin.name
+                                .clone(),
+                        );
+                },
+                Err(e)
+                    if self.keep_going && !is_interrupted_error(&e) && !is_deadline_error(&e) =>
+                {
+                    warn!("Partition {} failed, continuing: {e}", bin.name);
+                    report
+                        .failed
+                        .push((
+                            bin.name
+                                .clone(),
+                            e.to_string(),
+                        ));
+                    self.prefetched_magic_bytes
+                        .clear();
+                    self.prefetched_ymodem_bytes
+                        .clear();
+                    let _ = self
+                        .port
+                        .clear_buffers();
+                },
+                Err(e) => return Err(e),
+            }
+
+            // Inter-partition delay to prevent serial data stale
+            // (MCU won't respond if next command follows immediately)
+            sleep_interruptible(&self.cancel, PARTITION_DELAY)?;
+        }
+
+        if !report
+            .failed
+            .is_empty()
+        {
+            return Err(Error::PartitionsFailed(report));
+        }
+
+        info!("Flashing complete!");
+        Ok(())
+    }
+
+    /// Download a single binary to flash with retry mechanism.
+    ///
+    /// `is_rom` marks the download command as a ROM image (formal byte 1),
+    /// as required for [`PartitionType::Rom`] partitions. `erase_size`
+    /// overrides the default erase of `data.len()` rounded up to whole
+    /// sectors.
+    #[allow(clippy::cast_possible_truncation)]
+    fn download_binary<F>(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        addr: u32,
+        is_rom: bool,
+        erase_size: Option<u32>,
+        progress: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(&str, usize, usize) -> ProgressControl,
+    {
+        self.cancel
+            .check()?;
+
+        // Checked once up front: an oversized image is not worth retrying.
+        if let Some(limit) = self.max_partition_bytes {
+            if data.len() > limit {
+                return Err(Error::PartitionTooLarge {
+                    name: name.to_string(),
+                    size: data.len(),
+                    limit,
+                });
+            }
+        }
+
+        let mut last_error = None;
+
+        for attempt in 1..=MAX_DOWNLOAD_RETRIES {
+            self.cancel
+                .check()?;
+            self.check_total_deadline()?;
+
+            match self.try_download_binary(name, data, addr, is_rom, erase_size, progress) {
+                Ok(()) => {
+                    return Ok(());
+                },
+                Err(e) => {
+                    if is_interrupted_error(&e)
+                        || is_stall_error(&e)
+                        || self
+                            .cancel
+                            .is_cancelled()
+                    {
+                        return Err(e);
+                    }
+
+                    if attempt < MAX_DOWNLOAD_RETRIES {
+                        warn!(
+                            "Download failed for {name} (attempt \
+                             {attempt}/{MAX_DOWNLOAD_RETRIES}): {e}"
+                        );
+                        warn!("Retrying...");
+                        last_error = Some(e);
+
+                        // Clear buffers and wait before retry
+                        let _ = self
+                            .port
+                            .clear_buffers();
+                        sleep_interruptible(&self.cancel, CONNECT_RETRY_DELAY)?;
+                    } else {
+                        return Err(e);
+                    }
+                },
+            }
+        }
+
+        // Use unwrap_or_else to ensure we never lose error information
+        Err(last_error.unwrap_or_else(|| {
+            Error::Protocol("Download failed after all retries (no error captured)".into())
+        }))
+    }
+
+    /// Single attempt to download a binary.
+    fn try_download_binary<F>(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        addr: u32,
+        is_rom: bool,
+        erase_size: Option<u32>,
+        progress: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(&str, usize, usize) -> ProgressControl,
+    {
+        self.cancel
+            .check()?;
+
+        // Check for oversized data that would truncate
+        let len = u32::try_from(data.len()).map_err(|_| {
+            Error::Protocol(format!("Firmware too large ({} bytes > 4GB)", data.len()))
+        })?;
+
+        debug!(
+            "Downloading {} ({} bytes) to 0x{:08X}",
+            name,
+            data.len(),
+            addr
+        );
+
+        // Calculate aligned erase size (align up to 0x1000 = 4KB boundary)
+        // This matches the official fbb_burntool behavior.
+        let erase_size = erase_size.unwrap_or((len + SECTOR_SIZE - 1) & !(SECTOR_SIZE - 1));
+
+        // Send download command
+        let frame = CommandFrame::download_image(addr, len, erase_size, is_rom);
+        self.send_command(&frame)?;
+
+        // Wait for ACK frame (SEBOOT magic response) from device
+        // The device responds with a SEBOOT frame after processing the download
+        // command. ws63flash calls uart_read_until_magic() here.
+        self.wait_for_magic(POST_TRANSFER_MAGIC_TIMEOUT)?;
+
+        self.ymodem_send(name, data, progress)
+    }
+
+    /// YMODEM-transfer `data` after an acknowledged download command, then
+    /// wait for the SEBOOT ACK that ends the transfer.
+    fn ymodem_send<F>(&mut self, name: &str, data: &[u8], progress: &mut F) -> Result<()>
+    where
+        F: FnMut(&str, usize, usize) -> ProgressControl,
+    {
+        // Transfer using YMODEM
+        // Note: ymodem.transfer() internally calls wait_for_c(), so we don't need
+        // to call it here. The device sends 'C' after the ACK frame.
+        let config = YmodemConfig {
+            char_timeout: Duration::from_secs(1),
+            c_timeout: Duration::from_secs(30),
+            max_retries: 10,
+            finish_without_c: self.finish_without_c,
+            verbose: self.verbose,
+            tolerate_lost_eot: self.tolerate_lost_eot,
+            filename_override: None,
+            stall_timeout: self.stall_timeout,
+            checksum_fallback: self.checksum_fallback,
+        };
+
+        let prefetched_input = std::mem::take(&mut self.prefetched_ymodem_bytes);
+        let mut ymodem = YmodemTransfer::with_config(&mut self.port, config, &self.cancel)
+            .with_prefetched_input(prefetched_input)
+            .with_port_timeout_control();
+        ymodem.transfer_controlled(name, data, |current, total| progress(name, current, total))?;
+        let eot_confirmed = ymodem.eot_confirmed();
+        self.prefetched_magic_bytes = ymodem.take_trailing_data();
+
+        // BurnTool waits for a SEBOOT ACK after each partition transfer before
+        // issuing the next download command. BS2X requires the same sequencing.
+        match self.wait_for_magic(POST_TRANSFER_MAGIC_TIMEOUT) {
+            Err(Error::Timeout(_)) if !eot_confirmed && self.tolerate_lost_eot => {
+                warn!(
+                    "{name}: all data blocks were ACKed but the end of transfer was not \
+                     confirmed; assuming the partition is written"
+                );
+            },
+            result => result?,
+        }
+
+        debug!("{name} transfer complete");
+        Ok(())
+    }
+
+    /// Write raw binary data to flash.
+    ///
+    /// # Arguments
+    ///
+    /// * `loaderboot` - LoaderBoot binary data (required for first-stage boot)
+    /// * `bins` - List of (data, address) pairs to flash
+    pub fn write_bins(&mut self, loaderboot: &[u8], bins: &[(&[u8], u32)]) -> Result<()> {
+        self.cancel
+            .check()?;
+
+        if self.skip_loaderboot {
+            info!("Skipping LoaderBoot transfer");
+        } else {
+            self.start_loaderboot("loaderboot", loaderboot, MAGIC_TIMEOUT, &mut |_, _, _| {
+                ProgressControl::Continue
+            })?;
+        }
+
+        // Change baud rate if in late mode
+        if self
+            .config
+            .late_baud_switch
+            && self
+                .config
+                .target_baud
+                != self
+                    .config
+                    .init_baud
+        {
+            self.change_baud_rate(
+                self.config
+                    .target_baud,
+            )?;
+        }
+
+        // Download remaining binaries
+        self.download_bins_only(bins, |_, _, _| {})
+    }
+
+    /// Write raw binary data to flash over an already running LoaderBoot.
+    ///
+    /// Only the download command and YMODEM transfer are sent for each
+    /// binary; LoaderBoot is not transferred again and the baud rate is left
+    /// as is. This must follow a successful LoaderBoot transfer on the same
+    /// connection (e.g. [`Self::flash_fwpkg`] or [`Self::write_bins`]),
+    /// otherwise the device is still in first-stage boot and rejects the
+    /// download commands.
+    ///
+    /// # Arguments
+    ///
+    /// * `bins` - List of (data, address) pairs to flash
+    /// * `progress` - Progress callback (binary_name, current_bytes,
+    ///   total_bytes)
+    pub fn download_bins_only<F>(&mut self, bins: &[(&[u8], u32)], mut progress: F) -> Result<()>
+    where
+        F: FnMut(&str, usize, usize),
+    {
+        for (i, (data, addr)) in bins
+            .iter()
+            .enumerate()
+        {
+            self.cancel
+                .check()?;
+
+            let name = format!("binary_{i}");
+            info!("Writing {} ({} bytes) to 0x{:08X}", name, data.len(), addr);
+            self.download_binary(
+                &name,
+                data,
+                *addr,
+                false,
+                None,
+                &mut |name, current, total| {
+                    progress(name, current, total);
+                    ProgressControl::Continue
+                },
+            )?;
+
+            // Inter-partition delay
+            sleep_interruptible(&self.cancel, PARTITION_DELAY)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write `data` at `flash_addr`, leaving the rest of the partition alone.
+    ///
+    /// The download command covers exactly `data.len()` bytes, and only the
+    /// 4 KiB sectors the region touches are erased. Flash is erased a whole
+    /// sector at a time, so if the region does not start and end on a sector
+    /// boundary, the other bytes of its first and last sector are erased
+    /// too. Like [`Self::download_bins_only`], this must follow a LoaderBoot
+    /// transfer on the same connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `flash_addr` - Flash address of the first byte
+    /// * `data` - Bytes to write
+    /// * `progress` - Progress callback (name, current_bytes, total_bytes)
+    pub fn write_at<F>(&mut self, flash_addr: u32, data: &[u8], mut progress: F) -> Result<()>
+    where
+        F: FnMut(&str, usize, usize),
+    {
+        let erase_size = u32::try_from(data.len())
+            .ok()
+            .and_then(|len| region_erase_range(flash_addr, len))
+            .map(|(_, size)| size)
+            .ok_or_else(|| {
+                Error::Config(format!(
+                    "Invalid region: {} bytes at 0x{flash_addr:08X}",
+                    data.len()
+                ))
+            })?;
+
+        info!(
+            "Writing {} bytes to 0x{flash_addr:08X} (erasing 0x{erase_size:X} bytes)",
+            data.len()
+        );
+        self.download_binary(
+            "region",
+            data,
+            flash_addr,
+            false,
+            Some(erase_size),
+            &mut |name, current, total| {
+                progress(name, current, total);
+                ProgressControl::Continue
+            },
+        )
+    }
+
+    /// Erase entire flash.
+    ///
+    /// Fails with [`Error::Config`] unless [`Self::arm_destructive`] was
+    /// called first.
+    pub fn erase_all(&mut self) -> Result<()> {
+        self.send_erase_all()?;
+
+        // Wait for erase to complete; Ctrl-C aborts the wait promptly.
+        sleep_interruptible(&self.cancel, ERASE_ALL_WAIT)?;
+
+        info!("Flash erased");
+        Ok(())
+    }
+
+    /// Send the full-chip erase command, using up the permission granted by
+    /// [`Self::arm_destructive`].
+    fn send_erase_all(&mut self) -> Result<()> {
+        self.take_destructive_permission()?;
+        self.cancel
+            .check()?;
+
+        info!("Erasing entire flash...");
+
+        let frame = CommandFrame::erase_all();
+        self.send_command(&frame)
+    }
+
+    /// Program OTP/eFuse data. This cannot be undone.
+    ///
+    /// Sends the download OTP/eFuse command (0xC3) and transfers `data` over
+    /// YMODEM. Like [`Self::download_bins_only`], this must follow a
+    /// LoaderBoot transfer on the same connection. A rejected ACK, for the
+    /// command or for the transfer, fails with [`Error::Protocol`]. A failed
+    /// write is never retried, as part of the data may already be burned in.
+    #[allow(clippy::needless_pass_by_value)] // The token is used up on purpose.
+    pub fn write_otp(&mut self, data: &[u8], _confirm: OtpConfirmation) -> Result<()> {
+        self.cancel
+            .check()?;
+
+        if data.is_empty() {
+            return Err(Error::Config("OTP/eFuse data is empty".to_string()));
+        }
+        let len = u32::try_from(data.len()).map_err(|_| {
+            Error::Config(format!("OTP/eFuse data too large ({} bytes)", data.len()))
+        })?;
+
+        warn!("Programming {len} bytes of OTP/eFuse data; this cannot be undone");
+        let frame = CommandFrame::download_otp_efuse(len);
+        self.send_command(&frame)?;
+        self.wait_for_magic(POST_TRANSFER_MAGIC_TIMEOUT)?;
+        self.ymodem_send("otp", data, &mut |_, _, _| ProgressControl::Continue)?;
+
+        info!("OTP/eFuse data written");
+        Ok(())
+    }
+
+    /// Switch the device to DFU mode.
+    ///
+    /// Sends the SEBOOT switch-DFU command (0x1E) over a connected session
+    /// and waits for its ACK. Afterwards the device has left the serial
+    /// download loop: further commands on this flasher are not answered, and
+    /// the device stays in DFU mode for a DFU tool to take over until it is
+    /// reset.
+    pub fn switch_to_dfu(&mut self) -> Result<()> {
+        self.cancel
+            .check()?;
+
+        info!("Switching device to DFU mode...");
+
+        let frame = CommandFrame::switch_dfu();
+        self.send_command(&frame)?;
+        self.wait_for_magic(MAGIC_TIMEOUT)?;
+
+        info!("Device is in DFU mode");
+        Ok(())
+    }
+
+    /// Reset the device.
+    ///
+    /// Returns after the settle delay (see [`Self::set_reset_settle`]); Ctrl-C
+    /// cuts the wait short.
+    pub fn reset(&mut self) -> Result<()> {
+        self.cancel
+            .check()?;
+
+        info!("Resetting device...");
+
+        let frame = CommandFrame::reset();
+        self.send_command(&frame)?;
+
+        sleep_interruptible(&self.cancel, self.reset_settle)
+    }
+}
+
+// Native-specific convenience functions
+#[cfg(feature = "native")]
+mod native_impl {
+    use {
+        super::{
+            DEFAULT_BAUD, Duration, Error, Result, Ws63Flasher, debug, sleep_interruptible, warn,
+        },
+        crate::port::NativePort,
+    };
+
+    /// Shorten the latency timer of FTDI adapters (best effort).
+    ///
+    /// Only FTDI ports have the timer, and [`NativePort::set_low_latency`]
+    /// looks it up for the opened port itself, so other adapters are
+    /// untouched without enumerating every port on the system.
+    fn set_low_latency_if_ftdi(port: &mut NativePort) {
+        if let Err(e) = port.set_low_latency() {
+            debug!("Could not lower the FTDI latency timer: {e}");
+        }
+    }
+
+    impl Ws63Flasher<NativePort> {
+        /// Create a new WS63 flasher by opening a serial port.
+        ///
+        /// This is a convenience function for native platforms that opens
+        /// the port with default settings.
+        ///
+        /// # Arguments
+        ///
+        /// * `port_name` - Serial port name (e.g., "/dev/ttyUSB0" or "COM3")
+        /// * `target_baud` - Target baud rate for data transfer
+        pub fn open(port_name: &str, target_baud: u32) -> Result<Self> {
+            Self::open_with_retry(port_name, target_baud)
+        }
+
+        /// Open a serial port with full configuration (P0: 完整配置支持).
+        ///
+        /// This allows customization of all serial port parameters.
+        ///
+        /// # Arguments
+        ///
+        /// * `config` - Serial port configuration
+        pub fn open_with_config(config: crate::port::SerialConfig) -> Result<Self> {
+            Self::open_with_config_retry(config)
+        }
+
+        /// Open serial port with full config and retry mechanism.
+        #[allow(clippy::needless_pass_by_value)]
+        fn open_with_config_retry(config: crate::port::SerialConfig) -> Result<Self> {
+            const MAX_OPEN_PORT_ATTEMPTS: usize = 3;
+            const OPEN_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+            let mut last_error = None;
+
+            for attempt in 1..=MAX_OPEN_PORT_ATTEMPTS {
+                match NativePort::open(&config) {
+                    Ok(mut port) => {
+                        if attempt > 1 {
+                            debug!("Port opened on attempt {attempt}");
+                        }
+                        set_low_latency_if_ftdi(&mut port);
+                        return Ok(Self::with_cancel(
+                            port,
+                            config.baud_rate,
+                            crate::cancel_context_from_global(),
+                        ));
+                    },
+                    Err(e) => {
+                        warn!(
+                            "Failed to open port {} (attempt {}/{}): {e}",
+                            config.port_name, attempt, MAX_OPEN_PORT_ATTEMPTS
+                        );
+                        last_error = Some(e);
+
+                        if attempt < MAX_OPEN_PORT_ATTEMPTS {
+                            sleep_interruptible(
+                                &crate::cancel_context_from_global(),
+                                OPEN_RETRY_DELAY,
+                            )?;
+                        }
+                    },
+                }
+            }
+
+            Err(last_error.unwrap_or_else(|| {
+                Error::Config(format!(
+                    "Failed to open port after {MAX_OPEN_PORT_ATTEMPTS} attempts"
+                ))
+            }))
+        }
+
+        /// Open serial port with retry mechanism.
+        fn open_with_retry(port_name: &str, target_baud: u32) -> Result<Self> {
+            const MAX_OPEN_PORT_ATTEMPTS: usize = 3;
+            const OPEN_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+            let mut last_error = None;
+
+            for attempt in 1..=MAX_OPEN_PORT_ATTEMPTS {
+                let config = crate::port::SerialConfig::new(port_name, DEFAULT_BAUD);
+                match NativePort::open(&config) {
+                    Ok(mut port) => {
+                        if attempt > 1 {
+                            debug!("Port opened on attempt {attempt}");
+                        }
+                        set_low_latency_if_ftdi(&mut port);
+                        return Ok(Self::with_cancel(
+                            port,
+                            target_baud,
+                            crate::cancel_context_from_global(),
+                        ));
+                    },
+                    Err(e) => {
+                        warn!(
+                            "Failed to open port {port_name} (attempt \
+                             {attempt}/{MAX_OPEN_PORT_ATTEMPTS}): {e}"
+                        );
+                        last_error = Some(e);
+
+                        if attempt < MAX_OPEN_PORT_ATTEMPTS {
+                            sleep_interruptible(
+                                &crate::cancel_context_from_global(),
+                                OPEN_RETRY_DELAY,
+                            )?;
+                        }
+                    },
+                }
+            }
+
+            Err(last_error.unwrap_or_else(|| {
+                Error::Config(format!(
+                    "Failed to open port {port_name} after {MAX_OPEN_PORT_ATTEMPTS} attempts"
+                ))
+            }))
+        }
+    }
+}
+
+impl<P: Port> crate::target::Flasher for Ws63Flasher<P> {
+    fn connect(&mut self) -> Result<()> {
+        self.connect()
+    }
+
+    fn flash_fwpkg(
+        &mut self,
+        fwpkg: &Fwpkg,
+        filter: Option<&[PartitionFilter]>,
+        progress: &mut dyn FnMut(&str, usize, usize),
+    ) -> Result<()> {
+        self.flash_fwpkg(fwpkg, filter, |name, current, total| {
+            progress(name, current, total);
+        })
+    }
+
+    fn flash_fwpkg_controlled(
+        &mut self,
+        fwpkg: &Fwpkg,
+        filter: Option<&[PartitionFilter]>,
+        progress: &mut dyn FnMut(&str, usize, usize) -> ProgressControl,
+    ) -> Result<()> {
+        self.flash_fwpkg_controlled(fwpkg, filter, progress)
+    }
+
+    fn write_bins(&mut self, loaderboot: &[u8], bins: &[(&[u8], u32)]) -> Result<()> {
+        self.write_bins(loaderboot, bins)
+    }
+
+    fn download_bins_only(
+        &mut self,
+        bins: &[(&[u8], u32)],
+        progress: &mut dyn FnMut(&str, usize, usize),
+    ) -> Result<()> {
+        self.download_bins_only(bins, |name, current, total| {
+            progress(name, current, total);
+        })
+    }
+
+    fn arm_destructive(&mut self) {
+        self.arm_destructive();
+    }
+
+    fn erase_all(&mut self) -> Result<()> {
+        self.erase_all()
+    }
+
+    fn write_at(
+        &mut self,
+        flash_addr: u32,
+        data: &[u8],
+        progress: &mut dyn FnMut(&str, usize, usize),
+    ) -> Result<()> {
+        self.write_at(flash_addr, data, |name, current, total| {
+            progress(name, current, total);
+        })
+    }
+
+    fn write_otp(&mut self, data: &[u8], confirm: OtpConfirmation) -> Result<()> {
+        self.write_otp(data, confirm)
+    }
+
+    fn abort(&mut self) {
+        self.abort();
+    }
+
+    fn abort_handle(&self) -> AbortHandle {
+        self.abort_handle()
+    }
+
+    fn set_tolerate_lost_eot(&mut self, tolerate: bool) {
+        self.set_tolerate_lost_eot(tolerate);
+    }
+
+    fn set_checksum_fallback(&mut self, fallback: bool) {
+        self.set_checksum_fallback(fallback);
+    }
+
+    fn set_keep_going(&mut self, keep_going: bool) {
+        self.set_keep_going(keep_going);
+    }
+
+    fn set_stall_timeout(&mut self, timeout: Option<Duration>) {
+        self.set_stall_timeout(timeout);
+    }
+
+    fn set_total_deadline(&mut self, deadline: Option<Duration>) {
+        self.set_total_deadline(deadline);
+    }
+
+    fn set_reset_settle(&mut self, settle: Duration) {
+        self.set_reset_settle(settle);
+    }
+
+    fn set_max_partition_bytes(&mut self, max: Option<usize>) {
+        self.set_max_partition_bytes(max);
+    }
+
+    fn set_handshake_baud(&mut self, baud: u32) {
+        self.config
+            .init_baud = baud;
+    }
+
+    fn set_skip_loaderboot(&mut self, skip: bool) {
+        self.set_skip_loaderboot(skip);
+    }
+
+    fn set_pre_erase(&mut self, pre_erase: bool) {
+        self.set_pre_erase(pre_erase);
+    }
+
+    fn set_clear_interrupt_on_start(&mut self, clear: bool) {
+        self.set_clear_interrupt_on_start(clear);
+    }
+
+    fn set_auto_bootloader(&mut self, entry: Option<BootloaderEntry>) {
+        self.set_auto_bootloader(entry);
+    }
+
+    fn set_auto_reset_on_app_mode(&mut self, enabled: bool) {
+        self.set_auto_reset_on_app_mode(enabled);
+    }
+
+    fn set_app_detect_threshold(&mut self, bytes: usize) {
+        self.set_app_detect_threshold(bytes);
+    }
+
+    fn set_trace_sink(&mut self, sink: Box<dyn Write + Send>) {
+        self.set_trace_sink(sink);
+    }
+
+    fn set_device_error_sink(&mut self, sink: Box<dyn Write + Send>) {
+        self.set_device_error_sink(sink);
+    }
+
+    fn switch_to_dfu(&mut self) -> Result<()> {
+        self.switch_to_dfu()
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.reset()
+    }
+
+    fn connection_baud(&self) -> u32 {
+        self.config
+            .init_baud
+    }
+
+    fn target_baud(&self) -> Option<u32> {
+        Some(
+            self.config
+                .target_baud,
+        )
+    }
+
+    fn chip_config(&self) -> &ChipConfig {
+        self.chip_config()
+    }
+
+    fn close(&mut self) {
+        // Close the underlying port to release resources
+        // This is important for proper cleanup after reset
+        let _ = self
+            .port
+            .close();
+    }
+
+    fn into_monitor(self: Box<Self>, baud_rate: u32) -> Result<crate::monitor::MonitorSession> {
+        let Self { port, .. } = *self;
+        port.into_monitor_session(baud_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{port::Port, target::ws63::protocol::HANDSHAKE_ACK},
+        std::{
+            io::{Read, Write},
+            sync::{Arc, Mutex},
+        },
+    };
+
+    /// Mock port implementation for testing without real hardware.
+    ///
+    /// This implementation uses an internal buffer to simulate serial port
+    /// behavior, allowing unit tests to run without actual hardware.
+    #[derive(Clone)]
+    struct MockPort {
+        name: String,
+        baud_rate: u32,
+        timeout: Duration,
+        max_read_size: usize,
+        read_buffer: Arc<Mutex<Vec<u8>>>,
+        write_buffer: Arc<Mutex<Vec<u8>>>,
+        dtr: bool,
+        rts: bool,
+    }
+
+    impl MockPort {
+        fn new(name: &str) -> Self {
+            Self {
+                name: name.to_string(),
+                baud_rate: 115200,
+                timeout: Duration::from_secs(1),
+                max_read_size: 1,
+                read_buffer: Arc::new(Mutex::new(Vec::new())),
+                write_buffer: Arc::new(Mutex::new(Vec::new())),
+                dtr: false,
+                rts: false,
+            }
+        }
+
+        /// Add data to the read buffer (simulates receiving data from device).
+        fn add_read_data(&self, data: &[u8]) {
+            let mut buf = self
+                .read_buffer
+                .lock()
+                .unwrap();
+            buf.extend_from_slice(data);
+        }
+
+        /// Get data written to the port (simulates sending data to device).
+        fn get_written_data(&self) -> Vec<u8> {
+            let buf = self
+                .write_buffer
+                .lock()
+                .unwrap();
+            buf.clone()
+        }
+
+        /// Clear all buffers.
+        fn clear(&self) {
+            let mut read_buf = self
+                .read_buffer
+                .lock()
+                .unwrap();
+            let mut write_buf = self
+                .write_buffer
+                .lock()
+                .unwrap();
+            read_buf.clear();
+            write_buf.clear();
+        }
+    }
+
+    impl Port for MockPort {
+        fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+            self.timeout = timeout;
+            Ok(())
+        }
+
+        fn timeout(&self) -> Duration {
+            self.timeout
+        }
+
+        fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+            self.baud_rate = baud_rate;
+            Ok(())
+        }
+
+        fn baud_rate(&self) -> u32 {
+            self.baud_rate
+        }
+
+        fn clear_buffers(&mut self) -> Result<()> {
+            self.clear();
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn set_dtr(&mut self, level: bool) -> Result<()> {
+            self.dtr = level;
+            Ok(())
+        }
+
+        fn set_rts(&mut self, level: bool) -> Result<()> {
+            self.rts = level;
+            Ok(())
+        }
+
+        fn read_cts(&mut self) -> Result<bool> {
+            Ok(true) // Assume CTS is asserted
+        }
+
+        fn read_dsr(&mut self) -> Result<bool> {
+            Ok(true) // Assume DSR is asserted
+        }
+
+        fn close(&mut self) -> Result<()> {
+            // Clear all buffers to simulate port closure
+            self.clear();
+            Ok(())
+        }
+    }
+
+    impl Read for MockPort {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let mut read_buf = self
+                .read_buffer
+                .lock()
+                .map_err(|e| std::io::Error::other(format!("mutex poisoned: {e}")))?;
+
+            if read_buf.is_empty() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "no data available",
+                ));
+            }
+
+            let to_read = std::cmp::min(buf.len(), read_buf.len()).min(self.max_read_size);
+            buf[..to_read].copy_from_slice(&read_buf[..to_read]);
+            read_buf.drain(..to_read);
+            Ok(to_read)
+        }
+    }
+
+    impl Write for MockPort {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let mut write_buf = self
+                .write_buffer
+                .lock()
+                .map_err(|e| std::io::Error::other(format!("mutex poisoned: {e}")))?;
+            write_buf.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Test creating a Ws63Flasher with a mock port.
+    #[test]
+    fn test_flasher_new_with_mock_port() {
+        let port = MockPort::new("/dev/ttyUSB0");
+        let flasher = Ws63Flasher::with_cancel(port, 921600, CancelContext::none());
+
+        assert_eq!(
+            flasher
+                .config
+                .target_baud,
+            921600
+        );
+        assert!(
+            !flasher
+                .config
+                .late_baud_switch
+        );
+        assert_eq!(flasher.verbose, 0);
+    }
+
+    /// Test builder methods on Ws63Flasher.
+    #[test]
+    fn test_flasher_builder_methods() {
+        let port = MockPort::new("/dev/ttyUSB0");
+        let flasher = Ws63Flasher::with_cancel(port, 921600, CancelContext::none())
+            .with_late_baud(true)
+            .with_verbose(2);
+
+        assert!(
+            flasher
+                .config
+                .late_baud_switch
+        );
+        assert_eq!(flasher.verbose, 2);
+    }
+
+    /// Test MockPort read/write operations.
+    #[test]
+    fn test_mock_port_read_write() {
+        let mut port = MockPort::new("/dev/ttyUSB0");
+
+        // Add some data to read buffer
+        port.add_read_data(&[0xDE, 0xAD, 0xBE, 0xEF]);
+
+        // Write some data
+        port.write_all(b"test")
+            .unwrap();
+        port.flush()
+            .unwrap();
+
+        // Verify written data
+        let written = port.get_written_data();
+        assert_eq!(written, b"test");
+
+        // Read data - use read_exact to handle partial reads properly
+        let mut buf = [0u8; 4];
+        std::io::Read::read_exact(&mut port, &mut buf).unwrap();
+        assert_eq!(&buf, &[0xDE, 0xAD, 0xBE, 0xEF]);
+    }
+
+    /// Test MockPort buffer operations.
+    #[test]
+    fn test_mock_port_buffers() {
+        let mut port = MockPort::new("/dev/ttyUSB0");
+
+        // Clear buffers
+        port.clear();
+        assert!(
+            port.get_written_data()
+                .is_empty()
+        );
+
+        // Write and add read data
+        port.write_all(b"hello")
+            .unwrap();
+        port.add_read_data(&[1, 2, 3]);
+
+        // Verify
+        assert_eq!(port.get_written_data(), b"hello");
+
+        let mut buf = [0u8; 3];
+        std::io::Read::read_exact(&mut port, &mut buf).unwrap();
+        assert_eq!(&buf, &[1, 2, 3]);
+
+        // Clear and verify
+        port.clear();
+        assert!(
+            port.get_written_data()
+                .is_empty()
+        );
+    }
+
+    /// Test MockPort pin control.
+    #[test]
+    fn test_mock_port_pin_control() {
+        let mut port = MockPort::new("/dev/ttyUSB0");
+
+        assert!(!port.dtr);
+        assert!(!port.rts);
+
+        port.set_dtr(true)
+            .unwrap();
+        port.set_rts(true)
+            .unwrap();
+
+        assert!(port.dtr);
+        assert!(port.rts);
+    }
+
+    /// Test MockPort baud rate and timeout.
+    #[test]
+    fn test_mock_port_baud_timeout() {
+        let mut port = MockPort::new("/dev/ttyUSB0");
+
+        assert_eq!(port.baud_rate(), 115200);
+        assert_eq!(port.timeout(), Duration::from_secs(1));
+
+        port.set_baud_rate(921600)
+            .unwrap();
+        port.set_timeout(Duration::from_millis(500))
+            .unwrap();
+
+        assert_eq!(port.baud_rate(), 921600);
+        assert_eq!(port.timeout(), Duration::from_millis(500));
+    }
+
+    /// Test MockPort name.
+    #[test]
+    fn test_mock_port_name() {
+        let port = MockPort::new("/dev/ttyUSB1");
+        assert_eq!(port.name(), "/dev/ttyUSB1");
+
+        let port2 = MockPort::new("COM3");
+        assert_eq!(port2.name(), "COM3");
+    }
+
+    /// Test creating flasher with mock port through
+    /// ChipFamily::create_flasher_with_port.
+    #[test]
+    fn test_create_flasher_with_mock_port() {
+        use crate::target::ChipFamily;
+
+        let port = MockPort::new("/dev/ttyUSB0");
+        let flasher = ChipFamily::Ws63.create_flasher_with_port(port, 921600, false, 0);
+
+        assert!(flasher.is_ok());
+        let flasher = flasher.unwrap();
+
+        // Flasher should be usable (even though connect will fail without mock response
+        // data)
+        assert_eq!(flasher.connection_baud(), 115200); // DEFAULT_BAUD for handshake
+        assert_eq!(flasher.target_baud(), Some(921600));
+    }
+
+    /// Replay a complete FWPKG flash against the recorded wire transcript.
+    #[test]
+    fn test_transcript_ws63_flash_fwpkg() {
+        use crate::{
+            port::mock::{Transcript, TranscriptPort},
+            target::ChipFamily,
+        };
+
+        let fwpkg = transcript_fwpkg();
+        let transcript =
+            Transcript::parse(include_str!("../../../testdata/ws63_flash_fwpkg.toml")).unwrap();
+        let port = TranscriptPort::new(transcript);
+        let mut flasher = ChipFamily::Ws63
+            .create_flasher_with_port(port.clone(), 921_600, false, 0)
+            .unwrap();
+        flasher.set_reset_settle(Duration::ZERO);
+
+        let result = flasher
+            .connect()
+            .and_then(|()| flasher.flash_fwpkg(&fwpkg, None, &mut |_, _, _| {}))
+            .and_then(|()| flasher.reset());
+        port.assert_complete();
+        result.unwrap();
+    }
+
+    /// Regression: the pre-erase ACK is read before the first download
+    /// command, so it cannot be taken for that command's ACK.
+    #[test]
+    fn test_transcript_pre_erase_waits_for_erase_ack() {
+        use crate::{
+            port::mock::{Step, Transcript, TranscriptPort, TranscriptStep},
+            target::ChipFamily,
+        };
+
+        let fwpkg = transcript_fwpkg();
+
+        // Without arming, nothing is sent.
+        let mut flasher = Ws63Flasher::new(MockPort::new("/dev/ttyUSB0"), 921600);
+        flasher.set_skip_loaderboot(true);
+        flasher.set_pre_erase(true);
+        assert!(matches!(
+            flasher.flash_fwpkg(&fwpkg, None, |_, _, _| {}),
+            Err(Error::Config(_))
+        ));
+        assert!(
+            flasher
+                .port
+                .get_written_data()
+                .is_empty()
+        );
+
+        let mut transcript =
+            Transcript::parse(include_str!("../../../testdata/ws63_flash_fwpkg.toml")).unwrap();
+        let first_download = transcript
+            .steps
+            .iter()
+            .position(|step| {
+                step.note
+                    .as_deref()
+                    .is_some_and(|note| note.starts_with("download app"))
+            })
+            .unwrap();
+        transcript
+            .steps
+            .splice(
+                first_download..first_download,
+                [
+                    TranscriptStep {
+                        step: Step::Tx(CommandFrame::erase_all().build()),
+                        note: Some("erase all".into()),
+                    },
+                    TranscriptStep {
+                        step: Step::Rx(SebootAck::HANDSHAKE_ACK.to_vec()),
+                        note: Some("erase ACK once the flash is erased".into()),
+                    },
+                ],
+            );
+        let port = TranscriptPort::new(transcript);
+        let mut flasher = ChipFamily::Ws63
+            .create_flasher_with_port(port.clone(), 921_600, false, 0)
+            .unwrap();
+        flasher.set_reset_settle(Duration::ZERO);
+        flasher.set_pre_erase(true);
+        flasher.arm_destructive();
+
+        let started = Instant::now();
+        let result = flasher
+            .connect()
+            .and_then(|()| flasher.flash_fwpkg(&fwpkg, None, &mut |_, _, _| {}))
+            .and_then(|()| flasher.reset());
+        port.assert_complete();
+        result.unwrap();
+        // The ACK ends the wait; the fixed erase delay is not used.
+        assert!(started.elapsed() < ERASE_ALL_WAIT);
+    }
+
+    /// The package driven through the WS63 flash transcripts: a 16-byte
+    /// LoaderBoot (00..0F) and a 32-byte "app" partition (80..9F) burnt at
+    /// 0x00230000.
+    fn transcript_fwpkg() -> Fwpkg {
+        use crate::image::fwpkg::{FwpkgBinInfo, FwpkgBuilder, FwpkgVersion};
+
+        let bin = |name: &str, offset, burn_addr, partition_type| FwpkgBinInfo {
+            name: name.into(),
+            offset,
+            length: 0,
+            burn_addr,
+            burn_size: 0x1000,
+            partition_type,
+            reserved: [0; 4],
+        };
+        let package = FwpkgBuilder::new(FwpkgVersion::V1)
+            .with_bin(
+                bin("loaderboot", 128, 0, PartitionType::Loader),
+                (0x00..0x10).collect(),
+            )
+            .with_bin(
+                bin("app", 160, 0x0023_0000, PartitionType::Normal),
+                (0x80..0xA0).collect(),
+            )
+            .build()
+            .unwrap();
+        Fwpkg::from_bytes(package).unwrap()
+    }
+
+    /// Test creating a flasher from a runtime-selected boxed port.
+    #[test]
+    fn test_create_flasher_from_boxed_port() {
+        use crate::target::ChipFamily;
+
+        let port: Box<dyn Port> = Box::new(MockPort::new("/dev/ttyUSB0"));
+        assert_eq!(port.name(), "/dev/ttyUSB0");
+        let flasher = ChipFamily::Ws63
+            .create_flasher_from_boxed(port, 921600, false, 0)
+            .unwrap();
+        assert_eq!(flasher.connection_baud(), 115200);
+        assert_eq!(flasher.target_baud(), Some(921600));
+
+        let port: Box<dyn Port> = Box::new(MockPort::new("/dev/ttyUSB0"));
+        assert!(matches!(
+            ChipFamily::Generic.create_flasher_from_boxed(port, 115200, false, 0),
+            Err(crate::error::Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_chip_config_through_trait_object() {
+        use crate::target::ChipFamily;
+
+        let mut flasher = ChipFamily::Bs2x
+            .create_flasher_with_port(MockPort::new("/dev/ttyUSB0"), 2_000_000, true, 0)
+            .unwrap();
+        let config = flasher.chip_config();
+        assert_eq!(config.family, ChipFamily::Bs2x);
+        assert_eq!(config.init_baud, ChipFamily::Bs2x.handshake_baud());
+        assert_eq!(config.target_baud, 2_000_000);
+        assert!(config.late_baud_switch);
+
+        flasher.set_handshake_baud(230_400);
+        assert_eq!(
+            flasher
+                .chip_config()
+                .init_baud,
+            flasher.connection_baud()
+        );
+        assert_eq!(
+            Ws63Flasher::new(MockPort::new("/dev/ttyUSB0"), 921600)
+                .chip_config()
+                .family,
+            ChipFamily::Ws63
+        );
+    }
+
+    /// Test that Flasher trait object works correctly.
+    #[test]
+    fn test_flasher_trait_object() {
+        use crate::target::Flasher;
+
+        let port = MockPort::new("/dev/ttyUSB0");
+        let flasher: Box<dyn Flasher> = Box::new(Ws63Flasher::with_cancel(
+            port,
+            921600,
+            CancelContext::none(),
+        ));
+
+        assert_eq!(flasher.connection_baud(), 115200);
+        assert_eq!(flasher.target_baud(), Some(921600));
+    }
+
+    #[test]
+    fn test_flasher_custom_handshake_baud() {
+        use crate::target::Flasher;
+
+        let flasher: Box<dyn Flasher> = Box::new(
+            Ws63Flasher::with_cancel(MockPort::new("/dev/ttyUSB0"), 921600, CancelContext::none())
+                .with_handshake_baud(230_400),
+        );
+
+        assert_eq!(flasher.connection_baud(), 230_400);
+        assert_eq!(flasher.target_baud(), Some(921600));
+    }
+
+    #[test]
+    fn test_flasher_trait_sets_handshake_baud() {
+        use crate::target::Flasher;
+
+        let mut flasher: Box<dyn Flasher> =
+            Box::new(Ws63Flasher::new(MockPort::new("/dev/ttyUSB0"), 921600));
+        flasher.set_handshake_baud(9600);
+
+        assert_eq!(flasher.connection_baud(), 9600);
+        assert_eq!(flasher.target_baud(), Some(921600));
+    }
+
+    #[test]
+    fn test_flasher_trait_sets_tolerate_lost_eot() {
+        use crate::target::Flasher;
+
+        let mut flasher =
+            Ws63Flasher::with_cancel(MockPort::new("/dev/ttyUSB0"), 921600, CancelContext::none());
+        assert!(!flasher.tolerate_lost_eot);
+
+        Flasher::set_tolerate_lost_eot(&mut flasher, true);
+        assert!(flasher.tolerate_lost_eot);
+    }
+
+    #[test]
+    fn test_loaderboot_checksum_fallback_on_nak() {
+        use crate::target::Flasher;
+
+        let port = MockPort::new("/dev/ttyUSB0");
+        // A legacy loader asks for the transfer with NAK instead of 'C', then
+        // ACKs block 0, the data block, EOT and the finish block.
+        port.add_read_data(&[0x15, 0x06, 0x06, 0x06, 0x06]);
+
+        let mut flasher = Ws63Flasher::with_cancel(port, 921600, CancelContext::none());
+        Flasher::set_checksum_fallback(&mut flasher, true);
+        flasher
+            .transfer_loaderboot("test.bin", &[0xAA], &mut |_, _, _| {
+                ProgressControl::Continue
+            })
+            .unwrap();
+
+        // Block 0 is SOH, seq 0, ~seq, 128 bytes of file info and a single
+        // checksum byte instead of a CRC16.
+        let written = flasher
+            .port
+            .get_written_data();
+        assert_eq!(&written[..3], &[0x01, 0x00, 0xFF]);
+        let sum = written[3..131]
+            .iter()
+            .fold(0u8, |acc, &b| acc.wrapping_add(b));
+        assert_eq!(written[131], sum);
+        assert_eq!(
+            written[132], 0x02,
+            "the STX data block must follow a 1-byte checksum"
+        );
+    }
+
+    #[test]
+    fn test_flasher_trait_sets_keep_going() {
+        use crate::target::Flasher;
+
+        let mut flasher = Ws63Flasher::new(MockPort::new("/dev/ttyUSB0"), 921600);
+        assert!(!flasher.keep_going);
+
+        Flasher::set_keep_going(&mut flasher, true);
+        assert!(flasher.keep_going);
+    }
+
+    #[test]
+    fn test_download_binary_rejects_oversized_partition() {
+        let port = MockPort::new("/dev/ttyUSB0");
+        let mut flasher = Ws63Flasher::with_cancel(port.clone(), 921600, CancelContext::none());
+        flasher.set_max_partition_bytes(Some(8));
+
+        let err = flasher
+            .download_binary(
+                "app",
+                &[0x11; 16],
+                0x0020_0000,
+                false,
+                None,
+                &mut |_, _, _| ProgressControl::Continue,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::PartitionTooLarge {
+                size: 16,
+                limit: 8,
+                ..
+            }
+        ));
+        assert!(
+            port.get_written_data()
+                .is_empty(),
+            "nothing may be sent for an oversized partition"
+        );
+    }
+
+    #[test]
+    fn test_data_transfer_uses_stall_timeout() {
+        use crate::target::Flasher;
+
+        let port = MockPort::new("/dev/ttyUSB0");
+        let mut flasher = Ws63Flasher::with_cancel(port.clone(), 921600, CancelContext::none());
+        Flasher::set_stall_timeout(&mut flasher, Some(Duration::from_millis(200)));
+
+        // SEBOOT ACK for the download command and 'C' + ACK for block 0, then
+        // the device goes silent.
+        port.add_read_data(&[
+            0xEF, 0xBE, 0xAD, 0xDE, 0x0C, 0x00, 0xE1, 0x1E, 0x5A, 0x00, 0x00, 0x00,
+        ]);
+        port.add_read_data(b"C");
+        port.add_read_data(&[0x06]);
+
+        let err = flasher
+            .download_binary(
+                "app",
+                &[0x11; 16],
+                0x0020_0000,
+                false,
+                None,
+                &mut |_, _, _| ProgressControl::Continue,
+            )
+            .unwrap_err();
+        assert!(matches!(err, Error::Timeout(ref msg) if msg.contains("flash stalled")));
+    }
+
+    #[test]
+    fn test_abort_handle_stops_running_transfer() {
+        use crate::target::Flasher;
+
+        // The device never answers the download command, so the transfer
+        // keeps waiting until the handle is tripped from another thread.
+        let port = MockPort::new("/dev/ttyUSB0");
+        let mut flasher: Box<dyn Flasher> = Box::new(Ws63Flasher::new(port, 921600));
+        let handle = flasher.abort_handle();
+        let aborter = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            handle.abort();
+        });
+
+        let started = Instant::now();
+        let err = flasher
+            .download_bins_only(&[(&[0x11; 16], 0x0020_0000)], &mut |_, _, _| {})
+            .unwrap_err();
+        aborter
+            .join()
+            .unwrap();
+
+        assert!(is_interrupted_error(&err), "unexpected error: {err}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_flasher_abort_cancels_and_clears_port() {
+        use crate::target::Flasher;
+
+        let port = MockPort::new("/dev/ttyUSB0");
+        port.add_read_data(&[0xEF, 0xBE, 0xAD, 0xDE]);
+        let mut observer = port.clone();
+        let mut flasher: Box<dyn Flasher> = Box::new(Ws63Flasher::new(port, 921600));
+
+        flasher.abort();
+
+        // Pending input was discarded: reading now times out.
+        let mut buf = [0u8; 4];
+        assert!(
+            observer
+                .read(&mut buf)
+                .is_err()
+        );
+        let err = flasher
+            .write_bins(&[0x01], &[])
+            .unwrap_err();
+        assert!(is_interrupted_error(&err));
+        assert!(
+            observer
+                .get_written_data()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_drain_stale_input_discards_stale_ack() {
+        let port = MockPort::new("/dev/ttyUSB0");
+        port.add_read_data(&[0x00, 0x11]);
+        port.add_read_data(&HANDSHAKE_ACK);
+        let mut observer = port.clone();
+        let mut flasher = Ws63Flasher::new(port, 921600);
+        flasher
+            .prefetched_magic_bytes
+            .extend_from_slice(&HANDSHAKE_ACK);
+
+        let drained = flasher
+            .drain_stale_input()
+            .unwrap();
+
+        assert_eq!(drained, 2 + HANDSHAKE_ACK.len());
+        assert!(
+            flasher
+                .prefetched_magic_bytes
+                .is_empty()
+        );
+        // Nothing is left that a following handshake could mistake for an ACK.
+        let mut buf = [0u8; 16];
+        assert!(
+            observer
+                .read(&mut buf)
+                .is_err()
+        );
+        // The port timeout is restored afterwards.
+        assert_eq!(
+            flasher
+                .port
+                .timeout(),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn test_loaderboot_running_detects_second_stage() {
+        // Boot ROM: a stray handshake ACK, then the YMODEM request.
+        let port = MockPort::new("/dev/ttyUSB0");
+        port.add_read_data(&HANDSHAKE_ACK);
+        port.add_read_data(&[control::C, control::C]);
+        let mut flasher = Ws63Flasher::new(port, 921600);
+        assert!(
+            !flasher
+                .loaderboot_running()
+                .unwrap()
+        );
+        assert!(
+            flasher
+                .prefetched_ymodem_bytes
+                .ends_with(&[control::C])
+        );
+
+        // LoaderBoot: a SEBOOT reply and no 'C'.
+        let port = MockPort::new("/dev/ttyUSB0");
+        port.add_read_data(&HANDSHAKE_ACK);
+        port.add_read_data(&[0xEF, 0xBE, 0xAD, 0xDE, 0x0C, 0x00, 0x87, 0x78, 0x5A, 0x00]);
+        let mut flasher = Ws63Flasher::new(port, 921600);
+        assert!(
+            flasher
+                .loaderboot_running()
+                .unwrap()
+        );
+        assert_eq!(
+            flasher
+                .port
+                .timeout(),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn test_drain_stale_input_stops_at_byte_limit() {
+        let port = MockPort::new("/dev/ttyUSB0");
+        port.add_read_data(&vec![0x55; DRAIN_MAX_BYTES + 100]);
+        let mut flasher = Ws63Flasher::new(port, 921600);
+
+        let drained = flasher
+            .drain_stale_input()
+            .unwrap();
+
+        assert!(drained >= DRAIN_MAX_BYTES);
+        assert!(drained < DRAIN_MAX_BYTES + 100);
+    }
+
+    /// Trace sink whose contents stay readable after being boxed.
+    #[derive(Clone, Default)]
+    struct SharedSink(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .lock()
+                .unwrap()
+                .extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_trace_sink_logs_handshake_traffic_while_connecting() {
+        let port = MockPort::new("/dev/ttyUSB0");
+        port.add_read_data(&[0x00, 0x11]);
+        let sink = SharedSink::default();
+        let mut flasher = Ws63Flasher::new(port, 921600);
+        flasher.set_trace_sink(Box::new(sink.clone()));
+
+        flasher.trace_started = Some(Instant::now());
+        flasher
+            .drain_stale_input()
+            .unwrap();
+        let frame = CommandFrame::set_baud_rate(921600);
+        flasher
+            .send_command(&frame)
+            .unwrap();
+        flasher.trace_started = None;
+        // Traffic outside connect() is not traced.
+        flasher
+            .send_command(&frame)
+            .unwrap();
+
+        let log = String::from_utf8(
+            sink.0
+                .lock()
+                .unwrap()
+                .clone(),
+        )
+        .unwrap();
+        let lines: Vec<&str> = log
+            .lines()
+            .collect();
+        assert_eq!(lines.len(), 4, "{log}");
+        assert!(lines[0].starts_with('['));
+        assert!(lines[0].ends_with("] RX    1: 00"));
+        assert!(lines[1].ends_with("] RX    1: 11"));
+        let hex: Vec<String> = frame
+            .build()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        assert!(lines[2].ends_with(&format!("] TX {:>4}: {}", hex.len(), hex.join(" "))));
+        assert!(lines[3].ends_with(&format!("] #   {}", frame.describe())));
+    }
+
+    #[test]
+    fn test_reset_waits_for_settle_delay() {
+        let mut flasher = Ws63Flasher::new(MockPort::new("/dev/ttyUSB0"), 921600);
+        flasher.set_reset_settle(Duration::from_millis(50));
+
+        let started = Instant::now();
+        flasher
+            .reset()
+            .unwrap();
+
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert_eq!(
+            flasher
+                .port
+                .get_written_data(),
+            CommandFrame::reset().build()
+        );
+    }
+
+    #[test]
+    fn test_erase_all_requires_arming() {
+        let mut flasher = Ws63Flasher::new(MockPort::new("/dev/ttyUSB0"), 921600);
+        let result = flasher.erase_all();
+        assert!(
+            matches!(result, Err(Error::Config(ref msg)) if msg == "destructive operation not armed")
+        );
+        assert!(
+            flasher
+                .port
+                .get_written_data()
+                .is_empty()
+        );
+
+        // Arming is used up by the next attempt.
+        flasher.arm_destructive();
+        assert!(
+            flasher
+                .take_destructive_permission()
+                .is_ok()
+        );
+        assert!(
+            flasher
+                .take_destructive_permission()
+                .is_err()
+        );
+
+        // Arming again permits exactly one more.
+        flasher.arm_destructive();
+        assert!(
+            flasher
+                .take_destructive_permission()
+                .is_ok()
+        );
+        assert!(
+            flasher
+                .take_destructive_permission()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_write_otp_sends_otp_command_once() {
+        let mut flasher = Ws63Flasher::new(MockPort::new("/dev/ttyUSB0"), 921600);
+        assert!(matches!(
+            flasher.write_otp(&[], OtpConfirmation::i_understand_otp_is_permanent()),
+            Err(Error::Config(_))
+        ));
+        assert!(
+            flasher
+                .port
+                .get_written_data()
+                .is_empty()
+        );
+
+        // No ACK arrives: the command is sent once and not retried.
+        let started = Instant::now();
+        let cancel = CancelContext::new(move || started.elapsed() >= Duration::from_millis(50));
+        let mut flasher = Ws63Flasher::with_cancel(MockPort::new("/dev/ttyUSB0"), 921600, cancel);
+        assert!(
+            flasher
+                .write_otp(&[0x5A; 8], OtpConfirmation::i_understand_otp_is_permanent())
+                .is_err()
+        );
+        assert_eq!(
+            flasher
+                .port
+                .get_written_data(),
+            CommandFrame::download_otp_efuse(8).build()
+        );
+    }
+
+    #[test]
+    fn test_write_otp_fails_on_rejected_ack() {
+        let port = MockPort::new("/dev/ttyUSB0");
+        port.add_read_data(&[
+            0xEF, 0xBE, 0xAD, 0xDE, 0x0C, 0x00, 0xE1, 0x1E, 0xA5, 0x03, 0x00, 0x00,
+        ]);
+        let mut flasher = Ws63Flasher::new(port, 921600);
+
+        let err = flasher
+            .write_otp(&[0x5A; 8], OtpConfirmation::i_understand_otp_is_permanent())
+            .unwrap_err();
+
+        assert!(matches!(err, Error::Protocol(_)), "{err}");
+        // Nothing beyond the command is sent once the device refuses it.
+        assert_eq!(
+            flasher
+                .port
+                .get_written_data(),
+            CommandFrame::download_otp_efuse(8).build()
+        );
+    }
+
+    #[test]
+    fn test_erase_all_wait_is_interruptible() {
+        let started = Instant::now();
+        let cancel = CancelContext::new(move || started.elapsed() >= Duration::from_millis(50));
+        let mut flasher = Ws63Flasher::with_cancel(MockPort::new("/dev/ttyUSB0"), 921600, cancel);
+        flasher.arm_destructive();
+
+        let result = flasher.erase_all();
+
+        assert!(matches!(
+            result,
+            Err(Error::Io(ref io)) if io.kind() == std::io::ErrorKind::Interrupted
+        ));
+        assert!(
+            started.elapsed() < ERASE_ALL_WAIT / 2,
+            "erase wait should stop shortly after cancellation"
+        );
+        assert!(
+            !flasher
+                .port
+                .get_written_data()
+                .is_empty(),
+            "erase command should be sent before waiting"
+        );
+    }
+
+    #[test]
+    fn test_switch_to_dfu_sends_command_and_waits_for_ack() {
+        let port = MockPort::new("/dev/ttyUSB0");
+        port.add_read_data(&[
+            0xEF, 0xBE, 0xAD, 0xDE, 0x0C, 0x00, 0xE1, 0x1E, 0x5A, 0x00, 0x00, 0x00,
+        ]);
+        let mut flasher = Ws63Flasher::with_cancel(port, 921600, CancelContext::none());
+
+        flasher
+            .switch_to_dfu()
+            .unwrap();
+
+        assert_eq!(
+            flasher
+                .port
+                .get_written_data(),
+            CommandFrame::switch_dfu().build()
+        );
+    }
+
+    /// Test multiple flasher instances with same mock port clone.
+    #[test]
+    fn test_multiple_flashers_same_port() {
+        use crate::target::ChipFamily;
+
+        let port = MockPort::new("/dev/ttyUSB0");
+        let port_clone = port.clone();
+
+        let flasher1 = ChipFamily::Ws63.create_flasher_with_port(port, 921600, false, 0);
+        let flasher2 = ChipFamily::Ws63.create_flasher_with_port(port_clone, 115200, true, 1);
+
+        assert!(flasher1.is_ok());
+        assert!(flasher2.is_ok());
+
+        let flasher1 = flasher1.unwrap();
+        let flasher2 = flasher2.unwrap();
+
+        assert_eq!(flasher1.target_baud(), Some(921600));
+        assert_eq!(flasher2.target_baud(), Some(115200));
+    }
+
+    /// Test shared SEBOOT chip families can reuse the generic serial flasher.
+    #[test]
+    fn test_create_flasher_with_port_shared_seboot_chips() {
+        use crate::target::ChipFamily;
+
+        let port = MockPort::new("/dev/ttyUSB0");
+        let result = ChipFamily::Bs2x.create_flasher_with_port(port, 115200, false, 0);
+
+        assert!(result.is_ok());
+
+        let port = MockPort::new("/dev/ttyUSB1");
+        let result = ChipFamily::Bs25.create_flasher_with_port(port, 115200, false, 0);
+
+        assert!(result.is_ok());
+    }
+
+    /// Test unsupported chip family still returns an error for generic ports.
+    #[test]
+    fn test_create_flasher_with_port_unsupported_chip() {
+        use crate::target::ChipFamily;
+
+        let port = MockPort::new("/dev/ttyUSB0");
+        let result = ChipFamily::Generic.create_flasher_with_port(port, 115200, false, 0);
+
+        assert!(result.is_err());
+        // Verify error is the Unsupported variant
+        assert!(matches!(result, Err(crate::error::Error::Unsupported(_))));
+    }
+
+    #[test]
+    fn test_is_interrupted_error_for_io_interrupted_and_message() {
+        let e1 = Error::Io(std::io::Error::new(
+            std::io::ErrorKind::Interrupted,
+            "operation interrupted",
+        ));
+        assert!(is_interrupted_error(&e1));
+
+        let e2 = Error::Io(std::io::Error::other("Interrupted system call"));
+        assert!(is_interrupted_error(&e2));
+    }
+
+    #[test]
+    fn test_download_binary_interrupted_short_circuits_retry() {
+        let _guard = crate::INTERRUPT_TEST_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        crate::test_set_interrupted(true);
+
+        let port = MockPort::new("/dev/ttyUSB0");
+        let cancel = crate::cancel_context_from_global();
+        let mut flasher = Ws63Flasher::with_cancel(port, 921600, cancel);
+        let mut progress_calls = 0usize;
+
+        let result = flasher.download_binary(
+            "app.bin",
+            &[0x01, 0x02, 0x03],
+            0x0023_0000,
+            false,
+            None,
+            &mut |_, _, _| {
+                progress_calls += 1;
+                ProgressControl::Continue
+            },
+        );
+
+        assert!(matches!(
+            result,
+            Err(Error::Io(ref io)) if io.kind() == std::io::ErrorKind::Interrupted
+        ));
+        assert_eq!(progress_calls, 0);
+        assert!(
+            flasher
+                .port
+                .get_written_data()
+                .is_empty(),
+            "Interrupted download should not send frames or enter retry loop"
+        );
+
+        crate::test_set_interrupted(false);
+    }
+
+    #[test]
+    fn test_total_deadline_stops_retry_loops() {
+        let mut flasher = Ws63Flasher::new(MockPort::new("/dev/ttyUSB0"), 921600);
+        flasher.set_total_deadline(Some(Duration::ZERO));
+
+        let err = flasher
+            .connect()
+            .unwrap_err();
+        assert!(is_deadline_error(&err), "{err}");
+        assert!(
+            flasher
+                .port
+                .get_written_data()
+                .is_empty()
+        );
+
+        let err = flasher
+            .download_binary(
+                "app.bin",
+                &[0x01],
+                0x0023_0000,
+                false,
+                None,
+                &mut |_, _, _| ProgressControl::Continue,
+            )
+            .unwrap_err();
+        assert!(is_deadline_error(&err), "{err}");
+
+        flasher.set_total_deadline(None);
+        assert!(
+            flasher
+                .check_total_deadline()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_connect_clears_stale_global_interrupt_when_enabled() {
+        let _guard = crate::INTERRUPT_TEST_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        for clear in [false, true] {
+            crate::test_set_interrupted(true);
+            // The explicit context stops connect() before any handshake.
+            let cancel = CancelContext::new(|| true).or_global();
+            let mut flasher =
+                Ws63Flasher::with_cancel(MockPort::new("/dev/ttyUSB0"), 921600, cancel);
+            flasher.set_clear_interrupt_on_start(clear);
+
+            assert!(
+                flasher
+                    .connect()
+                    .is_err()
+            );
+            assert_eq!(crate::is_interrupted_requested(), !clear);
+        }
+
+        crate::test_set_interrupted(false);
+    }
+
+    #[test]
+    fn test_enter_bootloader_drives_control_lines() {
+        let mut flasher = Ws63Flasher::new(MockPort::new("/dev/ttyUSB0"), 921600);
+        flasher
+            .enter_bootloader()
+            .unwrap();
+        assert!(
+            !flasher
+                .port
+                .dtr
+                && !flasher
+                    .port
+                    .rts
+        );
+
+        flasher.set_auto_bootloader(Some(
+            "D1|R1|W0.01|R0"
+                .parse()
+                .unwrap(),
+        ));
+        flasher
+            .enter_bootloader()
+            .unwrap();
+        assert!(
+            flasher
+                .port
+                .dtr
+        );
+        assert!(
+            !flasher
+                .port
+                .rts
+        );
+    }
+
+    #[test]
+    fn test_app_mode_resets_only_when_enabled() {
+        let mut flasher = Ws63Flasher::new(MockPort::new("/dev/ttyUSB0"), 921600);
+        flasher.set_auto_bootloader(Some(
+            "D1|R1|W0.01|R0"
+                .parse()
+                .unwrap(),
+        ));
+        flasher
+            .handle_app_mode()
+            .unwrap();
+        assert!(
+            !flasher
+                .port
+                .dtr
+        );
+
+        flasher.set_auto_reset_on_app_mode(true);
+        flasher
+            .port
+            .add_read_data(b"app log");
+        flasher
+            .handle_app_mode()
+            .unwrap();
+        assert!(
+            flasher
+                .port
+                .dtr
+        );
+        assert!(
+            flasher
+                .port
+                .read_buffer
+                .lock()
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    /// Connect through `app_bytes` bytes of application output and return
+    /// the trace log; the flasher resets the board on app mode.
+    fn connect_after_app_output(app_bytes: usize, threshold: Option<usize>) -> String {
+        use crate::{
+            port::mock::{Transcript, TranscriptPort},
+            target::ChipFamily,
+        };
+
+        let handshake = "EF BE AD DE 12 00 F0 0F 00 10 0E 00 08 01 00 00 6E 80";
+        let transcript = Transcript::parse(&format!(
+            "[[step]]\ntx = \"{handshake}\"\n\
+             [[step]]\nrx = \"41*{app_bytes}\"\n\
+             [[step]]\ntx = \"{handshake}\"\n\
+             [[step]]\nrx = \"EF BE AD DE 0C 00 E1 1E 5A 00 95 22\"\n"
+        ))
+        .unwrap();
+        let port = TranscriptPort::new(transcript);
+        let mut flasher = ChipFamily::Ws63
+            .create_flasher_with_port(port.clone(), 921_600, true, 0)
+            .unwrap();
+        let sink = SharedSink::default();
+        flasher.set_trace_sink(Box::new(sink.clone()));
+        flasher.set_auto_reset_on_app_mode(true);
+        if let Some(threshold) = threshold {
+            flasher.set_app_detect_threshold(threshold);
+        }
+
+        let result = flasher.connect();
+        port.assert_complete();
+        result.unwrap();
+        String::from_utf8(
+            sink.0
+                .lock()
+                .unwrap()
+                .clone(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_app_mode_detected_at_default_threshold() {
+        let below = connect_after_app_output(APP_DETECT_THRESHOLD_BYTES - 1, None);
+        assert!(!below.contains("# app mode"), "{below}");
+
+        let at = connect_after_app_output(APP_DETECT_THRESHOLD_BYTES, None);
+        assert!(at.contains("# app mode, reset"), "{at}");
+    }
+
+    #[test]
+    fn test_app_mode_detected_at_configured_threshold() {
+        let below = connect_after_app_output(7, Some(8));
+        assert!(!below.contains("# app mode"), "{below}");
+
+        let at = connect_after_app_output(8, Some(8));
+        assert!(at.contains("# app mode, reset"), "{at}");
+    }
+
+    #[test]
+    fn test_probe_baud_switch_rejects_garbage_only() {
+        let mut flasher = Ws63Flasher::new(MockPort::new("/dev/ttyUSB0"), 921600);
+        flasher
+            .port
+            .add_read_data(&[0x00, 0x43]);
+        assert!(
+            flasher
+                .probe_baud_switch()
+                .unwrap()
+        );
+
+        flasher
+            .port
+            .add_read_data(&[0x00, 0xFE, 0x80]);
+        assert!(
+            !flasher
+                .probe_baud_switch()
+                .unwrap()
+        );
+        assert_eq!(
+            flasher
+                .port
+                .timeout(),
+            Duration::from_secs(1)
+        );
+
+        // Silence is not treated as a failed switch.
+        assert!(
+            flasher
+                .probe_baud_switch()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_download_binary_explicit_context_short_circuits_retry() {
+        use std::sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        };
+
+        let stop = Arc::new(AtomicBool::new(true));
+        let flag = Arc::clone(&stop);
+        let cancel = CancelContext::new(move || flag.load(Ordering::SeqCst));
+        let mut flasher = Ws63Flasher::with_cancel(MockPort::new("/dev/ttyUSB0"), 921600, cancel);
+
+        let result = flasher.download_binary(
+            "app.bin",
+            &[0x01],
+            0x0023_0000,
+            false,
+            None,
+            &mut |_, _, _| ProgressControl::Continue,
+        );
+
+        assert!(matches!(
+            result,
+            Err(Error::Io(ref io)) if io.kind() == std::io::ErrorKind::Interrupted
+        ));
+        assert!(
+            flasher
+                .port
+                .get_written_data()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_download_bins_only_starts_with_download_command() {
+        let port = MockPort::new("/dev/ttyUSB0");
+        // Stop as soon as the first frame is on the wire.
+        let observer = port.clone();
+        let cancel = CancelContext::new(move || {
+            !observer
+                .get_written_data()
+                .is_empty()
+        });
+        let mut flasher = Ws63Flasher::with_cancel(port, 921600, cancel);
+
+        let data = [0xAA; 16];
+        let result = flasher.download_bins_only(&[(&data[..], 0x0023_0000)], |_, _, _| {});
+
+        assert!(matches!(
+            result,
+            Err(Error::Io(ref io)) if io.kind() == std::io::ErrorKind::Interrupted
+        ));
+        let written = flasher
+            .port
+            .get_written_data();
+        assert_eq!(&written[..4], &[0xEF, 0xBE, 0xAD, 0xDE]);
+        assert_eq!(
+            &written[6..8],
+            &[0xD2, 0x2D],
+            "first frame must be the download command, not a LoaderBoot transfer"
+        );
+        assert_eq!(
+            flasher
+                .port
+                .baud_rate(),
+            115200
+        );
+    }
+
+    #[test]
+    fn test_write_at_erases_only_touched_sectors() {
+        let port = MockPort::new("/dev/ttyUSB0");
+        // Stop as soon as the download command is on the wire.
+        let observer = port.clone();
+        let cancel = CancelContext::new(move || {
+            !observer
+                .get_written_data()
+                .is_empty()
+        });
+        let mut flasher = Ws63Flasher::with_cancel(port, 921600, cancel);
+
+        let data = [0x5A; 0x1000];
+        let result = flasher.write_at(0x0023_0800, &data, |_, _, _| {});
+        assert!(matches!(
+            result,
+            Err(Error::Io(ref io)) if io.kind() == std::io::ErrorKind::Interrupted
+        ));
+        assert_eq!(
+            flasher
+                .port
+                .get_written_data(),
+            CommandFrame::download_image(0x0023_0800, 0x1000, 0x2000, false).build()
+        );
+
+        assert!(matches!(
+            flasher.write_at(0x0023_0000, &[], |_, _, _| {}),
+            Err(Error::Config(_))
+        ));
+    }
+
+    // =====================================================================
+    // Regression tests for protocol fixes (CRC fix + flash protocol fix)
+    // =====================================================================
+
+    /// Regression: erase_size must be aligned to 0x1000 (4KB) boundary.
+    ///
+    /// The official fbb_burntool aligns erase_size to 0x1000:
+    ///   `if (eraseSize % 0x1000 != 0) eraseSize = 0x1000 * (eraseSize / 0x1000
+    /// + 1)`
+    ///
+    /// Previously hisiflash passed `len` directly as erase_size without
+    /// alignment.
+    #[test]
+    fn test_erase_size_alignment_4k() {
+        // Already aligned values should stay the same
+        assert_eq!((0x1000u32 + 0xFFF) & !0xFFF, 0x1000);
+        assert_eq!((0x2000u32 + 0xFFF) & !0xFFF, 0x2000);
+        assert_eq!((0x10000u32 + 0xFFF) & !0xFFF, 0x10000);
+
+        // Non-aligned values should be rounded up to next 4KB boundary
+        assert_eq!((1u32 + 0xFFF) & !0xFFF, 0x1000);
+        assert_eq!((0x1001u32 + 0xFFF) & !0xFFF, 0x2000);
+        assert_eq!((0x2001u32 + 0xFFF) & !0xFFF, 0x3000);
+        assert_eq!((0xFFFu32 + 0xFFF) & !0xFFF, 0x1000);
+
+        // Typical firmware sizes from ws63-liteos-app_all.fwpkg
+        // root_params_sign.bin: length = 0x8F4 (2292 bytes)
+        assert_eq!((0x8F4u32 + 0xFFF) & !0xFFF, 0x1000);
+        // root_params_sign_b.bin: similar
+        assert_eq!((0x900u32 + 0xFFF) & !0xFFF, 0x1000);
+        // A larger typical partition
+        assert_eq!((0x12345u32 + 0xFFF) & !0xFFF, 0x13000);
+    }
+
+    /// Regression: wait_for_magic correctly detects SEBOOT magic bytes.
+    ///
+    /// After LoaderBoot transfer and after each download command, the device
+    /// sends a SEBOOT frame starting with 0xDEADBEEF (little-endian: EF BE AD
+    /// DE). wait_for_magic must find this pattern in the byte stream.
+    #[test]
+    fn test_wait_for_magic_finds_magic() {
+        let port = MockPort::new("/dev/ttyUSB0");
+
+        // Simulate device response: some garbage then magic + frame data
+        let mut response = vec![0x00, 0x41, 0x42]; // garbage bytes
+        response.extend_from_slice(&[0xEF, 0xBE, 0xAD, 0xDE]); // magic
+        response.extend_from_slice(&[0x0C, 0x00, 0xE1, 0x1E, 0x5A, 0x00, 0x00, 0x00]); // frame
+        port.add_read_data(&response);
+
+        let mut flasher = Ws63Flasher::with_cancel(port, 921600, CancelContext::none());
+        let result = flasher.wait_for_magic(Duration::from_millis(500));
+        assert!(
+            result.is_ok(),
+            "wait_for_magic should succeed when magic is present"
+        );
+    }
+
+    #[test]
+    fn test_device_error_sink_captures_failed_ack() {
+        let failed_ack = [
+            0xEF, 0xBE, 0xAD, 0xDE, 0x0C, 0x00, 0xE1, 0x1E, 0xA5, 0x03, 0x00, 0x00,
+        ];
+
+        // Without a sink, the command fails all the same.
+        let port = MockPort::new("/dev/ttyUSB0");
+        port.add_read_data(&failed_ack);
+        let mut flasher = Ws63Flasher::new(port, 921600);
+        let err = flasher
+            .wait_for_magic(Duration::from_millis(500))
+            .unwrap_err();
+        assert!(matches!(err, Error::Protocol(_)), "{err}");
+
+        let port = MockPort::new("/dev/ttyUSB0");
+        port.add_read_data(&failed_ack);
+        let sink = SharedSink::default();
+        let mut flasher = Ws63Flasher::new(port, 921600);
+        flasher.set_device_error_sink(Box::new(sink.clone()));
+        let err = flasher
+            .wait_for_magic(Duration::from_millis(500))
+            .unwrap_err();
+        assert!(matches!(err, Error::Protocol(_)), "{err}");
+        assert!(
+            err.to_string()
+                .contains("error code 0x03"),
+            "{err}"
+        );
+        assert!(
+            err.to_string()
+                .ends_with("ef be ad de 0c 00 e1 1e a5 03 00 00"),
+            "{err}"
+        );
+        assert_eq!(
+            *sink
+                .0
+                .lock()
+                .unwrap(),
+            failed_ack
+        );
+
+        // Successful ACKs are not captured.
+        flasher
+            .port
+            .add_read_data(&SebootAck::HANDSHAKE_ACK);
+        flasher
+            .wait_for_magic(Duration::from_millis(500))
+            .unwrap();
+        assert_eq!(
+            sink.0
+                .lock()
+                .unwrap()
+                .len(),
+            failed_ack.len()
+        );
+    }
+
+    /// Regression: wait_for_magic times out when no magic present.
+    #[test]
+    fn test_wait_for_magic_timeout_no_magic() {
+        let port = MockPort::new("/dev/ttyUSB0");
+        // No data in buffer -> should timeout
+        let mut flasher = Ws63Flasher::with_cancel(port, 921600, CancelContext::none());
+        let result = flasher.wait_for_magic(Duration::from_millis(100));
+        assert!(
+            result.is_err(),
+            "wait_for_magic should timeout with no data"
+        );
+    }
+
+    /// Regression: wait_for_magic with magic preceded by partial match.
+    ///
+    /// Tests the edge case where some bytes of the magic appear before the
+    /// full magic sequence (e.g., 0xEF followed by garbage, then the real
+    /// magic).
+    #[test]
+    fn test_wait_for_magic_partial_then_real() {
+        let port = MockPort::new("/dev/ttyUSB0");
+
+        // Partial magic (0xEF 0xBE) then non-magic, then real magic
+        let mut response = Vec::new();
+        response.extend_from_slice(&[0xEF, 0xBE, 0x00]); // partial match then break
+        response.extend_from_slice(&[0xEF, 0xBE, 0xAD, 0xDE]); // real magic
+        response.extend_from_slice(&[0x0C, 0x00, 0xE1, 0x1E, 0x5A, 0x00, 0x00, 0x00]); // complete frame tail
+        port.add_read_data(&response);
+
+        let mut flasher = Ws63Flasher::with_cancel(port, 921600, CancelContext::none());
+        let result = flasher.wait_for_magic(Duration::from_millis(500));
+        assert!(
+            result.is_ok(),
+            "wait_for_magic should handle partial matches"
+        );
+    }
+
+    /// Regression: LoaderBoot must NOT send download command (0xD2).
+    ///
+    /// In the official fbb_burntool, `SendBurnCmd()` skips the download payload
+    /// for LOADER type: `if (GetCurrentCmdType() != BurnCtrl::LOADER)`.
+    /// ws63flash also only calls ymodem_xfer() directly after handshake for
+    /// LoaderBoot.
+    ///
+    /// Previously hisiflash called download_binary() for LoaderBoot, which sent
+    /// a 0xD2 download command frame. This caused the device to misinterpret
+    /// the frame as data corruption.
+    #[test]
+    fn test_loaderboot_no_download_command() {
+        let port = MockPort::new("/dev/ttyUSB0");
+
+        // Simulate: device sends 'C' for YMODEM, then ACKs all blocks, then magic
+        let response = vec![
+            b'C', // YMODEM 'C' request
+            0x06, // ACK for block 0 (file info)
+            0x06, // ACK for data block
+            0x06, // ACK for EOT
+            0x06, // ACK for finish block
+        ];
+        port.add_read_data(&response);
+
+        let mut flasher = Ws63Flasher::with_cancel(port, 921600, CancelContext::none());
+        let result = flasher.transfer_loaderboot("test.bin", &[0xAA], &mut |_, _, _| {
+            ProgressControl::Continue
+        });
+
+        // Transfer should succeed (or fail on mock port details, but NOT send 0xD2)
+        // The key assertion: check that no download command frame was written
+        let written = flasher
+            .port
+            .get_written_data();
+
+        // Download command frame starts with magic + has cmd byte 0xD2
+        // Scan the written data for 0xD2 command byte at the expected position
+        // Frame format: [EF BE AD DE] [len_lo len_hi] [CMD] [SCMD] ...
+        let has_download_cmd = written
+            .windows(8)
+            .any(|w| {
+                w[0] == 0xEF
+                    && w[1] == 0xBE
+                    && w[2] == 0xAD
+                    && w[3] == 0xDE
+                    && w[6] == 0xD2
+                    && w[7] == 0x2D
+            });
+
+        assert!(
+            !has_download_cmd,
+            "LoaderBoot transfer must NOT send download command (0xD2). Written data should only \
+             contain YMODEM blocks, not SEBOOT command frames."
+        );
+
+        // Also verify that the YMODEM transfer actually wrote something
+        assert!(
+            !written.is_empty(),
+            "YMODEM transfer should have written data for LoaderBoot"
+        );
+
+        // Verify the result succeeded
+        assert!(
+            result.is_ok(),
+            "LoaderBoot transfer should succeed: {:?}",
+            result.err()
+        );
+    }
+
+    /// Regression: download_binary for normal partitions MUST send download
+    /// command (0xD2).
+    ///
+    /// After LoaderBoot, all subsequent partitions require a download command
+    /// with addr, len, and aligned erase_size before the YMODEM transfer.
+    #[test]
+    fn test_normal_partition_sends_download_command() {
+        let port = MockPort::new("/dev/ttyUSB0");
+
+        // Simulate: device sends magic ACK after download command, then 'C' for YMODEM
+        let mut response = Vec::new();
+        // ACK frame for download command (magic + frame data)
+        response.extend_from_slice(&[0xEF, 0xBE, 0xAD, 0xDE]);
+        response.extend_from_slice(&[0x0C, 0x00, 0xE1, 0x1E, 0x5A, 0x00, 0x00, 0x00]);
+        // Note: wait_for_magic drains remaining bytes after the magic in one read call,
+        // so YMODEM responses (C, ACKs) get consumed. This is a mock limitation.
+        // We just verify the download command was sent; full flow is tested on
+        // hardware.
+        port.add_read_data(&response);
+
+        let mut flasher = Ws63Flasher::with_cancel(port, 921600, CancelContext::none());
+        let test_data = vec![0xBB; 100];
+        // The transfer will fail because 'C' and ACKs were drained by wait_for_magic,
+        // but we only care about verifying the download command was sent.
+        let _result = flasher.try_download_binary(
+            "test_partition.bin",
+            &test_data,
+            0x00800000,
+            false,
+            None,
+            &mut |_, _, _| ProgressControl::Continue,
+        );
+
+        let written = flasher
+            .port
+            .get_written_data();
+
+        // Verify download command WAS sent
+        let has_download_cmd = written
+            .windows(8)
+            .any(|w| {
+                w[0] == 0xEF
+                    && w[1] == 0xBE
+                    && w[2] == 0xAD
+                    && w[3] == 0xDE
+                    && w[6] == 0xD2
+                    && w[7] == 0x2D
+            });
+
+        assert!(
+            has_download_cmd,
+            "Normal partition download must send download command (0xD2). Written data should \
+             contain a SEBOOT command frame."
+        );
+    }
+
+    /// ROM partitions set the formal byte of the download command.
+    #[test]
+    fn test_rom_partition_sets_formal_byte() {
+        let port = MockPort::new("/dev/ttyUSB0");
+        let observer = port.clone();
+        let cancel = CancelContext::new(move || {
+            !observer
+                .get_written_data()
+                .is_empty()
+        });
+        let mut flasher = Ws63Flasher::with_cancel(port, 921600, cancel);
+
+        let _result =
+            flasher.try_download_binary("rom.bin", &[0xCC; 8], 0, true, None, &mut |_, _, _| {
+                ProgressControl::Continue
+            });
+
+        let written = flasher
+            .port
+            .get_written_data();
+        assert_eq!(&written[6..8], &[0xD2, 0x2D]);
+        assert_eq!(&written[20..22], &[0x01, 0xFE]);
+    }
+
+    /// Regression: download command frame must contain properly aligned
+    /// erase_size.
+    ///
+    /// Verifies the actual bytes written in the download command frame have
+    /// the erase_size field aligned to 0x1000 (4KB).
+    #[test]
+    fn test_download_frame_erase_size_in_bytes() {
+        // Test with a non-aligned length (100 bytes = 0x64)
+        // Expected erase_size: (0x64 + 0xFFF) & !0xFFF = 0x1000
+        let frame = CommandFrame::download(0x00800000, 100, (100 + 0xFFF) & !0xFFF);
+        let data = frame.build();
+
+        // Frame layout: Magic(4) + Len(2) + CMD(1) + SCMD(1) + addr(4) + len(4) +
+        // erase_size(4) + const(2) + CRC(2) erase_size starts at offset 16
+        let erase_size = u32::from_le_bytes([data[16], data[17], data[18], data[19]]);
+        assert_eq!(
+            erase_size, 0x1000,
+            "erase_size for 100 bytes should be 0x1000 (4KB aligned), got 0x{erase_size:X}"
+        );
+
+        // Test with exactly 4KB
+        let frame2 = CommandFrame::download(0x00800000, 0x1000, (0x1000u32 + 0xFFF) & !0xFFF);
+        let data2 = frame2.build();
+        let erase_size2 = u32::from_le_bytes([data2[16], data2[17], data2[18], data2[19]]);
+        assert_eq!(
+            erase_size2, 0x1000,
+            "erase_size for exactly 4KB should remain 0x1000"
+        );
+
+        // Test with 4KB + 1
+        let frame3 = CommandFrame::download(0x00800000, 0x1001, (0x1001u32 + 0xFFF) & !0xFFF);
+        let data3 = frame3.build();
+        let erase_size3 = u32::from_le_bytes([data3[16], data3[17], data3[18], data3[19]]);
+        assert_eq!(
+            erase_size3, 0x2000,
+            "erase_size for 0x1001 bytes should be 0x2000 (next 4KB boundary)"
+        );
+    }
+}
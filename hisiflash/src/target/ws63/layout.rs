@@ -0,0 +1,128 @@
+//! Default WS63 flash layout.
+//!
+//! Burn addresses of the standard partitions in the WS63 SDK layout, so tools
+//! can place a partition by name instead of asking for its address. Custom
+//! layouts still need explicit addresses. Also holds the sector geometry
+//! that decides what a write erases.
+
+use crate::image::fwpkg::PartitionType;
+
+/// A partition of [`DEFAULT_LAYOUT`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutEntry {
+    /// Short partition name, matched case-insensitively by [`find_partition`].
+    pub name: &'static str,
+    /// Partition type used for this partition in FWPKG packages.
+    pub partition_type: PartitionType,
+    /// Flash address the partition is written to.
+    pub burn_addr: u32,
+}
+
+/// Partitions of the default WS63 layout (4 MiB flash mapped at 0x200000).
+pub const DEFAULT_LAYOUT: &[LayoutEntry] = &[
+    LayoutEntry {
+        name: "params",
+        partition_type: PartitionType::Normal,
+        burn_addr: 0x0020_0000,
+    },
+    LayoutEntry {
+        name: "ssb",
+        partition_type: PartitionType::Normal,
+        burn_addr: 0x0020_2000,
+    },
+    LayoutEntry {
+        name: "flashboot",
+        partition_type: PartitionType::Flashboot,
+        burn_addr: 0x0022_0000,
+    },
+    LayoutEntry {
+        name: "app",
+        partition_type: PartitionType::Normal,
+        burn_addr: 0x0023_0000,
+    },
+    LayoutEntry {
+        name: "nv",
+        partition_type: PartitionType::KvNv,
+        burn_addr: 0x005F_C000,
+    },
+];
+
+/// Flash erase granularity: writes erase whole sectors of this size.
+pub const SECTOR_SIZE: u32 = 0x1000;
+
+/// Sector-aligned range erased to write `[addr, addr + len)`, as the
+/// address of its first sector and its size in bytes.
+///
+/// `None` if the region is empty or runs past the 32-bit address space.
+pub fn region_erase_range(addr: u32, len: u32) -> Option<(u32, u32)> {
+    if len == 0 {
+        return None;
+    }
+    let end = u64::from(addr) + u64::from(len);
+    if end > 1 << 32 {
+        return None;
+    }
+    let first = addr & !(SECTOR_SIZE - 1);
+    let last = end.next_multiple_of(u64::from(SECTOR_SIZE));
+    let size = u32::try_from(last - u64::from(first)).ok()?;
+    Some((first, size))
+}
+
+/// Look up a partition of [`DEFAULT_LAYOUT`] by name, ignoring case.
+pub fn find_partition(name: &str) -> Option<&'static LayoutEntry> {
+    DEFAULT_LAYOUT
+        .iter()
+        .find(|entry| {
+            entry
+                .name
+                .eq_ignore_ascii_case(name)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_partition_ignores_case() {
+        let app = find_partition("APP").unwrap();
+        assert_eq!(app.burn_addr, 0x0023_0000);
+        assert_eq!(
+            find_partition("nv")
+                .unwrap()
+                .partition_type,
+            PartitionType::KvNv
+        );
+        assert!(find_partition("custom").is_none());
+    }
+
+    #[test]
+    fn test_region_erase_range_covers_touched_sectors() {
+        assert_eq!(
+            region_erase_range(0x0023_0000, 0x1000),
+            Some((0x0023_0000, 0x1000))
+        );
+        assert_eq!(
+            region_erase_range(0x0023_0000, 1),
+            Some((0x0023_0000, 0x1000))
+        );
+        assert_eq!(
+            region_erase_range(0x0023_0FFF, 1),
+            Some((0x0023_0000, 0x1000))
+        );
+        assert_eq!(
+            region_erase_range(0x0023_0FFF, 2),
+            Some((0x0023_0000, 0x2000))
+        );
+        assert_eq!(
+            region_erase_range(0x0023_0800, 0x1000),
+            Some((0x0023_0000, 0x2000))
+        );
+        assert_eq!(
+            region_erase_range(0xFFFF_F000, 0x1000),
+            Some((0xFFFF_F000, 0x1000))
+        );
+        assert_eq!(region_erase_range(0x0023_0000, 0), None);
+        assert_eq!(region_erase_range(0xFFFF_F000, 0x1001), None);
+    }
+}
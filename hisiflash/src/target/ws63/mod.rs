@@ -0,0 +1,5 @@
+//! WS63 chip support.
+
+pub(super) mod flasher; // 只在 ws63 模块内可见，通过 Flasher trait 暴露接口
+pub mod layout;
+pub mod protocol;